@@ -563,6 +563,10 @@ pub enum ChatResponseStream {
         conversation_id: Option<String>,
         utterance_id: Option<String>,
     },
+    /// Metadata about the request, sent by the service at the end of the stream.
+    MetadataEvent {
+        token_usage: Option<TokenUsage>,
+    },
     SupplementaryWebLinksEvent(()),
     ToolUseEvent {
         tool_use_id: String,
@@ -609,6 +613,11 @@ impl From<amzn_codewhisperer_streaming_client::types::ChatResponseStream> for Ch
                 conversation_id,
                 utterance_id,
             },
+            amzn_codewhisperer_streaming_client::types::ChatResponseStream::MetadataEvent(
+                amzn_codewhisperer_streaming_client::types::MetadataEvent { token_usage, .. },
+            ) => ChatResponseStream::MetadataEvent {
+                token_usage: token_usage.map(Into::into),
+            },
             amzn_codewhisperer_streaming_client::types::ChatResponseStream::ToolUseEvent(
                 amzn_codewhisperer_streaming_client::types::ToolUseEvent {
                     tool_use_id,
@@ -665,6 +674,11 @@ impl From<amzn_qdeveloper_streaming_client::types::ChatResponseStream> for ChatR
                 conversation_id,
                 utterance_id,
             },
+            amzn_qdeveloper_streaming_client::types::ChatResponseStream::MetadataEvent(
+                amzn_qdeveloper_streaming_client::types::MetadataEvent { token_usage, .. },
+            ) => ChatResponseStream::MetadataEvent {
+                token_usage: token_usage.map(Into::into),
+            },
             amzn_qdeveloper_streaming_client::types::ChatResponseStream::ToolUseEvent(
                 amzn_qdeveloper_streaming_client::types::ToolUseEvent {
                     tool_use_id,
@@ -687,6 +701,44 @@ impl From<amzn_qdeveloper_streaming_client::types::ChatResponseStream> for ChatR
     }
 }
 
+/// Number of tokens used by a request, as reported by the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub uncached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cache_write_input_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Total number of input tokens, including tokens read from or written to the prompt cache.
+    pub fn input_tokens(&self) -> u64 {
+        self.uncached_input_tokens + self.cache_read_input_tokens + self.cache_write_input_tokens
+    }
+}
+
+impl From<amzn_codewhisperer_streaming_client::types::TokenUsage> for TokenUsage {
+    fn from(value: amzn_codewhisperer_streaming_client::types::TokenUsage) -> Self {
+        Self {
+            uncached_input_tokens: value.uncached_input_tokens.max(0) as u64,
+            output_tokens: value.output_tokens.max(0) as u64,
+            cache_read_input_tokens: value.cache_read_input_tokens.unwrap_or_default().max(0) as u64,
+            cache_write_input_tokens: value.cache_write_input_tokens.unwrap_or_default().max(0) as u64,
+        }
+    }
+}
+
+impl From<amzn_qdeveloper_streaming_client::types::TokenUsage> for TokenUsage {
+    fn from(value: amzn_qdeveloper_streaming_client::types::TokenUsage) -> Self {
+        Self {
+            uncached_input_tokens: value.uncached_input_tokens.max(0) as u64,
+            output_tokens: value.output_tokens.max(0) as u64,
+            cache_read_input_tokens: value.cache_read_input_tokens.unwrap_or_default().max(0) as u64,
+            cache_write_input_tokens: value.cache_write_input_tokens.unwrap_or_default().max(0) as u64,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EnvState {
     pub operating_system: Option<String>,
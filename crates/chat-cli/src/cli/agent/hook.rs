@@ -21,6 +21,12 @@ pub enum HookTrigger {
     PreToolUse,
     /// Triggered after tool execution
     PostToolUse,
+    /// Triggered when the assistant finishes responding to a user turn
+    Stop,
+    /// Triggered when the chat session exits
+    SessionEnd,
+    /// Triggered before the conversation history is compacted
+    PreCompact,
 }
 
 impl Display for HookTrigger {
@@ -30,6 +36,9 @@ impl Display for HookTrigger {
             HookTrigger::UserPromptSubmit => write!(f, "userPromptSubmit"),
             HookTrigger::PreToolUse => write!(f, "preToolUse"),
             HookTrigger::PostToolUse => write!(f, "postToolUse"),
+            HookTrigger::Stop => write!(f, "stop"),
+            HookTrigger::SessionEnd => write!(f, "sessionEnd"),
+            HookTrigger::PreCompact => write!(f, "preCompact"),
         }
     }
 }
@@ -145,9 +145,13 @@ pub struct Agent {
     /// List of tools the agent is explicitly allowed to use
     #[serde(default)]
     pub allowed_tools: HashSet<String>,
-    /// Files to include in the agent's context
+    /// Files and MCP resources to include in the agent's context
     #[serde(default)]
     pub resources: Vec<ResourcePath>,
+    /// Directories MCP servers are told they may operate in, in addition to the working directory.
+    /// Relative paths are resolved against the working directory
+    #[serde(default)]
+    pub roots: Vec<String>,
     /// Commands to run when a chat session is created
     #[serde(default)]
     pub hooks: HashMap<HookTrigger, Vec<Hook>>,
@@ -193,6 +197,7 @@ impl Default for Agent {
             .into_iter()
             .map(Into::into)
             .collect::<Vec<_>>(),
+            roots: Default::default(),
             hooks: Default::default(),
             tools_settings: Default::default(),
             use_legacy_mcp_json: true,
@@ -1205,6 +1210,7 @@ mod tests {
             allowed_tools,
             tools_settings: Default::default(),
             resources: Vec::new(),
+            roots: Vec::new(),
             hooks: Default::default(),
             use_legacy_mcp_json: false,
             model: None,
@@ -92,7 +92,8 @@ pub fn tool_settings_schema(generator: &mut SchemaGenerator) -> Schema {
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq, JsonSchema)]
 pub struct ResourcePath(
     // You can extend this list via "|". e.g. r"^(file://|database://)"
-    #[schemars(regex(pattern = r"^(file://)"))]
+    // MCP resources are referenced as @server:uri, the same way they are mentioned in chat
+    #[schemars(regex(pattern = r"^(file://|@[^/:]+:)"))]
     String,
 );
 
@@ -1,8 +1,11 @@
 use std::collections::{
+    BTreeMap,
     HashMap,
+    HashSet,
     VecDeque,
 };
 use std::path::{
+    Component,
     Path,
     PathBuf,
 };
@@ -15,6 +18,7 @@ use chrono::{
     DateTime,
     Local,
 };
+use clap::ValueEnum;
 use crossterm::style::Stylize;
 use eyre::{
     Result,
@@ -29,8 +33,37 @@ use tracing::debug;
 
 use crate::cli::ConversationState;
 use crate::cli::chat::conversation::HistoryEntry;
+use crate::database::settings::Setting;
 use crate::os::Os;
 
+/// Name of the temporary index used to assemble checkpoint trees in touched mode
+const TREE_INDEX_FILE: &str = "checkpoint-tree-index";
+/// Name of the index the work tree is staged in before a command runs, in touched mode outside of
+/// a git repository
+const COMMAND_INDEX_FILE: &str = "checkpoint-command-index";
+
+/// Which files checkpoints snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointMode {
+    /// Snapshot the whole work tree
+    #[default]
+    Full,
+    /// Snapshot only the files touched by fs_write and execute_bash
+    Touched,
+}
+
+impl CheckpointMode {
+    /// The mode configured with `chat.checkpointMode`, [CheckpointMode::Full] by default.
+    pub fn from_settings(os: &Os) -> Self {
+        os.database
+            .settings
+            .get_string(Setting::ChatCheckpointMode)
+            .and_then(|mode| Self::from_str(&mode, true).ok())
+            .unwrap_or_default()
+    }
+}
+
 /// Manages a shadow git repository for tracking and restoring workspace changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckpointManager {
@@ -61,6 +94,43 @@ pub struct CheckpointManager {
     /// Cached file change statistics
     #[serde(default)]
     pub file_stats_cache: HashMap<String, FileStats>,
+
+    /// Which files checkpoints snapshot
+    #[serde(default)]
+    pub mode: CheckpointMode,
+
+    /// Path of the work tree relative to the root of the user's git repository, if the work tree
+    /// is inside one
+    #[serde(default)]
+    pub repo_prefix: Option<String>,
+
+    /// Files snapshotted in touched mode, by path relative to the work tree
+    #[serde(default)]
+    pub touched_files: BTreeMap<String, TouchedFile>,
+
+    /// State of the work tree before the running command started
+    #[serde(skip)]
+    before_command: Option<BeforeCommand>,
+}
+
+/// State of the work tree noted by [CheckpointManager::start_command]
+#[derive(Debug, Clone)]
+enum BeforeCommand {
+    /// Files of the user's repository that were modified, with the blob id of their content
+    Dirty(HashMap<String, Option<String>>),
+    /// The work tree isn't in a git repository, so all of its files were staged in
+    /// [COMMAND_INDEX_FILE]
+    Staged,
+}
+
+/// A file snapshotted in touched mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TouchedFile {
+    /// Blob id of the file's content before it was first touched, or `None` if it didn't exist
+    pub baseline: Option<String>,
+    /// Number of checkpoints that existed when the file was first touched. Earlier checkpoints
+    /// don't contain the file, its baseline stands in for it.
+    pub since: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -86,15 +156,17 @@ impl CheckpointManager {
         os: &Os,
         shadow_path: impl AsRef<Path>,
         current_history: &VecDeque<HistoryEntry>,
+        mode: CheckpointMode,
     ) -> Result<Self> {
         if !is_git_installed() {
             bail!("Checkpoints are not available. Git is required but not installed.");
         }
-        if !is_in_git_repo() {
+        // Touched mode only snapshots what tools change, which is cheap anywhere
+        if mode == CheckpointMode::Full && !is_in_git_repo() {
             bail!("Checkpoints are not available in this directory. Use '/checkpoint init' to enable checkpoints.");
         }
 
-        let manager = Self::manual_init(os, shadow_path, current_history).await?;
+        let manager = Self::manual_init(os, shadow_path, current_history, mode).await?;
         Ok(manager)
     }
 
@@ -103,12 +175,21 @@ impl CheckpointManager {
         os: &Os,
         path: impl AsRef<Path>,
         current_history: &VecDeque<HistoryEntry>,
+        mode: CheckpointMode,
     ) -> Result<Self> {
-        let path = path.as_ref();
-        os.fs.create_dir_all(path).await?;
-
         let work_tree_path =
             std::env::current_dir().map_err(|e| eyre!("Failed to get current working directory: {}", e))?;
+        Self::init(os, path.as_ref(), work_tree_path, current_history, mode).await
+    }
+
+    async fn init(
+        os: &Os,
+        path: &Path,
+        work_tree_path: PathBuf,
+        current_history: &VecDeque<HistoryEntry>,
+        mode: CheckpointMode,
+    ) -> Result<Self> {
+        os.fs.create_dir_all(path).await?;
 
         // Initialize bare repository
         run_git(path, None, &["init", "--bare", &path.to_string_lossy()])?;
@@ -116,8 +197,20 @@ impl CheckpointManager {
         // Configure git
         configure_git(&path.to_string_lossy())?;
 
-        // Create initial checkpoint
-        stage_commit_tag(&path.to_string_lossy(), &work_tree_path, "Initial state", "0")?;
+        // Honour the ignore rules of the user's repository that don't live in the work tree
+        let repo_prefix = user_git(&work_tree_path, &["rev-parse", "--show-prefix"])
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+        if repo_prefix.is_some() {
+            copy_user_excludes(path, &work_tree_path)?;
+        }
+
+        // Create initial checkpoint. In touched mode no file has been touched yet.
+        let paths = match mode {
+            CheckpointMode::Full => None,
+            CheckpointMode::Touched => Some(&[][..]),
+        };
+        stage_commit_tag(&path.to_string_lossy(), &work_tree_path, "Initial state", "0", paths)?;
 
         let initial_checkpoint = Checkpoint {
             tag: "0".to_string(),
@@ -141,6 +234,10 @@ impl CheckpointManager {
             pending_user_message: None,
             message_locked: false,
             file_stats_cache: HashMap::new(),
+            mode,
+            repo_prefix,
+            touched_files: BTreeMap::new(),
+            before_command: None,
         })
     }
 
@@ -154,11 +251,17 @@ impl CheckpointManager {
         tool_name: Option<String>,
     ) -> Result<()> {
         // Stage, commit and tag
+        let touched = self.touched_files.keys().cloned().collect::<Vec<_>>();
+        let paths = match self.mode {
+            CheckpointMode::Full => None,
+            CheckpointMode::Touched => Some(touched.as_slice()),
+        };
         stage_commit_tag(
             &self.shadow_repo_path.to_string_lossy(),
             &self.work_tree_path,
             description,
             tag,
+            paths,
         )?;
 
         // Record checkpoint metadata
@@ -182,11 +285,169 @@ impl CheckpointManager {
         Ok(())
     }
 
+    /// Start snapshotting `paths` in touched mode. Call before a tool changes them.
+    pub fn track_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        if self.mode != CheckpointMode::Touched {
+            return Ok(());
+        }
+
+        let mut new_paths = Vec::new();
+        for path in paths {
+            if let Some(path) = self.relative_path(path) {
+                if !self.touched_files.contains_key(&path) && !new_paths.contains(&path) {
+                    new_paths.push(path);
+                }
+            }
+        }
+        let new_paths = self.filter_ignored(new_paths)?;
+        let baselines = self.hash_work_tree_files(&new_paths)?;
+
+        let since = self.checkpoints.len();
+        for (path, baseline) in new_paths.into_iter().zip(baselines) {
+            self.touched_files.insert(path, TouchedFile { baseline, since });
+        }
+        Ok(())
+    }
+
+    /// Note the content of the files that are modified before a command runs, so that
+    /// [Self::finish_command] can tell which files the command touched. Outside of a git
+    /// repository, every file of the work tree is hashed.
+    pub fn start_command(&mut self) -> Result<()> {
+        if self.mode != CheckpointMode::Touched {
+            return Ok(());
+        }
+
+        if self.repo_prefix.is_none() {
+            // Files that haven't changed since the previous command aren't hashed again
+            self.command_index_git(&["add", "-A"])?;
+            self.before_command = Some(BeforeCommand::Staged);
+            return Ok(());
+        }
+
+        let dirty = self
+            .dirty_files()?
+            .into_iter()
+            .filter(|path| !self.touched_files.contains_key(path))
+            .collect::<Vec<_>>();
+        let blobs = self.hash_work_tree_files(&dirty)?;
+        self.before_command = Some(BeforeCommand::Dirty(dirty.into_iter().zip(blobs).collect()));
+        Ok(())
+    }
+
+    /// Start snapshotting the files changed by the command since [Self::start_command]. Files that
+    /// were already modified are compared against their content before the command, which becomes
+    /// their baseline. The baseline of other files is their content in the user's `HEAD`.
+    pub fn finish_command(&mut self) -> Result<()> {
+        let changed = match self.before_command.take() {
+            None => return Ok(()),
+            Some(BeforeCommand::Dirty(dirty_before)) => self.changed_since_dirty(dirty_before)?,
+            Some(BeforeCommand::Staged) => self.changed_since_staged()?,
+        };
+
+        let paths = self.filter_ignored(changed.iter().map(|(path, _)| path.clone()).collect())?;
+        let since = self.checkpoints.len();
+        for (path, baseline) in changed {
+            if paths.contains(&path) {
+                self.touched_files.insert(path, TouchedFile { baseline, since });
+            }
+        }
+        Ok(())
+    }
+
+    /// Files of the user's repository changed since `dirty_before` was noted, with their baseline.
+    fn changed_since_dirty(
+        &self,
+        dirty_before: HashMap<String, Option<String>>,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let after = self
+            .dirty_files()?
+            .into_iter()
+            .filter(|path| !self.touched_files.contains_key(path))
+            .collect::<Vec<_>>();
+        // Files modified before the command may have been changed further, or reverted
+        let before = dirty_before.keys().cloned().collect::<Vec<_>>();
+        let current = self.hash_work_tree_files(&before)?;
+
+        let mut changed = Vec::new();
+        for ((path, baseline), blob) in dirty_before.iter().zip(current) {
+            if *baseline != blob {
+                changed.push((path.clone(), baseline.clone()));
+            }
+        }
+        for path in after.into_iter().filter(|path| !dirty_before.contains_key(path)) {
+            let baseline = self.hash_head_file(&path)?;
+            changed.push((path, baseline));
+        }
+        Ok(changed)
+    }
+
+    /// Files of the work tree that differ from what was staged in [COMMAND_INDEX_FILE], with their
+    /// staged content as baseline.
+    fn changed_since_staged(&self) -> Result<Vec<(String, Option<String>)>> {
+        let output =
+            self.command_index_git(&["status", "--porcelain", "-z", "--no-renames", "--untracked-files=all"])?;
+        // Entries look like "XY path", where Y is the state of the work tree against the index
+        let mut changed = Vec::new();
+        let mut created = HashSet::new();
+        for entry in split_nul(&output.stdout) {
+            let (Some(state), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+                continue;
+            };
+            if self.touched_files.contains_key(path) {
+                continue;
+            }
+            match state {
+                "??" => {
+                    created.insert(path.to_string());
+                },
+                _ if !state.ends_with(' ') => changed.push(path.to_string()),
+                _ => {},
+            }
+        }
+
+        let mut baselines = HashMap::new();
+        if !changed.is_empty() {
+            let mut args = vec!["ls-files", "--stage", "-z", "--"];
+            args.extend(changed.iter().map(String::as_str));
+            let output = self.command_index_git(&args)?;
+            // Entries look like "mode blob stage\tpath"
+            for entry in split_nul(&output.stdout) {
+                if let Some((info, path)) = entry.split_once('\t') {
+                    if let Some(blob) = info.split(' ').nth(1) {
+                        baselines.insert(path.to_string(), blob.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(changed
+            .into_iter()
+            .map(|path| {
+                let baseline = baselines.remove(&path);
+                (path, baseline)
+            })
+            .chain(created.into_iter().map(|path| (path, None)))
+            .collect())
+    }
+
+    /// Runs git against the shadow repository with [COMMAND_INDEX_FILE] as its index.
+    fn command_index_git(&self, args: &[&str]) -> Result<Output> {
+        let mut cmd = git_command(&self.shadow_repo_path, Some(&self.work_tree_path));
+        cmd.env("GIT_INDEX_FILE", self.shadow_repo_path.join(COMMAND_INDEX_FILE))
+            .env("GIT_LITERAL_PATHSPECS", "1")
+            .args(args);
+        run_command(cmd)
+    }
+
     /// Restore workspace to a specific checkpoint
     pub fn restore(&self, conversation: &mut ConversationState, tag: &str, hard: bool) -> Result<()> {
         let checkpoint = self.get_checkpoint(tag)?;
 
-        if hard {
+        if self.mode == CheckpointMode::Touched {
+            // Only the touched files are snapshotted, anything else is left alone
+            let paths = self.touched_files.keys().cloned().collect::<Vec<_>>();
+            self.restore_paths(tag, &paths, hard)?;
+        } else if hard {
             // Hard: reset the whole work-tree to the tag
             let output = run_git(&self.shadow_repo_path, Some(&self.work_tree_path), &[
                 "reset", "--hard", tag,
@@ -222,6 +483,69 @@ impl CheckpointManager {
         Ok(())
     }
 
+    /// Restore `files` to their content at a checkpoint, without changing the conversation. Files
+    /// that didn't exist at the checkpoint are deleted. Returns the restored paths, relative to
+    /// the work tree.
+    pub fn restore_files(&self, tag: &str, files: &[PathBuf]) -> Result<Vec<String>> {
+        self.get_checkpoint(tag)?;
+
+        let paths = files
+            .iter()
+            .map(|file| {
+                self.relative_path(file)
+                    .ok_or_else(|| eyre!("{} is not in {}", file.display(), self.work_tree_path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.restore_paths(tag, &paths, true)?;
+        Ok(paths)
+    }
+
+    /// Check out `paths` from the tree of a checkpoint. Paths missing from the tree are deleted
+    /// when `remove_missing` is set, as long as a checkpoint snapshotted them.
+    fn restore_paths(&self, tag: &str, paths: &[String], remove_missing: bool) -> Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let tree = self.tree(tag)?;
+        let mut args = vec!["ls-tree", "-r", "-z", "--name-only", &tree, "--"];
+        args.extend(paths.iter().map(String::as_str));
+        let output = run_git(&self.shadow_repo_path, None, &args)?;
+        let present = split_nul(&output.stdout);
+
+        if !present.is_empty() {
+            let mut args = vec!["checkout", &tree, "--"];
+            args.extend(present.iter().map(String::as_str));
+            run_git(&self.shadow_repo_path, Some(&self.work_tree_path), &args)?;
+        }
+
+        if remove_missing {
+            let indexed = self.indexed_files(paths)?;
+            for path in paths.iter().filter(|path| !present.contains(*path)) {
+                let snapshotted = indexed.contains(path) || self.touched_files.contains_key(path);
+                let file = self.work_tree_path.join(path);
+                if snapshotted && file.is_file() {
+                    std::fs::remove_file(&file)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render the changes between two checkpoints as a patch that `git apply` accepts.
+    pub fn export_patch(&self, from: &str, to: &str) -> Result<Vec<u8>> {
+        let output = run_git(&self.shadow_repo_path, None, &[
+            "diff",
+            "--binary",
+            "--full-index",
+            "--no-color",
+            &self.tree(from)?,
+            &self.tree(to)?,
+        ])?;
+        Ok(output.stdout)
+    }
+
     /// Return true iff the given tag/tree has any tracked paths.
     fn tag_has_any_paths(&self, tag: &str) -> eyre::Result<bool> {
         // Use `git ls-tree -r --name-only <tag>` to check if the tree is empty
@@ -246,7 +570,8 @@ impl CheckpointManager {
 
     /// Compute file statistics between two checkpoints
     pub fn compute_stats_between(&self, from: &str, to: &str) -> Result<FileStats> {
-        let output = run_git(&self.shadow_repo_path, None, &["diff", "--name-status", from, to])?;
+        let (from, to) = (self.tree(from)?, self.tree(to)?);
+        let output = run_git(&self.shadow_repo_path, None, &["diff", "--name-status", &from, &to])?;
 
         let mut stats = FileStats::default();
         for line in String::from_utf8_lossy(&output.stdout).lines() {
@@ -267,9 +592,10 @@ impl CheckpointManager {
     /// Generate detailed diff between checkpoints
     pub fn diff(&self, from: &str, to: &str) -> Result<String> {
         let mut result = String::new();
+        let (from, to) = (self.tree(from)?, self.tree(to)?);
 
         // Get file changes
-        let output = run_git(&self.shadow_repo_path, None, &["diff", "--name-status", from, to])?;
+        let output = run_git(&self.shadow_repo_path, None, &["diff", "--name-status", &from, &to])?;
 
         for line in String::from_utf8_lossy(&output.stdout).lines() {
             if let Some((status, file)) = line.split_once('\t') {
@@ -286,8 +612,8 @@ impl CheckpointManager {
         // Add statistics
         let stat_output = run_git(&self.shadow_repo_path, None, &[
             "diff",
-            from,
-            to,
+            &from,
+            &to,
             "--stat",
             "--color=always",
         ])?;
@@ -302,10 +628,15 @@ impl CheckpointManager {
 
     /// Check for uncommitted changes
     pub fn has_changes(&self) -> Result<bool> {
-        let output = run_git(&self.shadow_repo_path, Some(&self.work_tree_path), &[
-            "status",
-            "--porcelain",
-        ])?;
+        let mut args = vec!["status", "--porcelain"];
+        if self.mode == CheckpointMode::Touched {
+            if self.touched_files.is_empty() {
+                return Ok(false);
+            }
+            args.push("--");
+            args.extend(self.touched_files.keys().map(String::as_str));
+        }
+        let output = run_git(&self.shadow_repo_path, Some(&self.work_tree_path), &args)?;
         Ok(!output.stdout.is_empty())
     }
 
@@ -323,6 +654,168 @@ impl CheckpointManager {
             .and_then(|&idx| self.checkpoints.get(idx))
             .ok_or_else(|| eyre!("Checkpoint '{}' not found", tag))
     }
+
+    /// Returns the tree holding the files of a checkpoint (or `HEAD`, the latest one).
+    ///
+    /// In touched mode, a checkpoint only contains the files touched before it was created.
+    /// Files touched later are added with their baseline content, which is what they held at
+    /// the time.
+    fn tree(&self, tag: &str) -> Result<String> {
+        if self.mode == CheckpointMode::Full {
+            return Ok(tag.to_string());
+        }
+
+        let idx = match tag {
+            "HEAD" => self.checkpoints.len().saturating_sub(1),
+            tag => *self
+                .tag_index
+                .get(tag)
+                .ok_or_else(|| eyre!("Checkpoint '{}' not found", tag))?,
+        };
+        let cacheinfo = self
+            .touched_files
+            .iter()
+            .filter(|(_, file)| file.since > idx)
+            .filter_map(|(path, file)| Some(format!("100644,{},{}", file.baseline.as_ref()?, path)))
+            .collect::<Vec<_>>();
+        if cacheinfo.is_empty() {
+            return Ok(tag.to_string());
+        }
+
+        let index_file = self.shadow_repo_path.join(TREE_INDEX_FILE);
+        let _ = std::fs::remove_file(&index_file);
+        let git = |args: &[&str]| {
+            let mut cmd = git_command(&self.shadow_repo_path, None);
+            cmd.env("GIT_INDEX_FILE", &index_file).args(args);
+            run_command(cmd)
+        };
+
+        git(&["read-tree", tag])?;
+        let mut args = vec!["update-index", "--add"];
+        for info in &cacheinfo {
+            args.extend(["--cacheinfo", info]);
+        }
+        git(&args)?;
+        let output = git(&["write-tree"])?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Returns `path` relative to the work tree, with `/` separators, or `None` if it is outside
+    /// of it.
+    fn relative_path(&self, path: &Path) -> Option<String> {
+        let path = self.work_tree_path.join(path);
+        let mut parts = Vec::new();
+        for component in path.strip_prefix(&self.work_tree_path).ok()?.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_str()?),
+                Component::CurDir => {},
+                Component::ParentDir => {
+                    parts.pop()?;
+                },
+                _ => return None,
+            }
+        }
+        match parts.first() {
+            None | Some(&".git") => None,
+            Some(_) => Some(parts.join("/")),
+        }
+    }
+
+    /// Removes the paths matched by the ignore rules.
+    fn filter_ignored(&self, paths: Vec<String>) -> Result<Vec<String>> {
+        if paths.is_empty() {
+            return Ok(paths);
+        }
+
+        let mut cmd = git_command(&self.shadow_repo_path, Some(&self.work_tree_path));
+        cmd.args(["-c", "core.quotePath=false", "check-ignore", "--"])
+            .args(&paths);
+        let output = cmd.output()?;
+        // Exits with 1 when no path is ignored
+        if !matches!(output.status.code(), Some(0 | 1)) {
+            bail!(
+                "Checkpoint operation failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        let ignored = String::from_utf8_lossy(&output.stdout);
+        let ignored = ignored.lines().collect::<HashSet<_>>();
+        Ok(paths
+            .into_iter()
+            .filter(|path| !ignored.contains(path.as_str()))
+            .collect())
+    }
+
+    /// Paths among `paths` that are in the shadow repository's index.
+    fn indexed_files(&self, paths: &[String]) -> Result<HashSet<String>> {
+        let mut args = vec!["ls-files", "-z", "--"];
+        args.extend(paths.iter().map(String::as_str));
+        let output = run_git(&self.shadow_repo_path, Some(&self.work_tree_path), &args)?;
+        Ok(split_nul(&output.stdout))
+    }
+
+    /// Writes the current content of `paths` to the shadow repository. Returns the blob id of
+    /// each path, or `None` for paths that aren't files.
+    fn hash_work_tree_files(&self, paths: &[String]) -> Result<Vec<Option<String>>> {
+        let files = paths
+            .iter()
+            .filter(|path| self.work_tree_path.join(path).is_file())
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let mut blobs = HashMap::new();
+        if !files.is_empty() {
+            let mut args = vec!["hash-object", "-w", "--"];
+            args.extend(&files);
+            let output = run_git(&self.shadow_repo_path, Some(&self.work_tree_path), &args)?;
+            let ids = String::from_utf8_lossy(&output.stdout);
+            blobs.extend(files.into_iter().zip(ids.lines().map(str::to_string)));
+        }
+        Ok(paths.iter().map(|path| blobs.remove(path.as_str())).collect())
+    }
+
+    /// Writes the content of `path` in the user's `HEAD` to the shadow repository. Returns its
+    /// blob id, or `None` if `HEAD` doesn't have the file.
+    fn hash_head_file(&self, path: &str) -> Result<Option<String>> {
+        let prefix = self.repo_prefix.as_deref().unwrap_or_default();
+        let Ok(output) = user_git(&self.work_tree_path, &[
+            "cat-file",
+            "blob",
+            &format!("HEAD:{prefix}{path}"),
+        ]) else {
+            return Ok(None);
+        };
+
+        let blob_file = self.shadow_repo_path.join("checkpoint-blob");
+        std::fs::write(&blob_file, &output.stdout)?;
+        let output = run_git(&self.shadow_repo_path, None, &[
+            "hash-object",
+            "-w",
+            "--no-filters",
+            &blob_file.to_string_lossy(),
+        ]);
+        let _ = std::fs::remove_file(&blob_file);
+        Ok(Some(String::from_utf8_lossy(&output?.stdout).trim().to_string()))
+    }
+
+    /// Files of the work tree that differ from the user's `HEAD`, excluding ignored files.
+    fn dirty_files(&self) -> Result<HashSet<String>> {
+        let output = user_git(&self.work_tree_path, &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--no-renames",
+            "--untracked-files=all",
+            "--",
+            ".",
+        ])?;
+        let prefix = self.repo_prefix.as_deref().unwrap_or_default();
+        Ok(split_nul(&output.stdout)
+            .into_iter()
+            // Entries look like "XY path", with a path relative to the repository root
+            .filter_map(|entry| Some(entry.get(3..)?.strip_prefix(prefix)?.to_string()))
+            .collect())
+    }
 }
 
 impl Drop for CheckpointManager {
@@ -383,9 +876,51 @@ fn configure_git(shadow_path: &str) -> Result<()> {
     Ok(())
 }
 
-fn stage_commit_tag(shadow_path: &str, work_tree: &Path, message: &str, tag: &str) -> Result<()> {
-    // Stage all changes
-    run_git(Path::new(shadow_path), Some(work_tree), &["add", "-A"])?;
+/// Copies the exclude file of the user's repository into the shadow repository.
+fn copy_user_excludes(shadow_path: &Path, work_tree: &Path) -> Result<()> {
+    let output = user_git(work_tree, &["rev-parse", "--git-path", "info/exclude"])?;
+    let exclude = work_tree.join(String::from_utf8_lossy(&output.stdout).trim());
+    if exclude.is_file() {
+        std::fs::create_dir_all(shadow_path.join("info"))?;
+        std::fs::copy(&exclude, shadow_path.join("info").join("exclude"))?;
+    }
+    Ok(())
+}
+
+/// Stages and commits the work tree. With `paths`, only those paths are staged.
+fn stage_commit_tag(
+    shadow_path: &str,
+    work_tree: &Path,
+    message: &str,
+    tag: &str,
+    paths: Option<&[String]>,
+) -> Result<()> {
+    match paths {
+        // Stage all changes
+        None => {
+            run_git(Path::new(shadow_path), Some(work_tree), &["add", "-A"])?;
+        },
+        Some(paths) if !paths.is_empty() => {
+            // Paths that never existed can't be staged
+            let mut args = vec!["ls-files", "-z", "--"];
+            args.extend(paths.iter().map(String::as_str));
+            let output = run_git(Path::new(shadow_path), Some(work_tree), &args)?;
+            let indexed = split_nul(&output.stdout);
+
+            // Ignored paths were filtered out when they were touched
+            let mut args = vec!["add", "-A", "-f", "--"];
+            args.extend(
+                paths
+                    .iter()
+                    .filter(|path| indexed.contains(*path) || work_tree.join(path).exists())
+                    .map(String::as_str),
+            );
+            if args.len() > 4 {
+                run_git(Path::new(shadow_path), Some(work_tree), &args)?;
+            }
+        },
+        Some(_) => {},
+    }
 
     // Commit
     let output = run_git(Path::new(shadow_path), Some(work_tree), &[
@@ -415,16 +950,34 @@ fn stage_commit_tag(shadow_path: &str, work_tree: &Path, message: &str, tag: &st
     Ok(())
 }
 
+/// Runs git against the shadow repository. Paths given to it are taken literally.
 fn run_git(dir: &Path, work_tree: Option<&Path>, args: &[&str]) -> Result<Output> {
+    let mut cmd = git_command(dir, work_tree);
+    cmd.env("GIT_LITERAL_PATHSPECS", "1").args(args);
+    run_command(cmd)
+}
+
+/// Runs git against the user's own repository.
+fn user_git(work_tree: &Path, args: &[&str]) -> Result<Output> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(work_tree).args(args);
+    run_command(cmd)
+}
+
+/// A git command for the shadow repository. Paths given to it are relative to the work tree.
+fn git_command(dir: &Path, work_tree: Option<&Path>) -> Command {
     let mut cmd = Command::new("git");
     cmd.arg(format!("--git-dir={}", dir.display()));
 
     if let Some(work_tree_path) = work_tree {
         cmd.arg(format!("--work-tree={}", work_tree_path.display()));
+        cmd.current_dir(work_tree_path);
     }
 
-    cmd.args(args);
+    cmd
+}
 
+fn run_command(mut cmd: Command) -> Result<Output> {
     debug!("Executing git command: {:?}", cmd);
     let output = cmd.output()?;
 
@@ -447,6 +1000,14 @@ fn run_git(dir: &Path, work_tree: Option<&Path>, args: &[&str]) -> Result<Output
     Ok(output)
 }
 
+fn split_nul(output: &[u8]) -> HashSet<String> {
+    output
+        .split(|&b| b == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).into_owned())
+        .collect()
+}
+
 fn get_previous_tag(tag: &str) -> String {
     // Parse turn.tool format
     if let Some((turn_str, tool_str)) = tag.split_once('.') {
@@ -466,3 +1027,152 @@ fn get_previous_tag(tag: &str) -> String {
 
     "0".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        user_git(dir, args).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_touched_mode() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let os = Os::new().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let work_tree = dir.path().join("repo");
+        std::fs::create_dir_all(&work_tree).unwrap();
+        git(&work_tree, &["init", "-q"]);
+        git(&work_tree, &["config", "user.name", "test"]);
+        git(&work_tree, &["config", "user.email", "test@local"]);
+        std::fs::write(work_tree.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(work_tree.join("a.txt"), "a0").unwrap();
+        std::fs::write(work_tree.join("untouched.txt"), "u0").unwrap();
+        git(&work_tree, &["add", "-A"]);
+        git(&work_tree, &["commit", "-q", "-m", "init"]);
+
+        let mut manager = CheckpointManager::init(
+            &os,
+            &dir.path().join("shadow"),
+            work_tree.clone(),
+            &VecDeque::new(),
+            CheckpointMode::Touched,
+        )
+        .await
+        .unwrap();
+        assert!(!manager.has_changes().unwrap());
+
+        // fs_write creates a new file, and an ignored one
+        manager
+            .track_files(&[work_tree.join("new.txt"), work_tree.join("out.log")])
+            .unwrap();
+        assert!(manager.touched_files.contains_key("new.txt"));
+        assert!(!manager.touched_files.contains_key("out.log"));
+        std::fs::write(work_tree.join("new.txt"), "n1").unwrap();
+        assert!(manager.has_changes().unwrap());
+        manager
+            .create_checkpoint("1", "turn 1", &VecDeque::new(), true, None)
+            .unwrap();
+
+        // execute_bash modifies a committed file, other files are left alone
+        std::fs::write(work_tree.join("untouched.txt"), "u1").unwrap();
+        std::fs::write(work_tree.join("dirty.txt"), "d0").unwrap();
+        manager.start_command().unwrap();
+        std::fs::write(work_tree.join("a.txt"), "a2").unwrap();
+        std::fs::write(work_tree.join("dirty.txt"), "d1").unwrap();
+        manager.finish_command().unwrap();
+        assert!(manager.touched_files.contains_key("a.txt"));
+        assert!(!manager.touched_files.contains_key("untouched.txt"));
+        // Files modified before the command keep the content they had then as their baseline
+        let baseline = manager.touched_files["dirty.txt"].baseline.clone().unwrap();
+        let output = run_git(&manager.shadow_repo_path, None, &["cat-file", "blob", &baseline]).unwrap();
+        assert_eq!(output.stdout, b"d0");
+        manager
+            .create_checkpoint("2", "turn 2", &VecDeque::new(), true, None)
+            .unwrap();
+        std::fs::write(work_tree.join("untouched.txt"), "u0").unwrap();
+
+        // Files touched later are compared against their baseline
+        let stats = manager.compute_stats_between("0", "2").unwrap();
+        assert_eq!((stats.added, stats.modified, stats.deleted), (1, 2, 0));
+
+        let patch = manager.export_patch("1", "2").unwrap();
+        let patch = String::from_utf8(patch).unwrap();
+        assert!(patch.contains("-a0") && patch.contains("+a2"));
+        assert!(!patch.contains("new.txt"));
+
+        // Per-file restores don't touch other files
+        manager.restore_files("1", &[PathBuf::from("a.txt")]).unwrap();
+        assert_eq!(std::fs::read_to_string(work_tree.join("a.txt")).unwrap(), "a0");
+        assert_eq!(std::fs::read_to_string(work_tree.join("new.txt")).unwrap(), "n1");
+        manager.restore_files("0", &[work_tree.join("new.txt")]).unwrap();
+        assert!(!work_tree.join("new.txt").exists());
+        assert!(manager.restore_files("0", &[PathBuf::from("../outside")]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_touched_mode_outside_repo() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let os = Os::new().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let work_tree = dir.path().join("work");
+        std::fs::create_dir_all(work_tree.join("sub")).unwrap();
+        std::fs::write(work_tree.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(work_tree.join("a.txt"), "a0").unwrap();
+        std::fs::write(work_tree.join("sub/b.txt"), "b0").unwrap();
+        std::fs::write(work_tree.join("untouched.txt"), "u0").unwrap();
+
+        let mut manager = CheckpointManager::init(
+            &os,
+            &dir.path().join("shadow"),
+            work_tree.clone(),
+            &VecDeque::new(),
+            CheckpointMode::Touched,
+        )
+        .await
+        .unwrap();
+        if manager.repo_prefix.is_some() {
+            // The temporary directory is inside a git repository
+            return;
+        }
+
+        // execute_bash modifies, deletes and creates files
+        manager.start_command().unwrap();
+        std::fs::write(work_tree.join("a.txt"), "a1").unwrap();
+        std::fs::remove_file(work_tree.join("sub/b.txt")).unwrap();
+        std::fs::write(work_tree.join("new.txt"), "n1").unwrap();
+        std::fs::write(work_tree.join("out.log"), "l1").unwrap();
+        manager.finish_command().unwrap();
+        let touched = manager.touched_files.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(touched, ["a.txt", "new.txt", "sub/b.txt"]);
+        assert!(manager.touched_files["new.txt"].baseline.is_none());
+        manager
+            .create_checkpoint("1", "turn 1", &VecDeque::new(), true, None)
+            .unwrap();
+
+        // A later command only picks up what it changed
+        std::fs::write(work_tree.join("untouched.txt"), "u1").unwrap();
+        manager.start_command().unwrap();
+        manager.finish_command().unwrap();
+        assert!(!manager.touched_files.contains_key("untouched.txt"));
+
+        manager
+            .restore_files("0", &[
+                PathBuf::from("a.txt"),
+                PathBuf::from("sub/b.txt"),
+                PathBuf::from("new.txt"),
+            ])
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(work_tree.join("a.txt")).unwrap(), "a0");
+        assert_eq!(std::fs::read_to_string(work_tree.join("sub/b.txt")).unwrap(), "b0");
+        assert!(!work_tree.join("new.txt").exists());
+        assert_eq!(std::fs::read_to_string(work_tree.join("untouched.txt")).unwrap(), "u1");
+    }
+}
@@ -0,0 +1,228 @@
+use std::io::Write;
+
+use clap::{
+    Args,
+    Subcommand,
+};
+use crossterm::style::{
+    self,
+    Color,
+    Stylize,
+};
+use crossterm::{
+    execute,
+    queue,
+};
+
+use crate::cli::chat::conversation::HistoryEntry;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+/// Maximum number of characters of a prompt or response shown in a history entry summary.
+const PREVIEW_MAX_CHARS: usize = 100;
+
+#[derive(Debug, PartialEq, Args)]
+pub struct BranchArgs {
+    #[command(subcommand)]
+    subcommand: Option<BranchSubcommand>,
+}
+
+/// History entries are numbered starting from 1, as shown by `/branch log`.
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum BranchSubcommand {
+    /// List branches
+    List,
+    /// Show the history entries of the current branch
+    Log,
+    /// Fork the conversation into a new branch and switch to it
+    Create {
+        /// Name of the new branch
+        name: String,
+        /// Fork after this history entry. Defaults to the latest entry
+        #[arg(long, value_name = "ENTRY")]
+        at: Option<usize>,
+    },
+    /// Switch to another branch
+    Switch {
+        /// Name of the branch to switch to
+        name: String,
+    },
+    /// Show what two branches produced since they were forked
+    Diff {
+        /// Name of the branch to compare
+        name: String,
+        /// Name of the branch to compare against. Defaults to the current branch
+        other: Option<String>,
+    },
+    /// Make a branch the main branch. The previous main branch is kept under the promoted
+    /// branch's name
+    Promote {
+        /// Name of the branch to promote
+        name: String,
+    },
+    /// Delete a branch
+    Delete {
+        /// Name of the branch to delete
+        name: String,
+    },
+}
+
+impl BranchArgs {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let conversation = &mut session.conversation;
+        let result = match self.subcommand.unwrap_or(BranchSubcommand::List) {
+            BranchSubcommand::List => {
+                print_branches(
+                    &mut session.stderr,
+                    &conversation.branches(),
+                    conversation.current_branch(),
+                )?;
+                Ok(None)
+            },
+            BranchSubcommand::Log => {
+                let entries = conversation.history().iter().collect::<Vec<_>>();
+                queue!(
+                    session.stderr,
+                    style::Print(format!("\nBranch {}\n", conversation.current_branch().green()))
+                )?;
+                print_entries(&mut session.stderr, &entries, 0)?;
+                Ok(None)
+            },
+            BranchSubcommand::Create { name, at } => conversation
+                .create_branch(&name, at)
+                .map(|_| Some(format!("Created branch {name} and switched to it"))),
+            BranchSubcommand::Switch { name } => conversation
+                .switch_branch(&name)
+                .map(|_| Some(format!("Switched to branch {name}"))),
+            BranchSubcommand::Diff { name, other } => {
+                let other = other.unwrap_or_else(|| conversation.current_branch().to_string());
+                match conversation.diff_branches(&name, &other) {
+                    Ok(diff) => {
+                        queue!(
+                            session.stderr,
+                            style::Print(format!("\n{} history entries in common\n", diff.common))
+                        )?;
+                        for (branch, entries) in [(&name, &diff.left), (&other, &diff.right)] {
+                            queue!(
+                                session.stderr,
+                                style::Print(format!(
+                                    "\n{} ({} entries since fork)\n",
+                                    branch.as_str().green(),
+                                    entries.len()
+                                ))
+                            )?;
+                            print_entries(&mut session.stderr, entries, diff.common)?;
+                        }
+                        Ok(None)
+                    },
+                    Err(err) => Err(err),
+                }
+            },
+            BranchSubcommand::Promote { name } => conversation
+                .promote_branch(&name)
+                .map(|_| Some(format!("Promoted branch {name} to main"))),
+            BranchSubcommand::Delete { name } => conversation
+                .delete_branch(&name)
+                .map(|_| Some(format!("Deleted branch {name}"))),
+        };
+
+        match result {
+            Ok(Some(message)) => execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Green),
+                style::Print(format!("\n✔ {message}\n\n")),
+                style::SetForegroundColor(Color::Reset)
+            )?,
+            Ok(None) => session.stderr.flush()?,
+            Err(err) => return Err(ChatError::Custom(err.to_string().into())),
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        match self.subcommand.as_ref()? {
+            BranchSubcommand::List => Some("list"),
+            BranchSubcommand::Log => Some("log"),
+            BranchSubcommand::Create { .. } => Some("create"),
+            BranchSubcommand::Switch { .. } => Some("switch"),
+            BranchSubcommand::Diff { .. } => Some("diff"),
+            BranchSubcommand::Promote { .. } => Some("promote"),
+            BranchSubcommand::Delete { .. } => Some("delete"),
+        }
+    }
+}
+
+fn print_branches(output: &mut impl Write, branches: &[(&str, usize)], current: &str) -> Result<(), std::io::Error> {
+    queue!(output, style::Print("\n"))?;
+    for (name, entries) in branches {
+        let marker = match *name == current {
+            true => "*".green().bold(),
+            false => " ".stylize(),
+        };
+        queue!(
+            output,
+            style::Print(format!("{marker} {name} ")),
+            style::Print(format!("({entries} history entries)\n").dark_grey()),
+        )?;
+    }
+    queue!(output, style::Print("\n"))?;
+    Ok(())
+}
+
+/// Prints a one line summary of each entry, numbering them starting from `offset + 1`.
+fn print_entries(output: &mut impl Write, entries: &[&HistoryEntry], offset: usize) -> Result<(), std::io::Error> {
+    if entries.is_empty() {
+        return queue!(output, style::Print("  (no history entries)\n\n"));
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        let prompt = match entry.user().prompt() {
+            Some(prompt) => format!("> {}", preview(prompt)),
+            None => "(tool results)".to_string(),
+        };
+        queue!(
+            output,
+            style::Print(format!("{:>4}. ", offset + i + 1).dark_grey()),
+            style::Print(format!("{prompt}\n").cyan()),
+            style::Print(format!("      {}\n", preview(entry.assistant().content()))),
+        )?;
+
+        if let Some(tool_uses) = entry.assistant().tool_uses() {
+            let tools = tool_uses
+                .iter()
+                .map(
+                    |tool_use| match tool_use.args.get("path").and_then(|path| path.as_str()) {
+                        Some(path) => format!("{} ({path})", tool_use.name),
+                        None => tool_use.name.clone(),
+                    },
+                )
+                .collect::<Vec<_>>();
+            queue!(
+                output,
+                style::Print(format!("      tools: {}\n", tools.join(", ")).dark_grey())
+            )?;
+        }
+    }
+    queue!(output, style::Print("\n"))?;
+    Ok(())
+}
+
+/// Returns the first non-empty line of `text`, truncated to [PREVIEW_MAX_CHARS].
+fn preview(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    match line.char_indices().nth(PREVIEW_MAX_CHARS) {
+        Some((i, _)) => format!("{}...", &line[..i]),
+        None => line.to_string(),
+    }
+}
@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::path::PathBuf;
 
 use clap::Subcommand;
 use crossterm::style::{
@@ -16,6 +17,7 @@ use dialoguer::Select;
 use crate::cli::chat::checkpoint::{
     Checkpoint,
     CheckpointManager,
+    CheckpointMode,
     FileStats,
 };
 use crate::cli::chat::{
@@ -30,7 +32,11 @@ use crate::util::directories::get_shadow_repo_dir;
 #[derive(Debug, PartialEq, Subcommand)]
 pub enum CheckpointSubcommand {
     /// Initialize checkpoints manually
-    Init,
+    Init {
+        /// Which files checkpoints snapshot. Defaults to the chat.checkpointMode setting
+        #[arg(long, value_enum)]
+        mode: Option<CheckpointMode>,
+    },
 
     /// Restore workspace to a checkpoint
     #[command(
@@ -43,7 +49,11 @@ Default mode:
 
 With --hard:
   • Exactly matches the checkpoint state
-  • Removes files created after the checkpoint"#
+  • Removes files created after the checkpoint
+
+With --file:
+  • Only restores the given files, removing them if they didn't exist yet
+  • Keeps the conversation as it is"#
     )]
     Restore {
         /// Checkpoint tag (e.g., 3 or 3.1). Leave empty to select interactively.
@@ -52,6 +62,10 @@ With --hard:
         /// Exactly match checkpoint state (removes newer files)
         #[arg(long)]
         hard: bool,
+
+        /// Only restore this file. Can be repeated
+        #[arg(long = "file", value_name = "PATH")]
+        files: Vec<PathBuf>,
     },
 
     /// List all checkpoints
@@ -79,6 +93,23 @@ With --hard:
         #[arg(required = false)]
         tag2: Option<String>,
     },
+
+    /// Write the changes between checkpoints to a patch file
+    Export {
+        /// Checkpoint tag the patch starts from
+        from: String,
+
+        /// Checkpoint tag the patch ends at (defaults to the latest checkpoint)
+        to: Option<String>,
+
+        /// Path where the patch will be written
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Force overwrite if file already exists
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
 impl CheckpointSubcommand {
@@ -116,16 +147,31 @@ impl CheckpointSubcommand {
             });
         }
         match self {
-            Self::Init => self.handle_init(os, session).await,
-            Self::Restore { ref tag, hard } => self.handle_restore(session, tag.clone(), hard).await,
+            Self::Init { mode } => self.handle_init(os, session, mode).await,
+            Self::Restore {
+                ref tag,
+                hard,
+                ref files,
+            } => self.handle_restore(session, tag.clone(), hard, files).await,
             Self::List { limit } => Self::handle_list(session, limit),
             Self::Clean => self.handle_clean(os, session).await,
             Self::Expand { ref tag } => Self::handle_expand(session, tag.clone()),
             Self::Diff { ref tag1, ref tag2 } => Self::handle_diff(session, tag1.clone(), tag2.clone()),
+            Self::Export {
+                ref from,
+                ref to,
+                ref output,
+                force,
+            } => Self::handle_export(os, session, from, to.as_deref(), output, force).await,
         }
     }
 
-    async fn handle_init(&self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    async fn handle_init(
+        &self,
+        os: &Os,
+        session: &mut ChatSession,
+        mode: Option<CheckpointMode>,
+    ) -> Result<ChatState, ChatError> {
         if session.conversation.checkpoint_manager.is_some() {
             execute!(
                 session.stderr,
@@ -141,9 +187,14 @@ impl CheckpointSubcommand {
 
             let start = std::time::Instant::now();
             session.conversation.checkpoint_manager = Some(
-                CheckpointManager::manual_init(os, path, session.conversation.history())
-                    .await
-                    .map_err(|e| ChatError::Custom(format!("Checkpoints could not be initialized: {e}").into()))?,
+                CheckpointManager::manual_init(
+                    os,
+                    path,
+                    session.conversation.history(),
+                    mode.unwrap_or_else(|| CheckpointMode::from_settings(os)),
+                )
+                .await
+                .map_err(|e| ChatError::Custom(format!("Checkpoints could not be initialized: {e}").into()))?,
             );
 
             execute!(
@@ -169,6 +220,7 @@ impl CheckpointSubcommand {
         session: &mut ChatSession,
         tag: Option<String>,
         hard: bool,
+        files: &[PathBuf],
     ) -> Result<ChatState, ChatError> {
         // Take manager out temporarily to avoid borrow issues
         let Some(manager) = session.conversation.checkpoint_manager.take() else {
@@ -212,6 +264,23 @@ impl CheckpointSubcommand {
             },
         };
 
+        if !files.is_empty() {
+            let result = manager.restore_files(&tag, files);
+            session.conversation.checkpoint_manager = Some(manager);
+            let paths = result.map_err(|e| ChatError::Custom(format!("Failed to restore: {}", e).into()))?;
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Blue),
+                style::SetAttribute(Attribute::Bold),
+                style::Print(format!("✓ Restored {} to checkpoint {}\n", paths.join(", "), tag)),
+                style::SetForegroundColor(Color::Reset),
+                style::SetAttribute(Attribute::Reset),
+            )?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
         match manager.restore(&mut session.conversation, &tag, hard) {
             Ok(_) => {
                 execute!(
@@ -397,6 +466,69 @@ impl CheckpointSubcommand {
             skip_printing_tools: true,
         })
     }
+
+    async fn handle_export(
+        os: &Os,
+        session: &mut ChatSession,
+        from: &str,
+        to: Option<&str>,
+        output: &PathBuf,
+        force: bool,
+    ) -> Result<ChatState, ChatError> {
+        let Some(manager) = session.conversation.checkpoint_manager.as_ref() else {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print("⚠️ Checkpoints not enabled. Use '/checkpoint init' to enable.\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        };
+
+        if os.fs.exists(output) && !force {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Red),
+                style::Print(format!(
+                    "File at {} already exists. To overwrite, use -f or --force\n",
+                    output.display()
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
+        let to = to.unwrap_or("HEAD");
+        let patch = manager
+            .export_patch(from, to)
+            .map_err(|e| ChatError::Custom(format!("Failed to export checkpoints: {e}").into()))?;
+        os.fs
+            .write(output, &patch)
+            .await
+            .map_err(|e| ChatError::Custom(format!("Failed to write to {}: {e}", output.display()).into()))?;
+
+        execute!(
+            session.stderr,
+            style::SetForegroundColor(Color::Blue),
+            style::SetAttribute(Attribute::Bold),
+            style::Print(format!(
+                "✓ Exported changes from {} to {} to {}\n",
+                from,
+                to,
+                output.display()
+            )),
+            style::SetForegroundColor(Color::Reset),
+            style::SetAttribute(Attribute::Reset),
+        )?;
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
 }
 
 // Display helpers
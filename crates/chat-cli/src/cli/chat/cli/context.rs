@@ -15,7 +15,6 @@ use crate::cli::chat::context::{
     ContextFilePath,
     calc_max_context_files_size,
 };
-use crate::cli::chat::token_counter::TokenCounter;
 use crate::cli::chat::util::drop_matched_context_files;
 use crate::cli::chat::{
     ChatError,
@@ -185,9 +184,10 @@ impl ContextSubcommand {
                     )?;
                 } else {
                     let total = profile_context_files.len();
+                    let tokenizer = session.conversation.tokenizer(os);
                     let total_tokens = profile_context_files
                         .iter()
-                        .map(|(_, content, _)| TokenCounter::count_tokens(content))
+                        .map(|(_, content, _)| *tokenizer.count_text(content))
                         .sum::<usize>();
                     execute!(
                         session.stderr,
@@ -203,7 +203,7 @@ impl ContextSubcommand {
                     )?;
 
                     for (filename, content, is_temporary) in &profile_context_files {
-                        let est_tokens = TokenCounter::count_tokens(content);
+                        let est_tokens = *tokenizer.count_text(content);
                         let icon = if *is_temporary { "💬" } else { "👤" };
                         execute!(
                             session.stderr,
@@ -254,7 +254,7 @@ impl ContextSubcommand {
                             let truncated_dropped_files = &dropped_files[..10];
 
                             for (filename, content) in truncated_dropped_files {
-                                let est_tokens = TokenCounter::count_tokens(content);
+                                let est_tokens = *tokenizer.count_text(content);
                                 execute!(
                                     session.stderr,
                                     style::Print(format!("{} ", filename)),
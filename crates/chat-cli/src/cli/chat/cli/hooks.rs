@@ -28,6 +28,7 @@ use futures::stream::{
     FuturesUnordered,
     StreamExt,
 };
+use serde::Deserialize;
 use spinners::{
     Spinner,
     Spinners,
@@ -91,6 +92,61 @@ pub struct ToolContext {
     pub tool_response: Option<serde_json::Value>,
 }
 
+/// Session level context for hooks that are not tied to a tool use, i.e. [HookTrigger::Stop],
+/// [HookTrigger::SessionEnd], and [HookTrigger::PreCompact].
+#[derive(Debug, Clone)]
+pub struct SessionContext {
+    pub conversation_id: String,
+    /// Human-readable transcript of the conversation so far.
+    pub transcript: Vec<String>,
+    /// The final assistant response of the turn. Only set for [HookTrigger::Stop].
+    pub assistant_response: Option<String>,
+}
+
+/// Permission decision a [HookTrigger::PreToolUse] hook can make about a tool use.
+///
+/// Variants are ordered from least to most restrictive, so when several hooks make a decision
+/// about the same tool use, the maximum wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionDecision {
+    /// Run the tool without asking the user for permission
+    Allow,
+    /// Always ask the user for permission, even if the tool is trusted
+    Ask,
+    /// Block the tool use. The reason is returned to the model
+    Deny,
+}
+
+/// Structured output a [HookTrigger::PreToolUse] hook may print to stdout (with exit code 0)
+/// to control how a tool use proceeds.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PreToolUseOutput {
+    #[serde(default)]
+    pub decision: Option<PermissionDecision>,
+    /// Explanation for the decision. Sent to the model when the tool use is denied.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Replacement for the tool input, applied before the tool is invoked.
+    #[serde(default)]
+    pub updated_input: Option<serde_json::Value>,
+}
+
+impl PreToolUseOutput {
+    /// Parses hook stdout as a [PreToolUseOutput]. Returns [None] if the output is not a JSON
+    /// object, in which case the hook is treated as a plain hook.
+    pub fn parse(output: &str) -> Option<Self> {
+        let output = output.trim();
+        if !output.starts_with('{') {
+            return None;
+        }
+
+        serde_json::from_str(output)
+            .map_err(|err| tracing::warn!(?err, "failed to parse PreToolUse hook output"))
+            .ok()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CachedHook {
     output: String,
@@ -101,11 +157,19 @@ pub struct CachedHook {
 #[derive(Debug, Clone, Default)]
 pub struct HookExecutor {
     pub cache: HashMap<(HookTrigger, Hook), CachedHook>,
+    /// Results of hooks executed since the last call to [Self::take_results].
+    results: Vec<((HookTrigger, Hook), HookOutput)>,
 }
 
 impl HookExecutor {
     pub fn new() -> Self {
-        Self { cache: HashMap::new() }
+        Self::default()
+    }
+
+    /// Returns the results of hooks executed since the last call. Results served from the cache
+    /// are not included.
+    pub fn take_results(&mut self) -> Vec<((HookTrigger, Hook), HookOutput)> {
+        std::mem::take(&mut self.results)
     }
 
     /// Run and cache [`Hook`]s. Any hooks that are already cached will be returned without
@@ -123,6 +187,7 @@ impl HookExecutor {
         cwd: &str,
         prompt: Option<&str>,
         tool_context: Option<ToolContext>,
+        session_context: Option<SessionContext>,
     ) -> Result<Vec<((HookTrigger, Hook), HookOutput)>, ChatError> {
         let mut cached = vec![];
         let mut futures = FuturesUnordered::new();
@@ -142,7 +207,7 @@ impl HookExecutor {
                 cached.push((hook.clone(), (0, cache)));
                 continue;
             }
-            futures.push(self.run_hook(hook, cwd, prompt, tool_context.clone()));
+            futures.push(self.run_hook(hook, cwd, prompt, tool_context.clone(), session_context.clone()));
         }
 
         let mut complete = 0; // number of hooks that are run successfully with exit code 0
@@ -247,17 +312,22 @@ impl HookExecutor {
             if *exit_code != 0 {
                 continue; // Only cache successful hooks
             }
+            let expiry = match trigger {
+                HookTrigger::AgentSpawn => None,
+                HookTrigger::UserPromptSubmit => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
+                HookTrigger::PreToolUse => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
+                HookTrigger::PostToolUse => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
+                // These hooks run for their side effects rather than their output, so they have
+                // to run every time they are triggered
+                HookTrigger::Stop | HookTrigger::SessionEnd | HookTrigger::PreCompact => continue,
+            };
             self.cache.insert((*trigger, hook.clone()), CachedHook {
                 output: output.clone(),
-                expiry: match trigger {
-                    HookTrigger::AgentSpawn => None,
-                    HookTrigger::UserPromptSubmit => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
-                    HookTrigger::PreToolUse => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
-                    HookTrigger::PostToolUse => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
-                },
+                expiry,
             });
         }
 
+        self.results.extend(results.iter().cloned());
         results.append(&mut cached);
 
         Ok(results)
@@ -269,6 +339,7 @@ impl HookExecutor {
         cwd: &str,
         prompt: Option<&str>,
         tool_context: Option<ToolContext>,
+        session_context: Option<SessionContext>,
     ) -> ((HookTrigger, Hook), Result<HookOutput>, Duration) {
         let start_time = Instant::now();
 
@@ -318,6 +389,15 @@ impl HookExecutor {
                 hook_input["tool_response"] = response;
             }
         }
+
+        // Session lifecycle specific input
+        if let Some(session_ctx) = session_context {
+            hook_input["conversation_id"] = serde_json::Value::String(session_ctx.conversation_id);
+            hook_input["transcript"] = serde_json::json!(session_ctx.transcript);
+            if let Some(response) = session_ctx.assistant_response {
+                hook_input["assistant_response"] = serde_json::Value::String(response);
+            }
+        }
         let json_input = serde_json::to_string(&hook_input).unwrap_or_default();
 
         // Build a future for hook command w/ the JSON input passed in through STDIN
@@ -596,7 +676,7 @@ mod tests {
 
         // Run the hook
         let result = executor
-            .run_hooks(hooks, &mut output, ".", None, Some(tool_context))
+            .run_hooks(hooks, &mut output, ".", None, Some(tool_context), None)
             .await;
 
         assert!(result.is_ok());
@@ -612,6 +692,87 @@ mod tests {
         // TempDir automatically cleans up when dropped
     }
 
+    #[tokio::test]
+    async fn test_hook_executor_with_session_context() {
+        let mut executor = HookExecutor::new();
+        let mut output = Vec::new();
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("hook_output.json");
+        let test_file_str = test_file.to_string_lossy();
+
+        #[cfg(unix)]
+        let command = format!("cat > {}", test_file_str);
+        #[cfg(windows)]
+        let command = format!(
+            "powershell -Command \"$input | Out-File -FilePath '{}'\"",
+            test_file_str
+        );
+
+        let hook = Hook {
+            command,
+            timeout_ms: 5000,
+            cache_ttl_seconds: 0,
+            max_output_size: 1000,
+            matcher: None,
+            source: crate::cli::agent::hook::Source::Session,
+        };
+
+        let hooks = HashMap::from([(HookTrigger::Stop, vec![hook])]);
+
+        let session_context = SessionContext {
+            conversation_id: "conv_id".to_string(),
+            transcript: vec!["> hello".to_string(), "Hi there!".to_string()],
+            assistant_response: Some("Hi there!".to_string()),
+        };
+
+        let results = executor
+            .run_hooks(hooks, &mut output, ".", None, None, Some(session_context))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let content = std::fs::read_to_string(&test_file).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json["hook_event_name"], "stop");
+        assert_eq!(json["conversation_id"], "conv_id");
+        assert_eq!(json["transcript"][0], "> hello");
+        assert_eq!(json["assistant_response"], "Hi there!");
+        assert_eq!(json["tool_name"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_session_hooks_are_not_cached() {
+        let mut executor = HookExecutor::new();
+        let mut output = Vec::new();
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("runs.txt");
+        let test_file_str = test_file.to_string_lossy();
+
+        let command = format!("echo run >> {}", test_file_str);
+
+        let hook = Hook {
+            command,
+            timeout_ms: 5000,
+            cache_ttl_seconds: 300,
+            max_output_size: 1000,
+            matcher: None,
+            source: crate::cli::agent::hook::Source::Session,
+        };
+
+        for _ in 0..2 {
+            let hooks = HashMap::from([(HookTrigger::Stop, vec![hook.clone()])]);
+            executor
+                .run_hooks(hooks, &mut output, ".", None, None, None)
+                .await
+                .unwrap();
+        }
+
+        let content = std::fs::read_to_string(&test_file).unwrap();
+        assert_eq!(content.lines().count(), 2);
+    }
+
     #[tokio::test]
     async fn test_hook_filtering_no_match() {
         let mut executor = HookExecutor::new();
@@ -644,6 +805,7 @@ mod tests {
                 ".",  // cwd - using current directory for now
                 None, // prompt - no user prompt for this test
                 Some(tool_context),
+                None, // session_context
             )
             .await;
 
@@ -695,6 +857,7 @@ mod tests {
                 ".",  // cwd
                 None, // prompt
                 Some(tool_context),
+                None, // session_context
             )
             .await
             .unwrap();
@@ -707,4 +870,40 @@ mod tests {
         assert_eq!(*exit_code, 2);
         assert!(hook_output.contains("Tool execution blocked by security policy"));
     }
+
+    #[test]
+    fn test_pre_tool_use_output_parse() {
+        // Plain text output is not a decision
+        assert_eq!(PreToolUseOutput::parse("looks good"), None);
+        assert_eq!(PreToolUseOutput::parse(""), None);
+
+        // Malformed JSON is ignored
+        assert_eq!(PreToolUseOutput::parse("{\"decision\": \"maybe\"}"), None);
+
+        assert_eq!(
+            PreToolUseOutput::parse("  {\"decision\": \"deny\", \"reason\": \"no network access\"}\n"),
+            Some(PreToolUseOutput {
+                decision: Some(PermissionDecision::Deny),
+                reason: Some("no network access".to_string()),
+                updated_input: None,
+            })
+        );
+
+        assert_eq!(
+            PreToolUseOutput::parse(r#"{"decision": "allow", "updated_input": {"command": "make --dry-run"}}"#),
+            Some(PreToolUseOutput {
+                decision: Some(PermissionDecision::Allow),
+                reason: None,
+                updated_input: Some(serde_json::json!({ "command": "make --dry-run" })),
+            })
+        );
+
+        assert_eq!(
+            PreToolUseOutput::parse(r#"{"decision": "ask"}"#),
+            Some(PreToolUseOutput {
+                decision: Some(PermissionDecision::Ask),
+                ..Default::default()
+            })
+        );
+    }
 }
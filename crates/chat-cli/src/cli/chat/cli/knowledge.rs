@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::path::Path;
 
 use clap::Subcommand;
 use crossterm::queue;
@@ -8,6 +9,7 @@ use crossterm::style::{
 };
 use eyre::Result;
 use semantic_search_client::{
+    OperationState,
     OperationStatus,
     SystemStatus,
 };
@@ -36,15 +38,35 @@ pub enum KnowledgeSubcommand {
         /// Exclude patterns (e.g., `node_modules/**`, `target/**`)
         #[arg(long, action = clap::ArgAction::Append)]
         exclude: Vec<String>,
-        /// Index type to use (Fast, Best)
+        /// Index type to use (Fast, Best, Hybrid)
         #[arg(long)]
         index_type: Option<String>,
+        /// Keep the entry up to date as files under the path change
+        #[arg(long)]
+        watch: bool,
+        /// Rerank search results of the entry with a cross-encoder model
+        #[arg(long)]
+        rerank: bool,
     },
     /// Remove specified knowledge base entry by path
     #[command(alias = "rm")]
     Remove { path: String },
     /// Update a file or directory in knowledge base
     Update { path: String },
+    /// Export a knowledge base entry to an archive file that can be shared
+    Export {
+        /// Path or name of the entry
+        name: String,
+        /// Archive file to create
+        file: String,
+    },
+    /// Import a knowledge base entry from an archive file
+    Import {
+        file: String,
+        /// Local path of the indexed files, if it differs from the path they were indexed at
+        #[arg(long)]
+        source_path: Option<String>,
+    },
     /// Remove all knowledge base entries
     Clear,
     /// Show background operation status
@@ -120,9 +142,15 @@ impl KnowledgeSubcommand {
                 include,
                 exclude,
                 index_type,
-            } => Self::handle_add(os, session, path, include, exclude, index_type).await,
+                watch,
+                rerank,
+            } => Self::handle_add(os, session, path, include, exclude, index_type, *watch, *rerank).await,
             KnowledgeSubcommand::Remove { path } => Self::handle_remove(os, session, path).await,
             KnowledgeSubcommand::Update { path } => Self::handle_update(os, session, path).await,
+            KnowledgeSubcommand::Export { name, file } => Self::handle_export(os, session, name, file).await,
+            KnowledgeSubcommand::Import { file, source_path } => {
+                Self::handle_import(os, session, file, source_path.as_deref()).await
+            },
             KnowledgeSubcommand::Clear => Self::handle_clear(os, session).await,
             KnowledgeSubcommand::Status => Self::handle_status(os, session).await,
             KnowledgeSubcommand::Cancel { operation_id } => {
@@ -217,9 +245,29 @@ impl KnowledgeSubcommand {
                 style::Print(" • "),
                 style::SetForegroundColor(Color::DarkGrey),
                 style::Print(format!("{}", ctx.updated_at.format("%m/%d %H:%M"))),
-                style::SetForegroundColor(Color::Reset),
-                style::Print("\n\n")
+                style::SetForegroundColor(Color::Reset)
             )?;
+            if ctx.watched {
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(" • "),
+                    style::SetForegroundColor(Color::Cyan),
+                    style::Print("watching"),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            }
+            if ctx.rerank {
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(" • "),
+                    style::SetForegroundColor(Color::Magenta),
+                    style::Print("reranked"),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            }
+            queue!(session.stderr, style::Print("\n\n"))?;
         }
         Ok(())
     }
@@ -234,6 +282,7 @@ impl KnowledgeSubcommand {
             .unwrap_or_default()
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn handle_add(
         os: &Os,
         session: &mut ChatSession,
@@ -241,6 +290,8 @@ impl KnowledgeSubcommand {
         include_patterns: &[String],
         exclude_patterns: &[String],
         index_type: &Option<String>,
+        watch: bool,
+        rerank: bool,
     ) -> OperationResult {
         match Self::validate_and_sanitize_path(os, path) {
             Ok(sanitized_path) => {
@@ -274,7 +325,9 @@ impl KnowledgeSubcommand {
                 let options = crate::util::knowledge_store::AddOptions::new()
                     .with_include_patterns(include)
                     .with_exclude_patterns(exclude)
-                    .with_embedding_type(embedding_type_resolved);
+                    .with_embedding_type(embedding_type_resolved)
+                    .with_watch(watch)
+                    .with_rerank(rerank);
 
                 match store.add(path, &sanitized_path.clone(), options).await {
                     Ok(message) => OperationResult::Info(message),
@@ -342,6 +395,59 @@ impl KnowledgeSubcommand {
         }
     }
 
+    /// Handle export operation
+    async fn handle_export(os: &Os, session: &ChatSession, name: &str, file: &str) -> OperationResult {
+        let agent = Self::get_agent(session);
+        let async_knowledge_store = match KnowledgeStore::get_async_instance(os, agent).await {
+            Ok(store) => store,
+            Err(e) => return OperationResult::Error(format!("Error accessing knowledge base directory: {}", e)),
+        };
+        let store = async_knowledge_store.lock().await;
+
+        // Entries are found by path first, then by name
+        let sanitized_name = sanitize_path_tool_arg(os, name);
+        let path_or_name = if sanitized_name.exists() {
+            sanitized_name.to_string_lossy().to_string()
+        } else {
+            name.to_string()
+        };
+
+        match store.export(&path_or_name, &sanitize_path_tool_arg(os, file)).await {
+            Ok(message) => OperationResult::Success(message),
+            Err(e) => OperationResult::Error(e),
+        }
+    }
+
+    /// Handle import operation
+    async fn handle_import(os: &Os, session: &ChatSession, file: &str, source_path: Option<&str>) -> OperationResult {
+        let archive_path = match Self::validate_and_sanitize_path(os, file) {
+            Ok(path) => path,
+            Err(e) => return OperationResult::Error(e),
+        };
+        let source_path = match source_path
+            .map(|path| Self::validate_and_sanitize_path(os, path))
+            .transpose()
+        {
+            Ok(path) => path,
+            Err(e) => return OperationResult::Error(e),
+        };
+
+        let agent = Self::get_agent(session);
+        let async_knowledge_store = match KnowledgeStore::get_async_instance(os, agent).await {
+            Ok(store) => store,
+            Err(e) => return OperationResult::Error(format!("Error accessing knowledge base directory: {}", e)),
+        };
+        let mut store = async_knowledge_store.lock().await;
+
+        match store
+            .import(Path::new(&archive_path), source_path.as_deref().map(Path::new))
+            .await
+        {
+            Ok(message) => OperationResult::Success(message),
+            Err(e) => OperationResult::Error(e),
+        }
+    }
+
     /// Handle clear operation
     async fn handle_clear(os: &Os, session: &mut ChatSession) -> OperationResult {
         // Require confirmation
@@ -415,7 +521,7 @@ impl KnowledgeSubcommand {
     }
 
     /// Format status data for display (UI rendering responsibility)
-    fn format_status_display(status: &SystemStatus) -> String {
+    pub(crate) fn format_status_display(status: &SystemStatus) -> String {
         let mut status_lines = Vec::new();
 
         // Show knowledge base summary
@@ -447,9 +553,11 @@ impl KnowledgeSubcommand {
     fn format_operation_display(op: &OperationStatus) -> String {
         let elapsed = op.started_at.elapsed().unwrap_or_default();
 
-        let (status_icon, status_info) = if op.is_cancelled {
+        let (status_icon, status_info) = if op.state == OperationState::Completed {
+            ("✅", "Completed".to_string())
+        } else if op.state == OperationState::Cancelled {
             ("🛑", "Cancelled".to_string())
-        } else if op.is_failed {
+        } else if op.state == OperationState::Failed {
             ("❌", op.message.clone())
         } else if op.is_waiting {
             ("⏳", op.message.clone())
@@ -462,7 +570,7 @@ impl KnowledgeSubcommand {
         let operation_desc = op.operation_type.display_name();
 
         // Format with conditional elapsed time and ETA
-        if op.is_cancelled || op.is_failed {
+        if op.state.is_finished() {
             format!(
                 "  {} {} | {}\n    {}",
                 status_icon, op.short_id, operation_desc, status_info
@@ -582,6 +690,8 @@ impl KnowledgeSubcommand {
             KnowledgeSubcommand::Add { .. } => "add",
             KnowledgeSubcommand::Remove { .. } => "remove",
             KnowledgeSubcommand::Update { .. } => "update",
+            KnowledgeSubcommand::Export { .. } => "export",
+            KnowledgeSubcommand::Import { .. } => "import",
             KnowledgeSubcommand::Clear => "clear",
             KnowledgeSubcommand::Status => "status",
             KnowledgeSubcommand::Cancel { .. } => "cancel",
@@ -715,4 +825,43 @@ mod tests {
             panic!("Expected Add subcommand");
         }
     }
+
+    #[test]
+    fn test_watch_flag() {
+        let cli = TestCli::try_parse_from(["test", "add", "/some/path", "--watch"]).unwrap();
+        assert!(matches!(cli.knowledge, KnowledgeSubcommand::Add { watch: true, .. }));
+
+        let cli = TestCli::try_parse_from(["test", "add", "/some/path"]).unwrap();
+        assert!(matches!(cli.knowledge, KnowledgeSubcommand::Add { watch: false, .. }));
+    }
+
+    #[test]
+    fn test_rerank_flag() {
+        let cli = TestCli::try_parse_from(["test", "add", "/some/path", "--rerank"]).unwrap();
+        assert!(matches!(cli.knowledge, KnowledgeSubcommand::Add { rerank: true, .. }));
+
+        let cli = TestCli::try_parse_from(["test", "add", "/some/path"]).unwrap();
+        assert!(matches!(cli.knowledge, KnowledgeSubcommand::Add { rerank: false, .. }));
+    }
+
+    #[test]
+    fn test_export_and_import_commands() {
+        let cli = TestCli::try_parse_from(["test", "export", "project-docs", "docs.zip"]).unwrap();
+        assert_eq!(cli.knowledge, KnowledgeSubcommand::Export {
+            name: "project-docs".to_string(),
+            file: "docs.zip".to_string(),
+        });
+
+        let cli = TestCli::try_parse_from(["test", "import", "docs.zip", "--source-path", "~/repo/docs"]).unwrap();
+        assert_eq!(cli.knowledge, KnowledgeSubcommand::Import {
+            file: "docs.zip".to_string(),
+            source_path: Some("~/repo/docs".to_string()),
+        });
+
+        let cli = TestCli::try_parse_from(["test", "import", "docs.zip"]).unwrap();
+        assert!(matches!(cli.knowledge, KnowledgeSubcommand::Import {
+            source_path: None,
+            ..
+        }));
+    }
 }
@@ -1,26 +1,113 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::io::Write;
+use std::sync::LazyLock;
+use std::time::Duration;
 
-use clap::Args;
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::queue;
 use crossterm::style::{
     self,
     Color,
 };
+use percent_encoding::{
+    AsciiSet,
+    NON_ALPHANUMERIC,
+    utf8_percent_encode,
+};
+use regex::Regex;
+use rmcp::model::ResourceContents;
+use thiserror::Error;
 
+use crate::cli::chat::consts::MAX_USER_MESSAGE_SIZE;
+use crate::cli::chat::message::UserMessageContent;
 use crate::cli::chat::tool_manager::LoadingRecord;
+use crate::cli::chat::util::truncate_safe_in_place;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::mcp_client::McpClientError;
+
+/// Characters that are left as they are when a value is substituted into a uri template. These
+/// are the unreserved characters of RFC 3986.
+const URI_TEMPLATE_UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Characters that are left as they are by reserved and fragment expansions. These are the
+/// unreserved and reserved characters of RFC 3986.
+const URI_TEMPLATE_RESERVED: &AsciiSet = &URI_TEMPLATE_UNRESERVED
+    .remove(b':')
+    .remove(b'/')
+    .remove(b'?')
+    .remove(b'#')
+    .remove(b'[')
+    .remove(b']')
+    .remove(b'@')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b'+')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=');
+
+/// Matches the expressions of a uri template, e.g. `{path}` or `{+path}`
+static URI_TEMPLATE_EXPRESSION_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([^}]*)\}").unwrap());
+
+#[derive(Debug, Error)]
+pub enum ReadResourceError {
+    #[error("No server named {0} is loaded")]
+    ServerNotFound(String),
+    #[error("{0}")]
+    InvalidTemplate(String),
+    #[error("Timed out after {} seconds", .0.as_secs())]
+    Timeout(Duration),
+    #[error(transparent)]
+    McpClient(#[from] McpClientError),
+    #[error(transparent)]
+    Service(#[from] rmcp::ServiceError),
+}
 
 /// Arguments for the MCP (Model Context Protocol) command.
 ///
 /// This struct handles MCP-related functionality, allowing users to view
-/// the status of MCP servers and their loading progress.
-#[deny(missing_docs)]
+/// the status of MCP servers and their loading progress, and to browse and
+/// read the resources they offer.
 #[derive(Debug, PartialEq, Args)]
-pub struct McpArgs;
+pub struct McpArgs {
+    #[command(subcommand)]
+    subcommand: Option<McpSubcommand>,
+}
+
+/// Resources can also be attached to a message by mentioning them as @server:uri.
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum McpSubcommand {
+    /// List the resources and resource templates offered by the servers
+    Resources {
+        /// Only list the resources of this server
+        server: Option<String>,
+    },
+    /// Print the contents of a resource
+    Read {
+        /// Server offering the resource
+        server: String,
+        /// Uri or name of a resource or resource template
+        uri: String,
+        /// Values for the variables of a resource template, as key=value
+        args: Vec<String>,
+    },
+}
 
 impl McpArgs {
     pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
@@ -39,52 +126,426 @@ impl McpArgs {
             });
         }
 
-        let terminal_width = session.terminal_width();
-        let still_loading = session
-            .conversation
-            .tool_manager
-            .pending_clients()
-            .await
-            .into_iter()
-            .map(|name| format!(" - {name}\n"))
+        match self.subcommand {
+            None => print_load_records(session).await?,
+            Some(McpSubcommand::Resources { server }) => print_resources(session, server.as_deref()).await?,
+            Some(McpSubcommand::Read { server, uri, args }) => read_resource(session, &server, &uri, &args).await?,
+        }
+
+        session.stderr.flush()?;
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        match self.subcommand.as_ref()? {
+            McpSubcommand::Resources { .. } => Some("resources"),
+            McpSubcommand::Read { .. } => Some("read"),
+        }
+    }
+}
+
+async fn print_load_records(session: &mut ChatSession) -> Result<(), ChatError> {
+    let terminal_width = session.terminal_width();
+    let still_loading = session
+        .conversation
+        .tool_manager
+        .pending_clients()
+        .await
+        .into_iter()
+        .map(|name| format!(" - {name}\n"))
+        .collect::<Vec<_>>()
+        .join("");
+
+    for (server_name, msg) in session.conversation.tool_manager.mcp_load_record.lock().await.iter() {
+        let msg = msg
+            .iter()
+            .map(|record| match record {
+                LoadingRecord::Err(timestamp, content)
+                | LoadingRecord::Warn(timestamp, content)
+                | LoadingRecord::Success(timestamp, content) => format!("[{timestamp}]: {content}"),
+            })
             .collect::<Vec<_>>()
-            .join("");
-
-        for (server_name, msg) in session.conversation.tool_manager.mcp_load_record.lock().await.iter() {
-            let msg = msg
-                .iter()
-                .map(|record| match record {
-                    LoadingRecord::Err(timestamp, content)
-                    | LoadingRecord::Warn(timestamp, content)
-                    | LoadingRecord::Success(timestamp, content) => format!("[{timestamp}]: {content}"),
-                })
-                .collect::<Vec<_>>()
-                .join("\n--- tools refreshed ---\n");
+            .join("\n--- tools refreshed ---\n");
 
-            queue!(
-                session.stderr,
-                style::Print(server_name),
-                style::Print("\n"),
-                style::Print(format!("{}\n", "▔".repeat(terminal_width))),
-                style::Print(msg),
-                style::Print("\n")
-            )?;
+        queue!(
+            session.stderr,
+            style::Print(server_name),
+            style::Print("\n"),
+            style::Print(format!("{}\n", "▔".repeat(terminal_width))),
+            style::Print(msg),
+            style::Print("\n")
+        )?;
+    }
+
+    if !still_loading.is_empty() {
+        queue!(
+            session.stderr,
+            style::Print("Still loading:\n"),
+            style::Print(format!("{}\n", "▔".repeat(terminal_width))),
+            style::Print(still_loading),
+            style::Print("\n")
+        )?;
+    }
+
+    Ok(())
+}
+
+async fn print_resources(session: &mut ChatSession, server: Option<&str>) -> Result<(), ChatError> {
+    let terminal_width = session.terminal_width();
+    let mut servers = session
+        .conversation
+        .tool_manager
+        .mcp_resources
+        .lock()
+        .await
+        .clone()
+        .into_iter()
+        .filter(|(server_name, _)| server.is_none_or(|s| s == server_name))
+        .collect::<Vec<_>>();
+    servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    if servers
+        .iter()
+        .all(|(_, r)| r.resources.is_empty() && r.templates.is_empty())
+    {
+        let msg = match server {
+            Some(server) => format!("\nServer {server} does not offer any resources.\n\n"),
+            None => "\nNo server offers any resources.\n\n".to_string(),
+        };
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(msg),
+            style::SetForegroundColor(Color::Reset)
+        )?;
+        return Ok(());
+    }
+
+    for (server_name, server_resources) in servers {
+        queue!(
+            session.stderr,
+            style::Print("\n"),
+            style::SetAttribute(style::Attribute::Bold),
+            style::Print(&server_name),
+            style::SetAttribute(style::Attribute::Reset),
+            style::Print(format!("\n{}\n", "▔".repeat(terminal_width))),
+        )?;
+
+        let resources = server_resources
+            .resources
+            .iter()
+            .map(|r| (&r.raw.name, &r.raw.uri, &r.raw.description));
+        let templates = server_resources
+            .templates
+            .iter()
+            .map(|t| (&t.raw.name, &t.raw.uri_template, &t.raw.description));
+        for (heading, entries) in [
+            ("Resources", resources.collect::<Vec<_>>()),
+            ("Templates", templates.collect::<Vec<_>>()),
+        ] {
+            if entries.is_empty() {
+                continue;
+            }
+            queue!(session.stderr, style::Print(format!("{heading}:\n")))?;
+            for (name, uri, description) in entries {
+                queue!(
+                    session.stderr,
+                    style::Print("- "),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(name),
+                    style::SetForegroundColor(Color::Reset),
+                    style::Print(" "),
+                    style::SetForegroundColor(Color::Cyan),
+                    style::Print(uri),
+                    style::SetForegroundColor(Color::Reset),
+                    style::Print("\n"),
+                )?;
+                if let Some(description) = description {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!("  {description}\n")),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+            }
         }
+    }
 
-        if !still_loading.is_empty() {
+    queue!(
+        session.stderr,
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print("\nRead a resource with /mcp read <server> <uri>, or attach it to a message with @server:uri\n\n"),
+        style::SetForegroundColor(Color::Reset),
+    )?;
+
+    Ok(())
+}
+
+async fn read_resource(session: &mut ChatSession, server: &str, uri: &str, args: &[String]) -> Result<(), ChatError> {
+    let uri = {
+        let resources = session.conversation.tool_manager.mcp_resources.lock().await;
+        let server_resources = resources.get(server);
+        // Names are accepted as well since they are shorter to type than most uris
+        server_resources
+            .and_then(|r| {
+                r.resources
+                    .iter()
+                    .find(|r| r.raw.name == uri)
+                    .map(|r| r.raw.uri.clone())
+            })
+            .or_else(|| {
+                server_resources.and_then(|r| {
+                    r.templates
+                        .iter()
+                        .find(|t| t.raw.name == uri)
+                        .map(|t| t.raw.uri_template.clone())
+                })
+            })
+            .unwrap_or_else(|| uri.to_string())
+    };
+
+    let result = match parse_template_args(args).and_then(|args| expand_uri_template(&uri, &args)) {
+        Ok(uri) => session.conversation.tool_manager.read_resource(server, &uri).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(result) => {
+            for contents in &result.contents {
+                queue!(
+                    session.stderr,
+                    style::Print(format!("\n{}\n", format_resource_contents(contents)))
+                )?;
+            }
+            queue!(session.stderr, style::Print("\n"))?;
+        },
+        Err(e) => {
             queue!(
                 session.stderr,
-                style::Print("Still loading:\n"),
-                style::Print(format!("{}\n", "▔".repeat(terminal_width))),
-                style::Print(still_loading),
-                style::Print("\n")
+                style::SetForegroundColor(Color::Red),
+                style::Print(format!("\nFailed to read resource {uri}: {e}\n\n")),
+                style::SetForegroundColor(Color::Reset)
             )?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Appends the contents of the resources mentioned in `input` as `@server:uri` to it. Mentions
+/// of servers that are not loaded are left alone, and resources that cannot be read are reported
+/// without holding up the message. Contents that would make the message exceed
+/// [MAX_USER_MESSAGE_SIZE] are truncated.
+pub async fn attach_mentioned_resources(session: &mut ChatSession, mut input: String) -> Result<String, ChatError> {
+    let mut seen = HashSet::new();
+    let mentions = input
+        .split_whitespace()
+        .filter_map(parse_resource_mention)
+        .filter(|(server, _)| session.conversation.tool_manager.clients.contains_key(*server))
+        .map(|(server, uri)| (server.to_string(), uri.to_string()))
+        .filter(|mention| seen.insert(mention.clone()))
+        .collect::<Vec<_>>();
+
+    for (server, uri) in mentions {
+        match session.conversation.tool_manager.read_resource(&server, &uri).await {
+            Ok(result) => {
+                let contents = result
+                    .contents
+                    .iter()
+                    .map(format_resource_contents)
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                if append_attachment(&mut input, &contents, MAX_USER_MESSAGE_SIZE) {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print(format!(
+                            "Truncated @{server}:{uri} to fit within the message size limit\n"
+                        )),
+                        style::SetForegroundColor(Color::Reset)
+                    )?;
+                } else {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!("Attached @{server}:{uri}\n")),
+                        style::SetForegroundColor(Color::Reset)
+                    )?;
+                }
+            },
+            Err(e) => {
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!("Failed to attach @{server}:{uri}: {e}\n")),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
         }
+    }
 
-        session.stderr.flush()?;
+    Ok(input)
+}
 
-        Ok(ChatState::PromptUser {
-            skip_printing_tools: true,
+/// Appends the contents of an attached resource to `input`, truncating them so that `input` stays
+/// within `max_bytes`. Returns whether the contents were truncated.
+fn append_attachment(input: &mut String, contents: &str, max_bytes: usize) -> bool {
+    let mut attachment = format!("\n\n{contents}");
+    let available = max_bytes.saturating_sub(input.len());
+    if attachment.len() <= available {
+        input.push_str(&attachment);
+        return false;
+    }
+
+    truncate_safe_in_place(&mut attachment, available, UserMessageContent::TRUNCATED_SUFFIX);
+    // Nothing is appended when there is not even room for the suffix
+    if attachment.len() <= available {
+        input.push_str(&attachment);
+    }
+    true
+}
+
+/// Splits a `@server:uri` mention of a resource into the server name and the uri. Punctuation
+/// following a mention in a sentence is not considered part of the uri.
+pub fn parse_resource_mention(word: &str) -> Option<(&str, &str)> {
+    let (server, uri) = word.strip_prefix('@')?.split_once(':')?;
+    let uri = uri.trim_end_matches(['.', ',', ';', '!', '?', ')']);
+    // A slash in the server part means this is a prompt, e.g. @server/prompt
+    (!server.is_empty() && !server.contains('/') && !uri.is_empty()).then_some((server, uri))
+}
+
+/// Renders the contents of a resource as text for the model. The contents of binary resources
+/// are described rather than included.
+pub fn format_resource_contents(contents: &ResourceContents) -> String {
+    match contents {
+        ResourceContents::TextResourceContents { uri, text, .. } => format!("[{uri}]\n{text}"),
+        ResourceContents::BlobResourceContents {
+            uri, mime_type, blob, ..
+        } => {
+            let mime_type = mime_type.as_deref().unwrap_or("unknown");
+            format!(
+                "[{uri}]\nBinary resource of type {mime_type} ({} bytes base64 encoded) omitted",
+                blob.len()
+            )
+        },
+    }
+}
+
+fn parse_template_args(args: &[String]) -> Result<HashMap<String, String>, ReadResourceError> {
+    args.iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((key, value)) => Ok((key.to_string(), value.to_string())),
+            None => Err(ReadResourceError::InvalidTemplate(format!(
+                "Template argument {arg} is not of the form key=value"
+            ))),
         })
+        .collect()
+}
+
+/// Expands a uri template with the given variables. Simple (`{var}`), reserved (`{+var}`) and
+/// fragment (`{#var}`) expressions are supported. A uri without expressions is returned as is.
+fn expand_uri_template(template: &str, args: &HashMap<String, String>) -> Result<String, ReadResourceError> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut last = 0;
+    for captures in URI_TEMPLATE_EXPRESSION_REGEX.captures_iter(template) {
+        let (Some(whole), Some(expression)) = (captures.get(0), captures.get(1)) else {
+            continue;
+        };
+        expanded.push_str(&template[last..whole.start()]);
+        last = whole.end();
+
+        let expression = expression.as_str();
+        let (prefix, names, allowed) = match expression.chars().next() {
+            Some('+') => ("", &expression[1..], URI_TEMPLATE_RESERVED),
+            Some('#') => ("#", &expression[1..], URI_TEMPLATE_RESERVED),
+            Some(c) if c.is_alphanumeric() || c == '_' => ("", expression, URI_TEMPLATE_UNRESERVED),
+            _ => {
+                return Err(ReadResourceError::InvalidTemplate(format!(
+                    "Unsupported template expression {{{expression}}}"
+                )));
+            },
+        };
+        let values = names
+            .split(',')
+            .map(|name| {
+                let value = args
+                    .get(name)
+                    .ok_or_else(|| ReadResourceError::InvalidTemplate(format!("Missing template argument {name}")))?;
+                Ok(utf8_percent_encode(value, allowed).to_string())
+            })
+            .collect::<Result<Vec<_>, ReadResourceError>>()?;
+        expanded.push_str(prefix);
+        expanded.push_str(&values.join(","));
+    }
+    expanded.push_str(&template[last..]);
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resource_mention() {
+        assert_eq!(
+            parse_resource_mention("@docs:file:///guide.md"),
+            Some(("docs", "file:///guide.md"))
+        );
+        assert_eq!(
+            parse_resource_mention("@github:repo://owner/name/README.md,"),
+            Some(("github", "repo://owner/name/README.md"))
+        );
+        assert_eq!(parse_resource_mention("@prompt"), None);
+        assert_eq!(parse_resource_mention("@server/prompt:arg"), None);
+        assert_eq!(parse_resource_mention("@docs:"), None);
+        assert_eq!(parse_resource_mention("docs:file:///guide.md"), None);
+    }
+
+    #[test]
+    fn test_append_attachment() {
+        let mut input = "summarize @docs:guide".to_string();
+        assert!(!append_attachment(&mut input, "[guide]\nshort", 100));
+        assert_eq!(input, "summarize @docs:guide\n\n[guide]\nshort");
+
+        let mut input = "summarize @docs:guide".to_string();
+        assert!(append_attachment(&mut input, &"a".repeat(200), 100));
+        assert_eq!(input.len(), 100);
+        assert!(input.ends_with(UserMessageContent::TRUNCATED_SUFFIX));
+
+        let mut input = "a".repeat(95);
+        assert!(append_attachment(&mut input, "contents", 100));
+        assert_eq!(input, "a".repeat(95));
+    }
+
+    #[test]
+    fn test_expand_uri_template() {
+        let args = HashMap::from([
+            ("owner".to_string(), "aws".to_string()),
+            ("path".to_string(), "docs/read me.md".to_string()),
+        ]);
+
+        assert_eq!(
+            expand_uri_template("repo://{owner}/{path}", &args).unwrap(),
+            "repo://aws/docs%2Fread%20me.md"
+        );
+        assert_eq!(
+            expand_uri_template("repo://{owner}/{+path}", &args).unwrap(),
+            "repo://aws/docs/read%20me.md"
+        );
+        assert_eq!(expand_uri_template("file:///a.md", &args).unwrap(), "file:///a.md");
+        assert!(expand_uri_template("repo://{name}", &args).is_err());
+        assert!(expand_uri_template("repo://x{?query}", &args).is_err());
+    }
+
+    #[test]
+    fn test_parse_template_args() {
+        let args = parse_template_args(&["path=a=b".to_string()]).unwrap();
+        assert_eq!(args.get("path").map(String::as_str), Some("a=b"));
+        assert!(parse_template_args(&["path".to_string()]).is_err());
     }
 }
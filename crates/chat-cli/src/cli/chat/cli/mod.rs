@@ -1,3 +1,4 @@
+pub mod branch;
 pub mod changelog;
 pub mod checkpoint;
 pub mod clear;
@@ -13,12 +14,14 @@ pub mod persist;
 pub mod profile;
 pub mod prompts;
 pub mod reply;
+pub mod sessions;
 pub mod subscribe;
 pub mod tangent;
 pub mod todos;
 pub mod tools;
 pub mod usage;
 
+use branch::BranchArgs;
 use changelog::ChangelogArgs;
 use clap::Parser;
 use clear::ClearArgs;
@@ -34,6 +37,7 @@ use persist::PersistSubcommand;
 use profile::AgentSubcommand;
 use prompts::PromptsArgs;
 use reply::ReplyArgs;
+use sessions::SessionsArgs;
 use tangent::TangentArgs;
 use todos::TodoSubcommand;
 use tools::ToolsArgs;
@@ -92,7 +96,7 @@ pub enum SlashCommand {
     Hooks(HooksArgs),
     /// Show current session's context window usage
     Usage(UsageArgs),
-    /// See mcp server loaded
+    /// See mcp server loaded and browse their resources
     Mcp(McpArgs),
     /// Select a model for the current conversation session
     Model(ModelArgs),
@@ -106,10 +110,14 @@ pub enum SlashCommand {
     Tangent(TangentArgs),
     #[command(flatten)]
     Persist(PersistSubcommand),
+    /// Browse, resume, fork, rename and delete past sessions
+    Sessions(SessionsArgs),
+    /// Fork the conversation into named branches, switch between and compare them
+    Branch(BranchArgs),
     // #[command(flatten)]
     // Root(RootSubcommand),
     #[command(
-        about = "(Beta) Manage workspace checkpoints (init, list, restore, expand, diff, clean)\nExperimental features may be changed or removed at any time",
+        about = "(Beta) Manage workspace checkpoints (init, list, restore, expand, diff, export, clean)\nExperimental features may be changed or removed at any time",
         hide = true,
         subcommand
     )]
@@ -173,6 +181,8 @@ impl SlashCommand {
             Self::Subscribe(args) => args.execute(os, session).await,
             Self::Tangent(args) => args.execute(os, session).await,
             Self::Persist(subcommand) => subcommand.execute(os, session).await,
+            Self::Sessions(args) => args.execute(os, session).await,
+            Self::Branch(args) => args.execute(session).await,
             // Self::Root(subcommand) => {
             //     if let Err(err) = subcommand.execute(os, database, telemetry).await {
             //         return Err(ChatError::Custom(err.to_string().into()));
@@ -212,7 +222,10 @@ impl SlashCommand {
             Self::Persist(sub) => match sub {
                 PersistSubcommand::Save { .. } => "save",
                 PersistSubcommand::Load { .. } => "load",
+                PersistSubcommand::Export { .. } => "export",
             },
+            Self::Sessions(_) => "sessions",
+            Self::Branch(_) => "branch",
             Self::Checkpoint(_) => "checkpoint",
             Self::Todos(_) => "todos",
         }
@@ -225,6 +238,9 @@ impl SlashCommand {
             SlashCommand::Knowledge(sub) => Some(sub.name()),
             SlashCommand::Tools(arg) => arg.subcommand_name(),
             SlashCommand::Prompts(arg) => arg.subcommand_name(),
+            SlashCommand::Sessions(arg) => arg.subcommand_name(),
+            SlashCommand::Branch(arg) => arg.subcommand_name(),
+            SlashCommand::Mcp(arg) => arg.subcommand_name(),
             _ => None,
         }
     }
@@ -7,6 +7,10 @@ use crossterm::style::{
 };
 
 use crate::cli::ConversationState;
+use crate::cli::chat::export::{
+    self,
+    ExportFormat,
+};
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -31,6 +35,17 @@ pub enum PersistSubcommand {
         /// Path to the conversation file to load
         path: String,
     },
+    /// Export the current conversation as a readable Markdown or HTML document
+    Export {
+        /// Path where the document will be written
+        path: String,
+        /// Format of the document. Inferred from the file extension by default
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        #[arg(short, long)]
+        /// Force overwrite if file already exists
+        force: bool,
+    },
 }
 
 impl PersistSubcommand {
@@ -81,6 +96,32 @@ impl PersistSubcommand {
                     style::SetAttribute(Attribute::Reset)
                 )?;
             },
+            Self::Export { path, format, force } => {
+                if os.fs.exists(&path) && !force {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            "\nFile at {} already exists. To overwrite, use -f or --force\n\n",
+                            &path
+                        )),
+                        style::SetAttribute(Attribute::Reset)
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                }
+                let format = format.unwrap_or_else(|| ExportFormat::from_path(&path));
+                let contents = export::render(&session.conversation, format);
+                tri!(os.fs.write(&path, contents).await, "export to", &path);
+
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\n✔ Exported conversation transcript to {}\n\n", &path)),
+                    style::SetAttribute(Attribute::Reset)
+                )?;
+            },
             Self::Load { path } => {
                 // Try the original path first
                 let original_result = os.fs.read_to_string(&path).await;
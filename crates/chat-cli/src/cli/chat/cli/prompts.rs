@@ -33,6 +33,7 @@ use thiserror::Error;
 use unicode_width::UnicodeWidthStr;
 
 use crate::cli::chat::cli::editor::open_editor_file;
+use crate::cli::chat::cli::mcp::format_resource_contents;
 use crate::cli::chat::tool_manager::PromptBundle;
 use crate::cli::chat::{
     ChatError,
@@ -1996,18 +1997,7 @@ fn display_prompt_content(
         match content {
             PromptMessageContent::Text { text } => text.clone(),
             PromptMessageContent::Image { image } => image.raw.data.clone(),
-            PromptMessageContent::Resource { resource } => match &resource.raw.resource {
-                rmcp::model::ResourceContents::TextResourceContents {
-                    uri, mime_type, text, ..
-                } => {
-                    let mime_type = mime_type.as_deref().unwrap_or("unknown");
-                    format!("Text resource of uri: {uri}, mime_type: {mime_type}, text: {text}")
-                },
-                rmcp::model::ResourceContents::BlobResourceContents { uri, mime_type, .. } => {
-                    let mime_type = mime_type.as_deref().unwrap_or("unknown");
-                    format!("Blob resource of uri: {uri}, mime_type: {mime_type}")
-                },
-            },
+            PromptMessageContent::Resource { resource } => format_resource_contents(&resource.raw.resource),
             PromptMessageContent::ResourceLink { link } => {
                 format!("Resource link with uri: {}, name: {}", link.raw.uri, link.raw.name)
             },
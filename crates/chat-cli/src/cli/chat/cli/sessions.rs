@@ -0,0 +1,281 @@
+use std::io::Write;
+
+use chrono::Local;
+use clap::{
+    Args,
+    Subcommand,
+};
+use crossterm::style::{
+    self,
+    Color,
+    Stylize,
+};
+use crossterm::{
+    execute,
+    queue,
+};
+
+use crate::cli::ConversationState;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::database::SessionMetadata;
+use crate::os::Os;
+
+/// Number of characters of the session id shown when listing sessions.
+const SHORT_ID_LEN: usize = 8;
+
+#[derive(Debug, PartialEq, Args)]
+pub struct SessionsArgs {
+    #[command(subcommand)]
+    subcommand: Option<SessionsSubcommand>,
+}
+
+/// Sessions are identified by their id, or any unique prefix of it.
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum SessionsSubcommand {
+    /// List the sessions started in the current directory
+    List {
+        /// List sessions from all directories
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Switch to a previous session
+    Resume {
+        /// Id of the session to resume
+        id: String,
+    },
+    /// Copy a session into a new session and switch to it
+    Fork {
+        /// Id of the session to fork. Defaults to the current session
+        id: Option<String>,
+    },
+    /// Rename a session
+    Rename {
+        /// The new title
+        #[arg(required = true, num_args = 1..)]
+        title: Vec<String>,
+        /// Id of the session to rename. Defaults to the current session
+        #[arg(long)]
+        id: Option<String>,
+    },
+    /// Delete a session
+    Delete {
+        /// Id of the session to delete
+        id: String,
+    },
+}
+
+impl SessionsArgs {
+    pub async fn execute(self, os: &mut Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let current_id = session.conversation.conversation_id().to_string();
+        let cwd = std::env::current_dir()?;
+
+        match self.subcommand.unwrap_or(SessionsSubcommand::List { all: false }) {
+            SessionsSubcommand::List { all } => {
+                let sessions = os
+                    .database
+                    .list_sessions((!all).then_some(cwd.as_path()))
+                    .map_err(|e| ChatError::Custom(format!("Could not list sessions: {e}").into()))?;
+                print_sessions(&mut session.stderr, &sessions, Some(&current_id))?;
+            },
+            SessionsSubcommand::Resume { id } => {
+                let metadata = find_session(os, &id)?;
+                if metadata.id == current_id {
+                    execute!(session.stderr, style::Print("\nAlready in this session\n\n"))?;
+                } else {
+                    let conversation = load_session(os, &metadata.id)?;
+                    switch_conversation(session, conversation).await;
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(format!("\n✔ Resumed session {}\n\n", display_title(&metadata))),
+                        style::SetForegroundColor(Color::Reset)
+                    )?;
+                }
+            },
+            SessionsSubcommand::Fork { id } => {
+                let (mut conversation, title) = match id {
+                    Some(id) => {
+                        let metadata = find_session(os, &id)?;
+                        (load_session(os, &metadata.id)?, metadata.title)
+                    },
+                    None => {
+                        let title = os
+                            .database
+                            .get_sessions_by_id_prefix(&current_id)
+                            .ok()
+                            .and_then(|sessions| sessions.into_iter().next())
+                            .and_then(|metadata| metadata.title);
+                        (session.conversation.clone(), title)
+                    },
+                };
+
+                conversation.fork();
+                let mut metadata = conversation.session_metadata(os, &cwd.to_string_lossy());
+                if let Some(title) = title.or(metadata.title.take()) {
+                    metadata.title = Some(format!("{title} (fork)"));
+                }
+                os.database
+                    .set_session(&metadata, &conversation)
+                    .map_err(|e| ChatError::Custom(format!("Could not save the forked session: {e}").into()))?;
+
+                switch_conversation(session, conversation).await;
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\n✔ Forked into session {}\n\n", short_id(&metadata.id))),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
+            SessionsSubcommand::Rename { title, id } => {
+                let id = match id {
+                    Some(id) => find_session(os, &id)?.id,
+                    None => current_id,
+                };
+                let title = title.join(" ");
+                let renamed = os
+                    .database
+                    .rename_session(&id, &title)
+                    .map_err(|e| ChatError::Custom(format!("Could not rename the session: {e}").into()))?;
+                if !renamed {
+                    return Err(ChatError::Custom(
+                        "The current session has not been saved yet. Send a message first.".into(),
+                    ));
+                }
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\n✔ Renamed session {} to {}\n\n", short_id(&id), title)),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
+            SessionsSubcommand::Delete { id } => {
+                let metadata = find_session(os, &id)?;
+                if metadata.id == current_id {
+                    return Err(ChatError::Custom("Cannot delete the current session".into()));
+                }
+                os.database
+                    .delete_session(&metadata.id)
+                    .map_err(|e| ChatError::Custom(format!("Could not delete the session: {e}").into()))?;
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\n✔ Deleted session {}\n\n", display_title(&metadata))),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        match self.subcommand.as_ref()? {
+            SessionsSubcommand::List { .. } => Some("list"),
+            SessionsSubcommand::Resume { .. } => Some("resume"),
+            SessionsSubcommand::Fork { .. } => Some("fork"),
+            SessionsSubcommand::Rename { .. } => Some("rename"),
+            SessionsSubcommand::Delete { .. } => Some("delete"),
+        }
+    }
+}
+
+/// Finds the session whose id is, or starts with, `id`.
+pub fn find_session(os: &Os, id: &str) -> Result<SessionMetadata, ChatError> {
+    let mut sessions = os
+        .database
+        .get_sessions_by_id_prefix(id)
+        .map_err(|e| ChatError::Custom(format!("Could not look up sessions: {e}").into()))?;
+
+    if let Some(index) = sessions.iter().position(|session| session.id == id) {
+        return Ok(sessions.swap_remove(index));
+    }
+
+    match sessions.len() {
+        0 => Err(ChatError::Custom(format!("No session found with id {id}").into())),
+        1 => Ok(sessions.remove(0)),
+        n => Err(ChatError::Custom(
+            format!("{n} sessions have an id starting with {id}, use a longer id").into(),
+        )),
+    }
+}
+
+fn load_session(os: &Os, id: &str) -> Result<ConversationState, ChatError> {
+    match os.database.get_session(id) {
+        Ok(Some(conversation)) => Ok(conversation),
+        Ok(None) => Err(ChatError::Custom(format!("No session found with id {id}").into())),
+        Err(e) => Err(ChatError::Custom(format!("Could not load session {id}: {e}").into())),
+    }
+}
+
+/// Replaces the conversation of the chat session, keeping the tools, agents and context of the
+/// current one.
+async fn switch_conversation(session: &mut ChatSession, mut conversation: ConversationState) {
+    std::mem::swap(&mut conversation.tool_manager, &mut session.conversation.tool_manager);
+    std::mem::swap(&mut conversation.mcp_enabled, &mut session.conversation.mcp_enabled);
+    std::mem::swap(
+        &mut conversation.context_manager,
+        &mut session.conversation.context_manager,
+    );
+    std::mem::swap(&mut conversation.agents, &mut session.conversation.agents);
+    session.conversation = conversation;
+    session.conversation.update_state(true).await;
+    session.conversation.enforce_tool_use_history_invariants();
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..SHORT_ID_LEN).unwrap_or(id)
+}
+
+fn display_title(metadata: &SessionMetadata) -> String {
+    match &metadata.title {
+        Some(title) => format!("{} ({})", title, short_id(&metadata.id)),
+        None => short_id(&metadata.id).to_string(),
+    }
+}
+
+/// Writes a list of sessions to `output`, marking the session with id `current_id`.
+pub fn print_sessions(
+    output: &mut impl Write,
+    sessions: &[SessionMetadata],
+    current_id: Option<&str>,
+) -> Result<(), std::io::Error> {
+    if sessions.is_empty() {
+        return execute!(output, style::Print("\nNo sessions found\n\n"));
+    }
+
+    queue!(output, style::Print("\n"))?;
+    for session in sessions {
+        let marker = match current_id == Some(session.id.as_str()) {
+            true => "*".green().bold(),
+            false => " ".stylize(),
+        };
+        let mut details = vec![format!(
+            "updated {}",
+            session.updated_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        )];
+        details.extend(session.agent.as_ref().map(|agent| format!("agent {agent}")));
+        details.extend(session.model_id.as_ref().map(|model| format!("model {model}")));
+        details.push(format!(
+            "~{} in / ~{} out tokens",
+            session.input_tokens, session.output_tokens
+        ));
+
+        queue!(
+            output,
+            style::Print(format!("{} ", marker)),
+            style::Print(short_id(&session.id).cyan()),
+            style::Print(format!("  {}\n", session.title.as_deref().unwrap_or("(untitled)"))),
+            style::Print(format!("  {:SHORT_ID_LEN$}  {}\n", "", details.join(" · ")).dark_grey()),
+        )?;
+    }
+    queue!(output, style::Print("\n"))?;
+
+    output.flush()
+}
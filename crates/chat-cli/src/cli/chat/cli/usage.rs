@@ -10,10 +10,8 @@ use crossterm::{
 };
 
 use super::model::context_window_tokens;
-use crate::cli::chat::token_counter::{
-    CharCount,
-    TokenCount,
-};
+use crate::api_client::model::TokenUsage;
+use crate::cli::chat::token_counter::TokenCount;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -31,6 +29,11 @@ pub struct DetailedUsageData {
     pub tools_tokens: TokenCount,
     pub context_window_size: usize,
     pub dropped_context_files: Vec<(String, String)>,
+    /// Whether the token counts are estimated from the number of characters rather than counted
+    /// with the tokenizer of the model.
+    pub is_estimate: bool,
+    /// Token usage reported by the service for the latest request.
+    pub reported_usage: Option<TokenUsage>,
 }
 
 /// Calculate usage percentage from token counts
@@ -41,31 +44,33 @@ pub fn calculate_usage_percentage(tokens: TokenCount, context_window_size: usize
 /// Get detailed usage data for context window analysis
 pub async fn get_detailed_usage_data(session: &mut ChatSession, os: &Os) -> Result<DetailedUsageData, ChatError> {
     let context_window_size = context_window_tokens(session.conversation.model_info.as_ref());
+    let tokenizer = session.conversation.tokenizer(os);
+    let reported_usage = session.conversation.latest_token_usage();
 
     let state = session
         .conversation
         .backend_conversation_state(os, true, &mut std::io::stderr())
         .await?;
 
-    let data = state.calculate_conversation_size();
+    let data = state.calculate_token_counts(&tokenizer);
     let tool_specs_json: String = state
         .tools
         .values()
         .filter_map(|s| serde_json::to_string(s).ok())
         .collect::<Vec<String>>()
         .join("");
-    let tools_char_count: CharCount = tool_specs_json.len().into();
-    let total_tokens: TokenCount =
-        (data.context_messages + data.user_messages + data.assistant_messages + tools_char_count).into();
+    let tools_tokens = tokenizer.count_text(&tool_specs_json);
 
     Ok(DetailedUsageData {
-        total_tokens,
-        context_tokens: data.context_messages.into(),
-        assistant_tokens: data.assistant_messages.into(),
-        user_tokens: data.user_messages.into(),
-        tools_tokens: tools_char_count.into(),
+        total_tokens: data.total() + tools_tokens,
+        context_tokens: data.context_messages,
+        assistant_tokens: data.assistant_messages,
+        user_tokens: data.user_messages,
+        tools_tokens,
         context_window_size,
         dropped_context_files: state.dropped_context_files,
+        is_estimate: tokenizer.is_estimate(),
+        reported_usage,
     })
 }
 
@@ -194,13 +199,14 @@ impl UsageArgs {
 
         execute!(session.stderr, style::Print("\n\n"))?;
 
+        let approx = if usage_data.is_estimate { "~" } else { "" };
         queue!(
             session.stderr,
             style::SetForegroundColor(Color::DarkCyan),
             style::Print("█ Context files: "),
             style::SetForegroundColor(Color::Reset),
             style::Print(format!(
-                "~{} tokens ({:.2}%)\n",
+                "{approx}{} tokens ({:.2}%)\n",
                 usage_data.context_tokens,
                 calculate_usage_percentage(usage_data.context_tokens, usage_data.context_window_size)
             )),
@@ -208,7 +214,7 @@ impl UsageArgs {
             style::Print("█ Tools:    "),
             style::SetForegroundColor(Color::Reset),
             style::Print(format!(
-                " ~{} tokens ({:.2}%)\n",
+                " {approx}{} tokens ({:.2}%)\n",
                 usage_data.tools_tokens,
                 calculate_usage_percentage(usage_data.tools_tokens, usage_data.context_window_size)
             )),
@@ -216,7 +222,7 @@ impl UsageArgs {
             style::Print("█ Q responses: "),
             style::SetForegroundColor(Color::Reset),
             style::Print(format!(
-                "  ~{} tokens ({:.2}%)\n",
+                "  {approx}{} tokens ({:.2}%)\n",
                 usage_data.assistant_tokens,
                 calculate_usage_percentage(usage_data.assistant_tokens, usage_data.context_window_size)
             )),
@@ -224,12 +230,38 @@ impl UsageArgs {
             style::Print("█ Your prompts: "),
             style::SetForegroundColor(Color::Reset),
             style::Print(format!(
-                " ~{} tokens ({:.2}%)\n\n",
+                " {approx}{} tokens ({:.2}%)\n\n",
                 usage_data.user_tokens,
                 calculate_usage_percentage(usage_data.user_tokens, usage_data.context_window_size)
             )),
         )?;
 
+        if let Some(usage) = usage_data.reported_usage {
+            let cached = usage.cache_read_input_tokens + usage.cache_write_input_tokens;
+            queue!(
+                session.stderr,
+                style::Print("Last request, as reported by the service: "),
+                style::Print(format!(
+                    "{} input tokens ({} cached), {} output tokens\n",
+                    usage.input_tokens(),
+                    cached,
+                    usage.output_tokens
+                )),
+            )?;
+        }
+        if usage_data.is_estimate {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("Token counts are estimated. Set "),
+                style::SetForegroundColor(Color::DarkGreen),
+                style::Print("chat.tokenizers"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(" to count them with the model's tokenizer.\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+
         queue!(
             session.stderr,
             style::SetAttribute(Attribute::Bold),
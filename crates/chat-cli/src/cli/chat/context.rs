@@ -212,13 +212,16 @@ impl ContextManager {
         Ok(context_files)
     }
 
-    /// Collects context files and optionally drops files if the total size exceeds the limit.
+    /// Collects context files along with `additional` entries, such as the contents of resources,
+    /// and optionally drops entries if the total size exceeds the limit.
     /// Returns (files_to_use, dropped_files)
     pub async fn collect_context_files_with_limit(
         &self,
         os: &Os,
+        additional: Vec<(String, String)>,
     ) -> Result<(Vec<(String, String)>, Vec<(String, String)>)> {
         let mut files = self.get_context_files(os).await?;
+        files.extend(additional);
 
         let dropped_files = drop_matched_context_files(&mut files, self.max_context_files_size).unwrap_or_default();
 
@@ -251,12 +254,13 @@ impl ContextManager {
         os: &crate::os::Os,
         prompt: Option<&str>,
         tool_context: Option<crate::cli::chat::cli::hooks::ToolContext>,
+        session_context: Option<crate::cli::chat::cli::hooks::SessionContext>,
     ) -> Result<Vec<((HookTrigger, Hook), HookOutput)>, ChatError> {
         let mut hooks = self.hooks.clone();
         hooks.retain(|t, _| *t == trigger);
         let cwd = os.env.current_dir()?.to_string_lossy().to_string();
         self.hook_executor
-            .run_hooks(hooks, output, &cwd, prompt, tool_context)
+            .run_hooks(hooks, output, &cwd, prompt, tool_context, session_context)
             .await
     }
 }
@@ -397,11 +401,24 @@ mod tests {
         os.fs.write("test/to-drop.md", "long content that exceed limit").await?;
         manager.add_paths(&os, vec!["test/*.md".to_string()], false).await?;
 
-        let (used, dropped) = manager.collect_context_files_with_limit(&os).await.unwrap();
+        let (used, dropped) = manager.collect_context_files_with_limit(&os, Vec::new()).await.unwrap();
 
         assert!(used.len() + dropped.len() == 2);
         assert!(used.len() == 1);
         assert!(dropped.len() == 1);
+
+        let resource = (
+            "@docs:guide".to_string(),
+            "long resource that exceeds the limit".to_string(),
+        );
+        let (used, dropped) = manager
+            .collect_context_files_with_limit(&os, vec![resource])
+            .await
+            .unwrap();
+
+        assert_eq!(used.len(), 1);
+        assert_eq!(dropped.len(), 2);
+        assert!(dropped.iter().any(|(name, _)| name == "@docs:guide"));
         Ok(())
     }
 
@@ -1,4 +1,5 @@
 use std::collections::{
+    BTreeMap,
     HashMap,
     HashSet,
     VecDeque,
@@ -17,7 +18,6 @@ use rmcp::model::{
     PromptMessage,
     PromptMessageContent,
     PromptMessageRole,
-    ResourceContents,
 };
 use serde::{
     Deserialize,
@@ -30,6 +30,10 @@ use tracing::{
 
 use super::cli::compact::CompactStrategy;
 use super::cli::hooks::HookOutput;
+use super::cli::mcp::{
+    format_resource_contents,
+    parse_resource_mention,
+};
 use super::cli::model::context_window_tokens;
 use super::consts::{
     DUMMY_TOOL_NAME,
@@ -49,7 +53,8 @@ use super::parser::RequestMetadata;
 use super::token_counter::{
     CharCount,
     CharCounter,
-    TokenCounter,
+    TokenCount,
+    Tokenizer,
 };
 use super::tool_manager::ToolManager;
 use super::tools::{
@@ -58,11 +63,15 @@ use super::tools::{
     ToolOrigin,
     ToolSpec,
 };
-use super::util::serde_value_to_document;
+use super::util::{
+    serde_value_to_document,
+    truncate_safe_in_place,
+};
 use crate::api_client::model::{
     ChatMessage,
     ConversationState as FigConversationState,
     ImageBlock,
+    TokenUsage,
     Tool,
     ToolInputSchema,
     ToolSpecification,
@@ -83,11 +92,18 @@ use crate::cli::chat::cli::model::{
     get_model_info,
 };
 use crate::cli::chat::tools::custom_tool::CustomToolConfig;
+use crate::database::SessionMetadata;
 use crate::os::Os;
 
 pub const CONTEXT_ENTRY_START_HEADER: &str = "--- CONTEXT ENTRY BEGIN ---\n";
 pub const CONTEXT_ENTRY_END_HEADER: &str = "--- CONTEXT ENTRY END ---\n\n";
 
+/// Maximum length (in bytes) of the title generated for a session.
+const SESSION_TITLE_MAX_LEN: usize = 80;
+
+/// Name of the branch every conversation starts on.
+pub const MAIN_BRANCH: &str = "main";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     user: UserMessage,
@@ -96,6 +112,16 @@ pub struct HistoryEntry {
     request_metadata: Option<RequestMetadata>,
 }
 
+impl HistoryEntry {
+    pub fn user(&self) -> &UserMessage {
+        &self.user
+    }
+
+    pub fn assistant(&self) -> &AssistantMessage {
+        &self.assistant
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct McpServerInfo {
     pub name: String,
@@ -128,6 +154,10 @@ pub struct ConversationState {
     context_message_length: Option<usize>,
     /// Stores the latest conversation summary created by /compact
     latest_summary: Option<(String, RequestMetadata)>,
+    /// Contents of the MCP resources referenced by the agent, read for the current turn. [None]
+    /// for resources that couldn't be read.
+    #[serde(skip)]
+    resource_cache: HashMap<String, Option<String>>,
     #[serde(skip)]
     pub agents: Agents,
     /// Unused, kept only to maintain deserialization backwards compatibility with <=v1.13.3
@@ -149,6 +179,29 @@ pub struct ConversationState {
     /// Tangent mode checkpoint - stores main conversation when in tangent mode
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tangent_state: Option<ConversationCheckpoint>,
+    /// Name of the branch the current history belongs to
+    #[serde(default = "default_branch")]
+    current_branch: String,
+    /// Branches other than the current one, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    branches: BTreeMap<String, ConversationBranch>,
+}
+
+/// A line of conversation that is not currently active, see [ConversationState::create_branch].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ConversationBranch {
+    history: VecDeque<HistoryEntry>,
+    transcript: VecDeque<String>,
+    latest_summary: Option<(String, RequestMetadata)>,
+}
+
+/// What two branches produced since the last history entry they have in common.
+#[derive(Debug)]
+pub struct BranchDiff<'a> {
+    /// Number of history entries both branches start with
+    pub common: usize,
+    pub left: Vec<&'a HistoryEntry>,
+    pub right: Vec<&'a HistoryEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -205,6 +258,7 @@ impl ConversationState {
             tool_manager,
             context_message_length: None,
             latest_summary: None,
+            resource_cache: HashMap::new(),
             agents,
             model: None,
             model_info: model,
@@ -212,6 +266,8 @@ impl ConversationState {
             checkpoint_manager: None,
             mcp_enabled,
             tangent_state: None,
+            current_branch: default_branch(),
+            branches: BTreeMap::new(),
         }
     }
 
@@ -302,6 +358,174 @@ impl ConversationState {
         }
     }
 
+    /// Returns the name of the branch the conversation is currently on.
+    pub fn current_branch(&self) -> &str {
+        &self.current_branch
+    }
+
+    /// Returns the name and number of history entries of every branch, sorted by name.
+    pub fn branches(&self) -> Vec<(&str, usize)> {
+        let mut branches = self
+            .branches
+            .iter()
+            .map(|(name, branch)| (name.as_str(), branch.history.len()))
+            .chain(std::iter::once((self.current_branch.as_str(), self.history.len())))
+            .collect::<Vec<_>>();
+        branches.sort();
+        branches
+    }
+
+    fn branch_history(&self, name: &str) -> Option<&VecDeque<HistoryEntry>> {
+        if name == self.current_branch {
+            Some(&self.history)
+        } else {
+            self.branches.get(name).map(|branch| &branch.history)
+        }
+    }
+
+    fn ensure_can_branch(&self) -> Result<()> {
+        if self.is_in_tangent_mode() {
+            eyre::bail!("Branches can't be changed in tangent mode, exit tangent mode first");
+        }
+        Ok(())
+    }
+
+    /// Stores the current branch and replaces it with `branch`.
+    fn swap_branch(&mut self, name: String, branch: ConversationBranch) {
+        let previous = ConversationBranch {
+            history: std::mem::replace(&mut self.history, branch.history),
+            transcript: std::mem::replace(&mut self.transcript, branch.transcript),
+            latest_summary: std::mem::replace(&mut self.latest_summary, branch.latest_summary),
+        };
+        let previous_name = std::mem::replace(&mut self.current_branch, name);
+        self.branches.insert(previous_name, previous);
+
+        self.next_message = None;
+        self.valid_history_range = (0, self.history.len());
+        if let Some(manager) = self.checkpoint_manager.as_mut() {
+            manager.message_locked = false;
+            manager.pending_user_message = None;
+        }
+    }
+
+    /// Forks the current branch into a new branch named `name` and switches to it. The new
+    /// branch keeps the first `at` history entries of the current one, or all of them if `at` is
+    /// [None].
+    ///
+    /// When forking before the end of the history, the transcript is rebuilt from the kept entries
+    /// and the compaction summary is dropped, since neither may describe what came after `at`.
+    pub fn create_branch(&mut self, name: &str, at: Option<usize>) -> Result<()> {
+        self.ensure_can_branch()?;
+        if self.branch_history(name).is_some() {
+            eyre::bail!("Branch {name} already exists");
+        }
+        let at = at.unwrap_or(self.history.len());
+        if at > self.history.len() {
+            eyre::bail!(
+                "Branch {} only has {} history entries",
+                self.current_branch,
+                self.history.len()
+            );
+        }
+
+        let branch = match at == self.history.len() {
+            true => ConversationBranch {
+                history: self.history.clone(),
+                transcript: self.transcript.clone(),
+                latest_summary: self.latest_summary.clone(),
+            },
+            false => {
+                let history = self.history.iter().take(at).cloned().collect::<VecDeque<_>>();
+                let mut transcript = VecDeque::new();
+                for entry in &history {
+                    if let Some(prompt) = entry.user.prompt() {
+                        transcript.push_back(user_transcript_entry(prompt));
+                    }
+                    transcript.push_back(assistant_transcript_entry(&entry.assistant));
+                }
+                ConversationBranch {
+                    history,
+                    transcript,
+                    latest_summary: None,
+                }
+            },
+        };
+        self.swap_branch(name.to_string(), branch);
+        Ok(())
+    }
+
+    /// Switches to the branch named `name`.
+    pub fn switch_branch(&mut self, name: &str) -> Result<()> {
+        self.ensure_can_branch()?;
+        if name == self.current_branch {
+            eyre::bail!("Already on branch {name}");
+        }
+        let Some(branch) = self.branches.remove(name) else {
+            eyre::bail!("No branch named {name}");
+        };
+        self.swap_branch(name.to_string(), branch);
+        Ok(())
+    }
+
+    /// Makes the branch named `name` the main branch. The previous main branch is kept under
+    /// `name`.
+    pub fn promote_branch(&mut self, name: &str) -> Result<()> {
+        self.ensure_can_branch()?;
+        if name == MAIN_BRANCH {
+            eyre::bail!("Branch {MAIN_BRANCH} is already the main branch");
+        }
+        if self.branch_history(name).is_none() {
+            eyre::bail!("No branch named {name}");
+        }
+
+        let rename = |branch: &str| match branch {
+            b if b == name => MAIN_BRANCH.to_string(),
+            MAIN_BRANCH => name.to_string(),
+            b => b.to_string(),
+        };
+        self.current_branch = rename(&self.current_branch);
+        self.branches = std::mem::take(&mut self.branches)
+            .into_iter()
+            .map(|(branch_name, branch)| (rename(&branch_name), branch))
+            .collect();
+        Ok(())
+    }
+
+    /// Deletes the branch named `name`. The current branch can't be deleted.
+    pub fn delete_branch(&mut self, name: &str) -> Result<()> {
+        self.ensure_can_branch()?;
+        if name == self.current_branch {
+            eyre::bail!("Can't delete the current branch");
+        }
+        match self.branches.remove(name) {
+            Some(_) => Ok(()),
+            None => eyre::bail!("No branch named {name}"),
+        }
+    }
+
+    /// Compares the history of two branches.
+    pub fn diff_branches(&self, left: &str, right: &str) -> Result<BranchDiff<'_>> {
+        let history = |name: &str| {
+            self.branch_history(name)
+                .ok_or_else(|| eyre::eyre!("No branch named {name}"))
+        };
+        let (left, right) = (history(left)?, history(right)?);
+
+        // Branches share their entries up to the point they were forked, so the entries are
+        // compared by value.
+        let common = left
+            .iter()
+            .zip(right.iter())
+            .take_while(|(l, r)| serde_json::to_value(l).ok() == serde_json::to_value(r).ok())
+            .count();
+
+        Ok(BranchDiff {
+            common,
+            left: left.iter().skip(common).collect(),
+            right: right.iter().skip(common).collect(),
+        })
+    }
+
     /// Appends a collection prompts into history and returns the last message in the collection.
     /// It asserts that the collection ends with a prompt that assumes the role of user.
     pub fn append_prompts(&mut self, mut prompts: VecDeque<PromptMessage>) -> Option<String> {
@@ -309,23 +533,7 @@ impl ConversationState {
             match prompt_msg_content {
                 PromptMessageContent::Text { text } => text,
                 PromptMessageContent::Image { image } => image.raw.data,
-                PromptMessageContent::Resource { resource } => {
-                    // TODO: add support for resources for prompt
-                    match resource.raw.resource {
-                        ResourceContents::TextResourceContents {
-                            uri, mime_type, text, ..
-                        } => {
-                            let mime_type = mime_type.as_deref().unwrap_or("unknown");
-                            format!("Text resource of uri: {uri}, mime_type: {mime_type}, text: {text}")
-                        },
-                        ResourceContents::BlobResourceContents {
-                            uri, mime_type, blob, ..
-                        } => {
-                            let mime_type = mime_type.as_deref().unwrap_or("unknown");
-                            format!("Blob resource of uri: {uri}, mime_type: {mime_type}, blob: {blob}")
-                        },
-                    }
-                },
+                PromptMessageContent::Resource { resource } => format_resource_contents(&resource.raw.resource),
                 PromptMessageContent::ResourceLink { link } => serde_json::to_string(&link.raw).unwrap_or(format!(
                     "Resource link with uri: {}, name: {}",
                     link.raw.uri, link.raw.name
@@ -393,6 +601,8 @@ impl ConversationState {
 
         let msg = UserMessage::new_prompt(input, Some(Local::now().fixed_offset()));
         self.next_message = Some(msg);
+        // Resources are read again for each prompt
+        self.resource_cache.clear();
     }
 
     /// Sets the response message according to the currently set [Self::next_message].
@@ -413,10 +623,59 @@ impl ConversationState {
         });
 
         if let Ok(cwd) = std::env::current_dir() {
-            os.database.set_conversation_by_path(cwd, self).ok();
+            if let Some(metadata) = cwd.to_str().map(|path| self.session_metadata(os, path)) {
+                os.database
+                    .set_session(&metadata, self)
+                    .map_err(|err| warn!(?err, "failed to save the session"))
+                    .ok();
+            }
+        }
+    }
+
+    /// Returns the metadata used to store this conversation as a session started in `path`.
+    ///
+    /// The title defaults to the first line of the first prompt in the history.
+    pub fn session_metadata(&self, os: &Os, path: &str) -> SessionMetadata {
+        let title = self.history.iter().find_map(|entry| entry.user.prompt()).map(|prompt| {
+            let mut title = prompt.trim().lines().next().unwrap_or_default().to_string();
+            truncate_safe_in_place(&mut title, SESSION_TITLE_MAX_LEN, "...");
+            title
+        });
+        let tokenizer = self.tokenizer(os);
+        let now = chrono::Utc::now();
+
+        SessionMetadata {
+            id: self.conversation_id.clone(),
+            path: path.to_string(),
+            title,
+            agent: self.current_profile().map(str::to_string),
+            model_id: self.model_info.as_ref().map(|info| info.model_id.clone()),
+            input_tokens: *tokenizer.count(self.history.iter().map(HistoryEntry::user)) as u64,
+            output_tokens: *tokenizer.count(self.history.iter().map(HistoryEntry::assistant)) as u64,
+            created_at: now,
+            updated_at: now,
         }
     }
 
+    /// Assigns a new conversation id so that this conversation is saved as a separate session
+    /// from the one it was loaded from.
+    pub fn fork(&mut self) {
+        self.conversation_id = uuid::Uuid::new_v4().to_string();
+    }
+
+    /// Returns the token count of the history and the next user message, excluding context
+    /// files and tool specifications.
+    pub fn history_token_count(&self, tokenizer: &Tokenizer) -> TokenCount {
+        tokenizer.count(self.history.iter()) + tokenizer.count(self.next_message.iter())
+    }
+
+    /// Returns the content of the most recent assistant response, if present.
+    pub fn last_assistant_response(&self) -> Option<&str> {
+        self.history
+            .back()
+            .map(|HistoryEntry { assistant, .. }| assistant.content())
+    }
+
     /// Returns the conversation id.
     pub fn conversation_id(&self) -> &str {
         self.conversation_id.as_ref()
@@ -582,6 +841,7 @@ impl ConversationState {
                     os,
                     user_prompt,
                     None, // tool_context
+                    None, // session_context
                 )
                 .await?;
             agent_spawn_context = format_hook_context(&agent_spawn, HookTrigger::AgentSpawn);
@@ -594,6 +854,7 @@ impl ConversationState {
                         os,
                         next_message.prompt(),
                         None, // tool_context
+                        None, // session_context
                     )
                     .await?;
                 if let Some(ctx) = format_hook_context(&per_prompt, HookTrigger::UserPromptSubmit) {
@@ -812,26 +1073,41 @@ Return only the JSON configuration, no additional text.",
             context_content.push_str(CONTEXT_ENTRY_END_HEADER);
         }
 
-        // Add context files if available
-        if let Some(context_manager) = self.context_manager.as_mut() {
-            match context_manager.collect_context_files_with_limit(os).await {
-                Ok((files_to_use, files_dropped)) => {
-                    if !files_dropped.is_empty() {
-                        dropped_context_files.extend(files_dropped);
-                    }
-
-                    if !files_to_use.is_empty() {
-                        context_content.push_str(CONTEXT_ENTRY_START_HEADER);
-                        for (filename, content) in files_to_use {
-                            context_content.push_str(&format!("[{}]\n{}\n", filename, content));
-                        }
-                        context_content.push_str(CONTEXT_ENTRY_END_HEADER);
-                    }
+        // Add the MCP resources the agent references along with the context files, so that both
+        // count towards the context files size limit. Resources are read again on every turn so
+        // that changes on the server side are picked up.
+        let resources = self.agent_resource_contents().await;
+        let resource_names = resources.iter().map(|(name, _)| name.clone()).collect::<HashSet<_>>();
+        let entries = match self.context_manager.as_mut() {
+            Some(context_manager) => match context_manager.collect_context_files_with_limit(os, resources).await {
+                Ok((entries_to_use, entries_dropped)) => {
+                    dropped_context_files.extend(entries_dropped);
+                    entries_to_use
                 },
                 Err(e) => {
                     warn!("Failed to get context files: {}", e);
+                    Vec::new()
                 },
+            },
+            None => resources,
+        };
+        let (resources, files): (Vec<_>, Vec<_>) =
+            entries.into_iter().partition(|(name, _)| resource_names.contains(name));
+
+        if !resources.is_empty() {
+            context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+            for (_, contents) in resources {
+                context_content.push_str(&format!("{contents}\n"));
+            }
+            context_content.push_str(CONTEXT_ENTRY_END_HEADER);
+        }
+
+        if !files.is_empty() {
+            context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+            for (filename, content) in files {
+                context_content.push_str(&format!("[{}]\n{}\n", filename, content));
             }
+            context_content.push_str(CONTEXT_ENTRY_END_HEADER);
         }
 
         if let Some(context) = additional_context {
@@ -859,25 +1135,85 @@ Return only the JSON configuration, no additional text.",
         }
     }
 
+    /// Reads the MCP resources referenced by the active agent, as pairs of the `@server:uri`
+    /// mention and the contents. Reads are cached until the next user prompt so that the requests
+    /// made for tool uses within a turn don't read every resource again. Resources that can't be
+    /// read are left out.
+    async fn agent_resource_contents(&mut self) -> Vec<(String, String)> {
+        let mentions = self
+            .agents
+            .get_active()
+            .map(|agent| {
+                agent
+                    .resources
+                    .iter()
+                    .filter_map(|resource| parse_resource_mention(resource))
+                    .map(|(server, uri)| (server.to_string(), uri.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut contents = Vec::new();
+        for (server, uri) in mentions {
+            let mention = format!("@{server}:{uri}");
+            if !self.resource_cache.contains_key(&mention) {
+                let read = match self.tool_manager.read_resource(&server, &uri).await {
+                    Ok(result) => Some(
+                        result
+                            .contents
+                            .iter()
+                            .map(format_resource_contents)
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                    Err(e) => {
+                        warn!("Failed to read resource {mention} for context: {e}");
+                        None
+                    },
+                };
+                self.resource_cache.insert(mention.clone(), read);
+            }
+            if let Some(Some(text)) = self.resource_cache.get(&mention) {
+                contents.push((mention, text.clone()));
+            }
+        }
+        contents
+    }
+
     /// The length of the user message used as context, if any.
     pub fn context_message_length(&self) -> Option<usize> {
         self.context_message_length
     }
 
-    /// Calculate the total character count in the conversation
-    pub async fn calculate_char_count(&mut self, os: &Os) -> Result<CharCount, ChatError> {
+    /// Returns the tokenizer configured for the model of this conversation.
+    pub fn tokenizer(&self, os: &Os) -> Tokenizer {
+        Tokenizer::for_model(os, self.model_info.as_ref().map(|info| info.model_id.as_str()))
+    }
+
+    /// Returns the token usage reported by the service for the latest request, if any.
+    pub fn latest_token_usage(&self) -> Option<TokenUsage> {
+        self.history
+            .back()
+            .and_then(|entry| entry.request_metadata.as_ref())
+            .and_then(|metadata| metadata.token_usage)
+    }
+
+    /// Calculate the total token count in the conversation
+    pub async fn calculate_token_count(&mut self, os: &Os) -> Result<TokenCount, ChatError> {
+        let tokenizer = self.tokenizer(os);
         Ok(self
             .backend_conversation_state(os, false, &mut vec![])
             .await?
-            .char_count())
+            .calculate_token_counts(&tokenizer)
+            .total())
     }
 
     /// Get the current token warning level
     pub async fn get_token_warning_level(&mut self, os: &Os) -> Result<TokenWarningLevel, ChatError> {
-        let total_chars = self.calculate_char_count(os).await?;
-        let max_chars = TokenCounter::token_to_chars(context_window_tokens(self.model_info.as_ref()));
+        let total_tokens = self.calculate_token_count(os).await?;
+        let max_tokens = context_window_tokens(self.model_info.as_ref());
 
-        Ok(if *total_chars >= max_chars {
+        Ok(if *total_tokens >= max_tokens {
             TokenWarningLevel::Critical
         } else {
             TokenWarningLevel::None
@@ -885,14 +1221,11 @@ Return only the JSON configuration, no additional text.",
     }
 
     pub fn append_user_transcript(&mut self, message: &str) {
-        self.append_transcript(format!("> {}", message.replace("\n", "> \n")));
+        self.append_transcript(user_transcript_entry(message));
     }
 
     pub fn append_assistant_transcript(&mut self, message: &AssistantMessage) {
-        let tool_uses = message.tool_uses().map_or("none".to_string(), |tools| {
-            tools.iter().map(|tool| tool.name.clone()).collect::<Vec<_>>().join(",")
-        });
-        self.append_transcript(format!("{}\n[Tool uses: {tool_uses}]", message.content()));
+        self.append_transcript(assistant_transcript_entry(message));
     }
 
     pub fn append_transcript(&mut self, message: String) {
@@ -1040,6 +1373,15 @@ impl BackendConversationStateImpl<'_, std::collections::vec_deque::Iter<'_, Hist
             assistant_messages: assistant_chars.into(),
         }
     }
+
+    /// Like [Self::calculate_conversation_size], but counts tokens with `tokenizer`.
+    pub fn calculate_token_counts(&self, tokenizer: &Tokenizer) -> ConversationTokens {
+        ConversationTokens {
+            context_messages: tokenizer.count(self.context_messages.iter().flatten()),
+            user_messages: tokenizer.count(self.history.clone().map(HistoryEntry::user)),
+            assistant_messages: tokenizer.count(self.history.clone().map(HistoryEntry::assistant)),
+        }
+    }
 }
 
 /// Reflects a detailed accounting of the context window utilization for a given conversation.
@@ -1050,6 +1392,20 @@ pub struct ConversationSize {
     pub assistant_messages: CharCount,
 }
 
+/// Number of tokens used by each part of a conversation.
+#[derive(Debug, Clone, Copy)]
+pub struct ConversationTokens {
+    pub context_messages: TokenCount,
+    pub user_messages: TokenCount,
+    pub assistant_messages: TokenCount,
+}
+
+impl ConversationTokens {
+    pub fn total(&self) -> TokenCount {
+        self.context_messages + self.user_messages + self.assistant_messages
+    }
+}
+
 /// Converts a list of user/assistant message pairs into a flattened list of ChatMessage.
 fn flatten_history<'a, T>(history: T) -> Vec<ChatMessage>
 where
@@ -1263,6 +1619,22 @@ fn enforce_tool_use_history_invariants(history: &mut VecDeque<HistoryEntry>, too
 fn default_true() -> bool {
     true
 }
+
+fn default_branch() -> String {
+    MAIN_BRANCH.to_string()
+}
+
+fn user_transcript_entry(message: &str) -> String {
+    format!("> {}", message.replace("\n", "> \n"))
+}
+
+fn assistant_transcript_entry(message: &AssistantMessage) -> String {
+    let tool_uses = message.tool_uses().map_or("none".to_string(), |tools| {
+        tools.iter().map(|tool| tool.name.clone()).collect::<Vec<_>>().join(",")
+    });
+    format!("{}\n[Tool uses: {tool_uses}]", message.content())
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::message::AssistantToolUse;
@@ -1746,4 +2118,141 @@ mod tests {
         conversation.exit_tangent_mode_with_tail();
         assert_eq!(conversation.history.len(), main_history_len);
     }
+
+    #[tokio::test]
+    async fn test_session_store() {
+        let mut os = Os::new().await.unwrap();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "session_conv_id",
+            Agents::default(),
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false,
+        )
+        .await;
+
+        conversation
+            .set_next_user_message("first question\nwith details".to_string())
+            .await;
+        conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "answer".to_string()),
+            None,
+        );
+
+        let cwd = std::env::current_dir().unwrap();
+        let sessions = os.database.list_sessions(Some(&cwd)).unwrap();
+        let session = sessions.iter().find(|s| s.id == "session_conv_id").unwrap();
+        assert_eq!(session.title.as_deref(), Some("first question"));
+
+        // Renamed titles are kept when the conversation is saved again.
+        assert!(os.database.rename_session("session_conv_id", "renamed").unwrap());
+        conversation.set_next_user_message("second question".to_string()).await;
+        conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "answer".to_string()),
+            None,
+        );
+        let stored = os.database.get_session("session_conv_id").unwrap().unwrap();
+        assert_eq!(stored.history().len(), 2);
+        assert_eq!(
+            os.database.get_sessions_by_id_prefix("session_conv").unwrap()[0]
+                .title
+                .as_deref(),
+            Some("renamed")
+        );
+
+        // Forked conversations are saved as a separate session.
+        let mut fork = stored;
+        fork.fork();
+        assert_ne!(fork.conversation_id(), "session_conv_id");
+        os.database
+            .set_session(&fork.session_metadata(&os, cwd.to_str().unwrap()), &fork)
+            .unwrap();
+        assert_eq!(
+            os.database
+                .get_latest_session_by_path(&cwd)
+                .unwrap()
+                .unwrap()
+                .history()
+                .len(),
+            2
+        );
+
+        assert!(os.database.delete_session("session_conv_id").unwrap());
+        assert!(os.database.get_session("session_conv_id").unwrap().is_none());
+        assert!(os.database.get_session(fork.conversation_id()).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_branches() {
+        let mut os = Os::new().await.unwrap();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "branch_conv_id",
+            Agents::default(),
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false,
+        )
+        .await;
+
+        for prompt in ["setup", "approach a"] {
+            conversation.set_next_user_message(prompt.to_string()).await;
+            conversation.push_assistant_message(
+                &mut os,
+                AssistantMessage::new_response(None, prompt.to_string()),
+                None,
+            );
+        }
+        assert_eq!(conversation.current_branch(), MAIN_BRANCH);
+
+        conversation.latest_summary = Some(("summary of approach a".to_string(), RequestMetadata::default()));
+
+        // Fork after the setup entry.
+        assert!(conversation.create_branch("alt", Some(3)).is_err());
+        conversation.create_branch("alt", Some(1)).unwrap();
+        assert!(conversation.create_branch("alt", None).is_err());
+        assert_eq!(conversation.current_branch(), "alt");
+        assert_eq!(conversation.history().len(), 1);
+        assert_eq!(conversation.transcript, vec![
+            "> setup".to_string(),
+            "setup\n[Tool uses: none]".to_string()
+        ]);
+        assert_eq!(conversation.latest_summary(), None);
+        conversation.set_next_user_message("approach b".to_string()).await;
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "b".to_string()), None);
+        assert_eq!(conversation.branches(), vec![("alt", 2), (MAIN_BRANCH, 2)]);
+
+        let diff = conversation.diff_branches(MAIN_BRANCH, "alt").unwrap();
+        assert_eq!(diff.common, 1);
+        assert_eq!(diff.left[0].user().prompt(), Some("approach a"));
+        assert_eq!(diff.right[0].user().prompt(), Some("approach b"));
+
+        conversation.switch_branch(MAIN_BRANCH).unwrap();
+        assert_eq!(conversation.last_assistant_response(), Some("approach a"));
+        assert_eq!(conversation.latest_summary(), Some("summary of approach a"));
+        assert!(conversation.delete_branch(MAIN_BRANCH).is_err());
+
+        // Promoting swaps the names of the promoted and main branches.
+        conversation.promote_branch("alt").unwrap();
+        assert_eq!(conversation.current_branch(), "alt");
+        conversation.switch_branch(MAIN_BRANCH).unwrap();
+        assert_eq!(conversation.last_assistant_response(), Some("b"));
+
+        // Branches are kept when the conversation is saved.
+        let restored: ConversationState = serde_json::from_str(&serde_json::to_string(&conversation).unwrap()).unwrap();
+        assert_eq!(restored.branches(), vec![("alt", 2), (MAIN_BRANCH, 2)]);
+
+        conversation.delete_branch("alt").unwrap();
+        assert_eq!(conversation.branches(), vec![(MAIN_BRANCH, 2)]);
+
+        conversation.enter_tangent_mode();
+        assert!(conversation.create_branch("tangent", None).is_err());
+    }
 }
@@ -0,0 +1,540 @@
+//! Renders a conversation as a human readable Markdown or HTML document.
+//!
+//! Unlike `/save`, which writes the serialized [ConversationState], exports are meant to be read
+//! and shared. They cannot be loaded back into a chat session.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use base64::Engine;
+use chrono::Local;
+use clap::ValueEnum;
+
+use super::conversation::ConversationState;
+use super::message::{
+    AssistantToolUse,
+    ToolUseResult,
+    ToolUseResultBlock,
+    UserMessage,
+};
+use crate::api_client::model::{
+    ImageBlock,
+    ImageFormat,
+    ImageSource,
+    ToolResultStatus,
+};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const HTML_STYLE: &str = "
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Helvetica, Arial, sans-serif; max-width: 960px; margin: 2em auto; padding: 0 1em; line-height: 1.5; color: #1f2328; }
+header dl { display: grid; grid-template-columns: max-content auto; gap: 0.25em 1em; }
+header dt { font-weight: bold; }
+header dd { margin: 0; }
+section { border-radius: 6px; padding: 0.5em 1em; margin: 1em 0; }
+section.user { background: #ddf4ff; }
+section.assistant { background: #f6f8fa; }
+section.summary { background: #fff8c5; }
+h2 { font-size: 1em; margin: 0.5em 0; }
+h2 time { font-weight: normal; color: #59636e; margin-left: 0.5em; }
+pre { white-space: pre-wrap; word-wrap: break-word; background: #ffffff; border: 1px solid #d1d9e0; border-radius: 6px; padding: 0.5em; }
+.text { white-space: pre-wrap; }
+details { margin: 0.5em 0; }
+summary { cursor: pointer; }
+summary code { font-weight: bold; }
+.status-error { color: #d1242f; }
+img { max-width: 100%; }
+";
+
+/// Output format of an exported conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    /// Infers the format from the extension of `path`, defaulting to Markdown.
+    pub fn from_path(path: &str) -> Self {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("html" | "htm") => Self::Html,
+            _ => Self::Markdown,
+        }
+    }
+}
+
+/// Renders the conversation history as a document in the given format.
+pub fn render(conversation: &ConversationState, format: ExportFormat) -> String {
+    let document = Document::new(conversation);
+    match format {
+        ExportFormat::Markdown => document.to_markdown(),
+        ExportFormat::Html => document.to_html(),
+    }
+}
+
+/// A tool use paired with its result, if one was sent back to the model.
+struct ToolCall<'a> {
+    tool_use: &'a AssistantToolUse,
+    result: Option<&'a ToolUseResult>,
+}
+
+impl ToolCall<'_> {
+    fn status(&self) -> &'static str {
+        match self.result.map(|result| &result.status) {
+            Some(ToolResultStatus::Success) => "success",
+            Some(ToolResultStatus::Error) => "error",
+            None => "no result",
+        }
+    }
+
+    fn input(&self) -> String {
+        serde_json::to_string_pretty(&self.tool_use.args).unwrap_or_else(|_| self.tool_use.args.to_string())
+    }
+
+    fn output(&self) -> Option<String> {
+        let result = self.result?;
+        let blocks = result
+            .content
+            .iter()
+            .map(|block| match block {
+                ToolUseResultBlock::Text(text) => text.clone(),
+                ToolUseResultBlock::Json(value) => {
+                    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+                },
+            })
+            .collect::<Vec<_>>();
+        Some(blocks.join("\n"))
+    }
+}
+
+enum Turn<'a> {
+    User {
+        prompt: Option<&'a str>,
+        timestamp: Option<String>,
+        images: Vec<String>,
+    },
+    Assistant {
+        content: &'a str,
+        tool_calls: Vec<ToolCall<'a>>,
+    },
+}
+
+/// Format independent view of a conversation.
+struct Document<'a> {
+    conversation_id: &'a str,
+    model: Option<&'a str>,
+    agent: Option<&'a str>,
+    exported_at: String,
+    checkpoints: Vec<(&'a str, String, &'a str)>,
+    summary: Option<&'a str>,
+    transcript: Vec<&'a str>,
+    turns: Vec<Turn<'a>>,
+}
+
+impl<'a> Document<'a> {
+    fn new(conversation: &'a ConversationState) -> Self {
+        let history = conversation.history();
+
+        let results = history
+            .iter()
+            .filter_map(|entry| entry.user().tool_use_results())
+            .flatten()
+            .map(|result| (result.tool_use_id.as_str(), result))
+            .collect::<HashMap<_, _>>();
+
+        let mut turns = Vec::new();
+        for entry in history {
+            if let Some(turn) = user_turn(entry.user()) {
+                turns.push(turn);
+            }
+            let assistant = entry.assistant();
+            turns.push(Turn::Assistant {
+                content: assistant.content(),
+                tool_calls: assistant
+                    .tool_uses()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|tool_use| ToolCall {
+                        tool_use,
+                        result: results.get(tool_use.id.as_str()).copied(),
+                    })
+                    .collect(),
+            });
+        }
+
+        let summary = conversation.latest_summary();
+        Self {
+            conversation_id: conversation.conversation_id(),
+            model: conversation.model_info.as_ref().map(|info| info.model_id.as_str()),
+            agent: conversation.current_profile(),
+            exported_at: Local::now().format(TIMESTAMP_FORMAT).to_string(),
+            checkpoints: conversation
+                .checkpoint_manager
+                .as_ref()
+                .map(|manager| {
+                    manager
+                        .checkpoints
+                        .iter()
+                        .map(|checkpoint| {
+                            (
+                                checkpoint.tag.as_str(),
+                                checkpoint.timestamp.format(TIMESTAMP_FORMAT).to_string(),
+                                checkpoint.description.as_str(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            summary,
+            // The transcript only matters once the history was compacted, as it is otherwise a
+            // copy of the history.
+            transcript: match summary {
+                Some(_) => conversation.transcript.iter().map(String::as_str).collect(),
+                None => Vec::new(),
+            },
+            turns,
+        }
+    }
+
+    fn metadata(&self) -> Vec<(&'static str, &str)> {
+        let mut metadata = vec![("Conversation ID", self.conversation_id)];
+        metadata.extend(self.model.map(|model| ("Model", model)));
+        metadata.extend(self.agent.map(|agent| ("Agent", agent)));
+        metadata.push(("Exported", self.exported_at.as_str()));
+        metadata
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# Conversation\n\n");
+        for (name, value) in self.metadata() {
+            let _ = writeln!(out, "- **{name}:** {value}");
+        }
+        out.push('\n');
+
+        if !self.checkpoints.is_empty() {
+            out.push_str("## Checkpoints\n\n");
+            for (tag, timestamp, description) in &self.checkpoints {
+                let _ = writeln!(out, "- `{tag}` {timestamp} - {description}");
+            }
+            out.push('\n');
+        }
+
+        if let Some(summary) = self.summary {
+            out.push_str("## Summary of earlier conversation\n\n");
+            let _ = writeln!(out, "{summary}\n");
+            if !self.transcript.is_empty() {
+                out.push_str("<details>\n<summary>Transcript</summary>\n\n");
+                out.push_str(&markdown_code_block("text", &self.transcript.join("\n\n")));
+                out.push_str("</details>\n\n");
+            }
+        }
+
+        for turn in &self.turns {
+            match turn {
+                Turn::User {
+                    prompt,
+                    timestamp,
+                    images,
+                } => {
+                    match timestamp {
+                        Some(timestamp) => {
+                            let _ = writeln!(out, "## User ({timestamp})\n");
+                        },
+                        None => out.push_str("## User\n\n"),
+                    }
+                    if let Some(prompt) = prompt {
+                        let _ = writeln!(out, "{prompt}\n");
+                    }
+                    for (i, image) in images.iter().enumerate() {
+                        let _ = writeln!(out, "![image {}]({image})\n", i + 1);
+                    }
+                },
+                Turn::Assistant { content, tool_calls } => {
+                    out.push_str("## Assistant\n\n");
+                    if !content.is_empty() {
+                        let _ = writeln!(out, "{content}\n");
+                    }
+                    for call in tool_calls {
+                        let _ = writeln!(
+                            out,
+                            "<details>\n<summary>Tool use: <code>{}</code> ({})</summary>\n",
+                            html_escape(&call.tool_use.name),
+                            call.status()
+                        );
+                        out.push_str("**Input**\n\n");
+                        out.push_str(&markdown_code_block("json", &call.input()));
+                        if let Some(output) = call.output() {
+                            out.push_str("**Output**\n\n");
+                            out.push_str(&markdown_code_block("text", &output));
+                        }
+                        out.push_str("</details>\n\n");
+                    }
+                },
+            }
+        }
+
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let mut out = String::new();
+
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Conversation {}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n",
+            html_escape(self.conversation_id)
+        );
+
+        out.push_str("<header>\n<h1>Conversation</h1>\n<dl>\n");
+        for (name, value) in self.metadata() {
+            let _ = writeln!(out, "<dt>{name}</dt><dd>{}</dd>", html_escape(value));
+        }
+        out.push_str("</dl>\n");
+        if !self.checkpoints.is_empty() {
+            out.push_str("<h2>Checkpoints</h2>\n<ul>\n");
+            for (tag, timestamp, description) in &self.checkpoints {
+                let _ = writeln!(
+                    out,
+                    "<li><code>{}</code> <time>{}</time> - {}</li>",
+                    html_escape(tag),
+                    html_escape(timestamp),
+                    html_escape(description)
+                );
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("</header>\n<main>\n");
+
+        if let Some(summary) = self.summary {
+            out.push_str("<section class=\"summary\">\n<h2>Summary of earlier conversation</h2>\n");
+            let _ = writeln!(out, "<div class=\"text\">{}</div>", html_escape(summary));
+            if !self.transcript.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "<details>\n<summary>Transcript</summary>\n<pre>{}</pre>\n</details>",
+                    html_escape(&self.transcript.join("\n\n"))
+                );
+            }
+            out.push_str("</section>\n");
+        }
+
+        for turn in &self.turns {
+            match turn {
+                Turn::User {
+                    prompt,
+                    timestamp,
+                    images,
+                } => {
+                    out.push_str("<section class=\"user\">\n<h2>User");
+                    if let Some(timestamp) = timestamp {
+                        let _ = write!(out, "<time>{}</time>", html_escape(timestamp));
+                    }
+                    out.push_str("</h2>\n");
+                    if let Some(prompt) = prompt {
+                        let _ = writeln!(out, "<div class=\"text\">{}</div>", html_escape(prompt));
+                    }
+                    for (i, image) in images.iter().enumerate() {
+                        let _ = writeln!(out, "<img src=\"{image}\" alt=\"image {}\">", i + 1);
+                    }
+                    out.push_str("</section>\n");
+                },
+                Turn::Assistant { content, tool_calls } => {
+                    out.push_str("<section class=\"assistant\">\n<h2>Assistant</h2>\n");
+                    if !content.is_empty() {
+                        let _ = writeln!(out, "<div class=\"text\">{}</div>", html_escape(content));
+                    }
+                    for call in tool_calls {
+                        let status = call.status();
+                        let _ = writeln!(
+                            out,
+                            "<details>\n<summary>Tool use: <code>{}</code> <span class=\"status-{}\">({status})</span></summary>",
+                            html_escape(&call.tool_use.name),
+                            status.replace(' ', "-"),
+                        );
+                        let _ = writeln!(out, "<h3>Input</h3>\n<pre>{}</pre>", html_escape(&call.input()));
+                        if let Some(output) = call.output() {
+                            let _ = writeln!(out, "<h3>Output</h3>\n<pre>{}</pre>", html_escape(&output));
+                        }
+                        out.push_str("</details>\n");
+                    }
+                    out.push_str("</section>\n");
+                },
+            }
+        }
+
+        out.push_str("</main>\n</body>\n</html>\n");
+        out
+    }
+}
+
+/// Returns the user turn to render for `user`, or [None] if the message only carries tool results.
+fn user_turn(user: &UserMessage) -> Option<Turn<'_>> {
+    let prompt = user.prompt();
+    let images = user
+        .images
+        .iter()
+        .flatten()
+        .filter_map(image_data_uri)
+        .collect::<Vec<_>>();
+
+    if prompt.is_none() && images.is_empty() {
+        return None;
+    }
+
+    Some(Turn::User {
+        prompt,
+        timestamp: user
+            .timestamp
+            .map(|timestamp| timestamp.with_timezone(&Local).format(TIMESTAMP_FORMAT).to_string()),
+        images,
+    })
+}
+
+fn image_data_uri(image: &ImageBlock) -> Option<String> {
+    let ImageSource::Bytes(bytes) = &image.source else {
+        return None;
+    };
+    let mime_type = match image.format {
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Png => "image/png",
+        ImageFormat::Webp => "image/webp",
+    };
+    Some(format!(
+        "data:{mime_type};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// Wraps `content` in a fenced code block, using a fence longer than any run of backticks in
+/// `content`.
+fn markdown_code_block(language: &str, content: &str) -> String {
+    let mut longest_run = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            run += 1;
+            longest_run = longest_run.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat((longest_run + 1).max(3));
+    format!("{fence}{language}\n{}\n{fence}\n\n", content.trim_end_matches('\n'))
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::model::ToolResultStatus;
+    use crate::cli::agent::Agents;
+    use crate::cli::chat::message::AssistantMessage;
+    use crate::cli::chat::tool_manager::ToolManager;
+    use crate::os::Os;
+
+    async fn conversation_with_tool_use(os: &mut Os) -> ConversationState {
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "export_conv_id",
+            Agents::default(),
+            tool_manager.load_tools(os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            os,
+            false,
+        )
+        .await;
+
+        conversation
+            .set_next_user_message("What is in <main.rs>?".to_string())
+            .await;
+        conversation.push_assistant_message(
+            os,
+            AssistantMessage::new_tool_use(None, "Let me check.".to_string(), vec![AssistantToolUse {
+                id: "tool_1".to_string(),
+                name: "fs_read".to_string(),
+                orig_name: "fs_read".to_string(),
+                args: serde_json::json!({ "path": "main.rs" }),
+                orig_args: serde_json::json!({ "path": "main.rs" }),
+            }]),
+            None,
+        );
+        conversation.add_tool_results(vec![ToolUseResult {
+            tool_use_id: "tool_1".to_string(),
+            content: vec![ToolUseResultBlock::Text("```rust\nfn main() {}\n```".to_string())],
+            status: ToolResultStatus::Success,
+        }]);
+        conversation.push_assistant_message(
+            os,
+            AssistantMessage::new_response(None, "It defines an empty main.".to_string()),
+            None,
+        );
+        conversation
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ExportFormat::from_path("chat.html"), ExportFormat::Html);
+        assert_eq!(ExportFormat::from_path("chat.HTM"), ExportFormat::Html);
+        assert_eq!(ExportFormat::from_path("chat.md"), ExportFormat::Markdown);
+        assert_eq!(ExportFormat::from_path("chat"), ExportFormat::Markdown);
+    }
+
+    #[test]
+    fn test_markdown_code_block() {
+        assert_eq!(markdown_code_block("text", "a\n"), "```text\na\n```\n\n");
+        assert_eq!(
+            markdown_code_block("text", "```rust\n```"),
+            "````text\n```rust\n```\n````\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_markdown() {
+        let mut os = Os::new().await.unwrap();
+        let conversation = conversation_with_tool_use(&mut os).await;
+        let markdown = render(&conversation, ExportFormat::Markdown);
+
+        assert!(markdown.contains("- **Conversation ID:** export_conv_id"));
+        assert!(markdown.contains("What is in <main.rs>?"));
+        assert!(markdown.contains("<summary>Tool use: <code>fs_read</code> (success)</summary>"));
+        assert!(markdown.contains("````text\n```rust\nfn main() {}\n```\n````"));
+        assert!(markdown.contains("It defines an empty main."));
+        // The user message carrying the tool results is not rendered on its own.
+        assert_eq!(markdown.matches("## User").count(), 1);
+        assert_eq!(markdown.matches("## Assistant").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_render_html() {
+        let mut os = Os::new().await.unwrap();
+        let conversation = conversation_with_tool_use(&mut os).await;
+        let html = render(&conversation, ExportFormat::Html);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("What is in &lt;main.rs&gt;?"));
+        assert!(html.contains("<code>fs_read</code>"));
+        assert!(html.contains("&quot;path&quot;: &quot;main.rs&quot;"));
+        assert!(!html.contains("<main.rs>"));
+    }
+}
@@ -6,6 +6,10 @@ use super::prompt::{
     PromptQuerySender,
     rl,
 };
+use super::serve::{
+    PendingToolUse,
+    ServeInput,
+};
 #[cfg(unix)]
 use super::skim_integration::SkimCommandSelector;
 use crate::os::Os;
@@ -13,16 +17,27 @@ use crate::os::Os;
 #[derive(Debug)]
 pub struct InputSource(inner::Inner);
 
+/// An approval of the tool use awaiting approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolApproval {
+    /// Run the tool this once.
+    Accept,
+    /// Run the tool and trust it for the rest of the session.
+    Trust,
+}
+
 mod inner {
     use rustyline::Editor;
     use rustyline::history::FileHistory;
 
     use super::super::prompt::ChatHelper;
+    use super::super::serve::ServeInput;
 
     #[allow(clippy::large_enum_variant)]
     #[derive(Debug)]
     pub enum Inner {
         Readline(Editor<ChatHelper, FileHistory>),
+        Serve(ServeInput),
         #[allow(dead_code)]
         Mock {
             index: usize,
@@ -88,6 +103,32 @@ impl InputSource {
         }
     }
 
+    pub fn new_serve(input: ServeInput) -> Self {
+        Self(inner::Inner::Serve(input))
+    }
+
+    /// Tells a [ServeInput] which tool use, if any, is awaiting approval.
+    pub fn set_pending_tool_use(&mut self, tool_use: Option<PendingToolUse>) {
+        if let inner::Inner::Serve(input) = &mut self.0 {
+            input.set_pending_tool_use(tool_use);
+        }
+    }
+
+    /// Returns the approval of the pending tool use given by `input`, the last line read.
+    ///
+    /// At the terminal, tool uses are approved by typing `y` or `t`. A [ServeInput] only approves
+    /// them through an explicit request, so the text of a prompt is never taken as an approval.
+    pub fn tool_approval(&self, input: &str) -> Option<ToolApproval> {
+        match &self.0 {
+            inner::Inner::Serve(serve) => serve.tool_approval(),
+            _ => match input {
+                "y" | "Y" => Some(ToolApproval::Accept),
+                "t" | "T" => Some(ToolApproval::Trust),
+                _ => None,
+            },
+        }
+    }
+
     #[allow(dead_code)]
     pub fn new_mock(lines: Vec<String>) -> Self {
         Self(inner::Inner::Mock { index: 0, lines })
@@ -109,6 +150,7 @@ impl InputSource {
                     Err(err) => Err(err),
                 }
             },
+            inner::Inner::Serve(input) => Ok(input.read_line()),
             inner::Inner::Mock { index, lines } => {
                 *index += 1;
                 Ok(lines.get(*index - 1).cloned())
@@ -116,6 +158,40 @@ impl InputSource {
         }
     }
 
+    /// A printer for messages shown above the prompt while a line is read, when reading from a
+    /// terminal.
+    pub fn external_printer(&mut self) -> Option<Box<dyn rustyline::ExternalPrinter + Send>> {
+        match &mut self.0 {
+            inner::Inner::Readline(rl) => match rl.create_external_printer() {
+                Ok(printer) => Some(Box::new(printer)),
+                Err(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Reads the answer to a question asked on behalf of an MCP server. Unlike [Self::read_line],
+    /// answers are not added to the history. Secret answers are not echoed.
+    pub fn read_answer(&mut self, prompt: &str, secret: bool) -> Result<Option<String>, ReadlineError> {
+        match &mut self.0 {
+            inner::Inner::Readline(_) if secret => {
+                let term = dialoguer::console::Term::stderr();
+                term.write_str(prompt)?;
+                match term.read_secure_line() {
+                    Ok(line) => Ok(Some(line)),
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => Ok(None),
+                    Err(err) => Err(err.into()),
+                }
+            },
+            inner::Inner::Readline(rl) => match rl.readline(prompt) {
+                Ok(line) => Ok(Some(line)),
+                Err(ReadlineError::Interrupted | ReadlineError::Eof) => Ok(None),
+                Err(err) => Err(err),
+            },
+            _ => self.read_line(Some(prompt)),
+        }
+    }
+
     fn should_append_history(line: &str) -> bool {
         let trimmed = line.trim().to_lowercase();
         if trimmed.is_empty() {
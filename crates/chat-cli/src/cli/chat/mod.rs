@@ -2,6 +2,7 @@ pub mod cli;
 mod consts;
 pub mod context;
 mod conversation;
+mod export;
 mod input_source;
 mod message;
 mod parse;
@@ -13,9 +14,11 @@ mod prompt;
 mod prompt_parser;
 pub mod server_messenger;
 use crate::cli::chat::checkpoint::CHECKPOINT_MESSAGE_MAX_LENGTH;
+mod serve;
 #[cfg(unix)]
 mod skim_integration;
-mod token_counter;
+mod stream_json;
+pub mod token_counter;
 pub mod tool_manager;
 pub mod tools;
 pub mod util;
@@ -31,6 +34,10 @@ use std::io::{
 };
 use std::process::ExitCode;
 use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
 use std::time::{
     Duration,
     Instant,
@@ -44,12 +51,21 @@ use clap::{
     ValueEnum,
 };
 use cli::compact::CompactStrategy;
-use cli::hooks::ToolContext;
+use cli::hooks::{
+    PermissionDecision,
+    PreToolUseOutput,
+    SessionContext,
+    ToolContext,
+};
 use cli::model::{
     find_model,
     get_available_models,
     select_model,
 };
+use cli::sessions::{
+    find_session,
+    print_sessions,
+};
 pub use conversation::ConversationState;
 use conversation::TokenWarningLevel;
 use crossterm::style::{
@@ -70,7 +86,10 @@ use eyre::{
     bail,
     eyre,
 };
-use input_source::InputSource;
+use input_source::{
+    InputSource,
+    ToolApproval,
+};
 use message::{
     AssistantMessage,
     AssistantToolUse,
@@ -88,10 +107,15 @@ use parser::{
 };
 use regex::Regex;
 use rmcp::model::PromptMessage;
+use serve::PendingToolUse;
 use spinners::{
     Spinner,
     Spinners,
 };
+use stream_json::{
+    StopReason,
+    StreamEvent,
+};
 use thiserror::Error;
 use time::OffsetDateTime;
 use token_counter::TokenCounter;
@@ -99,7 +123,9 @@ use tokio::signal::ctrl_c;
 use tokio::sync::{
     Mutex,
     broadcast,
+    oneshot,
 };
+use tokio::task::JoinHandle;
 use tool_manager::{
     PromptQuery,
     PromptQueryResult,
@@ -130,6 +156,7 @@ use util::{
 use winnow::Partial;
 use winnow::stream::Offset;
 
+use super::agent::hook::HookTrigger;
 use super::agent::{
     Agent,
     DEFAULT_AGENT_NAME,
@@ -146,10 +173,15 @@ use crate::cli::TodoListState;
 use crate::cli::agent::Agents;
 use crate::cli::chat::checkpoint::{
     CheckpointManager,
+    CheckpointMode,
     truncate_message,
 };
 use crate::cli::chat::cli::SlashCommand;
 use crate::cli::chat::cli::editor::open_editor;
+use crate::cli::chat::cli::mcp::{
+    attach_mentioned_resources,
+    parse_resource_mention,
+};
 use crate::cli::chat::cli::prompts::{
     GetPromptError,
     PromptsSubcommand,
@@ -157,6 +189,10 @@ use crate::cli::chat::cli::prompts::{
 use crate::cli::chat::message::UserMessage;
 use crate::cli::chat::util::sanitize_unicode_tags;
 use crate::database::settings::Setting;
+use crate::mcp_client::{
+    UserPrompt,
+    UserPromptReceiver,
+};
 use crate::os::Os;
 use crate::telemetry::core::{
     AgentConfigInitArgs,
@@ -209,11 +245,26 @@ pub enum WrapMode {
     Auto,
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ChatOutputFormat {
+    /// Render output for a terminal (default)
+    #[default]
+    Text,
+    /// Emit one JSON event per line to stdout. Requires --no-interactive
+    StreamJson,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, Args)]
 pub struct ChatArgs {
-    /// Resumes the previous conversation from this directory.
+    /// Resumes the most recent session from this directory
     #[arg(short, long)]
     pub resume: bool,
+    /// Resumes the session with the given id, or any unique prefix of it
+    #[arg(long, value_name = "SESSION_ID", conflicts_with = "resume")]
+    pub resume_id: Option<String>,
+    /// List the sessions started in this directory and exit
+    #[arg(long, conflicts_with_all = ["resume", "resume_id", "input", "serve"])]
+    pub list_sessions: bool,
     /// Context profile to use
     #[arg(long = "agent", alias = "profile")]
     pub agent: Option<String>,
@@ -235,10 +286,22 @@ pub struct ChatArgs {
     /// Control line wrapping behavior (default: auto-detect)
     #[arg(short = 'w', long, value_enum)]
     pub wrap: Option<WrapMode>,
+    /// Format of the output written to stdout (default: text)
+    #[arg(long, value_enum)]
+    pub output_format: Option<ChatOutputFormat>,
+    /// Serve the chat session over JSON-RPC on stdin and stdout instead of using the terminal
+    #[arg(long, conflicts_with_all = ["no_interactive", "input", "output_format"])]
+    pub serve: bool,
 }
 
 impl ChatArgs {
     pub async fn execute(mut self, os: &mut Os) -> Result<ExitCode> {
+        if self.list_sessions {
+            let sessions = os.database.list_sessions(Some(&std::env::current_dir()?))?;
+            print_sessions(&mut std::io::stdout(), &sessions, None)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+
         let mut input = self.input;
 
         if self.no_interactive && input.is_none() {
@@ -261,7 +324,32 @@ impl ChatArgs {
             }
         }
 
-        let stdout = std::io::stdout();
+        let output_format = match self.serve {
+            true => ChatOutputFormat::StreamJson,
+            false => self.output_format.unwrap_or_default(),
+        };
+        if output_format == ChatOutputFormat::StreamJson && !self.no_interactive && !self.serve {
+            bail!("--output-format stream-json requires --no-interactive");
+        }
+
+        let previous_conversation = match (self.resume_id.take(), self.resume) {
+            (Some(id), _) => os.database.get_session(&find_session(os, &id)?.id)?,
+            (None, true) => {
+                let cwd = std::env::current_dir()?;
+                match os.database.get_latest_session_by_path(&cwd).ok().flatten() {
+                    Some(conversation) => Some(conversation),
+                    // Conversations used to be stored by directory instead of as sessions.
+                    None => os.database.get_conversation_by_path(&cwd).ok().flatten(),
+                }
+            },
+            (None, false) => None,
+        };
+
+        // Start serving before anything else is written to stdout.
+        let serve = match self.serve {
+            true => Some(serve::start()?),
+            false => None,
+        };
         let mut stderr = std::io::stderr();
 
         let args: Vec<String> = std::env::args().collect();
@@ -422,6 +510,15 @@ impl ChatArgs {
             .await?;
         let tool_config = tool_manager.load_tools(os, &mut stderr).await?;
 
+        let (stdout, stderr, input_source): (Box<dyn Write + Send>, Box<dyn Write + Send>, _) = match serve {
+            Some((input, events, display)) => (Box::new(events), Box::new(display), InputSource::new_serve(input)),
+            None => (
+                Box::new(std::io::stdout()),
+                Box::new(stderr),
+                InputSource::new(os, prompt_request_sender, prompt_response_receiver)?,
+            ),
+        };
+
         ChatSession::new(
             os,
             stdout,
@@ -429,8 +526,8 @@ impl ChatArgs {
             &conversation_id,
             agents,
             input,
-            InputSource::new(os, prompt_request_sender, prompt_response_receiver)?,
-            self.resume,
+            input_source,
+            previous_conversation,
             || terminal::window_size().map(|s| s.columns.into()).ok(),
             tool_manager,
             model_id,
@@ -438,6 +535,7 @@ impl ChatArgs {
             !self.no_interactive,
             mcp_enabled,
             self.wrap,
+            output_format,
         )
         .await?
         .spawn(os)
@@ -618,9 +716,9 @@ impl From<parser::RecvError> for ChatError {
 
 pub struct ChatSession {
     /// For output read by humans and machine
-    pub stdout: std::io::Stdout,
+    pub stdout: Box<dyn Write + Send>,
     /// For display output, only read by humans
-    pub stderr: std::io::Stderr,
+    pub stderr: Box<dyn Write + Send>,
     initial_input: Option<String>,
     /// Whether we're starting a new conversation or continuing an old one.
     existing_conversation: bool,
@@ -653,19 +751,22 @@ pub struct ChatSession {
     inner: Option<ChatState>,
     ctrlc_rx: broadcast::Receiver<()>,
     wrap: Option<WrapMode>,
+    output_format: ChatOutputFormat,
+    /// Set when the current user turn ended with an error. Reported in [StreamEvent::Stop].
+    stop_reason: Option<(StopReason, String)>,
 }
 
 impl ChatSession {
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
         os: &mut Os,
-        stdout: std::io::Stdout,
-        mut stderr: std::io::Stderr,
+        stdout: Box<dyn Write + Send>,
+        mut stderr: Box<dyn Write + Send>,
         conversation_id: &str,
         mut agents: Agents,
         mut input: Option<String>,
         input_source: InputSource,
-        resume_conversation: bool,
+        previous_conversation: Option<ConversationState>,
         terminal_width_provider: fn() -> Option<usize>,
         tool_manager: ToolManager,
         model_id: Option<String>,
@@ -673,20 +774,14 @@ impl ChatSession {
         interactive: bool,
         mcp_enabled: bool,
         wrap: Option<WrapMode>,
+        output_format: ChatOutputFormat,
     ) -> Result<Self> {
-        // Reload prior conversation
-        let mut existing_conversation = false;
-        let previous_conversation = std::env::current_dir()
-            .ok()
-            .and_then(|cwd| os.database.get_conversation_by_path(cwd).ok())
-            .flatten();
-
         // Only restore conversations where there were actual messages.
         // Prevents edge case where user clears conversation then exits without chatting.
-        let conversation = match resume_conversation
-            && previous_conversation
-                .as_ref()
-                .is_some_and(|cs| !cs.history().is_empty())
+        let mut existing_conversation = false;
+        let conversation = match previous_conversation
+            .as_ref()
+            .is_some_and(|cs| !cs.history().is_empty())
         {
             true => {
                 let mut cs = previous_conversation.unwrap();
@@ -765,6 +860,8 @@ impl ChatSession {
             inner: Some(ChatState::default()),
             ctrlc_rx,
             wrap,
+            output_format,
+            stop_reason: None,
         })
     }
 
@@ -772,6 +869,17 @@ impl ChatSession {
         // Update conversation state with new tool information
         self.conversation.update_state(false).await;
 
+        // Questions MCP servers sent while none of their tools was running
+        while let Some(prompt) = self
+            .conversation
+            .tool_manager
+            .user_prompts
+            .as_mut()
+            .and_then(|user_prompts| user_prompts.try_recv().ok())
+        {
+            answer_user_prompt(&mut self.input_source, &mut self.stderr, &mut self.spinner, prompt)?;
+        }
+
         let mut ctrl_c_stream = self.ctrlc_rx.resubscribe();
         let result = match self.inner.take().expect("state must always be Some") {
             ChatState::PromptUser { skip_printing_tools } => {
@@ -853,6 +961,13 @@ impl ChatSession {
         // We encountered an error. Handle it.
         error!(?err, "An error occurred processing the current state");
         let (reason, reason_desc) = get_error_reason(&err);
+        self.stop_reason = Some((
+            match err {
+                ChatError::NonInteractiveToolApproval => StopReason::ToolApprovalRequired,
+                _ => StopReason::Error,
+            },
+            err.to_string(),
+        ));
         self.send_error_telemetry(os, reason, Some(reason_desc), err.status_code())
             .await;
 
@@ -948,13 +1063,15 @@ impl ChatSession {
                             },
                         });
 
-                        execute!(
-                            self.stdout,
-                            style::SetForegroundColor(Color::Yellow),
-                            style::Print("The context window has overflowed, summarizing the history..."),
-                            style::SetAttribute(Attribute::Reset),
-                            style::Print("\n\n"),
-                        )?;
+                        if self.output_format == ChatOutputFormat::Text {
+                            execute!(
+                                self.stdout,
+                                style::SetForegroundColor(Color::Yellow),
+                                style::Print("The context window has overflowed, summarizing the history..."),
+                                style::SetAttribute(Attribute::Reset),
+                                style::Print("\n\n"),
+                            )?;
+                        }
 
                         return Ok(());
                     }
@@ -1339,7 +1456,14 @@ impl ChatSession {
         {
             let path = get_shadow_repo_dir(os, self.conversation.conversation_id().to_string())?;
             let start = std::time::Instant::now();
-            let checkpoint_manager = match CheckpointManager::auto_init(os, &path, self.conversation.history()).await {
+            let checkpoint_manager = match CheckpointManager::auto_init(
+                os,
+                &path,
+                self.conversation.history(),
+                CheckpointMode::from_settings(os),
+            )
+            .await
+            {
                 Ok(manager) => {
                     execute!(
                         self.stderr,
@@ -1368,8 +1492,18 @@ impl ChatSession {
 
         while !matches!(self.inner, Some(ChatState::Exit)) {
             self.next(os).await?;
+            self.emit_hook_results()?;
         }
 
+        self.run_session_hooks(os, HookTrigger::SessionEnd, None).await?;
+        self.emit_hook_results()?;
+
+        let (reason, message) = match self.stop_reason.take() {
+            Some((reason, message)) => (reason, Some(message)),
+            None => (StopReason::EndTurn, None),
+        };
+        self.emit_event(StreamEvent::Stop { reason, message })?;
+
         Ok(())
     }
 
@@ -1440,6 +1574,8 @@ impl ChatSession {
             });
         }
 
+        self.run_session_hooks(os, HookTrigger::PreCompact, None).await?;
+
         if strategy.truncate_large_messages {
             info!("truncating large messages");
             execute!(
@@ -1960,11 +2096,49 @@ impl ChatSession {
             style::SetForegroundColor(Color::Reset),
             style::SetAttribute(Attribute::Reset)
         )?;
+        let pending_tool_use = self
+            .pending_tool_index
+            .and_then(|i| self.tool_uses.get(i))
+            .map(|tool| PendingToolUse {
+                tool_use_id: tool.id.clone(),
+                name: tool.name.clone(),
+            });
+        self.input_source.set_pending_tool_use(pending_tool_use);
+
         let prompt = self.generate_tool_trust_prompt(os).await;
-        let user_input = match self.read_user_input(&prompt, false) {
+        // Questions MCP servers send while the user is at the prompt are asked once a line is read
+        let watcher = self
+            .conversation
+            .tool_manager
+            .user_prompts
+            .take()
+            .map(|user_prompts| UserPromptWatcher::spawn(user_prompts, self.input_source.external_printer()));
+        let user_prompt_pending = watcher
+            .as_ref()
+            .map(|watcher| Arc::clone(&watcher.pending))
+            .unwrap_or_default();
+        let user_input = self.read_user_input_or_enter(&prompt, false, &user_prompt_pending);
+        let user_prompt = match watcher {
+            Some(watcher) => watcher.stop().await.and_then(|(user_prompts, user_prompt)| {
+                self.conversation.tool_manager.user_prompts = Some(user_prompts);
+                user_prompt
+            }),
+            None => None,
+        };
+
+        let user_input = match user_input {
             Some(input) => input,
             None => return Ok(ChatState::Exit),
         };
+        if let Some(user_prompt) = user_prompt {
+            answer_user_prompt(&mut self.input_source, &mut self.stderr, &mut self.spinner, user_prompt)?;
+            // The user only pressed Enter to get to the question
+            if user_input.trim().is_empty() {
+                return Ok(ChatState::PromptUser {
+                    skip_printing_tools: true,
+                });
+            }
+        }
 
         self.conversation.append_user_transcript(&user_input);
         Ok(ChatState::HandleInput { input: user_input })
@@ -1972,6 +2146,8 @@ impl ChatSession {
 
     async fn handle_input(&mut self, os: &mut Os, mut user_input: String) -> Result<ChatState, ChatError> {
         queue!(self.stderr, style::Print('\n'))?;
+        // Servers are told about the working directory, which may have changed since the last input
+        self.conversation.tool_manager.update_roots(os).await;
         user_input = sanitize_unicode_tags(&user_input);
         let input = user_input.trim();
 
@@ -2071,7 +2247,10 @@ impl ChatSession {
             Ok(ChatState::PromptUser {
                 skip_printing_tools: false,
             })
-        } else if let Some(command) = input.strip_prefix("@") {
+        } else if let Some(command) = input
+            .strip_prefix("@")
+            .filter(|_| !self.starts_with_resource_mention(input))
+        {
             let input_parts =
                 shlex::split(command).ok_or(ChatError::Custom("Error splitting prompt command".into()))?;
 
@@ -2142,10 +2321,9 @@ impl ChatSession {
 
             // Check for a pending tool approval
             if let Some(index) = self.pending_tool_index {
-                let is_trust = ["t", "T"].contains(&input);
-                let tool_use = &mut self.tool_uses[index];
-                if ["y", "Y"].contains(&input) || is_trust {
-                    if is_trust {
+                if let Some(approval) = self.input_source.tool_approval(input) {
+                    let tool_use = &mut self.tool_uses[index];
+                    if approval == ToolApproval::Trust {
                         let formatted_tool_name = self
                             .conversation
                             .tool_manager
@@ -2179,6 +2357,8 @@ impl ChatSession {
                     .ok_or(ChatError::Custom("Prompt append failed".into()))?;
             }
 
+            user_input = attach_mentioned_resources(self, user_input).await?;
+
             // Otherwise continue with normal chat on 'n' or other responses
             self.tool_use_status = ToolUseStatus::Idle;
 
@@ -2290,6 +2470,13 @@ impl ChatSession {
                 });
             }
 
+            // Decisions made by PreToolUse hooks take precedence over the agent's permissions
+            let allowed = match tool.hook_decision {
+                Some(PermissionDecision::Allow) => true,
+                Some(PermissionDecision::Ask) => false,
+                Some(PermissionDecision::Deny) | None => allowed,
+            };
+
             if os
                 .database
                 .settings
@@ -2343,15 +2530,53 @@ impl ChatSession {
                 }
             }
 
-            let invoke_result = tool
-                .tool
-                .invoke(
-                    os,
-                    &mut self.stdout,
-                    &mut self.conversation.file_line_tracker,
-                    self.conversation.agents.get_active(),
-                )
-                .await;
+            // In stream-json mode, stdout is reserved for events. Tool output is reported with the
+            // tool result instead.
+            let mut sink = std::io::sink();
+            let mut tool_output: &mut dyn Write = match self.output_format {
+                ChatOutputFormat::Text => &mut self.stdout,
+                ChatOutputFormat::StreamJson => &mut sink,
+            };
+            let checkpoint_enabled = os
+                .database
+                .settings
+                .get_bool(Setting::EnabledCheckpoint)
+                .unwrap_or(false)
+                && !self.conversation.is_in_tangent_mode();
+
+            // In touched mode, checkpoints only snapshot the files tools touch. Note them before
+            // the tool changes them.
+            if let Some(manager) = self
+                .conversation
+                .checkpoint_manager
+                .as_mut()
+                .filter(|_| checkpoint_enabled)
+            {
+                let tracked = match &tool.tool {
+                    Tool::FsWrite(fs_write) => manager.track_files(&[fs_write.path(os)]),
+                    Tool::ExecuteCommand(_) => manager.start_command(),
+                    _ => Ok(()),
+                };
+                if let Err(e) = tracked {
+                    debug!("Failed to track touched files: {}", e);
+                }
+            }
+
+            // MCP servers may have questions for the user while their tools run
+            let invoke = tool.tool.invoke(
+                os,
+                &mut tool_output,
+                &mut self.conversation.file_line_tracker,
+                self.conversation.agents.get_active(),
+            );
+            let invoke_result = answering_user_prompts(
+                invoke,
+                &mut self.conversation.tool_manager.user_prompts,
+                &mut self.input_source,
+                &mut self.stderr,
+                &mut self.spinner,
+            )
+            .await?;
 
             if self.spinner.is_some() {
                 queue!(
@@ -2361,17 +2586,22 @@ impl ChatSession {
                     cursor::Show
                 )?;
             }
-            execute!(self.stdout, style::Print("\n"))?;
+            execute!(&mut tool_output, style::Print("\n"))?;
+
+            if let Some(manager) = self
+                .conversation
+                .checkpoint_manager
+                .as_mut()
+                .filter(|_| checkpoint_enabled)
+            {
+                if let Err(e) = manager.finish_command() {
+                    debug!("Failed to track files touched by the command: {}", e);
+                }
+            }
 
             // Handle checkpoint after tool execution - store tag for later display
             let checkpoint_tag: Option<String> = {
-                let enabled = os
-                    .database
-                    .settings
-                    .get_bool(Setting::EnabledCheckpoint)
-                    .unwrap_or(false)
-                    && !self.conversation.is_in_tangent_mode();
-                if invoke_result.is_err() || !enabled {
+                if invoke_result.is_err() || !checkpoint_enabled {
                     None
                 }
                 // Take manager out temporarily to avoid borrow conflicts
@@ -2466,7 +2696,7 @@ impl ChatSession {
 
                     debug!("tool result output: {:#?}", result);
                     execute!(
-                        self.stdout,
+                        &mut tool_output,
                         style::Print(CONTINUATION_LINE),
                         style::Print("\n"),
                         style::SetForegroundColor(Color::Green),
@@ -2476,7 +2706,7 @@ impl ChatSession {
                     )?;
                     if let Some(tag) = checkpoint_tag {
                         execute!(
-                            self.stdout,
+                            &mut tool_output,
                             style::SetForegroundColor(Color::Blue),
                             style::SetAttribute(Attribute::Bold),
                             style::Print(format!(" [{tag}]")),
@@ -2484,7 +2714,7 @@ impl ChatSession {
                             style::SetAttribute(Attribute::Reset),
                         )?;
                     }
-                    execute!(self.stdout, style::Print("\n\n"))?;
+                    execute!(&mut tool_output, style::Print("\n\n"))?;
 
                     tool_telemetry = tool_telemetry.and_modify(|ev| ev.is_success = Some(true));
                     if let Tool::Custom(_) = &tool.tool {
@@ -2601,6 +2831,7 @@ impl ChatSession {
                             os,
                             None,
                             Some(tool_context),
+                            None,
                         )
                         .await;
                 }
@@ -2609,6 +2840,7 @@ impl ChatSession {
 
         if !image_blocks.is_empty() {
             let images = image_blocks.into_iter().map(|(block, _)| block).collect();
+            self.emit_tool_results(&tool_results)?;
             self.conversation.add_tool_results_with_images(tool_results, images);
             execute!(
                 self.stderr,
@@ -2617,6 +2849,7 @@ impl ChatSession {
                 style::Print("\n")
             )?;
         } else {
+            self.emit_tool_results(&tool_results)?;
             self.conversation.add_tool_results(tool_results);
         }
 
@@ -2689,7 +2922,16 @@ impl ChatSession {
         }
 
         loop {
-            match rx.recv().await {
+            // MCP servers may also have questions for the user while the response streams in
+            let event = answering_user_prompts(
+                rx.recv(),
+                &mut self.conversation.tool_manager.user_prompts,
+                &mut self.input_source,
+                &mut self.stderr,
+                &mut self.spinner,
+            )
+            .await?;
+            match event {
                 Some(Ok(msg_event)) => {
                     trace!("Consumed: {:?}", msg_event);
                     match msg_event {
@@ -2700,8 +2942,15 @@ impl ChatSession {
                             tool_name_being_recvd = Some(name);
                         },
                         parser::ResponseEvent::AssistantText(text) => {
+                            if self.output_format == ChatOutputFormat::StreamJson {
+                                self.emit_event(StreamEvent::TextDelta { text: text.clone() })?;
+                            }
+
                             // Add Q response prefix before the first assistant text.
-                            if !response_prefix_printed && !text.trim().is_empty() {
+                            if self.output_format == ChatOutputFormat::Text
+                                && !response_prefix_printed
+                                && !text.trim().is_empty()
+                            {
                                 queue!(
                                     self.stdout,
                                     style::SetForegroundColor(Color::Green),
@@ -2722,6 +2971,11 @@ impl ChatSession {
                                     cursor::Show
                                 )?;
                             }
+                            self.emit_event(StreamEvent::ToolUse {
+                                tool_use_id: tool_use.id.clone(),
+                                name: tool_use.name.clone(),
+                                input: tool_use.args.clone(),
+                            })?;
                             tool_uses.push(tool_use);
                             tool_name_being_recvd = None;
                         },
@@ -2734,6 +2988,22 @@ impl ChatSession {
                             if message.content() == RESPONSE_TIMEOUT_CONTENT {
                                 error!(?request_id, ?message, "Encountered an unexpected model response");
                             }
+                            // Prefer the token usage reported by the service over our estimate.
+                            let (input_tokens, output_tokens) = match rm.token_usage {
+                                Some(usage) => (usage.input_tokens() as usize, usage.output_tokens as usize),
+                                // The history already includes the prompt of this turn
+                                None => {
+                                    let tokenizer = self.conversation.tokenizer(os);
+                                    (
+                                        self.conversation.history_token_count(&tokenizer).value(),
+                                        tokenizer.count([&message]).value(),
+                                    )
+                                },
+                            };
+                            self.emit_event(StreamEvent::Usage {
+                                input_tokens,
+                                output_tokens,
+                            })?;
                             self.conversation.push_assistant_message(os, message, Some(rm.clone()));
                             self.user_turn_request_metadata.push(rm);
                             ended = true;
@@ -2821,6 +3091,7 @@ impl ChatSession {
                                     )],
                                     status: ToolResultStatus::Error,
                                 }];
+                            self.emit_tool_results(&tool_results)?;
                             self.conversation.add_tool_results(tool_results);
                             self.send_tool_use_telemetry(os).await;
                             return Ok(ChatState::HandleResponseStream(
@@ -2860,17 +3131,20 @@ impl ChatSession {
                                 status: ToolResultStatus::Error,
                             }];
                             // User hint of what happened
-                            let _ = queue!(
-                                self.stdout,
-                                style::Print("\n\n"),
-                                style::SetForegroundColor(Color::Yellow),
-                                style::Print(format!(
-                                    "Tool validation failed: {}\n Retrying the request...",
-                                    error_message
-                                )),
-                                style::ResetColor,
-                                style::Print("\n"),
-                            );
+                            if self.output_format == ChatOutputFormat::Text {
+                                let _ = queue!(
+                                    self.stdout,
+                                    style::Print("\n\n"),
+                                    style::SetForegroundColor(Color::Yellow),
+                                    style::Print(format!(
+                                        "Tool validation failed: {}\n Retrying the request...",
+                                        error_message
+                                    )),
+                                    style::ResetColor,
+                                    style::Print("\n"),
+                                );
+                            }
+                            self.emit_tool_results(&tool_results)?;
                             self.conversation.add_tool_results(tool_results);
                             self.send_tool_use_telemetry(os).await;
                             return Ok(ChatState::HandleResponseStream(
@@ -2918,7 +3192,7 @@ impl ChatSession {
             }
 
             // Print the response for normal cases
-            loop {
+            while self.output_format == ChatOutputFormat::Text {
                 let input = Partial::new(&buf[offset..]);
                 match interpret_markdown(input, &mut self.stdout, &mut state) {
                     Ok(parsed) => {
@@ -2958,7 +3232,9 @@ impl ChatSession {
                 }
 
                 queue!(self.stderr, style::ResetColor, style::SetAttribute(Attribute::Reset))?;
-                execute!(self.stdout, style::Print("\n"))?;
+                if self.output_format == ChatOutputFormat::Text {
+                    execute!(self.stdout, style::Print("\n"))?;
+                }
 
                 for (i, citation) in &state.citations {
                     queue!(
@@ -3041,6 +3317,11 @@ impl ChatSession {
                 }
             }
 
+            let assistant_response = self.conversation.last_assistant_response().map(str::to_string);
+            self.run_session_hooks(os, HookTrigger::Stop, assistant_response)
+                .await?;
+            self.stop_reason = None;
+
             self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, true)
                 .await;
 
@@ -3084,6 +3365,7 @@ impl ChatSession {
                                 tool,
                                 accepted: false,
                                 tool_input,
+                                hook_decision: None,
                             });
                         },
                         Err(err) => {
@@ -3136,6 +3418,7 @@ impl ChatSession {
                 }
             }
 
+            self.emit_tool_results(&tool_results)?;
             self.conversation.add_tool_results(tool_results);
             self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, false)
                 .await;
@@ -3158,8 +3441,9 @@ impl ChatSession {
         // Execute PreToolUse hooks for all validated tools
         // The mental model is preToolHook is like validate tools, but its behavior can be customized by
         // user Note that after preTookUse hook, user can still reject the took run
+        let mut updated_inputs: Vec<(usize, serde_json::Value)> = Vec::new();
         if let Some(cm) = self.conversation.context_manager.as_mut() {
-            for tool in &queued_tools {
+            for (i, tool) in queued_tools.iter_mut().enumerate() {
                 let tool_context = ToolContext {
                     tool_name: match &tool.tool {
                         Tool::Custom(custom_tool) => custom_tool.namespaced_tool_name(), // for MCP tool, pass MCP
@@ -3177,30 +3461,129 @@ impl ChatSession {
                         os,
                         None, // prompt
                         Some(tool_context),
+                        None, // session_context
                     )
                     .await?;
 
                 // Here is how we handle the preToolUse hook output:
-                // Exit code is 0: nothing. stdout is not shown to user.
+                // Exit code is 0: if stdout is a JSON object, it is treated as a decision (see
+                // [PreToolUseOutput]). Otherwise nothing, stdout is not shown to user.
                 // Exit code is 2: block the tool use. return stderr to LLM. show warning to user
                 // Other error: show warning to user.
-
-                // Check for exit code 2 and add to tool_results
+                let mut decision = None;
+                let mut deny_reasons = Vec::new();
+                let mut hook_inputs: Vec<serde_json::Value> = Vec::new();
                 for (_, (exit_code, output)) in &hook_results {
-                    if *exit_code == 2 {
-                        tool_results.push(ToolUseResult {
-                            tool_use_id: tool.id.clone(),
-                            content: vec![ToolUseResultBlock::Text(format!(
-                                "PreToolHook blocked the tool execution: {}",
-                                output
-                            ))],
-                            status: ToolResultStatus::Error,
-                        });
+                    match *exit_code {
+                        2 => {
+                            tool_results.push(ToolUseResult {
+                                tool_use_id: tool.id.clone(),
+                                content: vec![ToolUseResultBlock::Text(format!(
+                                    "PreToolHook blocked the tool execution: {}",
+                                    output
+                                ))],
+                                status: ToolResultStatus::Error,
+                            });
+                        },
+                        0 => {
+                            let Some(hook_output) = PreToolUseOutput::parse(output) else {
+                                continue;
+                            };
+
+                            // When hooks disagree, the most restrictive decision wins: deny > ask > allow
+                            if hook_output.decision == Some(PermissionDecision::Deny) {
+                                deny_reasons.extend(hook_output.reason);
+                            }
+                            decision = decision.max(hook_output.decision);
+
+                            if let Some(input) = hook_output.updated_input {
+                                if !hook_inputs.contains(&input) {
+                                    hook_inputs.push(input);
+                                }
+                            }
+                        },
+                        _ => (),
                     }
                 }
+
+                if decision == Some(PermissionDecision::Deny) {
+                    tool_results.push(ToolUseResult {
+                        tool_use_id: tool.id.clone(),
+                        content: vec![ToolUseResultBlock::Text(if deny_reasons.is_empty() {
+                            "PreToolHook denied the tool execution".to_string()
+                        } else {
+                            format!("PreToolHook denied the tool execution: {}", deny_reasons.join("; "))
+                        })],
+                        status: ToolResultStatus::Error,
+                    });
+                    continue;
+                }
+
+                if hook_inputs.len() > 1 {
+                    tool_results.push(ToolUseResult {
+                        tool_use_id: tool.id.clone(),
+                        content: vec![ToolUseResultBlock::Text(
+                            "PreToolHook blocked the tool execution: multiple hooks returned conflicting tool inputs"
+                                .to_string(),
+                        )],
+                        status: ToolResultStatus::Error,
+                    });
+                    continue;
+                }
+
+                tool.hook_decision = decision;
+                if let Some(input) = hook_inputs.pop() {
+                    updated_inputs.push((i, input));
+                }
             }
         }
 
+        // Rewrite tool inputs returned by PreToolUse hooks. The new input goes through the same
+        // parsing and validation as input suggested by the model.
+        for (i, input) in updated_inputs {
+            let tool_use = AssistantToolUse {
+                id: queued_tools[i].id.clone(),
+                name: queued_tools[i].name.clone(),
+                orig_name: queued_tools[i].name.clone(),
+                args: input.clone(),
+                orig_args: input.clone(),
+            };
+            let tool = match self.conversation.tool_manager.get_tool_from_tool_use(tool_use).await {
+                Ok(mut tool) => {
+                    self.contextualize_tool(&mut tool);
+                    match tool.validate(os).await {
+                        Ok(()) => tool,
+                        Err(err) => {
+                            tool_results.push(ToolUseResult {
+                                tool_use_id: queued_tools[i].id.clone(),
+                                content: vec![ToolUseResultBlock::Text(format!(
+                                    "Failed to validate tool input updated by PreToolHook: {err}"
+                                ))],
+                                status: ToolResultStatus::Error,
+                            });
+                            continue;
+                        },
+                    }
+                },
+                Err(err) => {
+                    tool_results.push(err.into());
+                    continue;
+                },
+            };
+
+            queue!(
+                self.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!(
+                    "Tool input for {} was updated by a PreToolUse hook\n",
+                    queued_tools[i].name
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            queued_tools[i].tool = tool;
+            queued_tools[i].tool_input = input;
+        }
+
         // If we have any hook validation errors, return them immediately to the model
         if !tool_results.is_empty() {
             debug!(?tool_results, "Error found in PreToolUse hooks");
@@ -3218,6 +3601,7 @@ impl ChatSession {
                 }
             }
 
+            self.emit_tool_results(&tool_results)?;
             self.conversation.add_tool_results(tool_results);
             return Ok(ChatState::HandleResponseStream(
                 self.conversation
@@ -3286,6 +3670,11 @@ impl ChatSession {
     }
 
     async fn print_tool_description(&mut self, os: &Os, tool_index: usize, trusted: bool) -> Result<(), ChatError> {
+        // Tool uses are reported as stream events instead
+        if self.output_format == ChatOutputFormat::StreamJson {
+            return Ok(());
+        }
+
         let tool_use = &self.tool_uses[tool_index];
 
         queue!(
@@ -3328,11 +3717,22 @@ impl ChatSession {
 
     /// Helper function to read user input with a prompt and Ctrl+C handling
     fn read_user_input(&mut self, prompt: &str, exit_on_single_ctrl_c: bool) -> Option<String> {
+        self.read_user_input_or_enter(prompt, exit_on_single_ctrl_c, &AtomicBool::new(false))
+    }
+
+    /// Like [Self::read_user_input], but empty lines are returned once `user_prompt_pending` is
+    /// set, so that the user can get to a question from an MCP server by pressing Enter.
+    fn read_user_input_or_enter(
+        &mut self,
+        prompt: &str,
+        exit_on_single_ctrl_c: bool,
+        user_prompt_pending: &AtomicBool,
+    ) -> Option<String> {
         let mut ctrl_c = false;
         loop {
             match (self.input_source.read_line(Some(prompt)), ctrl_c) {
                 (Ok(Some(line)), _) => {
-                    if line.trim().is_empty() {
+                    if line.trim().is_empty() && !user_prompt_pending.load(Ordering::SeqCst) {
                         continue; // Reprompt if the input is empty
                     }
                     return Some(line);
@@ -3395,6 +3795,16 @@ impl ChatSession {
         (self.terminal_width_provider)().unwrap_or(80)
     }
 
+    /// Whether the input starts with a `@server:uri` mention of a resource rather than the name
+    /// of a prompt
+    fn starts_with_resource_mention(&self, input: &str) -> bool {
+        input
+            .split_whitespace()
+            .next()
+            .and_then(parse_resource_mention)
+            .is_some_and(|(server, _)| self.conversation.tool_manager.clients.contains_key(server))
+    }
+
     fn all_tools_trusted(&self) -> bool {
         self.conversation.agents.trust_all_tools
     }
@@ -3426,6 +3836,77 @@ impl ChatSession {
         Ok(())
     }
 
+    /// Runs the hooks configured for a session lifecycle `trigger`, i.e. [HookTrigger::Stop],
+    /// [HookTrigger::SessionEnd], or [HookTrigger::PreCompact].
+    ///
+    /// The output of these hooks is not added to the conversation. Hooks that fail are only
+    /// reported to the user.
+    async fn run_session_hooks(
+        &mut self,
+        os: &Os,
+        trigger: HookTrigger,
+        assistant_response: Option<String>,
+    ) -> Result<(), ChatError> {
+        let has_hooks = self
+            .conversation
+            .context_manager
+            .as_ref()
+            .and_then(|cm| cm.hooks.get(&trigger))
+            .is_some_and(|hooks| !hooks.is_empty());
+        if !has_hooks {
+            return Ok(());
+        }
+
+        let session_context = SessionContext {
+            conversation_id: self.conversation.conversation_id().to_string(),
+            transcript: self.conversation.transcript.iter().cloned().collect(),
+            assistant_response,
+        };
+
+        if let Some(cm) = self.conversation.context_manager.as_mut() {
+            cm.run_hooks(trigger, &mut self.stderr, os, None, None, Some(session_context))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `event` to stdout as a single line of JSON. Does nothing unless the session is
+    /// using [ChatOutputFormat::StreamJson].
+    fn emit_event(&mut self, event: StreamEvent) -> Result<(), ChatError> {
+        if self.output_format != ChatOutputFormat::StreamJson {
+            return Ok(());
+        }
+
+        let line = serde_json::to_string(&event)
+            .map_err(|err| ChatError::Custom(format!("Failed to serialize stream event: {err}").into()))?;
+        writeln!(self.stdout, "{line}")?;
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+
+    fn emit_tool_results(&mut self, tool_results: &[ToolUseResult]) -> Result<(), ChatError> {
+        for result in tool_results {
+            self.emit_event(result.into())?;
+        }
+
+        Ok(())
+    }
+
+    /// Emits the results of all hooks that were run since the last call.
+    fn emit_hook_results(&mut self) -> Result<(), ChatError> {
+        let Some(cm) = self.conversation.context_manager.as_mut() else {
+            return Ok(());
+        };
+
+        for result in &cm.hook_executor.take_results() {
+            self.emit_event(result.into())?;
+        }
+
+        Ok(())
+    }
+
     /// Resets state associated with the active user turn.
     ///
     /// This should *always* be called whenever a new user prompt is sent to the backend. Note
@@ -3708,6 +4189,112 @@ fn does_input_reference_file(input: &str) -> Option<ChatState> {
     None
 }
 
+/// Asks the user a question from a request of an MCP server and sends back the answer. The spinner
+/// is stopped first so that it doesn't draw over the question.
+fn answer_user_prompt(
+    input_source: &mut InputSource,
+    stderr: &mut impl Write,
+    spinner: &mut Option<Spinner>,
+    prompt: UserPrompt,
+) -> Result<(), ChatError> {
+    if spinner.take().is_some() {
+        queue!(
+            stderr,
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            cursor::MoveToColumn(0),
+        )?;
+    }
+    execute!(stderr, cursor::Show, style::Print(&prompt.message))?;
+
+    let answer = match input_source.read_answer(&prompt.question, prompt.secret) {
+        Ok(answer) => answer,
+        Err(e) => {
+            warn!("Failed to read the answer to a question from an MCP server: {e}");
+            None
+        },
+    };
+    // The request may have been cancelled in the meantime
+    let _ = prompt.answer.send(answer);
+    Ok(())
+}
+
+/// Runs `future` to completion, asking the user the questions MCP servers send in the meantime.
+async fn answering_user_prompts<T>(
+    future: impl Future<Output = T>,
+    user_prompts: &mut Option<UserPromptReceiver>,
+    input_source: &mut InputSource,
+    stderr: &mut impl Write,
+    spinner: &mut Option<Spinner>,
+) -> Result<T, ChatError> {
+    tokio::pin!(future);
+    loop {
+        let user_prompt = async {
+            match user_prompts.as_mut() {
+                Some(user_prompts) => user_prompts.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            result = &mut future => return Ok(result),
+            Some(prompt) = user_prompt => answer_user_prompt(input_source, stderr, spinner, prompt)?,
+        }
+    }
+}
+
+/// Shown above the prompt when an MCP server asks a question while the user is at the prompt
+const USER_PROMPT_NOTICE: &str = "An MCP server is waiting for an answer. Press Enter to answer it.\n";
+
+/// Waits for a question from an MCP server while a line is read from the user, and tells the user
+/// how to get to it. The line being read blocks the session, so the question is only asked once it
+/// has been read.
+struct UserPromptWatcher {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<(UserPromptReceiver, Option<UserPrompt>)>,
+    /// Set once a question came
+    pending: Arc<AtomicBool>,
+}
+
+impl UserPromptWatcher {
+    fn spawn(
+        mut user_prompts: UserPromptReceiver,
+        mut printer: Option<Box<dyn rustyline::ExternalPrinter + Send>>,
+    ) -> Self {
+        let (stop, mut stopped) = oneshot::channel();
+        let pending = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn({
+            let pending = Arc::clone(&pending);
+            async move {
+                let user_prompt = tokio::select! {
+                    Some(user_prompt) = user_prompts.recv() => Some(user_prompt),
+                    _ = &mut stopped => None,
+                };
+                if user_prompt.is_some() {
+                    pending.store(true, Ordering::SeqCst);
+                    if let Some(printer) = printer.as_mut() {
+                        if let Err(e) = printer.print(USER_PROMPT_NOTICE.to_string()) {
+                            warn!("Failed to show that an MCP server has a question: {e}");
+                        }
+                    }
+                }
+                (user_prompts, user_prompt)
+            }
+        });
+        Self { stop, task, pending }
+    }
+
+    /// Stops waiting. Returns the receiver of the questions and the question that came, if any.
+    async fn stop(self) -> Option<(UserPromptReceiver, Option<UserPrompt>)> {
+        let _ = self.stop.send(());
+        match self.task.await {
+            Ok(result) => Some(result),
+            Err(e) => {
+                error!("Failed to wait for questions from MCP servers: {e}");
+                None
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -3770,8 +4357,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -3780,7 +4367,7 @@ mod tests {
                 "y".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3788,6 +4375,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -3798,6 +4386,97 @@ mod tests {
         assert_eq!(os.fs.read_to_string("/file.txt").await.unwrap(), "Hello, world!\n");
     }
 
+    /// Collects what the session writes so tests can inspect it
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flow_stream_json() {
+        let mut os = Os::new().await.unwrap();
+        os.fs.write("/notes.txt", "remember the milk").await.unwrap();
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Let me read it",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_read",
+                    "args": {
+                        "operations": [{ "mode": "Line", "path": "/notes.txt" }]
+                    }
+                }
+            ],
+            [
+                "It says to remember the milk",
+            ],
+        ]));
+
+        let stdout = SharedBuffer::default();
+        let agents = get_test_agents(&os).await;
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            Box::new(stdout.clone()),
+            Box::new(std::io::sink()),
+            "fake_conv_id",
+            agents,
+            Some("what is in my notes?".to_string()),
+            InputSource::new_mock(vec![]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            false,
+            false,
+            None,
+            ChatOutputFormat::StreamJson,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        let output = String::from_utf8(stdout.0.lock().unwrap().clone()).unwrap();
+        let events = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("every line is a JSON event"))
+            .collect::<Vec<_>>();
+        let types = events.iter().map(|e| e["type"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(types.first(), Some(&"text_delta"));
+        assert_eq!(types.last(), Some(&"stop"));
+        assert_eq!(events.last().unwrap()["reason"], "end_turn");
+
+        let tool_use = events.iter().find(|e| e["type"] == "tool_use").unwrap();
+        assert_eq!(tool_use["tool_use_id"], "1");
+        assert_eq!(tool_use["name"], "fs_read");
+        let tool_result = events.iter().find(|e| e["type"] == "tool_result").unwrap();
+        assert_eq!(tool_result["tool_use_id"], "1");
+        assert_eq!(tool_result["status"], "success");
+        assert_eq!(types.iter().filter(|t| **t == "usage").count(), 2);
+
+        let text = events
+            .iter()
+            .filter(|e| e["type"] == "text_delta")
+            .map(|e| e["text"].as_str().unwrap())
+            .collect::<String>();
+        assert!(text.contains("Let me read it"));
+        assert!(text.contains("It says to remember the milk"));
+    }
+
     #[tokio::test]
     async fn test_flow_tool_permissions() {
         let mut os = Os::new().await.unwrap();
@@ -3900,8 +4579,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -3923,7 +4602,7 @@ mod tests {
                 "n".to_string(),             // cancel
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3931,6 +4610,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -4007,8 +4687,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -4021,7 +4701,7 @@ mod tests {
                 "y".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -4029,6 +4709,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -4085,8 +4766,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -4097,7 +4778,7 @@ mod tests {
                 "create a new file".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -4105,6 +4786,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -4143,13 +4825,13 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
             InputSource::new_mock(vec!["/subscribe".to_string(), "y".to_string(), "/quit".to_string()]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -4157,6 +4839,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -4248,8 +4931,8 @@ mod tests {
         // Test that PreToolUse hook runs
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None, // No initial input
@@ -4258,7 +4941,7 @@ mod tests {
                 "y".to_string(), // Accept tool execution
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -4266,6 +4949,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -4384,13 +5068,13 @@ mod tests {
         // Run chat session - hook should block tool execution
         let result = ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
             "test_conv_id",
             agents,
             None,
             InputSource::new_mock(vec!["read /sensitive.txt".to_string(), "exit".to_string()]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -4398,6 +5082,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Text,
         )
         .await
         .unwrap()
@@ -4411,6 +5096,88 @@ mod tests {
         );
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pretool_hook_json_decision_integration() {
+        use std::collections::HashMap;
+
+        use crate::cli::agent::hook::{
+            Hook,
+            HookTrigger,
+        };
+
+        let mut os = Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Sure, I'll create a file for you",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file.txt",
+                    }
+                }
+            ],
+            [
+                "Done",
+            ],
+        ]));
+
+        // Hook that allows the tool use without prompting, but redirects the write to another file
+        let hook_command = r#"echo '{"decision": "allow", "updated_input": {"command": "create", "file_text": "Hello, hook!", "path": "/redirected.txt"}}'"#;
+
+        let mut agents = Agents::default();
+        let mut hooks = HashMap::new();
+        hooks.insert(HookTrigger::PreToolUse, vec![Hook {
+            command: hook_command.to_string(),
+            timeout_ms: 5000,
+            max_output_size: 1024,
+            cache_ttl_seconds: 0,
+            matcher: Some("fs_write".to_string()),
+            source: crate::cli::agent::hook::Source::Agent,
+        }]);
+        let agent = Agent {
+            name: "PolicyAgent".to_string(),
+            hooks,
+            ..Default::default()
+        };
+        agents.agents.insert("PolicyAgent".to_string(), agent);
+        agents.switch("PolicyAgent").expect("Failed to switch agent");
+
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+
+        ChatSession::new(
+            &mut os,
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
+            "test_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec!["create a new file".to_string(), "exit".to_string()]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            ChatOutputFormat::Text,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        assert_eq!(os.fs.read_to_string("/redirected.txt").await.unwrap(), "Hello, hook!\n");
+        assert!(!os.fs.exists("/file.txt"));
+    }
+
     #[test]
     fn test_does_input_reference_file() {
         let tests = &[
@@ -4427,6 +5194,85 @@ mod tests {
             assert_eq!(actual, *expected, "expected {} for input {}", expected, input);
         }
     }
+
+    #[test]
+    fn test_answer_user_prompt() {
+        let mut input_source = InputSource::new_mock(vec!["y".to_string()]);
+        let mut stderr = Vec::new();
+
+        let (answer, mut answer_receiver) = tokio::sync::oneshot::channel();
+        let prompt = UserPrompt {
+            message: "MCP server docs requests a response\n".to_string(),
+            question: "Allow this request? ".to_string(),
+            secret: false,
+            answer,
+        };
+        answer_user_prompt(&mut input_source, &mut stderr, &mut None, prompt).unwrap();
+        assert_eq!(answer_receiver.try_recv().unwrap(), Some("y".to_string()));
+        assert!(String::from_utf8_lossy(&stderr).contains("MCP server docs requests a response"));
+
+        // Input ended, e.g. with Ctrl+D
+        let (answer, mut answer_receiver) = tokio::sync::oneshot::channel();
+        let prompt = UserPrompt {
+            message: String::new(),
+            question: "Allow this request? ".to_string(),
+            secret: false,
+            answer,
+        };
+        answer_user_prompt(&mut input_source, &mut stderr, &mut None, prompt).unwrap();
+        assert_eq!(answer_receiver.try_recv().unwrap(), None);
+    }
+
+    fn user_prompt(question: &str) -> (UserPrompt, oneshot::Receiver<Option<String>>) {
+        let (answer, answer_receiver) = oneshot::channel();
+        let prompt = UserPrompt {
+            message: String::new(),
+            question: question.to_string(),
+            secret: false,
+            answer,
+        };
+        (prompt, answer_receiver)
+    }
+
+    #[tokio::test]
+    async fn test_answering_user_prompts() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let mut user_prompts = Some(receiver);
+        let mut input_source = InputSource::new_mock(vec!["y".to_string()]);
+        let mut stderr = Vec::new();
+
+        // A question sent outside of a tool call, e.g. while a response streams in, is answered
+        // while the response is still being waited for
+        let response = async {
+            let (prompt, answer_receiver) = user_prompt("Allow this request? ");
+            sender.send(prompt).await.unwrap();
+            answer_receiver.await.unwrap()
+        };
+        let answer = answering_user_prompts(response, &mut user_prompts, &mut input_source, &mut stderr, &mut None)
+            .await
+            .unwrap();
+        assert_eq!(answer, Some("y".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_user_prompt_watcher() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+
+        // No question while the user was at the prompt
+        let watcher = UserPromptWatcher::spawn(receiver, None);
+        let (receiver, prompt) = watcher.stop().await.unwrap();
+        assert!(prompt.is_none());
+
+        // A question sent while the user is at the prompt is held until the line is read
+        let watcher = UserPromptWatcher::spawn(receiver, None);
+        let (prompt, _answer_receiver) = user_prompt("Allow this request? ");
+        sender.send(prompt).await.unwrap();
+        while !watcher.pending.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+        let (_, prompt) = watcher.stop().await.unwrap();
+        assert_eq!(prompt.unwrap().question, "Allow this request? ");
+    }
 }
 
 // Helper method to save the agent config to file
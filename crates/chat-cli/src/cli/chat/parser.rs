@@ -32,6 +32,7 @@ use super::message::{
 use crate::api_client::model::{
     ChatResponseStream,
     ConversationState,
+    TokenUsage,
 };
 use crate::api_client::send_message_output::SendMessageOutput;
 use crate::api_client::{
@@ -320,6 +321,8 @@ struct ResponseParser {
     request_start_time_sys: SystemTime,
     /// Total size (in bytes) of the response received so far.
     received_response_size: usize,
+    /// Token usage reported by the service.
+    token_usage: Option<TokenUsage>,
     time_to_first_chunk: Option<Duration>,
     time_between_chunks: Vec<Duration>,
 }
@@ -353,6 +356,7 @@ impl ResponseParser {
             request_start_time,
             request_start_time_sys,
             received_response_size: 0,
+            token_usage: None,
             time_to_first_chunk: None,
             time_between_chunks: Vec::new(),
             request_metadata,
@@ -614,6 +618,11 @@ impl ResponseParser {
                         ChatResponseStream::ToolUseEvent { input, .. } => {
                             self.received_response_size += input.as_ref().map(String::len).unwrap_or_default();
                         },
+                        ChatResponseStream::MetadataEvent { token_usage } => {
+                            if token_usage.is_some() {
+                                self.token_usage = *token_usage;
+                            }
+                        },
                         _ => {
                             warn!(?r, "received unexpected event from the response stream");
                         },
@@ -661,6 +670,7 @@ impl ResponseParser {
                 .map(|t| (t.id.clone(), t.name.clone()))
                 .collect::<_>(),
             model_id: self.model_id.clone(),
+            token_usage: self.token_usage,
         }
     }
 }
@@ -712,6 +722,9 @@ pub struct RequestMetadata {
     pub model_id: Option<String>,
     /// Meta tags for the request.
     pub message_meta_tags: Vec<MessageMetaTag>,
+    /// Token usage reported by the service, if any.
+    #[serde(default)]
+    pub token_usage: Option<TokenUsage>,
 }
 
 fn system_time_to_unix_ms(time: SystemTime) -> u64 {
@@ -63,6 +63,8 @@ pub const COMMANDS: &[&str] = &[
     "/tools trust-all",
     "/tools reset",
     "/mcp",
+    "/mcp resources",
+    "/mcp read",
     "/model",
     "/experiment",
     "/agent",
@@ -96,6 +98,21 @@ pub const COMMANDS: &[&str] = &[
     "/changelog",
     "/save",
     "/load",
+    "/export",
+    "/sessions",
+    "/sessions list",
+    "/sessions resume",
+    "/sessions fork",
+    "/sessions rename",
+    "/sessions delete",
+    "/branch",
+    "/branch list",
+    "/branch log",
+    "/branch create",
+    "/branch switch",
+    "/branch diff",
+    "/branch promote",
+    "/branch delete",
     "/subscribe",
     "/todos",
     "/todos resume",
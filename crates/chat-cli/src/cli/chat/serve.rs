@@ -0,0 +1,674 @@
+//! JSON-RPC 2.0 transport used by `q chat --serve`.
+//!
+//! Requests are read from stdin, one JSON object per line, and translated into the same input
+//! lines a user would type at the prompt. Tool uses are only approved through
+//! `chat/approve_tool`, never by the text of a prompt. Events emitted by the session in
+//! [ChatOutputFormat::StreamJson](super::ChatOutputFormat::StreamJson) format are forwarded as
+//! `chat/event` notifications, and everything else the session displays is captured and returned
+//! in the response to the request that produced it.
+
+use std::io::{
+    self,
+    BufRead,
+    Write,
+};
+use std::sync::mpsc::{
+    Receiver,
+    Sender,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+    MutexGuard,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Value,
+    json,
+};
+use strip_ansi_escapes::strip_str;
+use tracing::warn;
+
+use super::input_source::ToolApproval;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Returned when a tool approval is sent while no tool use is awaiting approval.
+const NO_PENDING_TOOL_USE: i64 = -32000;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A method supported by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Method {
+    /// `chat/prompt`: send a prompt, handled exactly as if it was typed by the user.
+    Prompt { text: String },
+    /// `chat/command`: run a slash command, e.g. `/context show`.
+    Command { command: String },
+    /// `chat/approve_tool`: approve the tool use awaiting approval, optionally trusting the tool
+    /// for the rest of the session.
+    ApproveTool { trust: bool },
+    /// `chat/deny_tool`: deny the tool use awaiting approval. If a reason is given, it is sent to
+    /// the model instead.
+    DenyTool { reason: Option<String> },
+    /// `chat/list_checkpoints`
+    ListCheckpoints,
+    /// `chat/restore_checkpoint`
+    RestoreCheckpoint { tag: String, hard: bool },
+    /// `shutdown`: end the session.
+    Shutdown,
+}
+
+impl Method {
+    fn parse(method: &str, params: Value) -> Result<Self, RpcError> {
+        #[derive(Deserialize)]
+        struct PromptParams {
+            text: String,
+        }
+
+        #[derive(Deserialize)]
+        struct CommandParams {
+            command: String,
+        }
+
+        #[derive(Deserialize)]
+        struct ApproveToolParams {
+            #[serde(default)]
+            trust: bool,
+        }
+
+        #[derive(Deserialize)]
+        struct DenyToolParams {
+            reason: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct RestoreCheckpointParams {
+            tag: String,
+            #[serde(default)]
+            hard: bool,
+        }
+
+        fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+            let params = match params {
+                Value::Null => Value::Object(Default::default()),
+                params => params,
+            };
+            serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+        }
+
+        let method = match method {
+            "chat/prompt" => {
+                let PromptParams { text } = parse_params(params)?;
+                if text.trim().is_empty() {
+                    return Err(RpcError::new(INVALID_PARAMS, "text must not be empty"));
+                }
+                Self::Prompt { text }
+            },
+            "chat/command" => {
+                let CommandParams { command } = parse_params(params)?;
+                if !command.starts_with('/') {
+                    return Err(RpcError::new(INVALID_PARAMS, "command must start with '/'"));
+                }
+                Self::Command { command }
+            },
+            "chat/approve_tool" => {
+                let ApproveToolParams { trust } = parse_params(params)?;
+                Self::ApproveTool { trust }
+            },
+            "chat/deny_tool" => {
+                let DenyToolParams { reason } = parse_params(params)?;
+                Self::DenyTool { reason }
+            },
+            "chat/list_checkpoints" => Self::ListCheckpoints,
+            "chat/restore_checkpoint" => {
+                let RestoreCheckpointParams { tag, hard } = parse_params(params)?;
+                if tag.is_empty() || tag.contains(char::is_whitespace) {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!("invalid checkpoint tag: '{tag}'"),
+                    ));
+                }
+                Self::RestoreCheckpoint { tag, hard }
+            },
+            "shutdown" => Self::Shutdown,
+            other => return Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method: {other}"))),
+        };
+
+        Ok(method)
+    }
+
+    /// Converts the method into the line the session reads as user input, along with the approval
+    /// of the pending tool use if the method grants one.
+    fn into_input(self, tool_use_pending: bool) -> Result<(String, Option<ToolApproval>), RpcError> {
+        let tool_approval = match self {
+            Self::ApproveTool { .. } | Self::DenyTool { .. } if !tool_use_pending => {
+                return Err(RpcError::new(NO_PENDING_TOOL_USE, "no tool use is awaiting approval"));
+            },
+            Self::ApproveTool { trust: true } => Some(ToolApproval::Trust),
+            Self::ApproveTool { trust: false } => Some(ToolApproval::Accept),
+            _ => None,
+        };
+
+        let line = match self {
+            Self::Prompt { text } => text,
+            Self::Command { command } => command,
+            Self::ApproveTool { trust: true } => "t".to_string(),
+            Self::ApproveTool { trust: false } => "y".to_string(),
+            Self::DenyTool { reason } => match reason {
+                Some(reason) if !reason.trim().is_empty() => reason,
+                _ => "n".to_string(),
+            },
+            Self::ListCheckpoints => "/checkpoint list".to_string(),
+            Self::RestoreCheckpoint { tag, hard } => match hard {
+                true => format!("/checkpoint restore {tag} --hard"),
+                false => format!("/checkpoint restore {tag}"),
+            },
+            Self::Shutdown => "/quit".to_string(),
+        };
+
+        Ok((line, tool_approval))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    /// [None] for notifications, which don't receive a response.
+    id: Option<Value>,
+    method: Method,
+}
+
+impl Request {
+    /// Parses a single line of input. On failure, returns the id of the request (if any) along
+    /// with the error to respond with.
+    fn parse(line: &str) -> Result<Self, (Option<Value>, RpcError)> {
+        #[derive(Deserialize)]
+        struct RawRequest {
+            jsonrpc: String,
+            id: Option<Value>,
+            method: String,
+            #[serde(default)]
+            params: Value,
+        }
+
+        let value: Value = serde_json::from_str(line)
+            .map_err(|err| (Some(Value::Null), RpcError::new(PARSE_ERROR, err.to_string())))?;
+        let id = value.get("id").cloned();
+        let raw: RawRequest = serde_json::from_value(value).map_err(|err| {
+            (
+                Some(id.clone().unwrap_or_default()),
+                RpcError::new(INVALID_REQUEST, err.to_string()),
+            )
+        })?;
+        if raw.jsonrpc != "2.0" {
+            return Err((
+                Some(raw.id.unwrap_or_default()),
+                RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+            ));
+        }
+
+        match Method::parse(&raw.method, raw.params) {
+            Ok(method) => Ok(Self { id: raw.id, method }),
+            Err(err) => Err((raw.id, err)),
+        }
+    }
+}
+
+/// A tool use awaiting approval through `chat/approve_tool` or `chat/deny_tool`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingToolUse {
+    pub tool_use_id: String,
+    pub name: String,
+}
+
+/// Writes JSON-RPC messages to the client.
+#[derive(Clone)]
+struct Transport(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl std::fmt::Debug for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Transport").finish()
+    }
+}
+
+impl Transport {
+    fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self(Arc::new(Mutex::new(writer)))
+    }
+
+    fn send(&self, message: &Value) -> io::Result<()> {
+        let mut writer = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        writeln!(writer, "{message}")?;
+        writer.flush()
+    }
+
+    fn send_result(&self, id: Value, result: Value) -> io::Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    fn send_error(&self, id: Option<Value>, error: RpcError) -> io::Result<()> {
+        // Notifications never receive a response, even on error.
+        let Some(id) = id else {
+            warn!(?error, "Invalid JSON-RPC notification");
+            return Ok(());
+        };
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "error": error }))
+    }
+}
+
+/// Collects the display output of the session (its `stderr`) while a request is being handled.
+#[derive(Debug, Clone, Default)]
+pub struct OutputCapture(Arc<Mutex<Vec<u8>>>);
+
+impl OutputCapture {
+    fn lock(&self) -> MutexGuard<'_, Vec<u8>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns everything written since the last call, without ANSI escape codes.
+    fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.lock());
+        strip_str(String::from_utf8_lossy(&bytes))
+    }
+}
+
+impl Write for OutputCapture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Used as the session's `stdout`. Each line of JSON written to it is sent as a `chat/event`
+/// notification, anything else is treated as display output.
+#[derive(Debug)]
+pub struct EventWriter {
+    transport: Transport,
+    display: OutputCapture,
+    buf: Vec<u8>,
+}
+
+impl Write for EventWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let line = self.buf.drain(..=pos).collect::<Vec<_>>();
+            match serde_json::from_slice::<Value>(&line) {
+                Ok(event @ Value::Object(_)) => {
+                    self.transport
+                        .send(&json!({ "jsonrpc": "2.0", "method": "chat/event", "params": event }))?;
+                },
+                _ => self.display.write_all(&line)?,
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads requests from the client for the session's [InputSource](super::InputSource).
+///
+/// The response to a request is sent once the session asks for the next line of input, i.e. once
+/// it has finished handling the request.
+#[derive(Debug)]
+pub struct ServeInput {
+    transport: Transport,
+    display: OutputCapture,
+    requests: Receiver<Request>,
+    /// Id of the request currently being handled by the session.
+    pending_id: Option<Value>,
+    pending_tool_use: Option<PendingToolUse>,
+    /// Approval granted by the request currently being handled, if any.
+    tool_approval: Option<ToolApproval>,
+}
+
+impl ServeInput {
+    pub fn set_pending_tool_use(&mut self, tool_use: Option<PendingToolUse>) {
+        self.pending_tool_use = tool_use;
+    }
+
+    /// Responds to the request currently being handled and blocks until the next valid request
+    /// is received. Returns [None] once stdin is closed.
+    pub fn read_line(&mut self) -> Option<String> {
+        self.respond();
+        self.tool_approval = None;
+
+        loop {
+            let request = self.requests.recv().ok()?;
+            match request.method.into_input(self.pending_tool_use.is_some()) {
+                Ok((line, tool_approval)) => {
+                    self.pending_id = request.id;
+                    self.tool_approval = tool_approval;
+                    return Some(line);
+                },
+                Err(err) => {
+                    let _ = self.transport.send_error(request.id, err);
+                },
+            }
+        }
+    }
+
+    /// Returns the approval granted by the request that produced the last line, if any.
+    pub fn tool_approval(&self) -> Option<ToolApproval> {
+        self.tool_approval
+    }
+
+    fn respond(&mut self) {
+        let output = self.display.take();
+        if let Some(id) = self.pending_id.take() {
+            let result = json!({
+                "output": output,
+                "pending_tool_use": self.pending_tool_use,
+            });
+            if let Err(err) = self.transport.send_result(id, result) {
+                warn!(?err, "Failed to send JSON-RPC response");
+            }
+        }
+    }
+}
+
+impl Drop for ServeInput {
+    fn drop(&mut self) {
+        self.respond();
+    }
+}
+
+/// Starts reading requests from stdin.
+///
+/// Returns the input source along with the writers to use as the session's `stdout` and
+/// `stderr`. On unix, the process' stdout is redirected to stderr so that only JSON-RPC messages
+/// are written to the original stdout.
+pub fn start() -> io::Result<(ServeInput, EventWriter, OutputCapture)> {
+    let transport = Transport::new(protocol_writer()?);
+    let display = OutputCapture::default();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let reader_transport = transport.clone();
+    std::thread::spawn(move || read_requests(io::stdin().lock(), &tx, &reader_transport));
+
+    Ok((
+        ServeInput {
+            transport: transport.clone(),
+            display: display.clone(),
+            requests: rx,
+            pending_id: None,
+            pending_tool_use: None,
+            tool_approval: None,
+        },
+        EventWriter {
+            transport,
+            display: display.clone(),
+            buf: Vec::new(),
+        },
+        display,
+    ))
+}
+
+fn read_requests(reader: impl BufRead, tx: &Sender<Request>, transport: &Transport) {
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match Request::parse(&line) {
+            Ok(request) => {
+                if tx.send(request).is_err() {
+                    break;
+                }
+            },
+            Err((id, err)) => {
+                let _ = transport.send_error(id, err);
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+fn protocol_writer() -> io::Result<Box<dyn Write + Send>> {
+    use std::os::fd::{
+        AsFd,
+        AsRawFd,
+    };
+
+    // Spinners and other output written directly to stdout would otherwise corrupt the protocol.
+    let mut stdout = io::stdout();
+    stdout.flush()?;
+    let fd = stdout.as_fd().try_clone_to_owned()?;
+    nix::unistd::dup2(io::stderr().as_raw_fd(), stdout.as_raw_fd())?;
+
+    Ok(Box::new(std::fs::File::from(fd)))
+}
+
+#[cfg(not(unix))]
+fn protocol_writer() -> io::Result<Box<dyn Write + Send>> {
+    Ok(Box::new(io::stdout()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Request, (Option<Value>, RpcError)> {
+        Request::parse(line)
+    }
+
+    fn sent(capture: &OutputCapture) -> Vec<Value> {
+        capture
+            .take()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_request_parse() {
+        assert_eq!(
+            parse(r#"{"jsonrpc":"2.0","id":1,"method":"chat/prompt","params":{"text":"hello"}}"#).unwrap(),
+            Request {
+                id: Some(json!(1)),
+                method: Method::Prompt {
+                    text: "hello".to_string()
+                },
+            }
+        );
+        assert_eq!(
+            parse(r#"{"jsonrpc":"2.0","id":"a","method":"chat/approve_tool"}"#).unwrap(),
+            Request {
+                id: Some(json!("a")),
+                method: Method::ApproveTool { trust: false },
+            }
+        );
+        assert_eq!(
+            parse(r#"{"jsonrpc":"2.0","method":"shutdown","params":null}"#).unwrap(),
+            Request {
+                id: None,
+                method: Method::Shutdown,
+            }
+        );
+
+        assert_eq!(parse("not json").unwrap_err().1.code, PARSE_ERROR);
+        assert_eq!(
+            parse(r#"{"jsonrpc":"1.0","id":2,"method":"shutdown"}"#).unwrap_err(),
+            (
+                Some(json!(2)),
+                RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")
+            )
+        );
+        assert_eq!(
+            parse(r#"{"jsonrpc":"2.0","id":3,"method":"chat/unknown"}"#)
+                .unwrap_err()
+                .1
+                .code,
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            parse(r#"{"jsonrpc":"2.0","id":4,"method":"chat/command","params":{"command":"help"}}"#)
+                .unwrap_err()
+                .1
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            parse(r#"{"jsonrpc":"2.0","id":5,"method":"chat/restore_checkpoint","params":{}}"#)
+                .unwrap_err()
+                .1
+                .code,
+            INVALID_PARAMS
+        );
+    }
+
+    #[test]
+    fn test_method_into_input() {
+        let input = |method: Method, pending: bool| method.into_input(pending).map(|(line, _)| line);
+        let approval = |method: Method| method.into_input(true).unwrap().1;
+
+        assert_eq!(input(Method::ApproveTool { trust: false }, true).unwrap(), "y");
+        assert_eq!(input(Method::ApproveTool { trust: true }, true).unwrap(), "t");
+        assert_eq!(input(Method::DenyTool { reason: None }, true).unwrap(), "n");
+        assert_eq!(
+            approval(Method::ApproveTool { trust: false }),
+            Some(ToolApproval::Accept)
+        );
+        assert_eq!(approval(Method::ApproveTool { trust: true }), Some(ToolApproval::Trust));
+        // Only chat/approve_tool approves a tool use, whatever the text of other requests.
+        assert_eq!(approval(Method::Prompt { text: "y".to_string() }), None);
+        assert_eq!(
+            approval(Method::DenyTool {
+                reason: Some("t".to_string())
+            }),
+            None
+        );
+        assert_eq!(
+            input(
+                Method::DenyTool {
+                    reason: Some("use fs_read instead".to_string())
+                },
+                true
+            )
+            .unwrap(),
+            "use fs_read instead"
+        );
+        assert_eq!(
+            input(Method::ApproveTool { trust: false }, false).unwrap_err().code,
+            NO_PENDING_TOOL_USE
+        );
+        assert_eq!(input(Method::ListCheckpoints, false).unwrap(), "/checkpoint list");
+        assert_eq!(
+            input(
+                Method::RestoreCheckpoint {
+                    tag: "2.1".to_string(),
+                    hard: true
+                },
+                false
+            )
+            .unwrap(),
+            "/checkpoint restore 2.1 --hard"
+        );
+        assert_eq!(input(Method::Shutdown, false).unwrap(), "/quit");
+    }
+
+    #[test]
+    fn test_serve_input() {
+        let client = OutputCapture::default();
+        let transport = Transport::new(Box::new(client.clone()));
+        let display = OutputCapture::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let mut events = EventWriter {
+            transport: transport.clone(),
+            display: display.clone(),
+            buf: Vec::new(),
+        };
+        let mut input = ServeInput {
+            transport: transport.clone(),
+            display: display.clone(),
+            requests: rx,
+            pending_id: None,
+            pending_tool_use: None,
+            tool_approval: None,
+        };
+
+        let requests = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"chat/prompt","params":{"text":"hi"}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"chat/deny_tool"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"chat/approve_tool","params":{"trust":true}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#,
+        ]
+        .join("\n");
+        read_requests(requests.as_bytes(), &tx, &transport);
+        drop(tx);
+
+        assert_eq!(input.read_line().as_deref(), Some("hi"));
+        write!(
+            events,
+            "{}\n{{\"type\":",
+            json!({ "type": "text_delta", "text": "Hello" })
+        )
+        .unwrap();
+        writeln!(events, "\"stop\"}}\nnot an event").unwrap();
+        writeln!(display.clone(), "\x1b[32mdone\x1b[0m").unwrap();
+        input.set_pending_tool_use(Some(PendingToolUse {
+            tool_use_id: "t1".to_string(),
+            name: "fs_write".to_string(),
+        }));
+
+        // The request to deny a tool use is valid now that a tool use is pending.
+        assert_eq!(input.read_line().as_deref(), Some("n"));
+        assert_eq!(input.tool_approval(), None);
+        assert_eq!(sent(&client), vec![
+            json!({ "jsonrpc": "2.0", "method": "chat/event", "params": { "type": "text_delta", "text": "Hello" } }),
+            json!({ "jsonrpc": "2.0", "method": "chat/event", "params": { "type": "stop" } }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "output": "not an event\ndone\n",
+                    "pending_tool_use": { "tool_use_id": "t1", "name": "fs_write" },
+                },
+            }),
+        ]);
+
+        assert_eq!(input.read_line().as_deref(), Some("t"));
+        assert_eq!(input.tool_approval(), Some(ToolApproval::Trust));
+
+        input.set_pending_tool_use(None);
+        assert_eq!(input.read_line().as_deref(), Some("/quit"));
+        assert_eq!(input.tool_approval(), None);
+        assert_eq!(input.read_line(), None);
+        let pending = json!({ "tool_use_id": "t1", "name": "fs_write" });
+        assert_eq!(sent(&client), vec![
+            json!({ "jsonrpc": "2.0", "id": 2, "result": { "output": "", "pending_tool_use": pending } }),
+            json!({ "jsonrpc": "2.0", "id": 3, "result": { "output": "", "pending_tool_use": null } }),
+            json!({ "jsonrpc": "2.0", "id": 4, "result": { "output": "", "pending_tool_use": null } }),
+        ]);
+    }
+}
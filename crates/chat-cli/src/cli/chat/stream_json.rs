@@ -0,0 +1,142 @@
+use serde::Serialize;
+
+use super::cli::hooks::HookOutput;
+use super::message::{
+    ToolUseResult,
+    ToolUseResultBlock,
+};
+use crate::api_client::model::ToolResultStatus;
+use crate::cli::agent::hook::{
+    Hook,
+    HookTrigger,
+};
+
+/// An event emitted to stdout, one JSON object per line, when running with
+/// `--output-format stream-json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Assistant text as it is received from the model.
+    TextDelta { text: String },
+    /// A tool use requested by the model.
+    ToolUse {
+        tool_use_id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// The result of a tool use that is sent back to the model.
+    ToolResult {
+        tool_use_id: String,
+        status: ToolResultEventStatus,
+        content: Vec<serde_json::Value>,
+    },
+    /// The result of a hook execution.
+    HookResult {
+        hook_event_name: String,
+        command: String,
+        exit_code: i32,
+        output: String,
+    },
+    /// Estimated token usage of a single model response.
+    Usage { input_tokens: usize, output_tokens: usize },
+    /// Emitted once when the session ends.
+    Stop {
+        reason: StopReason,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolResultEventStatus {
+    Success,
+    Error,
+}
+
+/// Why the session stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The model finished responding.
+    EndTurn,
+    /// The model requested a tool that requires approval, which can't be given in
+    /// non-interactive mode.
+    ToolApprovalRequired,
+    /// The session ended because of an error.
+    Error,
+}
+
+impl From<&ToolUseResult> for StreamEvent {
+    fn from(result: &ToolUseResult) -> Self {
+        Self::ToolResult {
+            tool_use_id: result.tool_use_id.clone(),
+            status: match result.status {
+                ToolResultStatus::Success => ToolResultEventStatus::Success,
+                ToolResultStatus::Error => ToolResultEventStatus::Error,
+            },
+            content: result
+                .content
+                .iter()
+                .map(|block| match block {
+                    ToolUseResultBlock::Text(text) => serde_json::Value::String(text.clone()),
+                    ToolUseResultBlock::Json(json) => json.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&((HookTrigger, Hook), HookOutput)> for StreamEvent {
+    fn from(((trigger, hook), (exit_code, output)): &((HookTrigger, Hook), HookOutput)) -> Self {
+        Self::HookResult {
+            hook_event_name: trigger.to_string(),
+            command: hook.command.clone(),
+            exit_code: *exit_code,
+            output: output.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_event_serialization() {
+        let event = StreamEvent::TextDelta {
+            text: "Hello".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "text_delta", "text": "Hello" })
+        );
+
+        let result = ToolUseResult {
+            tool_use_id: "1".to_string(),
+            content: vec![
+                ToolUseResultBlock::Text("done".to_string()),
+                ToolUseResultBlock::Json(serde_json::json!({ "exit_status": "0" })),
+            ],
+            status: ToolResultStatus::Success,
+        };
+        assert_eq!(
+            serde_json::to_value(StreamEvent::from(&result)).unwrap(),
+            serde_json::json!({
+                "type": "tool_result",
+                "tool_use_id": "1",
+                "status": "success",
+                "content": ["done", { "exit_status": "0" }],
+            })
+        );
+
+        let event = StreamEvent::Stop {
+            reason: StopReason::EndTurn,
+            message: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "stop", "reason": "end_turn" })
+        );
+    }
+}
@@ -1,4 +1,17 @@
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+use std::collections::HashMap;
 use std::ops::Deref;
+use std::path::Path;
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+use std::path::PathBuf;
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+use std::sync::{
+    Arc,
+    LazyLock,
+    Mutex,
+};
+
+use tracing::warn;
 
 use super::message::{
     AssistantMessage,
@@ -10,7 +23,10 @@ use super::message::{
 use crate::cli::chat::conversation::{
     BackendConversationState,
     ConversationSize,
+    HistoryEntry,
 };
+use crate::database::settings::Setting;
+use crate::os::Os;
 
 #[derive(Debug, Clone, Copy)]
 pub struct CharCount(usize);
@@ -66,6 +82,14 @@ impl From<CharCount> for TokenCount {
     }
 }
 
+impl std::ops::Add for TokenCount {
+    type Output = TokenCount;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.value() + rhs.value())
+    }
+}
+
 impl std::fmt::Display for TokenCount {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -88,9 +112,138 @@ impl TokenCounter {
     fn count_tokens_char_count(count: usize) -> usize {
         (count / Self::TOKEN_TO_CHAR_RATIO + 5) / 10 * 10
     }
+}
+
+/// Key of [Setting::ChatTokenizers] used for models without a tokenizer of their own.
+const DEFAULT_TOKENIZER_KEY: &str = "default";
+
+/// Tokenizers that have been loaded, by path. [None] if the tokenizer failed to load.
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+static LOADED_TOKENIZERS: LazyLock<Mutex<HashMap<PathBuf, Option<Arc<tokenizers::Tokenizer>>>>> =
+    LazyLock::new(Default::default);
+
+/// Backend used to count the tokens of a conversation.
+///
+/// The tokenizer of a model is configured with [Setting::ChatTokenizers], which maps model ids
+/// (or `"default"`) to the path of a `tokenizer.json` file in the Hugging Face tokenizers format.
+/// Models without a tokenizer, or whose tokenizer fails to load, use the [Tokenizer::Builtin]
+/// estimate. Tokenizers are not supported on Linux ARM, where the estimate is always used.
+#[derive(Debug, Clone, Default)]
+pub enum Tokenizer {
+    /// Estimates tokens from the number of characters, see [TokenCounter::count_tokens].
+    #[default]
+    Builtin,
+    /// Counts tokens with a BPE tokenizer.
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    Bpe(Arc<tokenizers::Tokenizer>),
+}
+
+impl Tokenizer {
+    /// Returns the tokenizer configured for `model_id`.
+    pub fn for_model(os: &Os, model_id: Option<&str>) -> Self {
+        let Some(tokenizers) = os
+            .database
+            .settings
+            .get(Setting::ChatTokenizers)
+            .and_then(|v| v.as_object())
+        else {
+            return Self::Builtin;
+        };
+
+        let path = model_id
+            .and_then(|id| tokenizers.get(id))
+            .or_else(|| tokenizers.get(DEFAULT_TOKENIZER_KEY))
+            .and_then(|v| v.as_str());
+        match path {
+            Some(path) => Self::load(Path::new(shellexpand::tilde(path).as_ref())),
+            None => Self::Builtin,
+        }
+    }
+
+    /// Loads the tokenizer file at `path`. Tokenizers are only loaded once per process.
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    pub fn load(path: &Path) -> Self {
+        let mut loaded = LOADED_TOKENIZERS.lock().unwrap_or_else(|e| e.into_inner());
+        let tokenizer =
+            loaded
+                .entry(path.to_path_buf())
+                .or_insert_with(|| match tokenizers::Tokenizer::from_file(path) {
+                    Ok(tokenizer) => Some(Arc::new(tokenizer)),
+                    Err(err) => {
+                        warn!(
+                            ?err,
+                            ?path,
+                            "Failed to load tokenizer, falling back to estimating tokens"
+                        );
+                        None
+                    },
+                });
+
+        match tokenizer {
+            Some(tokenizer) => Self::Bpe(Arc::clone(tokenizer)),
+            None => Self::Builtin,
+        }
+    }
+
+    /// Tokenizers are not supported on Linux ARM, so this always falls back to estimating tokens.
+    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+    pub fn load(path: &Path) -> Self {
+        warn!(
+            ?path,
+            "Tokenizers are not supported on this platform, estimating tokens instead"
+        );
+        Self::Builtin
+    }
+
+    /// Whether token counts are estimated rather than counted with the model's tokenizer.
+    pub fn is_estimate(&self) -> bool {
+        matches!(self, Self::Builtin)
+    }
+
+    /// Counts the tokens in `text`.
+    pub fn count_text(&self, text: &str) -> TokenCount {
+        match self {
+            Self::Builtin => CharCount(text.len()).into(),
+            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+            Self::Bpe(tokenizer) => TokenCount(bpe_token_count(tokenizer, text)),
+        }
+    }
+
+    /// Counts the tokens in all of `values`.
+    pub fn count<'a, T>(&self, values: impl IntoIterator<Item = &'a T>) -> TokenCount
+    where
+        T: TextContent + 'a,
+    {
+        match self {
+            // Characters are added up before estimating so that short texts are not rounded away.
+            Self::Builtin => values
+                .into_iter()
+                .fold(CharCount(0), |acc, value| acc + value.char_count())
+                .into(),
+            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+            Self::Bpe(tokenizer) => {
+                let mut count = 0;
+                for value in values {
+                    value.for_each_text(&mut |text| count += bpe_token_count(tokenizer, text));
+                }
+                TokenCount(count)
+            },
+        }
+    }
+}
 
-    pub const fn token_to_chars(token: usize) -> usize {
-        token * Self::TOKEN_TO_CHAR_RATIO
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+fn bpe_token_count(tokenizer: &tokenizers::Tokenizer, text: &str) -> usize {
+    if text.is_empty() {
+        return 0;
+    }
+    // Special tokens are left out so that the counts of separate pieces of text add up.
+    match tokenizer.encode_fast(text, false) {
+        Ok(encoding) => encoding.len(),
+        Err(err) => {
+            warn!(?err, "Failed to tokenize text, estimating tokens instead");
+            text.len() / TokenCounter::TOKEN_TO_CHAR_RATIO
+        },
     }
 }
 
@@ -103,6 +256,12 @@ pub trait CharCounter {
     fn char_count(&self) -> CharCount;
 }
 
+/// A trait for types containing text to be counted by a [Tokenizer].
+pub trait TextContent: CharCounter {
+    /// Calls `f` with each piece of text sent to the model for this type.
+    fn for_each_text(&self, f: &mut dyn FnMut(&str));
+}
+
 impl CharCounter for BackendConversationState<'_> {
     fn char_count(&self) -> CharCount {
         self.calculate_conversation_size().char_count()
@@ -153,6 +312,51 @@ impl CharCounter for AssistantMessage {
     }
 }
 
+impl CharCounter for HistoryEntry {
+    fn char_count(&self) -> CharCount {
+        self.user().char_count() + self.assistant().char_count()
+    }
+}
+
+impl TextContent for UserMessage {
+    fn for_each_text(&self, f: &mut dyn FnMut(&str)) {
+        f(self.additional_context());
+        if let Some(prompt) = self.prompt() {
+            f(prompt);
+        }
+        if let Some(results) = self.tool_use_results() {
+            results.for_each_text(f);
+        }
+    }
+}
+
+impl TextContent for AssistantMessage {
+    fn for_each_text(&self, f: &mut dyn FnMut(&str)) {
+        f(self.content());
+        for tool_use in self.tool_uses().unwrap_or_default() {
+            f(&tool_use.args.to_string());
+        }
+    }
+}
+
+impl TextContent for HistoryEntry {
+    fn for_each_text(&self, f: &mut dyn FnMut(&str)) {
+        self.user().for_each_text(f);
+        self.assistant().for_each_text(f);
+    }
+}
+
+impl TextContent for &[ToolUseResult] {
+    fn for_each_text(&self, f: &mut dyn FnMut(&str)) {
+        for block in self.iter().flat_map(|v| &v.content) {
+            match block {
+                ToolUseResultBlock::Json(v) => f(&v.to_string()),
+                ToolUseResultBlock::Text(s) => f(s),
+            }
+        }
+    }
+}
+
 impl CharCounter for &[ToolUseResult] {
     fn char_count(&self) -> CharCount {
         self.iter()
@@ -190,6 +394,73 @@ mod tests {
         assert_eq!(count, (text.len() / 3 + 5) / 10 * 10);
     }
 
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    const TEST_TOKENIZER: &str = r#"{
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": [],
+        "normalizer": null,
+        "pre_tokenizer": { "type": "Whitespace" },
+        "post_processor": null,
+        "decoder": null,
+        "model": {
+            "type": "BPE",
+            "dropout": null,
+            "unk_token": "[UNK]",
+            "continuing_subword_prefix": null,
+            "end_of_word_suffix": null,
+            "fuse_unk": false,
+            "byte_fallback": false,
+            "vocab": { "[UNK]": 0, "h": 1, "e": 2, "l": 3, "o": 4, "he": 5, "ll": 6, "hell": 7, "hello": 8 },
+            "merges": ["h e", "l l", "he ll", "hell o"]
+        }
+    }"#;
+
+    #[tokio::test]
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    async fn test_tokenizer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokenizer.json");
+        std::fs::write(&path, TEST_TOKENIZER).unwrap();
+
+        let mut os = Os::new().await.unwrap();
+        assert!(matches!(Tokenizer::for_model(&os, Some("model")), Tokenizer::Builtin));
+
+        os.database
+            .settings
+            .set(
+                Setting::ChatTokenizers,
+                serde_json::json!({ "model": path.to_str().unwrap(), "broken": "/does/not/exist.json" }),
+            )
+            .await
+            .unwrap();
+        assert!(Tokenizer::for_model(&os, Some("other")).is_estimate());
+        assert!(Tokenizer::for_model(&os, Some("broken")).is_estimate());
+
+        let tokenizer = Tokenizer::for_model(&os, Some("model"));
+        assert!(!tokenizer.is_estimate());
+        assert_eq!(*tokenizer.count_text("hello hello hel"), 4);
+        assert_eq!(*tokenizer.count_text(""), 0);
+
+        let messages = [
+            UserMessage::new_prompt("hello".to_string(), None),
+            UserMessage::new_prompt("hel lo".to_string(), None),
+        ];
+        assert_eq!(*tokenizer.count(&messages), 5);
+    }
+
+    #[test]
+    fn test_builtin_tokenizer() {
+        let text = "a".repeat(400);
+        assert_eq!(*Tokenizer::Builtin.count_text(&text), TokenCounter::count_tokens(&text));
+        assert_eq!(*Tokenizer::Builtin.count_text(""), 0);
+
+        // Short messages add up instead of each being rounded down to nothing
+        let messages = vec![UserMessage::new_prompt("a".repeat(20), None); 10];
+        assert_eq!(*Tokenizer::Builtin.count(&messages), 50);
+    }
+
     #[test]
     fn test_calculate_value_char_count() {
         // Test simple types
@@ -36,9 +36,17 @@ use futures::future;
 use regex::Regex;
 use rmcp::ServiceError;
 use rmcp::model::{
+    AnnotateAble,
     GetPromptRequestParam,
     GetPromptResult,
     Prompt,
+    PromptMessageContent,
+    RawEmbeddedResource,
+    ReadResourceRequestParam,
+    ReadResourceResult,
+    Resource,
+    ResourceTemplate,
+    Root,
 };
 use tokio::signal::ctrl_c;
 use tokio::sync::{
@@ -63,6 +71,7 @@ use crate::cli::agent::{
     Agent,
     McpServerConfig,
 };
+use crate::cli::chat::cli::mcp::ReadResourceError;
 use crate::cli::chat::cli::prompts::GetPromptError;
 use crate::cli::chat::consts::DUMMY_TOOL_NAME;
 use crate::cli::chat::message::AssistantToolUse;
@@ -87,11 +96,19 @@ use crate::cli::chat::tools::{
 };
 use crate::database::Database;
 use crate::database::settings::Setting;
+use crate::mcp_client::elicitation::ElicitationHandler;
 use crate::mcp_client::messenger::Messenger;
+use crate::mcp_client::roots::{
+    RootsProvider,
+    roots,
+};
+use crate::mcp_client::sampling::SamplingHandler;
 use crate::mcp_client::{
     InitializedMcpClient,
     InnerService,
     McpClientService,
+    UserPromptReceiver,
+    UserPromptSender,
 };
 use crate::os::Os;
 use crate::telemetry::TelemetryThread;
@@ -102,6 +119,9 @@ const NAMESPACE_DELIMITER: &str = "___";
 // This applies for both mcp server and tool name
 const VALID_TOOL_NAME: &str = "^[a-zA-Z][a-zA-Z0-9_]*$";
 const SPINNER_CHARS: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+/// How long to wait for a server to return the contents of a resource. Resources are read while
+/// preparing messages, so a server that doesn't respond must not hold up the conversation.
+const RESOURCE_READ_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub fn workspace_mcp_config_path(os: &Os) -> eyre::Result<PathBuf> {
     Ok(os.env.current_dir()?.join(".amazonq").join("mcp.json"))
@@ -180,10 +200,13 @@ pub struct ToolManagerBuilder {
     conversation_id: Option<String>,
     has_new_stuff: Arc<AtomicBool>,
     mcp_load_record: Arc<Mutex<HashMap<String, Vec<LoadingRecord>>>>,
+    mcp_resources: McpResources,
     new_tool_specs: NewToolSpecs,
     pending_clients: Option<Arc<RwLock<HashSet<String>>>>,
     is_first_launch: bool,
     agent: Option<Arc<Mutex<Agent>>>,
+    user_prompt_sender: Option<UserPromptSender>,
+    user_prompts: Option<UserPromptReceiver>,
 }
 
 impl Default for ToolManagerBuilder {
@@ -197,10 +220,13 @@ impl Default for ToolManagerBuilder {
             conversation_id: Default::default(),
             has_new_stuff: Default::default(),
             mcp_load_record: Default::default(),
+            mcp_resources: Default::default(),
             new_tool_specs: Default::default(),
             pending_clients: Default::default(),
             is_first_launch: true,
             agent: Default::default(),
+            user_prompt_sender: Default::default(),
+            user_prompts: Default::default(),
         }
     }
 }
@@ -218,8 +244,11 @@ impl From<&mut ToolManager> for ToolManagerBuilder {
             messenger_builder: value.messenger_builder.take(),
             has_new_stuff: value.has_new_stuff.clone(),
             mcp_load_record: value.mcp_load_record.clone(),
+            mcp_resources: value.mcp_resources.clone(),
             new_tool_specs: value.new_tool_specs.clone(),
             pending_clients: Some(value.pending_clients.clone()),
+            user_prompt_sender: value.user_prompt_sender.clone(),
+            user_prompts: value.user_prompts.take(),
             // if we are getting a builder from an instantiated tool manager this field would be
             // false
             is_first_launch: false,
@@ -283,6 +312,16 @@ impl ToolManagerBuilder {
         debug_assert!(self.conversation_id.is_some());
         let conversation_id = self.conversation_id.ok_or(eyre::eyre!("Missing conversation id"))?;
 
+        // Questions servers have for the user are asked by the chat session, which keeps receiving
+        // them when the tool manager is rebuilt for another agent
+        let (user_prompt_sender, user_prompts) = match self.user_prompt_sender {
+            Some(sender) => (sender, self.user_prompts),
+            None => {
+                let (sender, receiver) = tokio::sync::mpsc::channel(5);
+                (sender, Some(receiver))
+            },
+        };
+
         // Separate enabled and disabled servers
         let (enabled_servers, disabled_servers): (Vec<_>, Vec<_>) = mcp_servers
             .into_iter()
@@ -328,6 +367,7 @@ impl ToolManagerBuilder {
         })));
         let notify = Arc::new(Notify::new());
         let load_record = self.mcp_load_record;
+        let mcp_resources = self.mcp_resources;
         let agent = self.agent.unwrap_or_default();
         let database = os.database.clone();
         let mut messenger_builder = self.messenger_builder.take();
@@ -375,6 +415,7 @@ impl ToolManagerBuilder {
                 regex,
                 notify_weak,
                 load_record.clone(),
+                mcp_resources.clone(),
                 telemetry,
                 loading_status_sender,
                 new_tool_specs,
@@ -385,6 +426,9 @@ impl ToolManagerBuilder {
 
         debug_assert!(messenger_builder.is_some());
         let messenger_builder = messenger_builder.unwrap();
+        let sampling = SamplingHandler::new(os.clone(), interactive, user_prompt_sender.clone());
+        let roots_provider = RootsProvider::new(os.clone(), agent.clone());
+        let elicitation = ElicitationHandler::new(interactive, user_prompt_sender.clone());
         let pre_initialized = enabled_servers
             .into_iter()
             .map(|(server_name, server_config)| {
@@ -394,6 +438,9 @@ impl ToolManagerBuilder {
                         server_name.clone(),
                         server_config,
                         messenger_builder.build_with_name(server_name),
+                        sampling.clone(),
+                        roots_provider.clone(),
+                        elicitation.clone(),
                     ),
                 )
             })
@@ -434,6 +481,8 @@ impl ToolManagerBuilder {
             }
         }
 
+        let current_roots = roots(os, &*agent.lock().await);
+
         Ok(ToolManager {
             conversation_id,
             clients,
@@ -445,6 +494,8 @@ impl ToolManagerBuilder {
             has_new_stuff,
             is_interactive: interactive,
             mcp_load_record: load_record,
+            mcp_resources,
+            roots: current_roots,
             agent,
             disabled_servers: disabled_servers_display,
             prompts_sender_receiver_pair: {
@@ -456,6 +507,8 @@ impl ToolManagerBuilder {
             },
             messenger_builder: Some(messenger_builder),
             is_first_launch: self.is_first_launch,
+            user_prompt_sender: Some(user_prompt_sender),
+            user_prompts,
             ..Default::default()
         })
     }
@@ -472,6 +525,13 @@ pub struct PromptBundle {
     pub prompt_get: Prompt,
 }
 
+/// The resources and resource templates offered by a server, as of the last time they were listed
+#[derive(Clone, Debug, Default)]
+pub struct ServerResources {
+    pub resources: Vec<Resource>,
+    pub templates: Vec<ResourceTemplate>,
+}
+
 #[derive(Clone, Debug)]
 pub enum PromptQuery {
     List,
@@ -532,6 +592,10 @@ type ServerName = String;
 /// tool name).
 type NewToolSpecs = Arc<Mutex<HashMap<ServerName, (HashMap<ModelToolName, ToolInfo>, Vec<ToolSpec>)>>>;
 
+/// Resources offered by each server. Unlike prompts, these are shared with the orchestrator task
+/// directly since they are only ever looked up by server name.
+type McpResources = Arc<Mutex<HashMap<ServerName, ServerResources>>>;
+
 /// A pair of channels used for prompt list communication between the tool manager and chat helper.
 /// The sender broadcasts a list of available prompt names, while the receiver listens for
 /// search queries to filter the prompt list.
@@ -601,6 +665,12 @@ pub struct ToolManager {
     /// The value is the load message (i.e. load time, warnings, and errors)
     pub mcp_load_record: Arc<Mutex<HashMap<String, Vec<LoadingRecord>>>>,
 
+    /// Resources and resource templates offered by the servers, keyed by server name
+    pub mcp_resources: McpResources,
+
+    /// The roots servers were last told about
+    roots: Vec<Root>,
+
     /// List of disabled MCP server names for display purposes
     disabled_servers: Vec<String>,
 
@@ -615,6 +685,12 @@ pub struct ToolManager {
     pub agent: Arc<Mutex<Agent>>,
 
     is_first_launch: bool,
+
+    /// Used to give the servers of the next agent the same channel for questions to the user
+    user_prompt_sender: Option<UserPromptSender>,
+
+    /// Questions servers have for the user, asked by the chat session
+    pub user_prompts: Option<UserPromptReceiver>,
 }
 
 impl Clone for ToolManager {
@@ -627,6 +703,7 @@ impl Clone for ToolManager {
             schema: self.schema.clone(),
             is_interactive: self.is_interactive,
             mcp_load_record: self.mcp_load_record.clone(),
+            mcp_resources: self.mcp_resources.clone(),
             disabled_servers: self.disabled_servers.clone(),
             ..Default::default()
         }
@@ -645,6 +722,7 @@ impl ToolManager {
     /// - Calling load tools
     pub async fn swap_agent(&mut self, os: &mut Os, output: &mut impl Write, agent: &Agent) -> eyre::Result<()> {
         let to_evict = self.clients.drain().collect::<Vec<_>>();
+        let roots = std::mem::take(&mut self.roots);
         tokio::spawn(async move {
             for (server_name, initialized_client) in to_evict {
                 info!("Evicting {server_name} due to agent swap");
@@ -686,16 +764,38 @@ impl ToolManager {
         drop(agent_lock);
 
         self.mcp_load_record.lock().await.clear();
+        self.mcp_resources.lock().await.clear();
 
         let builder = ToolManagerBuilder::from(&mut *self);
         let mut new_tool_manager = builder.build(os, Box::new(std::io::sink()), true).await?;
         std::mem::swap(self, &mut new_tool_manager);
 
         self.load_tools(os, output).await?;
+        self.roots = roots;
+        self.update_roots(os).await;
 
         Ok(())
     }
 
+    /// Notifies servers that their roots changed if the working directory or the roots of the agent
+    /// changed since they were last told about them
+    pub async fn update_roots(&mut self, os: &Os) {
+        let roots = roots(os, &*self.agent.lock().await);
+        if roots == self.roots {
+            return;
+        }
+        self.roots = roots;
+
+        for (server_name, client) in &mut self.clients {
+            let Ok(running_service) = client.get_running_service().await else {
+                continue;
+            };
+            if let Err(e) = running_service.notify_roots_list_changed().await {
+                warn!("Failed to notify {server_name} of changed roots: {e}");
+            }
+        }
+    }
+
     pub async fn load_tools(
         &mut self,
         os: &mut Os,
@@ -1103,7 +1203,41 @@ impl ToolManager {
                         arguments,
                     };
                     let running_service = client.get_running_service().await?;
-                    let resp = running_service.get_prompt(params).await?;
+                    let mut resp = running_service.get_prompt(params).await?;
+
+                    // Resource links are read here so the messages carry their content the same
+                    // way embedded resources do. Links that cannot be read are left as they are.
+                    for message in &mut resp.messages {
+                        let PromptMessageContent::ResourceLink { link } = &message.content else {
+                            continue;
+                        };
+                        let param = ReadResourceRequestParam {
+                            uri: link.raw.uri.clone(),
+                        };
+                        match tokio::time::timeout(RESOURCE_READ_TIMEOUT, running_service.read_resource(param)).await {
+                            Ok(Ok(ReadResourceResult { mut contents })) if contents.len() == 1 => {
+                                message.content = PromptMessageContent::Resource {
+                                    resource: RawEmbeddedResource {
+                                        meta: None,
+                                        resource: contents.remove(0),
+                                    }
+                                    .no_annotation(),
+                                };
+                            },
+                            Ok(Ok(_)) => warn!(
+                                "Resource {} linked by prompt {prompt_name} is not a single item",
+                                link.raw.uri
+                            ),
+                            Ok(Err(e)) => warn!(
+                                "Failed to read resource {} linked by prompt {prompt_name}: {e}",
+                                link.raw.uri
+                            ),
+                            Err(_) => warn!(
+                                "Timed out reading resource {} linked by prompt {prompt_name}",
+                                link.raw.uri
+                            ),
+                        }
+                    }
 
                     Ok(resp)
                 },
@@ -1115,6 +1249,26 @@ impl ToolManager {
         }
     }
 
+    /// Reads a resource from a server. `uri` has to be a concrete uri, i.e. resource templates
+    /// have to be expanded beforehand.
+    pub async fn read_resource(
+        &mut self,
+        server_name: &str,
+        uri: &str,
+    ) -> Result<ReadResourceResult, ReadResourceError> {
+        let client = self
+            .clients
+            .get_mut(server_name)
+            .ok_or_else(|| ReadResourceError::ServerNotFound(server_name.to_string()))?;
+        let running_service = client.get_running_service().await?;
+        let param = ReadResourceRequestParam { uri: uri.to_string() };
+
+        tokio::time::timeout(RESOURCE_READ_TIMEOUT, running_service.read_resource(param))
+            .await
+            .map_err(|_elapsed| ReadResourceError::Timeout(RESOURCE_READ_TIMEOUT))?
+            .map_err(ReadResourceError::from)
+    }
+
     pub async fn pending_clients(&self) -> Vec<String> {
         self.pending_clients.read().await.iter().cloned().collect::<Vec<_>>()
     }
@@ -1274,6 +1428,7 @@ fn spawn_orchestrator_task(
     regex: Regex,
     notify_weak: std::sync::Weak<Notify>,
     load_record: Arc<Mutex<HashMap<String, Vec<LoadingRecord>>>>,
+    mcp_resources: McpResources,
     telemetry: TelemetryThread,
     loading_status_sender: Option<LoadingStatusSender>,
     new_tool_specs: NewToolSpecs,
@@ -1365,6 +1520,7 @@ fn spawn_orchestrator_task(
             new_tool_specs: &NewToolSpecs,
             has_new_stuff: &Arc<AtomicBool>,
             load_record: &Arc<Mutex<HashMap<String, Vec<LoadingRecord>>>>,
+            mcp_resources: &McpResources,
             notify_weak: &std::sync::Weak<Notify>,
             initialized: &mut HashSet<String>,
             prompts: &mut HashMap<String, Vec<PromptBundle>>,
@@ -1646,8 +1802,39 @@ fn spawn_orchestrator_task(
                             .or_insert(vec![record]);
                     },
                 },
-                UpdateEventMessage::ListResourcesResult { .. } => {},
-                UpdateEventMessage::ResourceTemplatesListResult { .. } => {},
+                UpdateEventMessage::ListResourcesResult {
+                    server_name,
+                    result,
+                    peer,
+                } => match result {
+                    Ok(resource_list_result) => {
+                        if peer.is_none_or(|peer| peer.is_transport_closed()) {
+                            error!("Received resource list result from {server_name} without an open peer. Ignoring.");
+                            return;
+                        }
+                        // Like prompts, the list declares everything that is available
+                        mcp_resources.lock().await.entry(server_name).or_default().resources =
+                            resource_list_result.resources;
+                    },
+                    Err(e) => error!("Error fetching resources from server {server_name}: {:?}", e),
+                },
+                UpdateEventMessage::ResourceTemplatesListResult {
+                    server_name,
+                    result,
+                    peer,
+                } => match result {
+                    Ok(template_list_result) => {
+                        if peer.is_none_or(|peer| peer.is_transport_closed()) {
+                            error!(
+                                "Received resource template list result from {server_name} without an open peer. Ignoring."
+                            );
+                            return;
+                        }
+                        mcp_resources.lock().await.entry(server_name).or_default().templates =
+                            template_list_result.resource_templates;
+                    },
+                    Err(e) => error!("Error fetching resource templates from server {server_name}: {:?}", e),
+                },
                 UpdateEventMessage::OauthLink { server_name, link } => {
                     let mut buf_writer = BufWriter::new(&mut *record_temp_buf);
                     let msg = eyre::eyre!(link);
@@ -1682,13 +1869,14 @@ fn spawn_orchestrator_task(
                     loading_servers.insert(server_name, std::time::Instant::now());
                 },
                 UpdateEventMessage::Deinit { server_name, .. } => {
-                    // Only prompts are stored here so we'll just be clearing that
+                    // Only prompts and resources are stored here so we'll just be clearing those
                     // In the future if we are also storing tools, we need to make sure that
                     // the tools are also pruned.
                     for (_prompt_name, bundles) in prompts.iter_mut() {
                         bundles.retain(|bundle| bundle.server_name != server_name);
                     }
                     prompts.retain(|_, bundles| !bundles.is_empty());
+                    mcp_resources.lock().await.remove(&server_name);
                     has_new_stuff.store(true, Ordering::Release);
                 },
             }
@@ -1714,6 +1902,7 @@ fn spawn_orchestrator_task(
                             &new_tool_specs,
                             &has_new_stuff,
                             &load_record,
+                            &mcp_resources,
                             &notify_weak,
                             &mut initialized,
                             &mut prompts,
@@ -3,11 +3,16 @@ use std::collections::HashMap;
 use std::io::Write;
 
 use crossterm::{
+    cursor,
     queue,
     style,
+    terminal,
 };
 use eyre::Result;
-use rmcp::model::CallToolRequestParam;
+use rmcp::model::{
+    CallToolRequestParam,
+    ProgressNotificationParam,
+};
 use schemars::JsonSchema;
 use serde::{
     Deserialize,
@@ -22,6 +27,7 @@ use crate::cli::agent::{
 };
 use crate::cli::chat::CONTINUATION_LINE;
 use crate::cli::chat::token_counter::TokenCounter;
+use crate::cli::chat::util::strip_control_chars;
 use crate::mcp_client::{
     RunningService,
     oauth_util,
@@ -44,6 +50,19 @@ impl Default for TransportType {
     }
 }
 
+/// Whether a server may ask for responses from the model through sampling requests
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SamplingPermission {
+    /// Ask the user to approve each request (default)
+    #[default]
+    Ask,
+    /// Serve requests without asking
+    Allow,
+    /// Reject all requests
+    Deny,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomToolConfig {
@@ -74,6 +93,9 @@ pub struct CustomToolConfig {
     /// A boolean flag to denote whether or not to load this mcp server
     #[serde(default)]
     pub disabled: bool,
+    /// Whether the server may ask for responses from the model. Defaults to asking the user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SamplingPermission>,
     /// A flag to denote whether this is a server from the legacy mcp.json
     #[serde(skip)]
     pub is_from_legacy_mcp_json: bool,
@@ -113,13 +135,42 @@ impl CustomTool {
         format!("@{}{}{}", self.server_name, MCP_SERVER_TOOL_DELIMITER, self.name)
     }
 
-    pub async fn invoke(&self, _os: &Os, _updates: &mut impl Write) -> Result<InvokeOutput> {
+    pub async fn invoke(&self, _os: &Os, updates: &mut impl Write) -> Result<InvokeOutput> {
         let params = CallToolRequestParam {
             name: Cow::from(self.name.clone()),
             arguments: self.params.clone(),
         };
 
-        let resp = self.client.call_tool(params.clone()).await?;
+        // The progress reported by the server is shown on a status line while the call runs
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let call = self.client.call_tool(params, progress_tx);
+        tokio::pin!(call);
+        let mut updates_shown = 0;
+        let resp = loop {
+            tokio::select! {
+                resp = &mut call => break resp,
+                Some(progress) = progress_rx.recv() => {
+                    queue!(
+                        updates,
+                        cursor::MoveToColumn(0),
+                        terminal::Clear(terminal::ClearType::CurrentLine),
+                        style::SetForegroundColor(style::Color::DarkGrey),
+                        style::Print(progress_line(&progress, updates_shown)),
+                        style::ResetColor,
+                    )?;
+                    updates.flush()?;
+                    updates_shown += 1;
+                },
+            }
+        };
+        if updates_shown > 0 {
+            queue!(
+                updates,
+                cursor::MoveToColumn(0),
+                terminal::Clear(terminal::ClearType::CurrentLine)
+            )?;
+        }
+        let resp = resp?;
 
         if resp.is_error.is_none_or(|v| !v) {
             Ok(InvokeOutput {
@@ -183,3 +234,59 @@ impl CustomTool {
         }
     }
 }
+
+/// Frames of the spinner shown with the progress of a tool call
+const PROGRESS_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Formats the progress of a tool call, e.g. `⠋ 40% Indexing files`. The message of the server is
+/// stripped of control characters and kept on one line since the line is redrawn in place.
+fn progress_line(progress: &ProgressNotificationParam, frame: usize) -> String {
+    let amount = match progress.total {
+        Some(total) if total > 0.0 => format!("{:.0}%", progress.progress / total * 100.0),
+        _ => progress.progress.to_string(),
+    };
+    let mut line = format!(
+        "{CONTINUATION_LINE}{} {amount}",
+        PROGRESS_FRAMES[frame % PROGRESS_FRAMES.len()]
+    );
+    if let Some(message) = &progress.message {
+        line.push(' ');
+        line.push_str(&strip_control_chars(message).replace(['\n', '\t'], " "));
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::{
+        NumberOrString,
+        ProgressToken,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_progress_line() {
+        let mut progress = ProgressNotificationParam {
+            progress_token: ProgressToken(NumberOrString::Number(0)),
+            progress: 4.0,
+            total: Some(10.0),
+            message: Some("Indexing files".to_string()),
+        };
+        assert_eq!(
+            progress_line(&progress, 0),
+            format!("{CONTINUATION_LINE}⠋ 40% Indexing files")
+        );
+
+        progress.message = Some("\x1b[2K\rIndexing\nfiles\x07".to_string());
+        assert_eq!(
+            progress_line(&progress, 0),
+            format!("{CONTINUATION_LINE}⠋ 40% [2KIndexing files")
+        );
+
+        progress.total = None;
+        progress.message = None;
+        assert_eq!(progress_line(&progress, 11), format!("{CONTINUATION_LINE}⠙ 4"));
+    }
+}
@@ -31,6 +31,8 @@ mod windows;
 #[cfg(windows)]
 pub use windows::*;
 
+#[cfg(not(windows))]
+mod sandbox;
 #[cfg(not(windows))]
 mod unix;
 #[cfg(not(windows))]
@@ -41,6 +43,53 @@ pub const READONLY_COMMANDS: &[&str] = &[
     "ls", "cat", "echo", "pwd", "which", "head", "tail", "find", "grep", "dir", "type",
 ];
 
+/// Restrictions applied to commands, configured with `toolsSettings.execute_bash.sandbox`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxSettings {
+    /// Run commands in the sandbox
+    #[serde(default)]
+    pub enabled: bool,
+    /// Paths commands may write to besides the current directory
+    #[serde(default)]
+    pub writable_paths: Vec<String>,
+    /// Whether commands may access the network
+    #[serde(default = "default_allow_network")]
+    pub allow_network: bool,
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            writable_paths: Vec::new(),
+            allow_network: default_allow_network(),
+        }
+    }
+}
+
+impl SandboxSettings {
+    /// The sandbox settings of `agent`, if any.
+    pub fn from_agent(agent: Option<&Agent>) -> Self {
+        let tool_name = if cfg!(windows) { "execute_cmd" } else { "execute_bash" };
+        agent
+            .and_then(|agent| agent.tools_settings.get(tool_name))
+            .and_then(|settings| settings.get("sandbox"))
+            .and_then(|sandbox| match serde_json::from_value::<Self>(sandbox.clone()) {
+                Ok(sandbox) => Some(sandbox),
+                Err(e) => {
+                    error!("Failed to deserialize sandbox settings for execute_bash: {:?}", e);
+                    None
+                },
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn default_allow_network() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ExecuteCommand {
     pub command: String,
@@ -140,8 +189,9 @@ impl ExecuteCommand {
         false
     }
 
-    pub async fn invoke(&self, os: &Os, output: &mut impl Write) -> Result<InvokeOutput> {
-        let output = run_command(os, &self.command, MAX_TOOL_RESPONSE_SIZE / 3, Some(output)).await?;
+    pub async fn invoke(&self, os: &Os, output: &mut impl Write, agent: Option<&Agent>) -> Result<InvokeOutput> {
+        let sandbox = SandboxSettings::from_agent(agent);
+        let output = run_command(os, &self.command, MAX_TOOL_RESPONSE_SIZE / 3, Some(output), &sandbox).await?;
         let clean_stdout = sanitize_unicode_tags(&output.stdout);
         let clean_stderr = sanitize_unicode_tags(&output.stderr);
 
@@ -197,6 +247,8 @@ impl ExecuteCommand {
             denied_commands: Vec<String>,
             #[serde(default = "default_allow_read_only")]
             auto_allow_readonly: bool,
+            #[serde(default)]
+            sandbox: SandboxSettings,
         }
 
         fn default_allow_read_only() -> bool {
@@ -212,6 +264,7 @@ impl ExecuteCommand {
                     allowed_commands,
                     denied_commands,
                     auto_allow_readonly,
+                    sandbox,
                 } = match serde_json::from_value::<Settings>(settings.clone()) {
                     Ok(settings) => settings,
                     Err(e) => {
@@ -231,9 +284,11 @@ impl ExecuteCommand {
                     return PermissionEvalResult::Deny(denied_match_set);
                 }
 
+                // Read-only commands only run without prompting if the sandbox can be enforced
+                let sandboxed = sandbox.enabled && sandbox::is_supported();
                 if is_in_allowlist {
                     PermissionEvalResult::Allow
-                } else if self.requires_acceptance(Some(&allowed_commands), auto_allow_readonly) {
+                } else if self.requires_acceptance(Some(&allowed_commands), auto_allow_readonly || sandboxed) {
                     PermissionEvalResult::Ask
                 } else {
                     PermissionEvalResult::Allow
@@ -489,6 +544,50 @@ mod tests {
         assert!(matches!(res, PermissionEvalResult::Deny(ref rules) if rules.contains(&"\\Agit .*\\z".to_string())));
     }
 
+    #[tokio::test]
+    async fn test_eval_perm_sandbox() {
+        let tool_name = if cfg!(windows) { "execute_cmd" } else { "execute_bash" };
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget(tool_name.to_string()),
+                    serde_json::json!({
+                        "sandbox": { "enabled": true, "writablePaths": ["/tmp"], "allowNetwork": false }
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+        let os = Os::new().await.unwrap();
+
+        assert_eq!(SandboxSettings::from_agent(Some(&agent)), SandboxSettings {
+            enabled: true,
+            writable_paths: vec!["/tmp".to_string()],
+            allow_network: false,
+        });
+        assert_eq!(SandboxSettings::from_agent(None), SandboxSettings::default());
+
+        // Read-only commands are allowed in the sandbox, as long as it can be enforced
+        let readonly_cmd = serde_json::from_value::<ExecuteCommand>(serde_json::json!({
+            "command": "ls -la",
+        }))
+        .unwrap();
+        let res = readonly_cmd.eval_perm(&os, &agent);
+        match sandbox::is_supported() {
+            true => assert!(matches!(res, PermissionEvalResult::Allow)),
+            false => assert!(matches!(res, PermissionEvalResult::Ask)),
+        }
+
+        let write_cmd = serde_json::from_value::<ExecuteCommand>(serde_json::json!({
+            "command": "rm file.txt",
+        }))
+        .unwrap();
+        assert!(matches!(write_cmd.eval_perm(&os, &agent), PermissionEvalResult::Ask));
+    }
+
     #[tokio::test]
     async fn test_eval_perm_allow_read_only_default() {
         use crate::cli::agent::Agent;
@@ -0,0 +1,138 @@
+use eyre::Result;
+
+use super::SandboxSettings;
+
+/// Version of the Landlock ABI supported by the kernel, or 0 if Landlock isn't available.
+#[cfg(target_os = "linux")]
+pub fn landlock_abi() -> i64 {
+    static ABI: std::sync::LazyLock<i64> = std::sync::LazyLock::new(|| {
+        // LANDLOCK_CREATE_RULESET_VERSION
+        const VERSION_FLAG: libc::c_uint = 1;
+        // SAFETY: with this flag, no ruleset is created and the attributes aren't read
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<libc::c_void>(),
+                0 as libc::size_t,
+                VERSION_FLAG,
+            )
+        };
+        abi.max(0)
+    });
+    *ABI
+}
+
+/// Whether commands can be sandboxed on this system.
+pub fn is_supported() -> bool {
+    #[cfg(target_os = "linux")]
+    return landlock_abi() > 0;
+    #[cfg(not(target_os = "linux"))]
+    return false;
+}
+
+/// Restricts what `cmd` can do according to `settings`.
+///
+/// Writes are only allowed to the current directory, `/dev/null` and the configured writable
+/// paths, using Landlock. Network access is removed by running the command in its own network
+/// namespace. Setting up the sandbox fails rather than running the command unrestricted.
+///
+/// Older kernels only restrict some kinds of writes. In that case, returns a warning describing
+/// what isn't restricted.
+#[cfg(target_os = "linux")]
+pub fn apply(cmd: &mut tokio::process::Command, settings: &SandboxSettings) -> Result<Option<String>> {
+    use std::ffi::CString;
+    use std::path::PathBuf;
+
+    use eyre::Context as EyreContext;
+    use landlock::{
+        ABI,
+        AccessFs,
+        CompatLevel,
+        Compatible,
+        Ruleset,
+        RulesetAttr,
+        RulesetCreatedAttr,
+        RulesetStatus,
+        path_beneath_rules,
+    };
+
+    // Landlock ABI 3 is the first to restrict truncating files. Older kernels enforce what they
+    // support, which is reported below.
+    let abi = ABI::V3;
+    let downgrade = match landlock_abi() {
+        0 => eyre::bail!("The execute_bash sandbox requires Landlock support (Linux 5.13 or newer)"),
+        1 => Some("moving files between directories and truncating files aren't restricted (requires Linux 6.2)"),
+        2 => Some("truncating files isn't restricted (requires Linux 6.2)"),
+        _ => None,
+    };
+    let mut writable_paths = vec![std::env::current_dir()?, PathBuf::from("/dev/null")];
+    writable_paths.extend(
+        settings
+            .writable_paths
+            .iter()
+            .map(|path| PathBuf::from(shellexpand::tilde(path).as_ref())),
+    );
+    let ruleset = Ruleset::default()
+        .set_compatibility(CompatLevel::BestEffort)
+        .handle_access(AccessFs::from_write(abi))
+        .and_then(|ruleset| ruleset.create())
+        .and_then(|ruleset| ruleset.add_rules(path_beneath_rules(&writable_paths, AccessFs::from_write(abi))))
+        .wrap_err("Failed to set up the execute_bash sandbox")?;
+
+    // Without root, creating a network namespace requires a user namespace, which maps the
+    // user to themselves.
+    let uid = nix::unistd::geteuid().as_raw();
+    let gid = nix::unistd::getegid().as_raw();
+    let id_maps = match (settings.allow_network, uid) {
+        (true, _) | (false, 0) => vec![],
+        (false, _) => vec![
+            (CString::new("/proc/self/setgroups")?, "deny".to_string()),
+            (CString::new("/proc/self/uid_map")?, format!("{uid} {uid} 1")),
+            (CString::new("/proc/self/gid_map")?, format!("{gid} {gid} 1")),
+        ],
+    };
+    let unshare_flags = match (settings.allow_network, uid) {
+        (true, _) => None,
+        (false, 0) => Some(libc::CLONE_NEWNET),
+        (false, _) => Some(libc::CLONE_NEWUSER | libc::CLONE_NEWNET),
+    };
+
+    let mut ruleset = Some(ruleset);
+    // SAFETY: the closure runs between fork and exec, so it only makes system calls and doesn't
+    // allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(flags) = unshare_flags {
+                if libc::unshare(flags) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                for (path, content) in &id_maps {
+                    let fd = libc::open(path.as_ptr(), libc::O_WRONLY);
+                    if fd < 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    let written = libc::write(fd, content.as_ptr().cast(), content.len());
+                    libc::close(fd);
+                    if written < 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+            }
+
+            if let Some(ruleset) = ruleset.take() {
+                match ruleset.restrict_self() {
+                    Ok(status) if status.ruleset != RulesetStatus::NotEnforced => {},
+                    _ => return Err(std::io::Error::from_raw_os_error(libc::EPERM)),
+                }
+            }
+            Ok(())
+        });
+    }
+
+    Ok(downgrade.map(|missing| format!("This kernel only partially supports the sandbox: {missing}")))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply(_cmd: &mut tokio::process::Command, _settings: &SandboxSettings) -> Result<Option<String>> {
+    eyre::bail!("The execute_bash sandbox is only supported on Linux")
+}
@@ -8,10 +8,14 @@ use eyre::{
 };
 use tokio::io::AsyncBufReadExt;
 use tokio::select;
-use tracing::error;
+use tracing::{
+    error,
+    warn,
+};
 
 use super::{
     CommandResult,
+    SandboxSettings,
     env_vars_with_user_agent,
     format_output,
 };
@@ -22,6 +26,7 @@ use crate::os::Os;
 /// * `command` - The command to run
 /// * `max_result_size` - max size of output streams, truncating if required
 /// * `updates` - output stream to push informational messages about the progress
+/// * `sandbox` - restrictions to run the command with
 /// # Returns
 /// A [`CommandResult`]
 pub async fn run_command<W: Write>(
@@ -29,6 +34,7 @@ pub async fn run_command<W: Write>(
     command: &str,
     max_result_size: usize,
     mut updates: Option<W>,
+    sandbox: &SandboxSettings,
 ) -> Result<CommandResult> {
     let shell = std::env::var("AMAZON_Q_CHAT_SHELL").unwrap_or("bash".to_string());
 
@@ -36,15 +42,25 @@ pub async fn run_command<W: Write>(
     let env_vars = env_vars_with_user_agent(os);
 
     // We need to maintain a handle on stderr and stdout, but pipe it to the terminal as well
-    let mut child = tokio::process::Command::new(shell)
-        .arg("-c")
+    let mut cmd = tokio::process::Command::new(shell);
+    cmd.arg("-c")
         .arg(command)
         .envs(env_vars)
         .stdin(Stdio::inherit())
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .wrap_err_with(|| format!("Unable to spawn command '{}'", command))?;
+        .stderr(Stdio::piped());
+    if sandbox.enabled {
+        if let Some(warning) = super::sandbox::apply(&mut cmd, sandbox)? {
+            warn!("{warning}");
+            if let Some(u) = updates.as_mut() {
+                writeln!(u, "Warning: {warning}")?;
+            }
+        }
+    }
+    let mut child = cmd.spawn().wrap_err_with(|| match sandbox.enabled {
+        true => format!("Unable to spawn command '{}' in the sandbox", command),
+        false => format!("Unable to spawn command '{}'", command),
+    })?;
 
     let stdout_final: String;
     let stderr_final: String;
@@ -127,6 +143,7 @@ pub async fn run_command<W: Write>(
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::cli::chat::tools::OutputKind;
     use crate::cli::chat::tools::execute::ExecuteCommand;
     use crate::os::Os;
@@ -143,7 +160,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None)
             .await
             .unwrap();
 
@@ -161,7 +178,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None)
             .await
             .unwrap();
 
@@ -179,7 +196,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None)
             .await
             .unwrap();
         if let OutputKind::Json(json) = out.output {
@@ -190,4 +207,47 @@ mod tests {
             panic!("Expected JSON output");
         }
     }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_sandbox() {
+        let os = Os::new().await.unwrap();
+        let writable = tempfile::tempdir().unwrap();
+        let read_only = tempfile::tempdir().unwrap();
+        let sandbox = SandboxSettings {
+            enabled: true,
+            writable_paths: vec![writable.path().to_string_lossy().to_string()],
+            allow_network: true,
+        };
+
+        if !super::super::sandbox::is_supported() {
+            eprintln!("Skipping test_sandbox: Landlock isn't available on this kernel");
+            return;
+        }
+
+        let touch = |dir: &std::path::Path| format!("touch {}", dir.join("file").display());
+        let result = run_command(&os, &touch(writable.path()), 1024, None::<std::io::Stdout>, &sandbox)
+            .await
+            .unwrap();
+        assert_eq!(result.exit_status, Some(0));
+        assert!(writable.path().join("file").exists());
+
+        let result = run_command(&os, &touch(read_only.path()), 1024, None::<std::io::Stdout>, &sandbox)
+            .await
+            .unwrap();
+        assert_ne!(result.exit_status, Some(0));
+        assert!(!read_only.path().join("file").exists());
+
+        // Truncating files is restricted from Landlock ABI 3
+        if super::super::sandbox::landlock_abi() >= 3 {
+            let existing = read_only.path().join("existing");
+            std::fs::write(&existing, "content").unwrap();
+            let truncate = format!("truncate -s 0 {}", existing.display());
+            let result = run_command(&os, &truncate, 1024, None::<std::io::Stdout>, &sandbox)
+                .await
+                .unwrap();
+            assert_ne!(result.exit_status, Some(0));
+            assert_eq!(std::fs::read_to_string(&existing).unwrap(), "content");
+        }
+    }
 }
@@ -12,6 +12,7 @@ use tracing::error;
 
 use super::{
     CommandResult,
+    SandboxSettings,
     env_vars_with_user_agent,
     format_output,
 };
@@ -22,6 +23,7 @@ use crate::os::Os;
 /// * `command` - The command to run
 /// * `max_result_size` - max size of output streams, truncating if required
 /// * `updates` - output stream to push informational messages about the progress
+/// * `sandbox` - restrictions to run the command with
 /// # Returns
 /// A [`CommandResult`]
 pub async fn run_command<W: Write>(
@@ -29,7 +31,12 @@ pub async fn run_command<W: Write>(
     command: &str,
     max_result_size: usize,
     mut updates: Option<W>,
+    sandbox: &SandboxSettings,
 ) -> Result<CommandResult> {
+    if sandbox.enabled {
+        eyre::bail!("The execute_cmd sandbox is only supported on Linux");
+    }
+
     // Set up environment variables with user agent metadata for CloudTrail tracking
     let env_vars = env_vars_with_user_agent(os);
 
@@ -136,7 +143,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None)
             .await
             .unwrap();
 
@@ -154,7 +161,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None)
             .await
             .unwrap();
 
@@ -172,7 +179,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None)
             .await
             .unwrap();
         if let OutputKind::Json(json) = out.output {
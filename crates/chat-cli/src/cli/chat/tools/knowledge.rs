@@ -1,11 +1,22 @@
 use std::io::Write;
 
+use chrono::{
+    DateTime,
+    NaiveDate,
+    NaiveTime,
+    Utc,
+};
 use crossterm::queue;
 use crossterm::style::{
     self,
     Color,
 };
 use eyre::Result;
+use semantic_search_client::{
+    FileType,
+    OperationState,
+    SearchFilter,
+};
 use serde::Deserialize;
 use tracing::warn;
 
@@ -19,7 +30,10 @@ use crate::cli::agent::{
 };
 use crate::database::settings::Setting;
 use crate::os::Os;
-use crate::util::knowledge_store::KnowledgeStore;
+use crate::util::knowledge_store::{
+    KnowledgeError,
+    KnowledgeStore,
+};
 use crate::util::tool_permission_checker::is_tool_in_allowlist;
 
 /// The Knowledge tool allows storing and retrieving information across chat sessions.
@@ -67,6 +81,49 @@ pub struct KnowledgeClear {
 pub struct KnowledgeSearch {
     pub query: String,
     pub context_id: Option<String>,
+    /// Glob pattern the source file path must match, e.g. `src/auth/**`
+    pub path_glob: Option<String>,
+    /// File type of the source file: text, markdown, json or code
+    pub file_type: Option<String>,
+    /// File extension of the source file, e.g. `rs`
+    pub extension: Option<String>,
+    /// Only files modified after this RFC 3339 timestamp or `YYYY-MM-DD` date
+    pub modified_after: Option<String>,
+}
+
+impl KnowledgeSearch {
+    /// Build the search filter from the optional filter parameters
+    pub fn filter(&self) -> Result<SearchFilter> {
+        let file_types = match &self.file_type {
+            Some(file_type) => vec![file_type.parse::<FileType>().map_err(|e| eyre::eyre!(e))?],
+            None => Vec::new(),
+        };
+        let modified_after = match &self.modified_after {
+            Some(modified_after) => Some(parse_modified_after(modified_after)?),
+            None => None,
+        };
+
+        Ok(SearchFilter {
+            path_globs: self.path_glob.iter().cloned().collect(),
+            file_types,
+            extensions: self.extension.iter().cloned().collect(),
+            modified_after,
+        })
+    }
+}
+
+fn parse_modified_after(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_parse_error| {
+            eyre::eyre!(
+                "Invalid modified_after '{}', expected an RFC 3339 timestamp or a YYYY-MM-DD date",
+                value
+            )
+        })
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -146,7 +203,11 @@ impl Knowledge {
                 }
                 Ok(())
             },
-            Knowledge::Search(_) => Ok(()),
+            Knowledge::Search(search) => {
+                let filter = search.filter()?;
+                filter.matcher()?;
+                Ok(())
+            },
             Knowledge::Show => Ok(()),
             Knowledge::Status => Ok(()),
             Knowledge::Cancel(_) => Ok(()),
@@ -428,7 +489,10 @@ impl Knowledge {
                 .await
                 .unwrap_or_else(|e| format!("Failed to clear knowledge base: {}", e)),
             Knowledge::Search(search) => {
-                let results = store.search(&search.query, search.context_id.as_deref()).await;
+                let results = match search.filter() {
+                    Ok(filter) => store.search(&search.query, search.context_id.as_deref(), &filter).await,
+                    Err(e) => Err(KnowledgeError::SearchError(e.to_string())),
+                };
                 match results {
                     Ok(results) => {
                         if results.is_empty() {
@@ -437,7 +501,13 @@ impl Knowledge {
                             let mut output = format!("Search results for \"{}\":\n\n", search.query);
                             for result in results {
                                 if let Some(text) = result.text() {
-                                    output.push_str(&format!("{}\n\n", text));
+                                    if let Some(citation) = result.citation() {
+                                        output.push_str(&format!("[source: {}] ", citation));
+                                    }
+                                    match result.rerank_score.or(result.fused_score) {
+                                        Some(score) => output.push_str(&format!("[score: {:.3}] {}\n\n", score, text)),
+                                        None => output.push_str(&format!("{}\n\n", text)),
+                                    }
                                 }
                             }
                             output
@@ -537,9 +607,11 @@ impl Knowledge {
     fn format_operation_display(op: &semantic_search_client::OperationStatus) -> String {
         let elapsed = op.started_at.elapsed().unwrap_or_default();
 
-        let status_info = if op.is_cancelled {
+        let status_info = if op.state == OperationState::Completed {
+            "Status: Completed".to_string()
+        } else if op.state == OperationState::Cancelled {
             "Status: Cancelled".to_string()
-        } else if op.is_failed {
+        } else if op.state == OperationState::Failed {
             format!("Status: Failed - {}", op.message)
         } else if op.is_waiting {
             format!("Status: Waiting - {}", op.message)
@@ -556,7 +628,7 @@ impl Knowledge {
         let operation_desc = op.operation_type.display_name();
 
         // Format with conditional elapsed time and ETA
-        if op.is_cancelled || op.is_failed {
+        if op.state.is_finished() {
             format!(
                 "Operation ID: {} | Type: {} | {}",
                 op.short_id, operation_desc, status_info
@@ -575,3 +647,49 @@ impl Knowledge {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    macro_rules! search {
+        ($value:tt) => {
+            serde_json::from_value::<KnowledgeSearch>(serde_json::json!($value)).unwrap()
+        };
+    }
+
+    #[test]
+    fn test_search_filter() {
+        let search = search! {{
+            "query": "login flow",
+            "path_glob": "src/auth/**",
+            "file_type": "Markdown",
+            "extension": "md",
+            "modified_after": "2025-06-01"
+        }};
+        assert_eq!(search.filter().unwrap(), SearchFilter {
+            path_globs: vec!["src/auth/**".to_string()],
+            file_types: vec![FileType::Markdown],
+            extensions: vec!["md".to_string()],
+            modified_after: Some(Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap()),
+        });
+
+        let search = search! {{
+            "query": "login flow",
+            "modified_after": "2025-06-01T12:00:00+02:00"
+        }};
+        assert_eq!(
+            search.filter().unwrap().modified_after,
+            Some(Utc.with_ymd_and_hms(2025, 6, 1, 10, 0, 0).unwrap())
+        );
+
+        let search = search! {{
+            "query": "login flow",
+            "file_type": "spreadsheet",
+            "modified_after": "yesterday"
+        }};
+        assert!(search.filter().is_err());
+    }
+}
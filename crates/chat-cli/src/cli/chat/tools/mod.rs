@@ -54,6 +54,7 @@ use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
+use crate::cli::chat::cli::hooks::PermissionDecision;
 use crate::cli::chat::line_tracker::FileLineTracker;
 use crate::os::Os;
 
@@ -136,7 +137,7 @@ impl Tool {
         match self {
             Tool::FsRead(fs_read) => fs_read.invoke(os, stdout).await,
             Tool::FsWrite(fs_write) => fs_write.invoke(os, stdout, line_tracker).await,
-            Tool::ExecuteCommand(execute_command) => execute_command.invoke(os, stdout).await,
+            Tool::ExecuteCommand(execute_command) => execute_command.invoke(os, stdout, agent).await,
             Tool::UseAws(use_aws) => use_aws.invoke(os, stdout).await,
             Tool::Custom(custom_tool) => custom_tool.invoke(os, stdout).await,
             Tool::GhIssue(gh_issue) => gh_issue.invoke(os, stdout).await,
@@ -282,6 +283,9 @@ pub struct QueuedTool {
     pub accepted: bool,
     pub tool: Tool,
     pub tool_input: serde_json::Value,
+    /// Permission decision made by [crate::cli::agent::hook::HookTrigger::PreToolUse] hooks, if
+    /// any.
+    pub hook_decision: Option<PermissionDecision>,
 }
 
 /// The schema specification describing a tool's fields.
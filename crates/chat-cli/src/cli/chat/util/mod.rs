@@ -101,6 +101,14 @@ pub fn sanitize_unicode_tags(text: &str) -> String {
     out
 }
 
+/// Removes control characters, such as the start of terminal escape sequences, and hidden
+/// characters from text of untrusted origin before it is printed. Newlines and tabs are kept.
+pub fn strip_control_chars(text: &str) -> String {
+    text.chars()
+        .filter(|&c| matches!(c, '\n' | '\t') || !(c.is_control() || is_hidden(c)))
+        .collect()
+}
+
 /// Play the terminal bell notification sound
 pub fn play_notification_bell(requires_confirmation: bool) {
     // Don't play bell for tools that don't require confirmation
@@ -310,6 +318,15 @@ mod tests {
         assert_eq!(sanitize_unicode_tags(visible), visible);
     }
 
+    #[test]
+    fn test_strip_control_chars() {
+        assert_eq!(
+            strip_control_chars("\x1b[2J\x1b]0;title\x07ok\r\n\tdone\u{200B}"),
+            "[2J]0;titleok\n\tdone"
+        );
+        assert_eq!(strip_control_chars("Rust 🦀 > C"), "Rust 🦀 > C");
+    }
+
     #[test]
     fn sanitize_handles_large_mixture() {
         let visible_block = "abcXYZ";
@@ -0,0 +1,444 @@
+use std::process::ExitCode;
+
+use anstream::eprintln;
+use clap::{
+    Args,
+    Subcommand,
+};
+use crossterm::style::Stylize;
+use eyre::{
+    Result,
+    bail,
+};
+use semantic_search_client::{
+    KnowledgeContext,
+    OperationState,
+    SearchResult,
+    SystemStatus,
+};
+use serde_json::{
+    Value,
+    json,
+};
+
+use super::OutputFormat;
+use super::agent::Agent;
+use crate::cli::chat::cli::knowledge::KnowledgeSubcommand;
+use crate::cli::chat::tools::knowledge::KnowledgeSearch;
+use crate::cli::chat::tools::sanitize_path_tool_arg;
+use crate::database::settings::Setting;
+use crate::os::Os;
+use crate::util::knowledge_store::{
+    AddOptions,
+    KnowledgeStore,
+};
+
+#[derive(Clone, Debug, Subcommand, PartialEq, Eq)]
+pub enum KnowledgeSubcommands {
+    /// Add a file or directory to the knowledge base and wait until it is indexed
+    Add {
+        path: String,
+        /// Name of the entry (defaults to the path)
+        #[arg(long)]
+        name: Option<String>,
+        /// Include patterns (e.g., `**/*.ts`, `**/*.md`)
+        #[arg(long, action = clap::ArgAction::Append)]
+        include: Vec<String>,
+        /// Exclude patterns (e.g., `node_modules/**`, `target/**`)
+        #[arg(long, action = clap::ArgAction::Append)]
+        exclude: Vec<String>,
+        /// Index type to use (Fast, Best, Hybrid)
+        #[arg(long)]
+        index_type: Option<String>,
+        /// Keep the entry up to date as files under the path change while chatting
+        #[arg(long)]
+        watch: bool,
+        /// Rerank search results of the entry with a cross-encoder model
+        #[arg(long)]
+        rerank: bool,
+    },
+    /// Re-index the added, changed and removed files of an entry and wait until it is done
+    Update { path: String },
+    /// Remove an entry by path or name
+    #[command(alias = "rm")]
+    Remove { path: String },
+    /// List the knowledge base entries
+    #[command(alias = "ls")]
+    List,
+    /// Show the knowledge base entry counts and background operations
+    Status,
+    /// Search the knowledge base
+    Search {
+        query: String,
+        /// Only search the entry with this ID
+        #[arg(long)]
+        context_id: Option<String>,
+        /// Only return results from files whose path matches this glob (e.g., `src/auth/**`)
+        #[arg(long)]
+        path_glob: Option<String>,
+        /// Only return results from files of this type (text, markdown, json, code, document)
+        #[arg(long)]
+        file_type: Option<String>,
+        /// Only return results from files with this extension (e.g., `rs`)
+        #[arg(long)]
+        extension: Option<String>,
+        /// Only return results from files modified after this RFC 3339 timestamp or YYYY-MM-DD date
+        #[arg(long)]
+        modified_after: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct KnowledgeArgs {
+    #[command(subcommand)]
+    cmd: KnowledgeSubcommands,
+    /// Agent whose knowledge base to use (defaults to the chat.defaultAgent setting)
+    #[arg(long, global = true)]
+    agent: Option<String>,
+    /// Format of the output
+    #[arg(long, short, value_enum, default_value_t, global = true)]
+    format: OutputFormat,
+}
+
+impl KnowledgeArgs {
+    pub async fn execute(self, os: &mut Os) -> Result<ExitCode> {
+        if !os
+            .database
+            .settings
+            .get_bool(Setting::EnabledKnowledge)
+            .unwrap_or(false)
+        {
+            bail!("Knowledge is disabled. Enable it with: q settings chat.enableKnowledge true");
+        }
+
+        let agent = load_agent(os, self.agent.as_deref()).await?;
+        let store = KnowledgeStore::get_async_instance(os, agent.as_ref())
+            .await
+            .map_err(|e| eyre::eyre!("Error accessing knowledge base: {}", e))?;
+        let mut store = store.lock().await;
+
+        match self.cmd {
+            KnowledgeSubcommands::Add {
+                path,
+                name,
+                include,
+                exclude,
+                index_type,
+                watch,
+                rerank,
+            } => {
+                let sanitized_path = existing_path(os, &path)?;
+                let mut options = AddOptions::with_db_defaults(os).with_watch(watch).with_rerank(rerank);
+                if !include.is_empty() {
+                    options = options.with_include_patterns(include);
+                }
+                if !exclude.is_empty() {
+                    options = options.with_exclude_patterns(exclude);
+                }
+                if index_type.is_some() {
+                    options = options.with_embedding_type(index_type);
+                }
+
+                let message = store
+                    .add(name.as_deref().unwrap_or(&path), &sanitized_path, options)
+                    .await
+                    .map_err(|e| eyre::eyre!(e))?;
+                eprintln!("{}", message);
+                store.wait_for_operations().await.map_err(|e| eyre::eyre!(e))?;
+
+                let context = find_context(&store, &sanitized_path).await;
+                self.format.print(
+                    || match &context {
+                        Some(context) => format!("✅ Indexed '{}' ({} items)", context.name, context.item_count),
+                        None => "✅ Indexing finished".to_string(),
+                    },
+                    || context.as_ref().map(context_json),
+                );
+            },
+            KnowledgeSubcommands::Update { path } => {
+                let sanitized_path = existing_path(os, &path)?;
+                let message = store
+                    .update_by_path(&sanitized_path)
+                    .await
+                    .map_err(|e| eyre::eyre!(e))?;
+                eprintln!("{}", message);
+                store.wait_for_operations().await.map_err(|e| eyre::eyre!(e))?;
+
+                let context = find_context(&store, &sanitized_path).await;
+                self.format.print(
+                    || match &context {
+                        Some(context) => format!("✅ Updated '{}' ({} items)", context.name, context.item_count),
+                        None => "✅ Update finished".to_string(),
+                    },
+                    || context.as_ref().map(context_json),
+                );
+            },
+            KnowledgeSubcommands::Remove { path } => {
+                let sanitized_path = sanitize_path_tool_arg(os, &path).to_string_lossy().to_string();
+                let context = match find_context(&store, &sanitized_path).await {
+                    Some(context) => Some(context),
+                    None => find_context(&store, &path).await,
+                };
+                let Some(context) = context else {
+                    bail!("No knowledge base entry found with path or name '{}'", path);
+                };
+                store.remove_by_id(&context.id).await.map_err(|e| eyre::eyre!(e))?;
+
+                self.format.print(
+                    || format!("Removed knowledge base entry '{}'", context.name),
+                    || context_json(&context),
+                );
+            },
+            KnowledgeSubcommands::List => {
+                let contexts = store.get_all().await.map_err(|e| eyre::eyre!(e))?;
+                self.format.print(
+                    || format_contexts(&contexts),
+                    || contexts.iter().map(context_json).collect::<Vec<_>>(),
+                );
+            },
+            KnowledgeSubcommands::Status => {
+                let status = store.get_status_data().await.map_err(|e| eyre::eyre!(e))?;
+                self.format.print(
+                    || KnowledgeSubcommand::format_status_display(&status),
+                    || status_json(&status),
+                );
+            },
+            KnowledgeSubcommands::Search {
+                query,
+                context_id,
+                path_glob,
+                file_type,
+                extension,
+                modified_after,
+            } => {
+                let search = KnowledgeSearch {
+                    query,
+                    context_id,
+                    path_glob,
+                    file_type,
+                    extension,
+                    modified_after,
+                };
+                let results = store
+                    .search(&search.query, search.context_id.as_deref(), &search.filter()?)
+                    .await
+                    .map_err(|e| eyre::eyre!(e))?;
+
+                self.format.print(
+                    || format_results(&search.query, &results),
+                    || results.iter().map(result_json).collect::<Vec<_>>(),
+                );
+            },
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Load the agent named by `--agent`, or the user's default agent as chat does
+async fn load_agent(os: &Os, name: Option<&str>) -> Result<Option<Agent>> {
+    if let Some(name) = name {
+        return Ok(Some(Agent::get_agent_by_name(os, name).await?.0));
+    }
+    match os.database.settings.get_string(Setting::ChatDefaultAgent) {
+        Some(name) => Ok(Agent::get_agent_by_name(os, &name).await.ok().map(|(agent, _)| agent)),
+        None => Ok(None),
+    }
+}
+
+fn existing_path(os: &Os, path: &str) -> Result<String> {
+    let sanitized_path = sanitize_path_tool_arg(os, path);
+    if !sanitized_path.exists() {
+        bail!("Path '{}' does not exist", path);
+    }
+    Ok(sanitized_path.to_string_lossy().to_string())
+}
+
+/// Find an entry by source path, then by name
+async fn find_context(store: &KnowledgeStore, path_or_name: &str) -> Option<KnowledgeContext> {
+    let contexts = store.get_all().await.ok()?;
+    let by_path = contexts.iter().find(|context| {
+        context.source_path.as_deref().is_some_and(|source_path| {
+            source_path == path_or_name
+                || std::fs::canonicalize(path_or_name).is_ok_and(|canonical| canonical.to_string_lossy() == source_path)
+        })
+    });
+    by_path
+        .or_else(|| contexts.iter().find(|context| context.name == path_or_name))
+        .cloned()
+}
+
+fn format_contexts(contexts: &[KnowledgeContext]) -> String {
+    if contexts.is_empty() {
+        return "No knowledge base entries found".to_string();
+    }
+
+    let mut output = Vec::new();
+    for context in contexts {
+        output.push(format!(
+            "📂 {} ({})",
+            context.name.as_str().bold(),
+            &context.id[..8.min(context.id.len())]
+        ));
+        if let Some(source_path) = &context.source_path {
+            output.push(format!("   {}", source_path));
+        }
+        output.push(format!(
+            "   {} items • {} • {}",
+            context.item_count,
+            context.embedding_type.description(),
+            context.updated_at.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    output.join("\n")
+}
+
+fn format_results(query: &str, results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return format!("No matching entries found for query: \"{}\"", query);
+    }
+
+    let mut output = Vec::new();
+    for result in results {
+        let source = result.citation().or_else(|| {
+            result
+                .point
+                .payload
+                .get("path")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+        let mut header = match result.rerank_score.or(result.fused_score) {
+            Some(score) => format!("[score: {:.3}]", score),
+            None => format!("[distance: {:.3}]", result.distance),
+        };
+        if let Some(source) = source {
+            header.push_str(&format!(" {}", source));
+        }
+        output.push(format!(
+            "{}\n{}\n",
+            header.dark_grey(),
+            result.text().unwrap_or_default()
+        ));
+    }
+    output.join("\n")
+}
+
+fn context_json(context: &KnowledgeContext) -> Value {
+    json!({
+        "id": context.id,
+        "name": context.name,
+        "description": context.description,
+        "path": context.source_path,
+        "items": context.item_count,
+        "index_type": context.embedding_type.description(),
+        "watched": context.watched,
+        "reranked": context.rerank,
+        "created_at": context.created_at.to_rfc3339(),
+        "updated_at": context.updated_at.to_rfc3339(),
+    })
+}
+
+fn result_json(result: &SearchResult) -> Value {
+    json!({
+        "text": result.text(),
+        "path": result.point.payload.get("path"),
+        "citation": result.citation(),
+        "distance": result.distance,
+        "score": result.rerank_score.or(result.fused_score),
+        "metadata": result.point.payload,
+    })
+}
+
+fn status_json(status: &SystemStatus) -> Value {
+    json!({
+        "total_entries": status.total_contexts,
+        "persistent_entries": status.persistent_contexts,
+        "volatile_entries": status.volatile_contexts,
+        "operations": status.operations.iter().map(|op| json!({
+            "id": op.id,
+            "type": op.operation_type.display_name(),
+            "message": op.message,
+            "current": op.current,
+            "total": op.total,
+            "completed": op.state == OperationState::Completed,
+            "cancelled": op.state == OperationState::Cancelled,
+            "failed": op.state == OperationState::Failed,
+            "waiting": op.is_waiting,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RootSubcommand;
+    use crate::util::test::assert_parse;
+
+    #[test]
+    fn test_knowledge_add() {
+        assert_parse!(
+            [
+                "knowledge",
+                "add",
+                "docs",
+                "--include",
+                "**/*.md",
+                "--agent",
+                "ci",
+                "--format",
+                "json"
+            ],
+            RootSubcommand::Knowledge(KnowledgeArgs {
+                cmd: KnowledgeSubcommands::Add {
+                    path: "docs".to_string(),
+                    name: None,
+                    include: vec!["**/*.md".to_string()],
+                    exclude: vec![],
+                    index_type: None,
+                    watch: false,
+                    rerank: false,
+                },
+                agent: Some("ci".to_string()),
+                format: OutputFormat::Json,
+            })
+        );
+    }
+
+    #[test]
+    fn test_knowledge_search() {
+        assert_parse!(
+            [
+                "knowledge",
+                "--format",
+                "json-pretty",
+                "search",
+                "login flow",
+                "--file-type",
+                "code"
+            ],
+            RootSubcommand::Knowledge(KnowledgeArgs {
+                cmd: KnowledgeSubcommands::Search {
+                    query: "login flow".to_string(),
+                    context_id: None,
+                    path_glob: None,
+                    file_type: Some("code".to_string()),
+                    extension: None,
+                    modified_after: None,
+                },
+                agent: None,
+                format: OutputFormat::JsonPretty,
+            })
+        );
+        assert_parse!(
+            ["knowledge", "rm", "docs"],
+            RootSubcommand::Knowledge(KnowledgeArgs {
+                cmd: KnowledgeSubcommands::Remove {
+                    path: "docs".to_string()
+                },
+                agent: None,
+                format: OutputFormat::Plain,
+            })
+        );
+    }
+}
@@ -4,6 +4,7 @@ mod debug;
 mod diagnostics;
 pub mod feed;
 mod issue;
+mod knowledge;
 mod mcp;
 mod settings;
 mod user;
@@ -43,6 +44,7 @@ use tracing::{
 };
 
 use crate::cli::chat::ChatArgs;
+use crate::cli::knowledge::KnowledgeArgs;
 use crate::cli::mcp::McpSubcommand;
 use crate::cli::user::{
     LoginArgs,
@@ -121,6 +123,8 @@ pub enum RootSubcommand {
     /// Model Context Protocol (MCP)
     #[command(subcommand)]
     Mcp(McpSubcommand),
+    /// Manage and search knowledge bases
+    Knowledge(KnowledgeArgs),
 }
 
 impl RootSubcommand {
@@ -169,6 +173,7 @@ impl RootSubcommand {
             Self::Version { changelog } => Cli::print_version(changelog),
             Self::Chat(args) => args.execute(os).await,
             Self::Mcp(args) => args.execute(os, &mut std::io::stderr()).await,
+            Self::Knowledge(args) => args.execute(os).await,
         }
     }
 }
@@ -193,6 +198,7 @@ impl Display for RootSubcommand {
             Self::Issue(_) => "issue",
             Self::Version { .. } => "version",
             Self::Mcp(_) => "mcp",
+            Self::Knowledge(_) => "knowledge",
         };
 
         write!(f, "{name}")
@@ -336,6 +342,7 @@ impl Cli {
 
 #[cfg(test)]
 mod test {
+    use chat::ChatOutputFormat;
     use chat::WrapMode::{
         Always,
         Auto,
@@ -375,6 +382,8 @@ mod test {
         assert_eq!(Cli::parse_from([CHAT_BINARY_NAME, "chat", "-vv"]), Cli {
             subcommand: Some(RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -382,6 +391,8 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })),
             verbose: 2,
             help_all: false,
@@ -415,6 +426,8 @@ mod test {
             ["chat", "--profile", "my-profile"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: Some("my-profile".to_string()),
                 model: None,
@@ -422,6 +435,8 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -432,6 +447,8 @@ mod test {
             ["chat", "--profile", "my-profile", "Hello"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: Some("Hello".to_string()),
                 agent: Some("my-profile".to_string()),
                 model: None,
@@ -439,6 +456,8 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -449,6 +468,8 @@ mod test {
             ["chat", "--profile", "my-profile", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: Some("my-profile".to_string()),
                 model: None,
@@ -456,6 +477,8 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -466,6 +489,8 @@ mod test {
             ["chat", "--no-interactive", "--resume"],
             RootSubcommand::Chat(ChatArgs {
                 resume: true,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -473,12 +498,16 @@ mod test {
                 trust_tools: None,
                 no_interactive: true,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
         assert_parse!(
             ["chat", "--non-interactive", "-r"],
             RootSubcommand::Chat(ChatArgs {
                 resume: true,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -486,6 +515,8 @@ mod test {
                 trust_tools: None,
                 no_interactive: true,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -496,6 +527,8 @@ mod test {
             ["chat", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -503,6 +536,8 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -513,6 +548,8 @@ mod test {
             ["chat", "--trust-tools="],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -520,6 +557,8 @@ mod test {
                 trust_tools: Some(vec!["".to_string()]),
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -530,6 +569,8 @@ mod test {
             ["chat", "--trust-tools=fs_read,fs_write"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -537,6 +578,8 @@ mod test {
                 trust_tools: Some(vec!["fs_read".to_string(), "fs_write".to_string()]),
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -547,6 +590,8 @@ mod test {
             ["chat", "-w", "never"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -554,12 +599,16 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: Some(Never),
+                output_format: None,
+                serve: false,
             })
         );
         assert_parse!(
             ["chat", "--wrap", "always"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -567,12 +616,16 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: Some(Always),
+                output_format: None,
+                serve: false,
             })
         );
         assert_parse!(
             ["chat", "--wrap", "auto"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_id: None,
+                list_sessions: false,
                 input: None,
                 agent: None,
                 model: None,
@@ -580,6 +633,116 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: Some(Auto),
+                output_format: None,
+                serve: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_output_format() {
+        assert_parse!(
+            ["chat", "--no-interactive", "--output-format", "stream-json", "Hello"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_id: None,
+                list_sessions: false,
+                input: Some("Hello".to_string()),
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: true,
+                wrap: None,
+                output_format: Some(ChatOutputFormat::StreamJson),
+                serve: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_serve() {
+        assert_parse!(
+            ["chat", "--serve"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_id: None,
+                list_sessions: false,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                wrap: None,
+                output_format: None,
+                serve: true,
+            })
+        );
+        assert!(Cli::try_parse_from([crate::util::CHAT_BINARY_NAME, "chat", "--serve", "--no-interactive"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_sessions() {
+        assert_parse!(
+            ["chat", "--resume-id", "3f2a9c1e"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_id: Some("3f2a9c1e".to_string()),
+                list_sessions: false,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                wrap: None,
+                output_format: None,
+                serve: false,
+            })
+        );
+        assert_parse!(
+            ["chat", "--resume", "fix the bug"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: true,
+                resume_id: None,
+                list_sessions: false,
+                input: Some("fix the bug".to_string()),
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                wrap: None,
+                output_format: None,
+                serve: false,
+            })
+        );
+        assert!(
+            Cli::try_parse_from([
+                crate::util::CHAT_BINARY_NAME,
+                "chat",
+                "--resume",
+                "--resume-id",
+                "3f2a9c1e"
+            ])
+            .is_err()
+        );
+        assert_parse!(
+            ["chat", "--list-sessions"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_id: None,
+                list_sessions: true,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                wrap: None,
+                output_format: None,
+                serve: false,
             })
         );
     }
@@ -7,12 +7,18 @@ use std::sync::PoisonError;
 
 use aws_sdk_cognitoidentity::primitives::DateTimeFormat;
 use aws_sdk_cognitoidentity::types::Credentials;
+use chrono::{
+    DateTime,
+    Utc,
+};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::types::FromSql;
 use rusqlite::{
     Connection,
     Error,
+    OptionalExtension,
+    Row,
     ToSql,
     params,
 };
@@ -71,7 +77,8 @@ const MIGRATIONS: &[Migration] = migrations![
     "004_state_table",
     "005_auth_table",
     "006_make_state_blob",
-    "007_conversations_table"
+    "007_conversations_table",
+    "008_sessions_table"
 ];
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -116,6 +123,47 @@ where
     }
 }
 
+/// Metadata of a chat session stored in the sessions table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionMetadata {
+    /// The conversation id of the session.
+    pub id: String,
+    /// The directory the session was started in.
+    pub path: String,
+    pub title: Option<String>,
+    pub agent: Option<String>,
+    pub model_id: Option<String>,
+    /// Estimated number of tokens in the user messages of the conversation.
+    pub input_tokens: u64,
+    /// Estimated number of tokens in the assistant messages of the conversation.
+    pub output_tokens: u64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SessionMetadata {
+    const COLUMNS: &str = "id, path, title, agent, model_id, input_tokens, output_tokens, created_at, updated_at";
+
+    fn from_row(row: &Row<'_>) -> Result<Self, rusqlite::Error> {
+        let timestamp = |idx: usize| -> Result<DateTime<Utc>, rusqlite::Error> {
+            let secs: i64 = row.get(idx)?;
+            Ok(DateTime::from_timestamp(secs, 0).unwrap_or_default())
+        };
+
+        Ok(Self {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            title: row.get(2)?,
+            agent: row.get(3)?,
+            model_id: row.get(4)?,
+            input_tokens: row.get(5)?,
+            output_tokens: row.get(6)?,
+            created_at: timestamp(7)?,
+            updated_at: timestamp(8)?,
+        })
+    }
+}
+
 // A cloneable error
 #[derive(Debug, Clone, thiserror::Error)]
 #[error("Failed to open database: {}", .0)]
@@ -370,6 +418,8 @@ impl Database {
     // }
 
     /// Get a chat conversation given a path to the conversation.
+    ///
+    /// Conversations are no longer stored by path, see [Self::get_latest_session_by_path].
     pub fn get_conversation_by_path(
         &mut self,
         path: impl AsRef<Path>,
@@ -383,19 +433,128 @@ impl Database {
         self.get_json_entry(Table::Conversations, path)
     }
 
-    /// Set a chat conversation given a path to the conversation.
-    pub fn set_conversation_by_path(
-        &mut self,
+    /// Get the conversation of a chat session.
+    pub fn get_session(&self, id: &str) -> Result<Option<ConversationState>, DatabaseError> {
+        let conn = self.pool.get()?;
+        let conversation: Option<String> = conn
+            .query_row("SELECT conversation FROM sessions WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        Ok(match conversation {
+            Some(conversation) => Some(serde_json::from_str(&conversation)?),
+            None => None,
+        })
+    }
+
+    /// Get the conversation of the most recently updated chat session started in `path`.
+    pub fn get_latest_session_by_path(
+        &self,
         path: impl AsRef<Path>,
-        state: &ConversationState,
-    ) -> Result<usize, DatabaseError> {
-        // We would need to encode this to support non utf8 paths.
+    ) -> Result<Option<ConversationState>, DatabaseError> {
         let path = match path.as_ref().to_str() {
             Some(path) => path,
-            None => return Ok(0),
+            None => return Ok(None),
         };
 
-        self.set_json_entry(Table::Conversations, path, state)
+        let conn = self.pool.get()?;
+        let conversation: Option<String> = conn
+            .query_row(
+                "SELECT conversation FROM sessions WHERE path = ?1 ORDER BY updated_at DESC, rowid DESC LIMIT 1",
+                [path],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(match conversation {
+            Some(conversation) => Some(serde_json::from_str(&conversation)?),
+            None => None,
+        })
+    }
+
+    /// List chat sessions, most recently updated first. If `path` is given, only sessions
+    /// started in that directory are returned.
+    pub fn list_sessions(&self, path: Option<&Path>) -> Result<Vec<SessionMetadata>, DatabaseError> {
+        let path = match path.map(|path| path.to_str()) {
+            Some(Some(path)) => Some(path),
+            Some(None) => return Ok(Vec::new()),
+            None => None,
+        };
+
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions WHERE ?1 IS NULL OR path = ?1 ORDER BY updated_at DESC, rowid DESC",
+            SessionMetadata::COLUMNS
+        ))?;
+        let sessions = stmt
+            .query_map([path], SessionMetadata::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sessions)
+    }
+
+    /// Get all chat sessions whose id starts with `prefix`.
+    pub fn get_sessions_by_id_prefix(&self, prefix: &str) -> Result<Vec<SessionMetadata>, DatabaseError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions WHERE substr(id, 1, length(?1)) = ?1 ORDER BY updated_at DESC, rowid DESC",
+            SessionMetadata::COLUMNS
+        ))?;
+        let sessions = stmt
+            .query_map([prefix], SessionMetadata::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sessions)
+    }
+
+    /// Create or update a chat session.
+    ///
+    /// The title and creation time of an existing session are kept, unless the session has no
+    /// title yet.
+    pub fn set_session(&self, metadata: &SessionMetadata, state: &ConversationState) -> Result<usize, DatabaseError> {
+        Ok(self.pool.get()?.execute(
+            &format!(
+                "INSERT INTO sessions ({}, conversation) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                ON CONFLICT(id) DO UPDATE SET
+                    path = excluded.path,
+                    title = COALESCE(sessions.title, excluded.title),
+                    agent = excluded.agent,
+                    model_id = excluded.model_id,
+                    input_tokens = excluded.input_tokens,
+                    output_tokens = excluded.output_tokens,
+                    updated_at = excluded.updated_at,
+                    conversation = excluded.conversation",
+                SessionMetadata::COLUMNS
+            ),
+            params![
+                metadata.id,
+                metadata.path,
+                metadata.title,
+                metadata.agent,
+                metadata.model_id,
+                metadata.input_tokens,
+                metadata.output_tokens,
+                metadata.created_at.timestamp(),
+                metadata.updated_at.timestamp(),
+                serde_json::to_string(state)?,
+            ],
+        )?)
+    }
+
+    /// Set the title of a chat session. Returns whether the session exists.
+    pub fn rename_session(&self, id: &str, title: &str) -> Result<bool, DatabaseError> {
+        let updated = self
+            .pool
+            .get()?
+            .execute("UPDATE sessions SET title = ?2 WHERE id = ?1", params![id, title])?;
+        Ok(updated > 0)
+    }
+
+    /// Delete a chat session. Returns whether the session existed.
+    pub fn delete_session(&self, id: &str) -> Result<bool, DatabaseError> {
+        let deleted = self.pool.get()?.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
     }
 
     pub async fn get_secret(&self, key: &str) -> Result<Option<Secret>, DatabaseError> {
@@ -39,6 +39,12 @@ pub enum Setting {
     KnowledgeChunkOverlap,
     #[strum(message = "Type of knowledge index to use (string)")]
     KnowledgeIndexType,
+    #[strum(message = "Weight of semantic over keyword ranking in hybrid knowledge search, 0.0 to 1.0 (number)")]
+    KnowledgeHybridWeight,
+    #[strum(message = "Store knowledge vectors as 8-bit integers to save disk space (boolean)")]
+    KnowledgeQuantizeVectors,
+    #[strum(message = "Rerank knowledge search results of all entries with a cross-encoder model (boolean)")]
+    KnowledgeRerank,
     #[strum(message = "Key binding for fuzzy search command (single character)")]
     SkimCommandKey,
     #[strum(message = "Key binding for autocompletion hint acceptance (single character)")]
@@ -83,6 +89,10 @@ pub enum Setting {
     EnabledTodoList,
     #[strum(message = "Enable the checkpoint feature (boolean)")]
     EnabledCheckpoint,
+    #[strum(message = "Files snapshotted by checkpoints: full or touched (string)")]
+    ChatCheckpointMode,
+    #[strum(message = "Tokenizer files used to count tokens, by model id (object)")]
+    ChatTokenizers,
 }
 
 impl AsRef<str> for Setting {
@@ -99,6 +109,9 @@ impl AsRef<str> for Setting {
             Self::KnowledgeChunkSize => "knowledge.chunkSize",
             Self::KnowledgeChunkOverlap => "knowledge.chunkOverlap",
             Self::KnowledgeIndexType => "knowledge.indexType",
+            Self::KnowledgeHybridWeight => "knowledge.hybridWeight",
+            Self::KnowledgeQuantizeVectors => "knowledge.quantizeVectors",
+            Self::KnowledgeRerank => "knowledge.rerank",
             Self::SkimCommandKey => "chat.skimCommandKey",
             Self::AutocompletionKey => "chat.autocompletionKey",
             Self::EnabledTangentMode => "chat.enableTangentMode",
@@ -120,7 +133,9 @@ impl AsRef<str> for Setting {
             Self::ChatEnableHistoryHints => "chat.enableHistoryHints",
             Self::EnabledTodoList => "chat.enableTodoList",
             Self::EnabledCheckpoint => "chat.enableCheckpoint",
+            Self::ChatCheckpointMode => "chat.checkpointMode",
             Self::EnabledContextUsageIndicator => "chat.enableContextUsageIndicator",
+            Self::ChatTokenizers => "chat.tokenizers",
         }
     }
 }
@@ -147,6 +162,9 @@ impl TryFrom<&str> for Setting {
             "knowledge.chunkSize" => Ok(Self::KnowledgeChunkSize),
             "knowledge.chunkOverlap" => Ok(Self::KnowledgeChunkOverlap),
             "knowledge.indexType" => Ok(Self::KnowledgeIndexType),
+            "knowledge.hybridWeight" => Ok(Self::KnowledgeHybridWeight),
+            "knowledge.quantizeVectors" => Ok(Self::KnowledgeQuantizeVectors),
+            "knowledge.rerank" => Ok(Self::KnowledgeRerank),
             "chat.skimCommandKey" => Ok(Self::SkimCommandKey),
             "chat.autocompletionKey" => Ok(Self::AutocompletionKey),
             "chat.enableTangentMode" => Ok(Self::EnabledTangentMode),
@@ -168,7 +186,9 @@ impl TryFrom<&str> for Setting {
             "chat.enableHistoryHints" => Ok(Self::ChatEnableHistoryHints),
             "chat.enableTodoList" => Ok(Self::EnabledTodoList),
             "chat.enableCheckpoint" => Ok(Self::EnabledCheckpoint),
+            "chat.checkpointMode" => Ok(Self::ChatCheckpointMode),
             "chat.enableContextUsageIndicator" => Ok(Self::EnabledContextUsageIndicator),
+            "chat.tokenizers" => Ok(Self::ChatTokenizers),
             _ => Err(DatabaseError::InvalidSetting(value.to_string())),
         }
     }
@@ -1,29 +1,50 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::process::Stdio;
+use std::sync::Arc;
 
 use regex::Regex;
 use rmcp::model::{
     CallToolRequestParam,
     CallToolResult,
+    CancelledNotification,
+    CancelledNotificationMethod,
+    CancelledNotificationParam,
+    ClientCapabilities,
+    ClientRequest,
     ClientResult,
+    ElicitationCapability,
     ErrorCode,
     GetPromptRequestParam,
     GetPromptResult,
     Implementation,
     InitializeRequestParam,
     ListPromptsResult,
+    ListResourceTemplatesResult,
+    ListResourcesResult,
+    ListRootsResult,
     ListToolsResult,
     LoggingLevel,
     LoggingMessageNotificationParam,
+    Meta,
+    NumberOrString,
     PaginatedRequestParam,
+    ProgressNotificationParam,
+    ProgressToken,
+    ReadResourceRequestParam,
+    ReadResourceResult,
+    Request,
+    RequestId,
+    RootsCapabilities,
     ServerNotification,
     ServerRequest,
+    ServerResult,
 };
 use rmcp::service::{
     ClientInitializeError,
     DynService,
     NotificationContext,
+    PeerRequestOptions,
 };
 use rmcp::transport::{
     ConfigureCommandExt,
@@ -47,7 +68,10 @@ use tracing::{
     info,
 };
 
+use super::elicitation::ElicitationHandler;
 use super::messenger::Messenger;
+use super::roots::RootsProvider;
+use super::sampling::SamplingHandler;
 use super::{
     AuthClientWrapper,
     HttpServiceBuilder,
@@ -56,6 +80,7 @@ use super::{
 use crate::cli::chat::server_messenger::ServerMessenger;
 use crate::cli::chat::tools::custom_tool::{
     CustomToolConfig,
+    SamplingPermission,
     TransportType,
 };
 use crate::os::Os;
@@ -233,6 +258,53 @@ impl Clone for InnerService {
     }
 }
 
+/// Sends the progress notifications of a request to whoever is waiting for it
+pub type ProgressSender = tokio::sync::mpsc::UnboundedSender<ProgressNotificationParam>;
+
+/// Progress senders of the requests in flight, by the progress token sent with the request
+type ProgressSenders = Arc<std::sync::Mutex<HashMap<ProgressToken, ProgressSender>>>;
+
+/// Notifies the server that a request is cancelled unless disarmed before being dropped. Since
+/// notifying is async, it is done in a task of its own.
+struct CancelOnDrop {
+    peer: rmcp::service::Peer<RoleClient>,
+    request_id: Option<RequestId>,
+    progress_token: ProgressToken,
+    progress_senders: ProgressSenders,
+}
+
+impl CancelOnDrop {
+    fn disarm(mut self) {
+        self.request_id.take();
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Ok(mut senders) = self.progress_senders.lock() {
+            senders.remove(&self.progress_token);
+        }
+        let Some(request_id) = self.request_id.take() else {
+            return;
+        };
+
+        let peer = self.peer.clone();
+        tokio::spawn(async move {
+            let notification = CancelledNotification {
+                params: CancelledNotificationParam {
+                    request_id,
+                    reason: Some("The user interrupted the request".to_string()),
+                },
+                method: CancelledNotificationMethod,
+                extensions: Default::default(),
+            };
+            if let Err(e) = peer.send_notification(notification.into()).await {
+                error!(target: "mcp", "Failed to notify server of cancelled request: {e}");
+            }
+        });
+    }
+}
+
 /// A wrapper around MCP (Model Context Protocol) service instances that manages
 /// authentication and enables cloning functionality.
 ///
@@ -248,6 +320,7 @@ impl Clone for InnerService {
 pub struct RunningService {
     pub inner_service: InnerService,
     auth_client: Option<AuthClientWrapper>,
+    progress_senders: ProgressSenders,
 }
 
 impl Clone for RunningService {
@@ -255,14 +328,88 @@ impl Clone for RunningService {
         RunningService {
             inner_service: self.inner_service.clone(),
             auth_client: self.auth_client.clone(),
+            progress_senders: self.progress_senders.clone(),
         }
     }
 }
 
 impl RunningService {
-    decorate_with_auth_retry!(CallToolRequestParam, call_tool, CallToolResult);
-
     decorate_with_auth_retry!(GetPromptRequestParam, get_prompt, GetPromptResult);
+
+    decorate_with_auth_retry!(ReadResourceRequestParam, read_resource, ReadResourceResult);
+
+    /// Calls a tool, forwarding the progress the server reports for the call to `progress`.
+    ///
+    /// Dropping the returned future, e.g. when the user interrupts the call, tells the server that
+    /// the call is cancelled so it can stop working on it.
+    pub async fn call_tool(
+        &self,
+        param: CallToolRequestParam,
+        progress: ProgressSender,
+    ) -> Result<CallToolResult, ServiceError> {
+        let peer = match &self.inner_service {
+            InnerService::Original(rs) => rs.peer(),
+            InnerService::Peer(peer) => peer,
+        };
+
+        match self.call_tool_once(peer, param.clone(), progress.clone()).await {
+            Ok(result) => Ok(result),
+            // Same as the methods decorated with [decorate_with_auth_retry]
+            Err(e) => match self.auth_client.as_ref() {
+                Some(auth_client) if auth_client.refresh_token().await.is_ok() => {
+                    info!("Token refreshed");
+                    self.call_tool_once(peer, param, progress).await
+                },
+                _ => Err(e),
+            },
+        }
+    }
+
+    async fn call_tool_once(
+        &self,
+        peer: &rmcp::service::Peer<RoleClient>,
+        param: CallToolRequestParam,
+        progress: ProgressSender,
+    ) -> Result<CallToolResult, ServiceError> {
+        // The progress sender is registered before the request is sent, since the server may report
+        // progress as soon as it receives the request. rmcp only generates its progress token when
+        // sending, so a token of our own is sent in the meta of the request instead.
+        let progress_token = ProgressToken(NumberOrString::String(uuid::Uuid::new_v4().to_string().into()));
+        if let Ok(mut senders) = self.progress_senders.lock() {
+            senders.insert(progress_token.clone(), progress);
+        }
+        let mut guard = CancelOnDrop {
+            peer: peer.clone(),
+            request_id: None,
+            progress_token: progress_token.clone(),
+            progress_senders: self.progress_senders.clone(),
+        };
+
+        let mut meta = Meta::new();
+        meta.set_progress_token(progress_token);
+        let options = PeerRequestOptions {
+            timeout: None,
+            meta: Some(meta),
+        };
+        let request = ClientRequest::CallToolRequest(Request::new(param));
+        let handle = peer.send_cancellable_request(request, options).await?;
+        guard.request_id = Some(handle.id.clone());
+
+        let response = handle.await_response().await;
+        guard.disarm();
+
+        match response? {
+            ServerResult::CallToolResult(result) => Ok(result),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    pub async fn notify_roots_list_changed(&self) -> Result<(), ServiceError> {
+        match &self.inner_service {
+            InnerService::Original(rs) => rs.notify_roots_list_changed().await,
+            InnerService::Peer(peer) => peer.notify_roots_list_changed().await,
+        }
+    }
 }
 
 /// This struct implements the [Service] trait from rmcp. It is within this trait the logic of
@@ -273,14 +420,29 @@ pub struct McpClientService {
     pub config: CustomToolConfig,
     server_name: String,
     messenger: ServerMessenger,
+    sampling: SamplingHandler,
+    roots: RootsProvider,
+    elicitation: ElicitationHandler,
+    progress_senders: ProgressSenders,
 }
 
 impl McpClientService {
-    pub fn new(server_name: String, config: CustomToolConfig, messenger: ServerMessenger) -> Self {
+    pub fn new(
+        server_name: String,
+        config: CustomToolConfig,
+        messenger: ServerMessenger,
+        sampling: SamplingHandler,
+        roots: RootsProvider,
+        elicitation: ElicitationHandler,
+    ) -> Self {
         Self {
             server_name,
             config,
             messenger,
+            sampling,
+            roots,
+            elicitation,
+            progress_senders: Default::default(),
         }
     }
 
@@ -290,6 +452,7 @@ impl McpClientService {
         let handle: JoinHandle<Result<RunningService, McpClientError>> = tokio::spawn(async move {
             let messenger_clone = self.messenger.clone();
             let server_name = self.server_name.clone();
+            let progress_senders = self.progress_senders.clone();
 
             let (service, child_stderr, auth_dropguard) = match self.into_service(&os_clone, &messenger_clone).await {
                 Ok((service, stderr, auth_dg)) => (service, stderr, auth_dg),
@@ -357,6 +520,30 @@ impl McpClientService {
                                 service_method: list_prompts,
                                 result_field: prompts,
                                 messenger_method: send_prompts_list_result,
+                                service: service_clone.clone(),
+                                messenger: messenger_clone,
+                                server_name: server_name
+                            };
+                        }
+
+                        if init_result.capabilities.resources.is_some() {
+                            paginated_fetch! {
+                                final_result_type: ListResourcesResult,
+                                content_type: rmcp::model::Resource,
+                                service_method: list_resources,
+                                result_field: resources,
+                                messenger_method: send_resources_list_result,
+                                service: service_clone.clone(),
+                                messenger: messenger_clone,
+                                server_name: server_name
+                            };
+
+                            paginated_fetch! {
+                                final_result_type: ListResourceTemplatesResult,
+                                content_type: rmcp::model::ResourceTemplate,
+                                service_method: list_resource_templates,
+                                result_field: resource_templates,
+                                messenger_method: send_resource_templates_list_result,
                                 service: service_clone,
                                 messenger: messenger_clone,
                                 server_name: server_name
@@ -375,6 +562,7 @@ impl McpClientService {
             Ok(RunningService {
                 inner_service: InnerService::Original(service),
                 auth_client: auth_dropguard,
+                progress_senders,
             })
         });
 
@@ -494,6 +682,16 @@ impl McpClientService {
         }
     }
 
+    fn on_progress(&self, params: ProgressNotificationParam) {
+        let Ok(senders) = self.progress_senders.lock() else {
+            return;
+        };
+        // Progress of requests that already finished is dropped
+        if let Some(sender) = senders.get(&params.progress_token) {
+            let _ = sender.send(params);
+        }
+    }
+
     async fn on_tool_list_changed(&self, context: NotificationContext<RoleClient>) {
         let NotificationContext { peer, .. } = context;
 
@@ -523,25 +721,64 @@ impl McpClientService {
             server_name: self.server_name
         };
     }
+
+    async fn on_resource_list_changed(&self, context: NotificationContext<RoleClient>) {
+        let NotificationContext { peer, .. } = context;
+
+        paginated_fetch! {
+            final_result_type: ListResourcesResult,
+            content_type: rmcp::model::Resource,
+            service_method: list_resources,
+            result_field: resources,
+            messenger_method: send_resources_list_result,
+            service: peer.clone(),
+            messenger: self.messenger,
+            server_name: self.server_name
+        };
+
+        // Templates are not covered by a notification of their own, so they are refreshed along
+        // with the resources
+        paginated_fetch! {
+            final_result_type: ListResourceTemplatesResult,
+            content_type: rmcp::model::ResourceTemplate,
+            service_method: list_resource_templates,
+            result_field: resource_templates,
+            messenger_method: send_resource_templates_list_result,
+            service: peer,
+            messenger: self.messenger,
+            server_name: self.server_name
+        };
+    }
 }
 
 impl Service<RoleClient> for McpClientService {
     async fn handle_request(
         &self,
         request: <RoleClient as rmcp::service::ServiceRole>::PeerReq,
-        _context: rmcp::service::RequestContext<RoleClient>,
+        context: rmcp::service::RequestContext<RoleClient>,
     ) -> Result<<RoleClient as rmcp::service::ServiceRole>::Resp, rmcp::ErrorData> {
         match request {
             ServerRequest::PingRequest(_) => Ok(ClientResult::empty(())),
-            ServerRequest::CreateMessageRequest(_) => Err(rmcp::ErrorData::method_not_found::<
-                rmcp::model::CreateMessageRequestMethod,
-            >()),
-            ServerRequest::ListRootsRequest(_) => {
-                Err(rmcp::ErrorData::method_not_found::<rmcp::model::ListRootsRequestMethod>())
+            ServerRequest::CreateMessageRequest(request) => {
+                let create_message = self.sampling.create_message(
+                    &self.server_name,
+                    self.config.sampling.unwrap_or_default(),
+                    request.params,
+                );
+                tokio::select! {
+                    result = create_message => result.map(Into::into),
+                    // Stops generating a response the server no longer waits for
+                    _ = context.ct.cancelled() => Err(ErrorData::internal_error("The request was cancelled", None)),
+                }
             },
-            ServerRequest::CreateElicitationRequest(_) => Err(rmcp::ErrorData::method_not_found::<
-                rmcp::model::ElicitationCreateRequestMethod,
-            >()),
+            ServerRequest::ListRootsRequest(_) => Ok(ClientResult::ListRootsResult(ListRootsResult {
+                roots: self.roots.list_roots().await,
+            })),
+            ServerRequest::CreateElicitationRequest(request) => self
+                .elicitation
+                .create_elicitation(&self.server_name, request.params)
+                .await
+                .map(ClientResult::CreateElicitationResult),
         }
     }
 
@@ -556,11 +793,20 @@ impl Service<RoleClient> for McpClientService {
                 self.on_logging_message(notification.params, context).await;
             },
             ServerNotification::PromptListChangedNotification(_) => self.on_prompt_list_changed(context).await,
+            ServerNotification::ResourceListChangedNotification(_) => self.on_resource_list_changed(context).await,
+            ServerNotification::ProgressNotification(notification) => self.on_progress(notification.params),
+            ServerNotification::CancelledNotification(notification) => {
+                // The request itself is cancelled by rmcp
+                info!(
+                    target: "mcp",
+                    "{} cancelled request {}: {}",
+                    self.server_name,
+                    notification.params.request_id,
+                    notification.params.reason.as_deref().unwrap_or("no reason given")
+                );
+            },
             // TODO: support these
-            ServerNotification::CancelledNotification(_) => (),
             ServerNotification::ResourceUpdatedNotification(_) => (),
-            ServerNotification::ResourceListChangedNotification(_) => (),
-            ServerNotification::ProgressNotification(_) => (),
         };
         Ok(())
     }
@@ -568,7 +814,16 @@ impl Service<RoleClient> for McpClientService {
     fn get_info(&self) -> <RoleClient as rmcp::service::ServiceRole>::Info {
         InitializeRequestParam {
             protocol_version: Default::default(),
-            capabilities: Default::default(),
+            capabilities: ClientCapabilities {
+                roots: Some(RootsCapabilities {
+                    list_changed: Some(true),
+                }),
+                elicitation: Some(ElicitationCapability {
+                    schema_validation: Some(true),
+                }),
+                sampling: (self.config.sampling != Some(SamplingPermission::Deny)).then(Default::default),
+                ..Default::default()
+            },
             client_info: Implementation {
                 name: "Q DEV CLI".to_string(),
                 version: "1.0.0".to_string(),
@@ -620,7 +875,165 @@ impl InitializedMcpClient {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use serde_json::{
+        Value,
+        json,
+    };
+    use tokio::io::{
+        AsyncBufReadExt,
+        AsyncWriteExt,
+        BufReader,
+        DuplexStream,
+        Lines,
+    };
+    use tokio::sync::Mutex;
+
     use super::*;
+    use crate::cli::Agent;
+    use crate::cli::chat::server_messenger::ServerMessengerBuilder;
+
+    /// The end of the connection played by the server in tests, exchanging one JSON-RPC message
+    /// per line
+    struct FakeServer {
+        lines: Lines<BufReader<tokio::io::ReadHalf<DuplexStream>>>,
+        writer: tokio::io::WriteHalf<DuplexStream>,
+    }
+
+    impl FakeServer {
+        async fn receive(&mut self) -> Value {
+            let line = tokio::time::timeout(Duration::from_secs(5), self.lines.next_line())
+                .await
+                .expect("timed out waiting for the client")
+                .unwrap()
+                .expect("the client closed the connection");
+            serde_json::from_str(&line).unwrap()
+        }
+
+        async fn send(&mut self, message: Value) {
+            let mut line = message.to_string();
+            line.push('\n');
+            self.writer.write_all(line.as_bytes()).await.unwrap();
+        }
+    }
+
+    /// Connects a client to a server played by the test
+    async fn connect() -> (RunningService, FakeServer) {
+        let os = Os::new().await.unwrap();
+        let (client_stream, server_stream) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_stream);
+        let mut server = FakeServer {
+            lines: BufReader::new(server_read).lines(),
+            writer: server_write,
+        };
+
+        let (_event_receiver, messenger_builder) = ServerMessengerBuilder::new(8);
+        let (user_prompt_sender, _user_prompts) = tokio::sync::mpsc::channel(1);
+        let client = McpClientService::new(
+            "fake".to_string(),
+            serde_json::from_value(json!({ "command": "fake-mcp" })).unwrap(),
+            messenger_builder.build_with_name("fake".to_string()),
+            SamplingHandler::new(os.clone(), false, user_prompt_sender.clone()),
+            RootsProvider::new(os.clone(), Arc::new(Mutex::new(Agent::default()))),
+            ElicitationHandler::new(false, user_prompt_sender),
+        );
+        let progress_senders = client.progress_senders.clone();
+
+        let handshake = async {
+            let initialize = server.receive().await;
+            server
+                .send(json!({
+                    "jsonrpc": "2.0",
+                    "id": initialize["id"],
+                    "result": {
+                        "protocolVersion": initialize["params"]["protocolVersion"],
+                        "capabilities": { "tools": {} },
+                        "serverInfo": { "name": "fake", "version": "1.0.0" }
+                    }
+                }))
+                .await;
+            assert_eq!(server.receive().await["method"], "notifications/initialized");
+        };
+        let (service, ()) = tokio::join!(client.into_dyn().serve(tokio::io::split(client_stream)), handshake);
+
+        let service = RunningService {
+            inner_service: InnerService::Original(service.unwrap()),
+            auth_client: None,
+            progress_senders,
+        };
+        (service, server)
+    }
+
+    type ProgressReceiver = tokio::sync::mpsc::UnboundedReceiver<ProgressNotificationParam>;
+
+    /// Calls a tool in a task of its own
+    fn call(service: &RunningService, name: &str) -> (JoinHandle<CallToolResult>, ProgressReceiver) {
+        let (progress_sender, progress_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let service = service.clone();
+        let param = CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: None,
+        };
+        let call = tokio::spawn(async move { service.call_tool(param, progress_sender).await.unwrap() });
+        (call, progress_receiver)
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_progress() {
+        let (service, mut server) = connect().await;
+        let (first, mut first_progress) = call(&service, "first");
+        let first_request = server.receive().await;
+        let (second, mut second_progress) = call(&service, "second");
+        let second_request = server.receive().await;
+        assert_eq!(first_request["params"]["name"], "first");
+        assert_eq!(second_request["params"]["name"], "second");
+
+        // Progress is reported for each call with the token it was sent with
+        for (request, message) in [(&second_request, "second"), (&first_request, "first")] {
+            server
+                .send(json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/progress",
+                    "params": {
+                        "progressToken": request["params"]["_meta"]["progressToken"],
+                        "progress": 1,
+                        "message": message
+                    }
+                }))
+                .await;
+        }
+        for request in [&first_request, &second_request] {
+            server
+                .send(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": { "content": [{ "type": "text", "text": request["params"]["name"] }] }
+                }))
+                .await;
+        }
+
+        assert_ne!(first.await.unwrap().is_error, Some(true));
+        assert_ne!(second.await.unwrap().is_error, Some(true));
+        assert_eq!(first_progress.recv().await.unwrap().message.as_deref(), Some("first"));
+        assert_eq!(second_progress.recv().await.unwrap().message.as_deref(), Some("second"));
+        // Senders are removed once the calls are done
+        assert!(first_progress.recv().await.is_none());
+        assert!(second_progress.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_cancelled_on_drop() {
+        let (service, mut server) = connect().await;
+        let (call, mut progress) = call(&service, "slow");
+        let request = server.receive().await;
+
+        call.abort();
+        let notification = server.receive().await;
+        assert_eq!(notification["method"], "notifications/cancelled");
+        assert_eq!(notification["params"]["requestId"], request["id"]);
+        assert!(progress.recv().await.is_none());
+    }
 
     #[tokio::test]
     async fn test_substitute_env_vars() {
@@ -0,0 +1,532 @@
+use std::io::IsTerminal;
+
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+use rmcp::ErrorData;
+use rmcp::model::{
+    CreateElicitationRequestParam,
+    CreateElicitationResult,
+    ElicitationAction,
+    JsonObject,
+};
+use serde_json::{
+    Map,
+    Number,
+    Value,
+};
+use tracing::warn;
+use url::Url;
+
+use super::{
+    USER_PROMPT_LOCK,
+    UserPromptSender,
+    ask_user,
+};
+use crate::cli::chat::util::strip_control_chars;
+
+/// Serves `elicitation/create` requests, with which servers ask the user for information while
+/// handling a request, e.g. a choice or a credential needed by a tool.
+#[derive(Clone, Debug)]
+pub struct ElicitationHandler {
+    /// Whether the user can be asked to fill in forms
+    interactive: bool,
+    /// Forms are filled in through the chat session
+    user_prompts: UserPromptSender,
+}
+
+impl ElicitationHandler {
+    pub fn new(interactive: bool, user_prompts: UserPromptSender) -> Self {
+        Self {
+            interactive,
+            user_prompts,
+        }
+    }
+
+    pub async fn create_elicitation(
+        &self,
+        server_name: &str,
+        param: CreateElicitationRequestParam,
+    ) -> Result<CreateElicitationResult, ErrorData> {
+        let fields = fields(&param.requested_schema).map_err(|e| ErrorData::invalid_params(e, None))?;
+
+        // Serve mode reads requests from stdin, so it can't be used to ask either
+        if !self.interactive || !std::io::stdin().is_terminal() {
+            warn!(
+                target: "mcp",
+                "Declined request for information from {server_name} since the session is not interactive: {}",
+                param.message
+            );
+            return Ok(CreateElicitationResult {
+                action: ElicitationAction::Decline,
+                content: None,
+            });
+        }
+
+        let _guard = USER_PROMPT_LOCK.lock().await;
+        fill_in_form(&self.user_prompts, server_name, &param.message, &fields)
+            .await
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))
+    }
+}
+
+/// A field of the form shown to the user, i.e. a property of the requested schema
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    name: String,
+    title: Option<String>,
+    description: Option<String>,
+    required: bool,
+    default: Option<Value>,
+    kind: FieldKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldKind {
+    String {
+        min_length: Option<u64>,
+        max_length: Option<u64>,
+        format: Option<String>,
+    },
+    Number {
+        integer: bool,
+        minimum: Option<f64>,
+        maximum: Option<f64>,
+    },
+    Boolean,
+    Enum {
+        values: Vec<String>,
+        names: Vec<String>,
+    },
+}
+
+/// Parses the fields of a requested schema. The specification restricts schemas to objects whose
+/// properties are strings, numbers, booleans or enums.
+fn fields(schema: &JsonObject) -> Result<Vec<Field>, String> {
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Ok(Vec::new());
+    };
+
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let string = |key: &str| property.get(key).and_then(Value::as_str).map(str::to_string);
+            let kind = match (property.get("enum"), property.get("type").and_then(Value::as_str)) {
+                (Some(values), _) => {
+                    let values = values
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|v| v.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|values| !values.is_empty())
+                        .ok_or_else(|| format!("Field {name} has to list its options as strings"))?;
+                    let names = property
+                        .get("enumNames")
+                        .and_then(Value::as_array)
+                        .map(|names| names.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                        .filter(|names: &Vec<String>| names.len() == values.len())
+                        .unwrap_or_else(|| values.clone());
+                    FieldKind::Enum { values, names }
+                },
+                (None, Some("string")) => FieldKind::String {
+                    min_length: property.get("minLength").and_then(Value::as_u64),
+                    max_length: property.get("maxLength").and_then(Value::as_u64),
+                    format: string("format"),
+                },
+                (None, Some(kind @ ("number" | "integer"))) => FieldKind::Number {
+                    integer: kind == "integer",
+                    minimum: property.get("minimum").and_then(Value::as_f64),
+                    maximum: property.get("maximum").and_then(Value::as_f64),
+                },
+                (None, Some("boolean")) => FieldKind::Boolean,
+                (None, kind) => {
+                    return Err(format!(
+                        "Field {name} has unsupported type {}",
+                        kind.unwrap_or("<none>")
+                    ));
+                },
+            };
+
+            Ok(Field {
+                name: name.clone(),
+                title: string("title"),
+                description: string("description"),
+                required: required.contains(&name.as_str()),
+                default: property.get("default").cloned(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+impl Field {
+    /// Parses the input of the user for this field. Returns `None` for optional fields left empty.
+    fn parse(&self, input: &str) -> Result<Option<Value>, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return match (&self.default, self.required) {
+                (Some(default), _) => Ok(Some(default.clone())),
+                (None, true) => Err("A value is required".to_string()),
+                (None, false) => Ok(None),
+            };
+        }
+
+        let value = match &self.kind {
+            FieldKind::String {
+                min_length,
+                max_length,
+                format,
+            } => {
+                let length = input.chars().count() as u64;
+                if let Some(min) = min_length.filter(|min| length < *min) {
+                    return Err(format!("Enter at least {min} characters"));
+                }
+                if let Some(max) = max_length.filter(|max| length > *max) {
+                    return Err(format!("Enter at most {max} characters"));
+                }
+                match format.as_deref() {
+                    Some("email") if !input.contains('@') => return Err("Enter an email address".to_string()),
+                    Some("uri") if Url::parse(input).is_err() => return Err("Enter a URI".to_string()),
+                    _ => {},
+                }
+                Value::String(input.to_string())
+            },
+            FieldKind::Number {
+                integer,
+                minimum,
+                maximum,
+            } => {
+                let number = input.parse::<f64>().map_err(|_e| "Enter a number".to_string())?;
+                if *integer && number.fract() != 0.0 {
+                    return Err("Enter a whole number".to_string());
+                }
+                if minimum.is_some_and(|min| number < min) || maximum.is_some_and(|max| number > max) {
+                    return Err(format!("Enter a number {}", range(*minimum, *maximum)));
+                }
+                if *integer {
+                    Value::Number(Number::from(number as i64))
+                } else {
+                    Number::from_f64(number).map(Value::Number).ok_or("Enter a number")?
+                }
+            },
+            FieldKind::Boolean => match input.to_lowercase().as_str() {
+                "y" | "yes" | "true" => Value::Bool(true),
+                "n" | "no" | "false" => Value::Bool(false),
+                _ => return Err("Enter y or n".to_string()),
+            },
+            FieldKind::Enum { values, names } => {
+                // Options can be chosen by number, value or name
+                let index = input
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| i.checked_sub(1))
+                    .filter(|i| *i < values.len())
+                    .or_else(|| values.iter().position(|v| v == input))
+                    .or_else(|| names.iter().position(|n| n.eq_ignore_ascii_case(input)))
+                    .ok_or_else(|| format!("Enter a number between 1 and {}", values.len()))?;
+                Value::String(values[index].clone())
+            },
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Whether the value must not be shown as it is typed. Besides fields with the `password`
+    /// format, fields named like a credential are treated as secrets.
+    fn is_secret(&self) -> bool {
+        let FieldKind::String { format, .. } = &self.kind else {
+            return false;
+        };
+        let name = self.name.to_lowercase();
+        format.as_deref() == Some("password")
+            || ["password", "secret", "token", "api_key", "apikey"]
+                .iter()
+                .any(|secret| name.contains(secret))
+    }
+
+    /// The hint shown after the label of the field, describing what can be entered
+    fn hint(&self) -> String {
+        let mut hints = Vec::new();
+        if self.is_secret() {
+            hints.push("hidden".to_string());
+        }
+        match &self.kind {
+            FieldKind::String {
+                format: Some(format), ..
+            } if format != "password" => hints.push(format.clone()),
+            FieldKind::Number { minimum, maximum, .. } if minimum.is_some() || maximum.is_some() => {
+                hints.push(range(*minimum, *maximum));
+            },
+            FieldKind::Boolean => hints.push("y/n".to_string()),
+            FieldKind::Enum { values, .. } => hints.push(format!("1-{}", values.len())),
+            _ => {},
+        }
+        if !self.required {
+            hints.push("optional".to_string());
+        }
+        match &self.default {
+            // Don't reveal default secrets
+            Some(_) if self.is_secret() => hints.push("has default".to_string()),
+            Some(Value::String(default)) => hints.push(format!("default: {default}")),
+            Some(Value::Bool(default)) => hints.push(format!("default: {}", if *default { "y" } else { "n" })),
+            Some(default) => hints.push(format!("default: {default}")),
+            None => {},
+        }
+
+        hints.join(", ")
+    }
+}
+
+fn range(minimum: Option<f64>, maximum: Option<f64>) -> String {
+    match (minimum, maximum) {
+        (Some(min), Some(max)) => format!("between {min} and {max}"),
+        (Some(min), None) => format!("of at least {min}"),
+        (None, Some(max)) => format!("of at most {max}"),
+        (None, None) => String::new(),
+    }
+}
+
+/// Asks the user whether to respond to the request and, if so, for the value of each field.
+/// Everything the server sent is stripped of control characters so that it can't rewrite what the
+/// terminal shows.
+async fn fill_in_form(
+    user_prompts: &UserPromptSender,
+    server_name: &str,
+    message: &str,
+    fields: &[Field],
+) -> std::io::Result<CreateElicitationResult> {
+    let cancelled = || CreateElicitationResult {
+        action: ElicitationAction::Cancel,
+        content: None,
+    };
+
+    let mut request = Vec::new();
+    queue!(
+        request,
+        style::Print("\n"),
+        style::SetForegroundColor(Color::Yellow),
+        style::Print("MCP server "),
+        style::SetForegroundColor(Color::Cyan),
+        style::Print(strip_control_chars(server_name)),
+        style::SetForegroundColor(Color::Yellow),
+        style::Print(" requests information:\n"),
+        style::SetForegroundColor(Color::Reset),
+        style::Print(strip_control_chars(message)),
+        style::Print("\n"),
+    )?;
+    let mut question = Vec::new();
+    queue!(
+        question,
+        style::Print("\nRespond? ["),
+        style::SetForegroundColor(Color::Green),
+        style::Print("y"),
+        style::SetForegroundColor(Color::Reset),
+        style::Print("]es, ["),
+        style::SetForegroundColor(Color::Green),
+        style::Print("d"),
+        style::SetForegroundColor(Color::Reset),
+        style::Print("]ecline or ["),
+        style::SetForegroundColor(Color::Green),
+        style::Print("c"),
+        style::SetForegroundColor(Color::Reset),
+        style::Print("]ancel: "),
+    )?;
+
+    let mut request = text(request);
+    let mut question = text(question);
+    let action = loop {
+        let Some(answer) = ask_user(user_prompts, std::mem::take(&mut request), question, false).await? else {
+            return Ok(cancelled());
+        };
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => break ElicitationAction::Accept,
+            "d" | "decline" | "n" | "no" => break ElicitationAction::Decline,
+            "c" | "cancel" => break ElicitationAction::Cancel,
+            _ => question = "Enter y, d or c: ".to_string(),
+        }
+    };
+    if action != ElicitationAction::Accept {
+        return Ok(CreateElicitationResult { action, content: None });
+    }
+
+    let mut content = Map::new();
+    for field in fields {
+        let mut options = Vec::new();
+        queue!(options, style::Print("\n"))?;
+        if let FieldKind::Enum { names, .. } = &field.kind {
+            for (i, name) in names.iter().enumerate() {
+                queue!(
+                    options,
+                    style::Print(format!("  {}. {}\n", i + 1, strip_control_chars(name)))
+                )?;
+            }
+        }
+        let mut label = Vec::new();
+        queue!(
+            label,
+            style::SetAttribute(style::Attribute::Bold),
+            style::Print(strip_control_chars(field.title.as_deref().unwrap_or(&field.name))),
+            style::SetAttribute(style::Attribute::Reset),
+        )?;
+        if let Some(description) = &field.description {
+            queue!(label, style::Print(format!(" - {}", strip_control_chars(description))))?;
+        }
+        let hint = field.hint();
+        if !hint.is_empty() {
+            queue!(
+                label,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!(" ({})", strip_control_chars(&hint))),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+        queue!(label, style::Print(": "))?;
+
+        let mut options = text(options);
+        let mut label = text(label);
+        loop {
+            let Some(input) = ask_user(user_prompts, std::mem::take(&mut options), label, field.is_secret()).await?
+            else {
+                return Ok(cancelled());
+            };
+            match field.parse(&input) {
+                Ok(value) => {
+                    if let Some(value) = value {
+                        content.insert(field.name.clone(), value);
+                    }
+                    break;
+                },
+                Err(e) => {
+                    let mut error = Vec::new();
+                    queue!(
+                        error,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(e),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print(": "),
+                    )?;
+                    label = text(error);
+                },
+            }
+        }
+    }
+
+    Ok(CreateElicitationResult {
+        action,
+        content: Some(Value::Object(content)),
+    })
+}
+
+/// Text queued with styles, to be printed by the chat session
+fn text(queued: Vec<u8>) -> String {
+    String::from_utf8_lossy(&queued).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema(value: Value) -> JsonObject {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_fields() {
+        let fields = fields(&schema(json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "title": "Name", "minLength": 1 },
+                "replicas": { "type": "integer", "minimum": 1, "maximum": 5, "default": 2 },
+                "confirm": { "type": "boolean" },
+                "env": { "type": "string", "enum": ["stg", "prod"], "enumNames": ["Staging", "Production"] }
+            },
+            "required": ["name", "env"]
+        })))
+        .unwrap();
+
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0].title.as_deref(), Some("Name"));
+        assert!(fields[0].required);
+        assert!(!fields[1].required);
+        assert_eq!(fields[1].kind, FieldKind::Number {
+            integer: true,
+            minimum: Some(1.0),
+            maximum: Some(5.0)
+        });
+        assert_eq!(fields[2].kind, FieldKind::Boolean);
+        assert_eq!(fields[3].kind, FieldKind::Enum {
+            values: vec!["stg".to_string(), "prod".to_string()],
+            names: vec!["Staging".to_string(), "Production".to_string()]
+        });
+
+        assert!(super::fields(&schema(json!({ "properties": { "tags": { "type": "array" } } }))).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let fields = fields(&schema(json!({
+            "properties": {
+                "name": { "type": "string", "minLength": 2 },
+                "replicas": { "type": "integer", "minimum": 1, "maximum": 5, "default": 2 },
+                "ratio": { "type": "number" },
+                "confirm": { "type": "boolean" },
+                "env": { "type": "string", "enum": ["stg", "prod"], "enumNames": ["Staging", "Production"] }
+            },
+            "required": ["name"]
+        })))
+        .unwrap();
+        let [name, replicas, ratio, confirm, env] = &fields[..] else {
+            panic!("unexpected fields {fields:?}");
+        };
+
+        assert_eq!(name.parse(" ab \n"), Ok(Some(json!("ab"))));
+        assert!(name.parse("a").is_err());
+        assert!(name.parse("").is_err());
+        assert_eq!(replicas.parse(""), Ok(Some(json!(2))));
+        assert_eq!(replicas.parse("3"), Ok(Some(json!(3))));
+        assert!(replicas.parse("6").is_err());
+        assert!(replicas.parse("2.5").is_err());
+        assert_eq!(ratio.parse("0.5"), Ok(Some(json!(0.5))));
+        assert_eq!(ratio.parse(""), Ok(None));
+        assert_eq!(confirm.parse("y"), Ok(Some(json!(true))));
+        assert!(confirm.parse("maybe").is_err());
+        assert_eq!(env.parse("2"), Ok(Some(json!("prod"))));
+        assert_eq!(env.parse("stg"), Ok(Some(json!("stg"))));
+        assert_eq!(env.parse("production"), Ok(Some(json!("prod"))));
+        assert!(env.parse("3").is_err());
+    }
+
+    #[test]
+    fn test_secret_fields() {
+        let fields = fields(&schema(json!({
+            "properties": {
+                "pin": { "type": "string", "format": "password" },
+                "apiToken": { "type": "string", "default": "abc" },
+                "user": { "type": "string", "format": "email" },
+                "tokens": { "type": "integer" }
+            }
+        })))
+        .unwrap();
+        let [pin, token, user, tokens] = &fields[..] else {
+            panic!("unexpected fields {fields:?}");
+        };
+
+        assert!(pin.is_secret());
+        assert_eq!(pin.hint(), "hidden, optional");
+        assert!(token.is_secret());
+        assert_eq!(token.hint(), "hidden, optional, has default");
+        assert!(!user.is_secret());
+        assert_eq!(user.hint(), "email, optional");
+        assert!(!tokens.is_secret());
+    }
+}
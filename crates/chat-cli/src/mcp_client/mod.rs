@@ -1,6 +1,56 @@
 pub mod client;
+pub mod elicitation;
 pub mod messenger;
 pub mod oauth_util;
+pub mod roots;
+pub mod sampling;
 
 pub use client::*;
 pub use oauth_util::*;
+use tokio::sync::{
+    Mutex,
+    mpsc,
+    oneshot,
+};
+
+/// Prompts of requests that ask the user something would garble each other, so only one is shown
+/// at a time
+static USER_PROMPT_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// A question for the user from a request of an MCP server. Servers send requests while the chat
+/// session is busy, e.g. running one of their tools, so the question is sent to the session, which
+/// asks it from its input loop and sends back the answer.
+#[derive(Debug)]
+pub struct UserPrompt {
+    /// Shown above the question, e.g. the details of the request. May be empty.
+    pub message: String,
+    /// Shown on the line the answer is typed on
+    pub question: String,
+    /// Whether the answer must not be shown as it is typed, e.g. a password
+    pub secret: bool,
+    /// Receives the answer, or [None] if the user cancelled with Ctrl+C or Ctrl+D
+    pub answer: oneshot::Sender<Option<String>>,
+}
+
+pub type UserPromptSender = mpsc::Sender<UserPrompt>;
+pub type UserPromptReceiver = mpsc::Receiver<UserPrompt>;
+
+/// Asks the user a question through the chat session, see [UserPrompt]. Returns [None] if the user
+/// cancelled.
+async fn ask_user(
+    sender: &UserPromptSender,
+    message: String,
+    question: String,
+    secret: bool,
+) -> std::io::Result<Option<String>> {
+    let (answer, answer_receiver) = oneshot::channel();
+    let prompt = UserPrompt {
+        message,
+        question,
+        secret,
+        answer,
+    };
+    let closed = || std::io::Error::other("The chat session is not taking input");
+    sender.send(prompt).await.map_err(|_e| closed())?;
+    answer_receiver.await.map_err(|_e| closed())
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rmcp::model::Root;
+use tokio::sync::Mutex;
+use tracing::warn;
+use url::Url;
+
+use crate::cli::Agent;
+use crate::os::Os;
+use crate::util::directories::canonicalizes_path;
+
+/// Serves `roots/list` requests, with which servers discover the directories they may operate in.
+///
+/// Roots are computed on every request so that servers always receive those of the current agent
+/// and working directory.
+#[derive(Clone, Debug)]
+pub struct RootsProvider {
+    os: Os,
+    agent: Arc<Mutex<Agent>>,
+}
+
+impl RootsProvider {
+    pub fn new(os: Os, agent: Arc<Mutex<Agent>>) -> Self {
+        Self { os, agent }
+    }
+
+    pub async fn list_roots(&self) -> Vec<Root> {
+        roots(&self.os, &*self.agent.lock().await)
+    }
+}
+
+/// Returns the working directory followed by the roots configured in the agent
+pub fn roots(os: &Os, agent: &Agent) -> Vec<Root> {
+    let mut paths = os.env.current_dir().into_iter().collect::<Vec<_>>();
+    for root in &agent.roots {
+        match canonicalizes_path(os, root) {
+            Ok(path) if !paths.iter().any(|p| p.as_os_str() == path.as_str()) => paths.push(PathBuf::from(path)),
+            Ok(_) => {},
+            Err(e) => warn!(target: "mcp", "Failed to resolve root {root}: {e}"),
+        }
+    }
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let uri = Url::from_directory_path(&path).ok()?;
+            Some(Root {
+                uri: uri.to_string(),
+                name: path.file_name().map(|name| name.to_string_lossy().to_string()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_roots() {
+        let os = Os::new().await.unwrap();
+        os.env.set_current_dir_for_test(PathBuf::from("/workspace/project"));
+        let agent = Agent {
+            roots: vec!["../shared".to_string(), "/workspace/project".to_string()],
+            ..Default::default()
+        };
+
+        let roots = roots(&os, &agent);
+        assert_eq!(roots.iter().map(|root| root.uri.as_str()).collect::<Vec<_>>(), vec![
+            "file:///workspace/project/",
+            "file:///workspace/shared/"
+        ]);
+        assert_eq!(roots[0].name.as_deref(), Some("project"));
+    }
+}
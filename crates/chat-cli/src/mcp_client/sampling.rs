@@ -0,0 +1,469 @@
+use std::io::IsTerminal;
+
+use base64::Engine;
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+use rmcp::ErrorData;
+use rmcp::model::{
+    Content,
+    CreateMessageRequestParam,
+    CreateMessageResult,
+    ErrorCode,
+    ModelPreferences,
+    RawContent,
+    Role,
+    SamplingMessage,
+};
+use tracing::info;
+
+use super::{
+    USER_PROMPT_LOCK,
+    UserPromptSender,
+    ask_user,
+};
+use crate::api_client::model::{
+    AssistantResponseMessage,
+    ChatMessage,
+    ChatResponseStream,
+    ConversationState,
+    ImageBlock,
+    ImageFormat,
+    ImageSource,
+    UserInputMessage,
+};
+use crate::cli::chat::cli::mcp::format_resource_contents;
+use crate::cli::chat::cli::model::{
+    find_model,
+    get_available_models,
+};
+use crate::cli::chat::tools::custom_tool::SamplingPermission;
+use crate::cli::chat::util::{
+    strip_control_chars,
+    truncate_safe,
+};
+use crate::database::settings::Setting;
+use crate::os::Os;
+
+/// Error code servers receive when a request is rejected, as suggested by the MCP specification
+const REJECTED: ErrorCode = ErrorCode(-1);
+
+/// Number of characters of each message shown when asking for approval
+const PREVIEW_MAX_BYTES: usize = 500;
+
+/// Serves `sampling/createMessage` requests, with which servers ask for a response from the model
+/// of the chat session.
+#[derive(Clone, Debug)]
+pub struct SamplingHandler {
+    os: Os,
+    /// Whether the user can be asked to approve requests
+    interactive: bool,
+    /// Approvals are asked by the chat session
+    user_prompts: UserPromptSender,
+}
+
+impl SamplingHandler {
+    pub fn new(os: Os, interactive: bool, user_prompts: UserPromptSender) -> Self {
+        Self {
+            os,
+            interactive,
+            user_prompts,
+        }
+    }
+
+    pub async fn create_message(
+        &self,
+        server_name: &str,
+        permission: SamplingPermission,
+        param: CreateMessageRequestParam,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        match permission {
+            SamplingPermission::Allow => {},
+            SamplingPermission::Deny => {
+                return Err(ErrorData::new(
+                    REJECTED,
+                    format!("Sampling is not allowed for server {server_name}"),
+                    None,
+                ));
+            },
+            // Serve mode reads requests from stdin, so it can't be used to ask either
+            SamplingPermission::Ask if !self.interactive || !std::io::stdin().is_terminal() => {
+                return Err(ErrorData::new(
+                    REJECTED,
+                    "Sampling requests need to be approved by the user, which is not possible in this session",
+                    None,
+                ));
+            },
+            SamplingPermission::Ask => {
+                if !ask_for_approval(&self.user_prompts, server_name, &param).await? {
+                    return Err(ErrorData::new(REJECTED, "The user rejected the sampling request", None));
+                }
+            },
+        }
+
+        let model_id = self.select_model(param.model_preferences.as_ref()).await?;
+        let conversation_state = conversation_state(&param, &model_id)?;
+        info!(target: "mcp", "Serving sampling request from {server_name} with model {model_id}");
+
+        let mut output = self
+            .os
+            .client
+            .send_message(conversation_state)
+            .await
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+        let mut text = String::new();
+        let mut stop_reason = CreateMessageResult::STOP_REASON_END_TURN;
+        while let Some(event) = output
+            .recv()
+            .await
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?
+        {
+            if let ChatResponseStream::AssistantResponseEvent { content } = event {
+                text.push_str(&content);
+            }
+            if let Some((end, reason)) = completion_end(&text, param.max_tokens, param.stop_sequences.as_deref()) {
+                text.truncate(end);
+                stop_reason = reason;
+                break;
+            }
+        }
+
+        Ok(CreateMessageResult {
+            model: model_id,
+            stop_reason: Some(stop_reason.to_string()),
+            message: SamplingMessage {
+                role: Role::Assistant,
+                content: Content::text(text),
+            },
+        })
+    }
+
+    /// Picks the first available model matching one of the hints of the server, falling back to
+    /// the default model of the user.
+    async fn select_model(&self, preferences: Option<&ModelPreferences>) -> Result<String, ErrorData> {
+        let (models, default_model) = get_available_models(&self.os)
+            .await
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+
+        // Hints may name a model family rather than a model, e.g. "sonnet"
+        let hinted = preferences
+            .and_then(|p| p.hints.as_ref())
+            .into_iter()
+            .flatten()
+            .filter_map(|hint| hint.name.as_deref())
+            .find_map(|name| {
+                let name = name.to_lowercase();
+                find_model(&models, &name).or_else(|| {
+                    models.iter().find(|m| {
+                        m.model_id.to_lowercase().contains(&name)
+                            || m.model_name.as_ref().is_some_and(|n| n.to_lowercase().contains(&name))
+                    })
+                })
+            });
+        let saved = || {
+            self.os
+                .database
+                .settings
+                .get_string(Setting::ChatDefaultModel)
+                .and_then(|saved| find_model(&models, &saved))
+        };
+
+        Ok(hinted
+            .or_else(saved)
+            .map_or(default_model.model_id.clone(), |m| m.model_id.clone()))
+    }
+}
+
+/// Shows the request to the user and asks whether to send it to the model. Everything the server
+/// sent is stripped of control characters so that it can't rewrite what the terminal shows.
+async fn ask_for_approval(
+    user_prompts: &UserPromptSender,
+    server_name: &str,
+    param: &CreateMessageRequestParam,
+) -> Result<bool, ErrorData> {
+    let _guard = USER_PROMPT_LOCK.lock().await;
+
+    let mut message = Vec::new();
+    let queued = (|| -> std::io::Result<()> {
+        queue!(
+            message,
+            style::Print("\n"),
+            style::SetForegroundColor(Color::Yellow),
+            style::Print("MCP server "),
+            style::SetForegroundColor(Color::Cyan),
+            style::Print(strip_control_chars(server_name)),
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(format!(
+                " requests a response of up to {} tokens from the model:\n",
+                param.max_tokens
+            )),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        if let Some(system_prompt) = &param.system_prompt {
+            queue!(
+                message,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("system: "),
+                style::SetForegroundColor(Color::Reset),
+                style::Print(preview(system_prompt)),
+                style::Print("\n"),
+            )?;
+        }
+        for sampling_message in &param.messages {
+            let role = match sampling_message.role {
+                Role::User => "user: ",
+                Role::Assistant => "assistant: ",
+            };
+            let content = match &sampling_message.content.raw {
+                RawContent::Text(text) => preview(&text.text),
+                RawContent::Image(image) => preview(&format!("[{} image]", image.mime_type)),
+                RawContent::Audio(audio) => preview(&format!("[{} audio]", audio.mime_type)),
+                RawContent::Resource(resource) => preview(&format_resource_contents(&resource.resource)),
+                RawContent::ResourceLink(link) => preview(&format!("[{}]", link.uri)),
+            };
+            queue!(
+                message,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(role),
+                style::SetForegroundColor(Color::Reset),
+                style::Print(content),
+                style::Print("\n"),
+            )?;
+        }
+        Ok(())
+    })();
+    queued.map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+
+    let mut question = Vec::new();
+    let queued = queue!(
+        question,
+        style::Print("\nAllow this request? ["),
+        style::SetForegroundColor(Color::Green),
+        style::Print("y"),
+        style::SetForegroundColor(Color::Reset),
+        style::Print("/"),
+        style::SetForegroundColor(Color::Green),
+        style::Print("n"),
+        style::SetForegroundColor(Color::Reset),
+        style::Print("]: "),
+    );
+    queued.map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+
+    let answer = ask_user(
+        user_prompts,
+        String::from_utf8_lossy(&message).into_owned(),
+        String::from_utf8_lossy(&question).into_owned(),
+        false,
+    )
+    .await
+    .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+
+    Ok(answer.is_some_and(|answer| matches!(answer.trim(), "y" | "Y" | "yes")))
+}
+
+/// The beginning of `text` without control characters, to be shown to the user
+fn preview(text: &str) -> String {
+    let text = strip_control_chars(text);
+    let truncated = truncate_safe(&text, PREVIEW_MAX_BYTES);
+    if truncated.len() < text.len() {
+        format!("{truncated}...")
+    } else {
+        truncated.to_string()
+    }
+}
+
+/// Converts the messages of a sampling request to a conversation for the model. Consecutive
+/// messages of the same role are merged since the conversation has to alternate between the
+/// user and the assistant. The system prompt is given as an instruction in the first user
+/// message, the same way agent prompts are.
+fn conversation_state(param: &CreateMessageRequestParam, model_id: &str) -> Result<ConversationState, ErrorData> {
+    let mut turns = Vec::<(Role, String, Vec<ImageBlock>)>::new();
+    if let Some(system_prompt) = &param.system_prompt {
+        turns.push((
+            Role::User,
+            format!("Follow this instruction: {system_prompt}"),
+            Vec::new(),
+        ));
+    }
+    for message in &param.messages {
+        let (text, image) = match &message.content.raw {
+            RawContent::Text(text) => (text.text.clone(), None),
+            RawContent::Image(image) => (String::new(), Some(image_block(&image.data, &image.mime_type)?)),
+            RawContent::Resource(resource) => (format_resource_contents(&resource.resource), None),
+            RawContent::ResourceLink(link) => (format!("[{}]", link.uri), None),
+            RawContent::Audio(_) => {
+                return Err(ErrorData::invalid_params("Audio content is not supported", None));
+            },
+        };
+        match turns.last_mut() {
+            Some((role, content, images)) if *role == message.role => {
+                if !text.is_empty() {
+                    if !content.is_empty() {
+                        content.push_str("\n\n");
+                    }
+                    content.push_str(&text);
+                }
+                images.extend(image);
+            },
+            _ => turns.push((message.role.clone(), text, image.into_iter().collect())),
+        }
+    }
+
+    let user_message = |content: String, images: Vec<ImageBlock>| UserInputMessage {
+        content,
+        user_input_message_context: None,
+        user_intent: None,
+        images: (!images.is_empty()).then_some(images),
+        model_id: Some(model_id.to_string()),
+    };
+    let Some((Role::User, content, images)) = turns.pop() else {
+        return Err(ErrorData::invalid_params(
+            "The last message of a sampling request has to be from the user",
+            None,
+        ));
+    };
+    if turns.first().is_some_and(|(role, ..)| *role == Role::Assistant) {
+        turns.insert(0, (Role::User, "Continue the conversation.".to_string(), Vec::new()));
+    }
+    let history = turns
+        .into_iter()
+        .map(|(role, content, images)| match role {
+            Role::User => ChatMessage::UserInputMessage(user_message(content, images)),
+            Role::Assistant => ChatMessage::AssistantResponseMessage(AssistantResponseMessage {
+                message_id: None,
+                content,
+                tool_uses: None,
+            }),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ConversationState {
+        conversation_id: None,
+        user_input_message: user_message(content, images),
+        history: (!history.is_empty()).then_some(history),
+    })
+}
+
+fn image_block(data: &str, mime_type: &str) -> Result<ImageBlock, ErrorData> {
+    let format = mime_type
+        .strip_prefix("image/")
+        .unwrap_or(mime_type)
+        .parse::<ImageFormat>()
+        .map_err(|e| ErrorData::invalid_params(e, None))?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| ErrorData::invalid_params(format!("Invalid image data: {e}"), None))?;
+
+    Ok(ImageBlock {
+        format,
+        source: ImageSource::Bytes(bytes),
+    })
+}
+
+/// Returns where the response has to be cut off, if it reached a stop sequence or the token limit
+/// of the request, along with the stop reason
+fn completion_end(text: &str, max_tokens: u32, stop_sequences: Option<&[String]>) -> Option<(usize, &'static str)> {
+    let stop = stop_sequences
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| text.find(s.as_str()))
+        .min();
+    if let Some(end) = stop {
+        return Some((end, CreateMessageResult::STOP_REASON_END_SEQUENCE));
+    }
+
+    // The token limit is applied to the length of the text, with the characters-per-token ratio of
+    // TokenCounter::count_tokens rather than the model's tokenizer
+    let max_bytes = max_tokens as usize * crate::cli::chat::token_counter::TokenCounter::TOKEN_TO_CHAR_RATIO;
+    (text.len() > max_bytes).then(|| {
+        (
+            truncate_safe(text, max_bytes).len(),
+            CreateMessageResult::STOP_REASON_END_MAX_TOKEN,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, text: &str) -> SamplingMessage {
+        SamplingMessage {
+            role,
+            content: Content::text(text),
+        }
+    }
+
+    fn param(messages: Vec<SamplingMessage>, system_prompt: Option<&str>) -> CreateMessageRequestParam {
+        CreateMessageRequestParam {
+            messages,
+            model_preferences: None,
+            system_prompt: system_prompt.map(str::to_string),
+            include_context: None,
+            temperature: None,
+            max_tokens: 100,
+            stop_sequences: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_conversation_state() {
+        let param = param(
+            vec![
+                message(Role::User, "Classify these tickets."),
+                message(Role::User, "1. Login fails"),
+                message(Role::Assistant, "bug"),
+                message(Role::User, "2. Add dark mode"),
+            ],
+            Some("Answer with one word."),
+        );
+
+        let state = conversation_state(&param, "model").unwrap();
+        assert_eq!(state.user_input_message.content, "2. Add dark mode");
+        assert_eq!(state.user_input_message.model_id.as_deref(), Some("model"));
+        let history = state.history.unwrap();
+        assert_eq!(history.len(), 2);
+        match &history[0] {
+            ChatMessage::UserInputMessage(m) => assert_eq!(
+                m.content,
+                "Follow this instruction: Answer with one word.\n\nClassify these tickets.\n\n1. Login fails"
+            ),
+            other @ ChatMessage::AssistantResponseMessage(_) => panic!("unexpected message {other:?}"),
+        }
+        match &history[1] {
+            ChatMessage::AssistantResponseMessage(m) => assert_eq!(m.content, "bug"),
+            other @ ChatMessage::UserInputMessage(_) => panic!("unexpected message {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_conversation_state_requires_user_message_last() {
+        let param = param(vec![message(Role::User, "hi"), message(Role::Assistant, "hello")], None);
+        assert!(conversation_state(&param, "model").is_err());
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview("\x1b[1A\x1b[2Kdone\r"), "[1A[2Kdone");
+        let long = "a".repeat(PREVIEW_MAX_BYTES + 1);
+        assert_eq!(preview(&long), format!("{}...", &long[..PREVIEW_MAX_BYTES]));
+    }
+
+    #[test]
+    fn test_completion_end() {
+        let stop = ["STOP".to_string()];
+        assert_eq!(completion_end("short", 10, None), None);
+        assert_eq!(
+            completion_end("abc STOP def", 10, Some(&stop)),
+            Some((4, CreateMessageResult::STOP_REASON_END_SEQUENCE))
+        );
+        assert_eq!(
+            completion_end(&"a".repeat(50), 10, None),
+            Some((40, CreateMessageResult::STOP_REASON_END_MAX_TOKEN))
+        );
+    }
+}
@@ -1,17 +1,24 @@
-use std::path::PathBuf;
+use std::path::{
+    Path,
+    PathBuf,
+};
 use std::sync::{
     Arc,
     LazyLock as Lazy,
 };
 
 use eyre::Result;
-use semantic_search_client::KnowledgeContext;
 use semantic_search_client::client::AsyncSemanticSearchClient;
 use semantic_search_client::embedding::EmbeddingType;
 use semantic_search_client::types::{
     AddContextRequest,
     SearchResult,
 };
+use semantic_search_client::{
+    KnowledgeContext,
+    OperationState,
+    SearchFilter,
+};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
@@ -26,6 +33,8 @@ pub struct AddOptions {
     pub include_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
     pub embedding_type: Option<String>,
+    pub watch: bool,
+    pub rerank: bool,
 }
 
 impl AddOptions {
@@ -70,6 +79,8 @@ impl AddOptions {
             include_patterns: default_include,
             exclude_patterns: default_exclude,
             embedding_type: default_embedding_type,
+            watch: false,
+            rerank: false,
         }
     }
 
@@ -87,6 +98,16 @@ impl AddOptions {
         self.embedding_type = embedding_type;
         self
     }
+
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    pub fn with_rerank(mut self, rerank: bool) -> Self {
+        self.rerank = rerank;
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -212,6 +233,7 @@ impl KnowledgeStore {
         os: &crate::os::Os,
         base_dir: PathBuf,
     ) -> semantic_search_client::config::SemanticSearchConfig {
+        use semantic_search_client::client::context::VectorEncoding;
         use semantic_search_client::config::SemanticSearchConfig;
         use semantic_search_client::embedding::EmbeddingType;
 
@@ -244,12 +266,30 @@ impl KnowledgeStore {
             .get_string(Setting::KnowledgeIndexType)
             .and_then(|s| EmbeddingType::from_str(&s))
             .unwrap_or_default();
+        let hybrid_weight = os
+            .database
+            .settings
+            .get(Setting::KnowledgeHybridWeight)
+            .and_then(|value| value.as_f64())
+            .map_or(default_config.hybrid_weight, |weight| weight.clamp(0.0, 1.0) as f32);
+        let vector_encoding = match os.database.settings.get_bool(Setting::KnowledgeQuantizeVectors) {
+            Some(true) => VectorEncoding::Int8,
+            _ => default_config.vector_encoding,
+        };
+        let rerank = os
+            .database
+            .settings
+            .get_bool(Setting::KnowledgeRerank)
+            .unwrap_or(default_config.rerank);
 
         SemanticSearchConfig {
             chunk_size,
             chunk_overlap,
             max_files,
             embedding_type,
+            hybrid_weight,
+            vector_encoding,
+            rerank,
             base_dir,
             ..default_config
         }
@@ -313,11 +353,16 @@ impl KnowledgeStore {
                 Some(s) => match EmbeddingType::from_str(s) {
                     Some(et) => Some(et),
                     None => {
-                        return Err(format!("Invalid embedding type '{}'. Valid options are: fast, best", s));
+                        return Err(format!(
+                            "Invalid embedding type '{}'. Valid options are: fast, best, hybrid",
+                            s
+                        ));
                     },
                 },
                 None => None,
             },
+            watch: options.watch,
+            rerank: options.rerank,
         };
 
         match self.agent_client.add_context(request).await {
@@ -356,13 +401,18 @@ impl KnowledgeStore {
         Ok(self.agent_client.get_contexts().await)
     }
 
-    /// Search - delegates to async client
-    pub async fn search(&self, query: &str, context_id: Option<&str>) -> Result<Vec<SearchResult>, KnowledgeError> {
+    /// Search - delegates to async client, returning only results that pass `filter`
+    pub async fn search(
+        &self,
+        query: &str,
+        context_id: Option<&str>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>, KnowledgeError> {
         if let Some(context_id) = context_id {
             // Search specific context
             let results = self
                 .agent_client
-                .search_context(context_id, query, None)
+                .search_context_with_filter(context_id, query, None, filter)
                 .await
                 .map_err(|e| KnowledgeError::SearchError(e.to_string()))?;
             Ok(results)
@@ -372,7 +422,7 @@ impl KnowledgeStore {
 
             let agent_results = self
                 .agent_client
-                .search_all(query, None)
+                .search_all_with_filter(query, None, filter)
                 .await
                 .map_err(|e| KnowledgeError::SearchError(e.to_string()))?;
 
@@ -391,6 +441,22 @@ impl KnowledgeStore {
         self.agent_client.get_status_data().await.map_err(|e| e.to_string())
     }
 
+    /// Wait until the background operations of this store have finished
+    ///
+    /// Commands that exit once they're done use this, as exiting would stop indexing midway.
+    pub async fn wait_for_operations(&self) -> Result<(), String> {
+        loop {
+            let status = self.get_status_data().await?;
+            if let Some(failed) = status.operations.iter().find(|op| op.state == OperationState::Failed) {
+                return Err(failed.message.clone());
+            }
+            if status.operations.iter().all(|op| op.state.is_finished()) {
+                return Ok(());
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+    }
+
     /// Cancel active operation.
     /// last operation if no operation id is provided.
     pub async fn cancel_operation(&mut self, operation_id: Option<&str>) -> Result<String, String> {
@@ -486,20 +552,7 @@ impl KnowledgeStore {
     /// Update context by path
     pub async fn update_by_path(&mut self, path_str: &str) -> Result<String, String> {
         if let Some(context) = self.agent_client.get_context_by_path(path_str).await {
-            // Remove the existing context first
-            self.agent_client
-                .remove_context_by_id(&context.id)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // Then add it back with the same name and original patterns (agent scope)
-            let options = AddOptions {
-                description: None,
-                include_patterns: context.include_patterns.clone(),
-                exclude_patterns: context.exclude_patterns.clone(),
-                embedding_type: None,
-            };
-            self.add(&context.name, path_str, options).await
+            self.update(&context, path_str).await
         } else {
             // Debug: List all available contexts
             let available_paths = self.agent_client.list_context_paths().await;
@@ -523,45 +576,69 @@ impl KnowledgeStore {
             .find(|c| c.id == context_id)
             .ok_or_else(|| format!("Context '{}' not found", context_id))?;
 
-        let context_name = context.name.clone();
-
-        // Remove the existing context first
-        self.agent_client
-            .remove_context_by_id(context_id)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        // Then add it back with the same name and original patterns
-        let options = AddOptions {
-            description: None,
-            include_patterns: context.include_patterns.clone(),
-            exclude_patterns: context.exclude_patterns.clone(),
-            embedding_type: None,
-        };
-        self.add(&context_name, path_str, options).await
+        self.update(context, path_str).await
     }
 
     /// Update context by name
     pub async fn update_context_by_name(&mut self, name: &str, path_str: &str) -> Result<String, String> {
         if let Some(context) = self.agent_client.get_context_by_name(name).await {
-            // Remove the existing context first
-            self.agent_client
-                .remove_context_by_id(&context.id)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // Then add it back with the same name and original patterns (agent scope)
-            let options = AddOptions {
-                description: None,
-                include_patterns: context.include_patterns.clone(),
-                exclude_patterns: context.exclude_patterns.clone(),
-                embedding_type: None,
-            };
-            self.add(name, path_str, options).await
+            self.update(&context, path_str).await
         } else {
             Err(format!("Context with name '{}' not found", name))
         }
     }
+
+    /// Export a context, found by path or name, to an archive file
+    pub async fn export(&self, path_or_name: &str, archive_path: &Path) -> Result<String, String> {
+        let context = match self.agent_client.get_context_by_path(path_or_name).await {
+            Some(context) => context,
+            None => self
+                .agent_client
+                .get_context_by_name(path_or_name)
+                .await
+                .ok_or_else(|| format!("No context found with path or name '{}'", path_or_name))?,
+        };
+
+        self.agent_client
+            .export_context(&context.id, archive_path)
+            .await
+            .map_err(|e| format!("Failed to export: {}", e))?;
+        Ok(format!(
+            "📦 Exported '{}' ({} items) to {}",
+            context.name,
+            context.item_count,
+            archive_path.display()
+        ))
+    }
+
+    /// Import a context from an archive file, optionally moving its source path to `source_path`
+    pub async fn import(&mut self, archive_path: &Path, source_path: Option<&Path>) -> Result<String, String> {
+        let context = self
+            .agent_client
+            .import_context(archive_path, source_path)
+            .await
+            .map_err(|e| format!("Failed to import: {}", e))?;
+
+        let mut message = format!("📥 Imported '{}' ({} items)", context.name, context.item_count);
+        if let Some(source_path) = &context.source_path {
+            message.push_str(&format!("\n📁 Path: {}", source_path));
+        }
+        Ok(message)
+    }
+
+    /// Re-index the files of a context that were added, changed or removed
+    async fn update(&mut self, context: &KnowledgeContext, path_str: &str) -> Result<String, String> {
+        let path = PathBuf::from(path_str);
+        match self.agent_client.update_context(&context.id, &path).await {
+            Ok((operation_id, _)) => Ok(format!(
+                "🚀 Started updating '{}'\n📁 Path: {}\n🆔 Operation ID: {}\n♻️ Only added, changed and removed files will be re-indexed",
+                context.name,
+                path.display(),
+                &operation_id.to_string()[..8]
+            )),
+            Err(e) => Err(format!("Failed to start update: {}", e)),
+        }
+    }
 }
 
 #[cfg(test)]
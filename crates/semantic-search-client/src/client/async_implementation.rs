@@ -3,7 +3,10 @@ use std::path::{
     PathBuf,
 };
 
-use tokio::sync::mpsc;
+use tokio::sync::{
+    OnceCell,
+    mpsc,
+};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
@@ -12,26 +15,37 @@ use super::background::BackgroundWorker;
 use super::context::ContextManager;
 use super::model::ModelDownloader;
 use super::operation::OperationManager;
+use super::watcher::{
+    self,
+    ContextWatcher,
+};
 use crate::client::embedder_factory;
 use crate::config::{
     self,
     SemanticSearchConfig,
 };
-use crate::embedding::TextEmbedderTrait;
+use crate::embedding::{
+    Reranker,
+    TextEmbedderTrait,
+};
 use crate::error::{
     Result,
     SemanticSearchError,
 };
+use crate::search_filter::SearchFilter;
 use crate::types::*;
 
 /// Async Semantic Search Client with proper cancellation support
 pub struct AsyncSemanticSearchClient {
     base_dir: PathBuf,
     embedder: Box<dyn TextEmbedderTrait>,
+    /// Cross-encoder reranker, loaded when a search first needs it, or `None` if it failed to load
+    reranker: OnceCell<Option<Box<dyn Reranker>>>,
     config: SemanticSearchConfig,
     job_tx: mpsc::UnboundedSender<IndexingJob>,
     context_manager: ContextManager,
     operation_manager: OperationManager,
+    watcher: ContextWatcher,
 }
 
 impl AsyncSemanticSearchClient {
@@ -84,15 +98,19 @@ impl AsyncSemanticSearchClient {
         ModelDownloader::ensure_models_downloaded(&config.embedding_type).await?;
 
         let embedder = embedder_factory::create_embedder(config.embedding_type)?;
-        let context_manager = ContextManager::new(&base_dir).await?;
+        let context_manager = ContextManager::new(&base_dir, config.hybrid_weight)
+            .await?
+            .with_reranking(config.rerank, config.rerank_candidates);
         let operation_manager = OperationManager::new();
 
         let (job_tx, job_rx) = mpsc::unbounded_channel();
+        let watcher = ContextWatcher::new(&job_tx, context_manager.clone(), operation_manager.clone(), &base_dir);
 
         let worker = BackgroundWorker::new(
             job_rx,
             context_manager.clone(),
             operation_manager.clone(),
+            watcher.clone(),
             config.clone(),
             base_dir.clone(),
         )
@@ -103,16 +121,63 @@ impl AsyncSemanticSearchClient {
         let client = Self {
             base_dir,
             embedder,
+            reranker: OnceCell::new(),
             config,
             job_tx,
             context_manager,
             operation_manager,
+            watcher,
         };
 
         client.context_manager.load_persistent_contexts().await?;
+        client.resume_watches().await;
         Ok(client)
     }
 
+    /// Watch the source paths of persistent contexts that were added with `watch` again, and
+    /// catch up with changes made since they were last indexed
+    async fn resume_watches(&self) {
+        for context in self.get_contexts().await {
+            let Some(path) = context
+                .source_path
+                .as_ref()
+                .filter(|_| context.watched)
+                .map(PathBuf::from)
+            else {
+                continue;
+            };
+            if let Err(e) = self
+                .watcher
+                .watch(&context.id, &path, &context.include_patterns, &context.exclude_patterns)
+            {
+                tracing::warn!("Failed to resume watching context {}: {}", context.name, e);
+                continue;
+            }
+            if let Err(e) = watcher::enqueue_update(&self.job_tx, &self.operation_manager, &context, path, None).await {
+                tracing::warn!("Failed to update watched context {}: {}", context.name, e);
+            }
+        }
+    }
+
+    /// Get the cross-encoder reranker, downloading and loading its model on first use
+    ///
+    /// A reranker that fails to load is reported once, after which searches return results in
+    /// their retrieval order.
+    async fn reranker(&self) -> Option<&dyn Reranker> {
+        self.reranker
+            .get_or_init(|| async {
+                let reranker = match ModelDownloader::ensure_reranker_downloaded().await {
+                    Ok(()) => embedder_factory::create_reranker(),
+                    Err(e) => Err(e),
+                };
+                reranker
+                    .inspect_err(|e| tracing::warn!("Search results won't be reranked: {}", e))
+                    .ok()
+            })
+            .await
+            .as_deref()
+    }
+
     /// Creates a new AsyncSemanticSearchClient with default configuration.
     ///
     /// This is a convenience method that creates a client with default settings
@@ -215,7 +280,9 @@ impl AsyncSemanticSearchClient {
     ///
     /// This method initiates the process of indexing a directory or file and adding it
     /// as a searchable context. The operation runs in the background and can be cancelled
-    /// using the returned cancellation token.
+    /// using the returned cancellation token. When `request.watch` is set, the path is then
+    /// watched and the context is updated incrementally whenever its files change. When
+    /// `request.rerank` is set, search results of the context are reranked with a cross-encoder.
     ///
     /// # Arguments
     ///
@@ -241,10 +308,8 @@ impl AsyncSemanticSearchClient {
     /// ```no_run
     /// use std::path::PathBuf;
     ///
-    /// use semantic_search_client::{
-    ///     AddContextRequest,
-    ///     AsyncSemanticSearchClient,
-    /// };
+    /// use semantic_search_client::AsyncSemanticSearchClient;
+    /// use semantic_search_client::types::AddContextRequest;
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// let client = AsyncSemanticSearchClient::new_with_default_dir().await?;
@@ -257,6 +322,8 @@ impl AsyncSemanticSearchClient {
     ///     include_patterns: Some(vec!["*.txt".to_string(), "*.md".to_string()]),
     ///     exclude_patterns: Some(vec!["*.tmp".to_string()]),
     ///     embedding_type: None, // Use default
+    ///     watch: false,
+    ///     rerank: false,
     /// };
     ///
     /// let (operation_id, cancel_token) = client.add_context(request).await?;
@@ -310,6 +377,8 @@ impl AsyncSemanticSearchClient {
             include_patterns: request.include_patterns.clone(),
             exclude_patterns: request.exclude_patterns.clone(),
             embedding_type: request.embedding_type,
+            watch: request.watch,
+            rerank: request.rerank,
         };
 
         self.job_tx
@@ -319,6 +388,35 @@ impl AsyncSemanticSearchClient {
         Ok((operation_id, cancel_token))
     }
 
+    /// Re-indexes the files of a context that changed since it was last indexed.
+    ///
+    /// Each context keeps a manifest of the content hash and modification time of its files.
+    /// This method starts a background operation that only processes files that were added or
+    /// changed, and removes the data points of files that were changed or removed. The progress
+    /// message reports the number of added, changed and removed files.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - The unique identifier of the context to update
+    /// * `path` - The file or directory to index, usually the context's source path
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<(Uuid, CancellationToken)>` for tracking the update operation.
+    pub async fn update_context(&self, context_id: &str, path: &Path) -> Result<(Uuid, CancellationToken)> {
+        let canonical_path = path.canonicalize().map_err(|_e| {
+            SemanticSearchError::InvalidPath(format!("Path does not exist or is not accessible: {}", path.display()))
+        })?;
+        let context = self
+            .get_contexts()
+            .await
+            .into_iter()
+            .find(|context| context.id == context_id)
+            .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?;
+
+        watcher::enqueue_update(&self.job_tx, &self.operation_manager, &context, canonical_path, None).await
+    }
+
     /// Retrieves all available contexts in the knowledge base.
     ///
     /// This method returns a list of all contexts (both persistent and volatile)
@@ -389,13 +487,18 @@ impl AsyncSemanticSearchClient {
     ///     println!(
     ///         "Results from context {}: {} matches",
     ///         context_id,
-    ///         search_results.results.len()
+    ///         search_results.len()
     ///     );
-    ///     for result in search_results.results.iter().take(3) {
+    ///     for result in search_results.iter().take(3) {
     ///         println!(
-    ///             "  Score: {:.3} - {}",
-    ///             result.score,
-    ///             result.text.chars().take(100).collect::<String>()
+    ///             "  Distance: {:.3} - {}",
+    ///             result.distance,
+    ///             result
+    ///                 .text()
+    ///                 .unwrap_or_default()
+    ///                 .chars()
+    ///                 .take(100)
+    ///                 .collect::<String>()
     ///         );
     ///     }
     /// }
@@ -406,6 +509,31 @@ impl AsyncSemanticSearchClient {
         &self,
         query_text: &str,
         result_limit: Option<usize>,
+    ) -> Result<Vec<(ContextId, SearchResults)>> {
+        self.search_all_with_filter(query_text, result_limit, &SearchFilter::default())
+            .await
+    }
+
+    /// Search across all contexts, returning only data points that pass a filter
+    ///
+    /// The filter is applied while each index is queried, so `result_limit` counts matching data
+    /// points only.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_text` - Search query
+    /// * `result_limit` - Maximum number of results to return per context (if None, uses
+    ///   default_results from config)
+    /// * `filter` - Path, file type, extension and recency restrictions
+    ///
+    /// # Returns
+    ///
+    /// A vector of (context_id, results) pairs
+    pub async fn search_all_with_filter(
+        &self,
+        query_text: &str,
+        result_limit: Option<usize>,
+        filter: &SearchFilter,
     ) -> Result<Vec<(ContextId, SearchResults)>> {
         if query_text.is_empty() {
             return Err(SemanticSearchError::InvalidArgument(
@@ -414,8 +542,18 @@ impl AsyncSemanticSearchClient {
         }
 
         let effective_limit = result_limit.unwrap_or(self.config.default_results);
+        let reranker = if self
+            .get_contexts()
+            .await
+            .iter()
+            .any(|c| self.context_manager.reranks(c))
+        {
+            self.reranker().await
+        } else {
+            None
+        };
         self.context_manager
-            .search_all(query_text, effective_limit, &*self.embedder)
+            .search_all(query_text, effective_limit, filter, &*self.embedder, reranker)
             .await
     }
 
@@ -436,6 +574,30 @@ impl AsyncSemanticSearchClient {
         context_id: &str,
         query_text: &str,
         result_limit: Option<usize>,
+    ) -> Result<SearchResults> {
+        self.search_context_with_filter(context_id, query_text, result_limit, &SearchFilter::default())
+            .await
+    }
+
+    /// Search in a specific context, returning only data points that pass a filter
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - ID of the context to search in
+    /// * `query_text` - Search query
+    /// * `result_limit` - Maximum number of results to return (if None, uses default_results from
+    ///   config)
+    /// * `filter` - Path, file type, extension and recency restrictions
+    ///
+    /// # Returns
+    ///
+    /// A vector of search results
+    pub async fn search_context_with_filter(
+        &self,
+        context_id: &str,
+        query_text: &str,
+        result_limit: Option<usize>,
+        filter: &SearchFilter,
     ) -> Result<SearchResults> {
         if context_id.is_empty() {
             return Err(SemanticSearchError::InvalidArgument(
@@ -451,8 +613,23 @@ impl AsyncSemanticSearchClient {
 
         let effective_limit = result_limit.unwrap_or(self.config.default_results);
 
+        let reranks = self
+            .context_manager
+            .get_contexts_ref()
+            .read()
+            .await
+            .get(context_id)
+            .is_some_and(|context| self.context_manager.reranks(context));
+        let reranker = if reranks { self.reranker().await } else { None };
         self.context_manager
-            .search_context(context_id, query_text, effective_limit, &*self.embedder)
+            .search_context(
+                context_id,
+                query_text,
+                effective_limit,
+                filter,
+                &*self.embedder,
+                reranker,
+            )
             .await?
             .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))
     }
@@ -477,6 +654,7 @@ impl AsyncSemanticSearchClient {
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// let client = AsyncSemanticSearchClient::new_with_default_dir().await?;
+    /// # let operation_id = uuid::Uuid::new_v4();
     /// // ... start an operation and get operation_id
     /// let status = client.cancel_operation(operation_id).await?;
     /// println!("Cancellation status: {}", status);
@@ -574,6 +752,7 @@ impl AsyncSemanticSearchClient {
     ///
     /// Returns a `Result<(Uuid, CancellationToken)>` for tracking the clear operation. immediately
     pub async fn clear_all_immediate(&self) -> Result<usize> {
+        self.watcher.unwatch_all();
         self.context_manager.clear_all_immediate(&self.base_dir).await
     }
 
@@ -586,11 +765,57 @@ impl AsyncSemanticSearchClient {
     ///
     /// * `context_id` - The unique identifier of the context to remove
     pub async fn remove_context_by_id(&self, context_id: &str) -> Result<()> {
+        self.watcher.unwatch(context_id);
         self.context_manager
             .remove_context_by_id(context_id, &self.base_dir)
             .await
     }
 
+    /// Exports a context to a single archive file that can be shared and imported elsewhere.
+    ///
+    /// The archive is a zip file holding the context metadata, its index data and the
+    /// identifier of the embedding model that built the index. Only persistent contexts can be
+    /// exported.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - The unique identifier of the context to export
+    /// * `archive_path` - The path of the archive file to create
+    ///
+    /// # Returns
+    ///
+    /// Returns the exported context.
+    pub async fn export_context(&self, context_id: &str, archive_path: &Path) -> Result<KnowledgeContext> {
+        self.context_manager.export_context(context_id, archive_path).await
+    }
+
+    /// Imports a context from an archive created by [`Self::export_context`].
+    ///
+    /// The archive's embedding model must match the model this client uses for contexts of the
+    /// same index type. The context is added as a new persistent context that isn't watched.
+    /// Indexes are often built on another machine, such as a CI job, so `source_path` can point
+    /// the context at the local copy of its files: the exported source path is replaced by it in
+    /// the context and in the paths of the indexed files.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive_path` - The path of the archive file
+    /// * `source_path` - The local path of the context's files, if it differs from the exported one
+    ///
+    /// # Returns
+    ///
+    /// Returns the imported context.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if:
+    /// - The archive cannot be read or was written by an incompatible version
+    /// - The archive's embedding model doesn't match
+    /// - The source path is already in the knowledge base
+    pub async fn import_context(&self, archive_path: &Path, source_path: Option<&Path>) -> Result<KnowledgeContext> {
+        self.context_manager.import_context(archive_path, source_path).await
+    }
+
     /// Retrieves a context by its source path.
     ///
     /// This method finds a context that was created from the specified file or directory path.
@@ -634,3 +859,289 @@ impl AsyncSemanticSearchClient {
         self.context_manager.list_context_paths().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::client::context::FileManifest;
+    use crate::embedding::EmbeddingType;
+
+    async fn wait_for_operations(client: &AsyncSemanticSearchClient) {
+        for _ in 0..100 {
+            if client.list_operation_ids().await.is_empty() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("operations didn't complete");
+    }
+
+    async fn indexed_paths(client: &AsyncSemanticSearchClient, context_id: &str) -> Vec<(usize, String)> {
+        let contexts = client.context_manager.get_volatile_contexts_ref().read().await;
+        let context = contexts[context_id].lock().await;
+        let mut paths: Vec<_> = context
+            .get_data_points()
+            .iter()
+            .map(|point| (point.id, point.payload["path"].as_str().unwrap().to_string()))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_update_context_incrementally() {
+        let dir = tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join("kept.txt"), "kept").unwrap();
+        std::fs::write(docs.join("edited.txt"), "before").unwrap();
+        std::fs::write(docs.join("deleted.txt"), "deleted").unwrap();
+        let docs = docs.canonicalize().unwrap();
+
+        let config = SemanticSearchConfig {
+            base_dir: dir.path().join("kb"),
+            embedding_type: EmbeddingType::Mock,
+            ..Default::default()
+        };
+        let client = AsyncSemanticSearchClient::with_config(dir.path().join("kb"), config)
+            .await
+            .unwrap();
+        client
+            .add_context(AddContextRequest {
+                path: docs.clone(),
+                name: "docs".to_string(),
+                description: String::new(),
+                persistent: true,
+                include_patterns: None,
+                exclude_patterns: None,
+                embedding_type: None,
+                watch: false,
+                rerank: false,
+            })
+            .await
+            .unwrap();
+        wait_for_operations(&client).await;
+        let context_id = client.get_contexts().await[0].id.clone();
+        assert_eq!(indexed_paths(&client, &context_id).await.len(), 3);
+
+        std::fs::write(docs.join("edited.txt"), "after the edit").unwrap();
+        std::fs::remove_file(docs.join("deleted.txt")).unwrap();
+        std::fs::write(docs.join("added.txt"), "added").unwrap();
+
+        client.update_context(&context_id, &docs).await.unwrap();
+        wait_for_operations(&client).await;
+
+        let path = |name: &str| docs.join(name).to_string_lossy().to_string();
+        let paths = indexed_paths(&client, &context_id).await;
+        // Ids are renumbered so that they stay equal to positions
+        assert_eq!(paths.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![0, 1, 2]);
+        let mut names: Vec<_> = paths.into_iter().map(|(_, path)| path).collect();
+        names.sort();
+        assert_eq!(names, vec![path("added.txt"), path("edited.txt"), path("kept.txt")]);
+
+        let manifest = FileManifest::load(&dir.path().join("kb").join(&context_id)).unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert!(manifest.files.contains_key(&path("added.txt")));
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_context() {
+        let dir = tempdir().unwrap();
+        let docs = dir.path().join("ci").join("docs");
+        std::fs::create_dir_all(docs.join("guide")).unwrap();
+        std::fs::write(docs.join("guide").join("intro.md"), "getting started guide").unwrap();
+        std::fs::write(docs.join("faq.md"), "frequently asked questions").unwrap();
+
+        let new_client = |name: &str| {
+            let base_dir = dir.path().join(name);
+            let config = SemanticSearchConfig {
+                base_dir: base_dir.clone(),
+                embedding_type: EmbeddingType::Mock,
+                ..Default::default()
+            };
+            AsyncSemanticSearchClient::with_config(base_dir, config)
+        };
+
+        let ci = new_client("ci-kb").await.unwrap();
+        ci.add_context(AddContextRequest {
+            path: docs.clone(),
+            name: "docs".to_string(),
+            description: String::new(),
+            persistent: true,
+            include_patterns: None,
+            exclude_patterns: None,
+            embedding_type: Some(EmbeddingType::Fast),
+            watch: false,
+            rerank: false,
+        })
+        .await
+        .unwrap();
+        wait_for_operations(&ci).await;
+        let exported = ci.get_context_by_name("docs").await.unwrap();
+        let archive = dir.path().join("docs.zip");
+        ci.export_context(&exported.id, &archive).await.unwrap();
+
+        // The archive is portable: the files live elsewhere on the importing machine
+        let local_docs = dir.path().join("local").join("docs");
+        std::fs::create_dir_all(local_docs.parent().unwrap()).unwrap();
+        std::fs::rename(&docs, &local_docs).unwrap();
+        let local = new_client("local-kb").await.unwrap();
+        let imported = local.import_context(&archive, Some(&local_docs)).await.unwrap();
+
+        let local_docs = local_docs.canonicalize().unwrap();
+        assert_ne!(imported.id, exported.id);
+        assert_eq!(imported.name, "docs");
+        assert_eq!(imported.item_count, exported.item_count);
+        assert_eq!(imported.source_path, Some(local_docs.to_string_lossy().to_string()));
+
+        let results = local.search_context(&imported.id, "guide", None).await.unwrap();
+        assert_eq!(results.len(), 1);
+        let path = results[0].point.payload["path"].as_str().unwrap();
+        assert_eq!(Path::new(path), local_docs.join("guide").join("intro.md"));
+        let manifest = FileManifest::load(&dir.path().join("local-kb").join(&imported.id)).unwrap();
+        assert!(
+            manifest
+                .files
+                .keys()
+                .all(|path| Path::new(path).starts_with(&local_docs))
+        );
+
+        // The same source path can't be imported twice
+        assert!(local.import_context(&archive, Some(&local_docs)).await.is_err());
+        assert_eq!(local.get_contexts().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filter() {
+        let dir = tempdir().unwrap();
+        let config = SemanticSearchConfig {
+            base_dir: dir.path().join("kb"),
+            embedding_type: EmbeddingType::Mock,
+            ..Default::default()
+        };
+        let client = AsyncSemanticSearchClient::with_config(dir.path().join("kb"), config)
+            .await
+            .unwrap();
+
+        // Filters apply to both vector and BM25 contexts
+        for (name, embedding_type) in [("semantic", None), ("bm25", Some(EmbeddingType::Fast))] {
+            let docs = dir.path().join(name);
+            std::fs::create_dir_all(docs.join("src")).unwrap();
+            for i in 0..5 {
+                std::fs::write(docs.join(format!("notes{}.md", i)), "alpha beta notes").unwrap();
+            }
+            std::fs::write(docs.join("src").join("lib.rs"), "alpha beta code").unwrap();
+
+            client
+                .add_context(AddContextRequest {
+                    path: docs.canonicalize().unwrap(),
+                    name: name.to_string(),
+                    description: String::new(),
+                    persistent: false,
+                    include_patterns: None,
+                    exclude_patterns: None,
+                    embedding_type,
+                    watch: false,
+                    rerank: false,
+                })
+                .await
+                .unwrap();
+            wait_for_operations(&client).await;
+        }
+        assert_eq!(client.get_contexts().await.len(), 2);
+
+        let path_of = |result: &SearchResult| result.point.payload["path"].as_str().unwrap().to_string();
+        let filters = [
+            SearchFilter {
+                extensions: vec!["rs".to_string()],
+                ..Default::default()
+            },
+            SearchFilter {
+                path_globs: vec!["src/**".to_string()],
+                file_types: vec![FileType::Code],
+                ..Default::default()
+            },
+        ];
+        for filter in &filters {
+            // The single matching file is found even though more relevant files fill the limit
+            let results = client
+                .search_all_with_filter("alpha beta", Some(1), filter)
+                .await
+                .unwrap();
+            assert_eq!(results.len(), 2);
+            for (_, context_results) in results {
+                assert_eq!(context_results.len(), 1);
+                assert!(path_of(&context_results[0]).ends_with("lib.rs"));
+            }
+        }
+
+        let recent = SearchFilter {
+            modified_after: Some(Utc::now() + chrono::Duration::days(1)),
+            ..Default::default()
+        };
+        let results = client
+            .search_all_with_filter("alpha beta", None, &recent)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+
+        let invalid = SearchFilter {
+            path_globs: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(client.search_all_with_filter("alpha", None, &invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_watch_context() {
+        let dir = tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join("first.txt"), "first").unwrap();
+        let docs = docs.canonicalize().unwrap();
+
+        let config = SemanticSearchConfig {
+            base_dir: dir.path().join("kb"),
+            embedding_type: EmbeddingType::Mock,
+            ..Default::default()
+        };
+        let client = AsyncSemanticSearchClient::with_config(dir.path().join("kb"), config)
+            .await
+            .unwrap();
+        client
+            .add_context(AddContextRequest {
+                path: docs.clone(),
+                name: "docs".to_string(),
+                description: String::new(),
+                persistent: true,
+                include_patterns: None,
+                exclude_patterns: None,
+                embedding_type: None,
+                watch: true,
+                rerank: false,
+            })
+            .await
+            .unwrap();
+        wait_for_operations(&client).await;
+        let context = client.get_contexts().await.remove(0);
+        assert!(context.watched);
+        assert!(client.watcher.is_watching(&context.id));
+
+        std::fs::write(docs.join("second.txt"), "second").unwrap();
+        for _ in 0..100 {
+            if indexed_paths(&client, &context.id).await.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(indexed_paths(&client, &context.id).await.len(), 2);
+
+        client.remove_context_by_id(&context.id).await.unwrap();
+        assert!(!client.watcher.is_watching(&context.id));
+    }
+}
@@ -1,9 +1,14 @@
-use std::path::PathBuf;
+use std::path::{
+    Path,
+    PathBuf,
+};
 use std::sync::Arc;
 
 use tokio::sync::{
     Semaphore,
+    SemaphorePermit,
     mpsc,
+    oneshot,
 };
 use tokio_util::sync::CancellationToken;
 use tracing::debug;
@@ -12,8 +17,10 @@ use uuid::Uuid;
 use super::super::context::{
     ContextCreator,
     ContextManager,
+    FileManifest,
 };
 use super::super::operation::OperationManager;
+use super::super::watcher::ContextWatcher;
 use super::file_processor::FileProcessor;
 use crate::client::{
     embedder_factory,
@@ -30,6 +37,7 @@ pub struct BackgroundWorker {
     job_rx: mpsc::UnboundedReceiver<IndexingJob>,
     context_manager: ContextManager,
     operation_manager: OperationManager,
+    watcher: ContextWatcher,
     embedder: Box<dyn TextEmbedderTrait>,
     config: SemanticSearchConfig,
     base_dir: PathBuf,
@@ -44,17 +52,19 @@ impl BackgroundWorker {
         job_rx: mpsc::UnboundedReceiver<IndexingJob>,
         context_manager: ContextManager,
         operation_manager: OperationManager,
+        watcher: ContextWatcher,
         config: SemanticSearchConfig,
         base_dir: PathBuf,
     ) -> crate::error::Result<Self> {
         let embedder = embedder_factory::create_embedder(config.embedding_type)?;
         let file_processor = FileProcessor::new(config.clone());
-        let context_creator = ContextCreator::new();
+        let context_creator = ContextCreator::new().with_vector_encoding(config.vector_encoding);
 
         Ok(Self {
             job_rx,
             context_manager,
             operation_manager,
+            watcher,
             embedder,
             config,
             base_dir,
@@ -80,6 +90,8 @@ impl BackgroundWorker {
                     include_patterns,
                     exclude_patterns,
                     embedding_type,
+                    watch,
+                    rerank,
                 } => {
                     let params = IndexingParams {
                         path,
@@ -89,10 +101,21 @@ impl BackgroundWorker {
                         include_patterns,
                         exclude_patterns,
                         embedding_type,
+                        watch,
+                        rerank,
                     };
 
                     self.process_add_directory(id, params, cancel).await;
                 },
+                IndexingJob::Update {
+                    id,
+                    cancel,
+                    context_id,
+                    path,
+                    done,
+                } => {
+                    self.process_update(id, context_id, path, cancel, done).await;
+                },
                 IndexingJob::Clear { id, cancel } => {
                     self.process_clear(id, cancel).await;
                 },
@@ -114,14 +137,81 @@ impl BackgroundWorker {
             return;
         }
 
+        let Some(_permit) = self.acquire_indexing_slot(operation_id).await else {
+            return;
+        };
+
+        let path = params.path.clone();
+        let watch = params.watch;
+        let include_patterns = params.include_patterns.clone().unwrap_or_default();
+        let exclude_patterns = params.exclude_patterns.clone().unwrap_or_default();
+        let result = self.perform_indexing(operation_id, params, cancel_token).await;
+
+        match result {
+            Ok(context_id) => {
+                debug!("Successfully indexed context: {}", context_id);
+                if watch {
+                    if let Err(e) = self
+                        .watcher
+                        .watch(&context_id, &path, &include_patterns, &exclude_patterns)
+                    {
+                        tracing::warn!("Failed to watch {}: {}", path.display(), e);
+                    }
+                }
+                self.mark_operation_completed(operation_id).await;
+            },
+            Err(e) => {
+                tracing::error!("Indexing failed: {}", e);
+                self.mark_operation_failed(operation_id, e).await;
+            },
+        }
+    }
+
+    async fn process_update(
+        &self,
+        operation_id: Uuid,
+        context_id: String,
+        path: PathBuf,
+        cancel_token: CancellationToken,
+        done: Option<oneshot::Sender<()>>,
+    ) {
+        // Dropping `done` on any return notifies the watcher that queued the update
+        let _done = done;
+        debug!("Processing Update job: {} -> {}", context_id, path.display());
+
+        if cancel_token.is_cancelled() {
+            self.mark_operation_cancelled(operation_id).await;
+            return;
+        }
+
+        let Some(_permit) = self.acquire_indexing_slot(operation_id).await else {
+            return;
+        };
+
+        match self
+            .perform_update(operation_id, &context_id, &path, cancel_token)
+            .await
+        {
+            Ok(summary) => {
+                debug!("Successfully updated context {}: {}", context_id, summary);
+                self.mark_operation_completed(operation_id).await;
+            },
+            Err(e) => {
+                tracing::error!("Update failed: {}", e);
+                self.mark_operation_failed(operation_id, e).await;
+            },
+        }
+    }
+
+    async fn acquire_indexing_slot(&self, operation_id: Uuid) -> Option<SemaphorePermit<'_>> {
         self.update_operation_status(operation_id, "Waiting in queue...".to_string())
             .await;
 
-        let _permit = match self.indexing_semaphore.try_acquire() {
+        match self.indexing_semaphore.try_acquire() {
             Ok(permit) => {
                 self.update_operation_status(operation_id, "Acquired slot, starting indexing...".to_string())
                     .await;
-                permit
+                Some(permit)
             },
             Err(_) => {
                 self.update_operation_status(
@@ -136,28 +226,23 @@ impl BackgroundWorker {
                     Ok(permit) => {
                         self.update_operation_status(operation_id, "Acquired slot, starting indexing...".to_string())
                             .await;
-                        permit
+                        Some(permit)
                     },
                     Err(_) => {
                         self.mark_operation_failed(operation_id, "Semaphore unavailable".to_string())
                             .await;
-                        return;
+                        None
                     },
                 }
             },
-        };
-
-        let result = self.perform_indexing(operation_id, params, cancel_token).await;
+        }
+    }
 
-        match result {
-            Ok(context_id) => {
-                debug!("Successfully indexed context: {}", context_id);
-                self.mark_operation_completed(operation_id).await;
-            },
-            Err(e) => {
-                tracing::error!("Indexing failed: {}", e);
-                self.mark_operation_failed(operation_id, e).await;
-            },
+    fn context_dir(&self, context_id: &str, persistent: bool) -> PathBuf {
+        if persistent {
+            self.base_dir.join(context_id)
+        } else {
+            std::env::temp_dir().join("semantic_search").join(context_id)
         }
     }
 
@@ -176,11 +261,7 @@ impl BackgroundWorker {
         }
 
         let context_id = utils::generate_context_id();
-        let context_dir = if params.persistent {
-            self.base_dir.join(&context_id)
-        } else {
-            std::env::temp_dir().join("semantic_search").join(&context_id)
-        };
+        let context_dir = self.context_dir(&context_id, params.persistent);
 
         tokio::fs::create_dir_all(&context_dir)
             .await
@@ -221,6 +302,8 @@ impl BackgroundWorker {
             return Err("Failed: Operation was cancelled before file processing".to_string());
         }
 
+        // Record what was indexed so that updates only re-process changed files
+        let mut manifest = FileManifest::default();
         let items = self
             .file_processor
             .process_directory_files(
@@ -231,6 +314,7 @@ impl BackgroundWorker {
                 &params.include_patterns,
                 &params.exclude_patterns,
                 &self.operation_manager,
+                &mut manifest,
             )
             .await?;
 
@@ -253,6 +337,10 @@ impl BackgroundWorker {
             )
             .await?;
 
+        manifest
+            .save(&context_dir)
+            .map_err(|e| format!("Failed to save file manifest: {}", e))?;
+
         self.store_context_metadata(
             &context_id,
             &params.name,
@@ -263,12 +351,143 @@ impl BackgroundWorker {
             &params.exclude_patterns,
             file_count,
             effective_embedding_type,
+            params.watch,
+            params.rerank,
         )
         .await?;
 
         Ok(context_id)
     }
 
+    /// Re-index the files of a context that were added, changed or removed since it was indexed
+    async fn perform_update(
+        &self,
+        operation_id: Uuid,
+        context_id: &str,
+        path: &Path,
+        cancel_token: CancellationToken,
+    ) -> std::result::Result<String, String> {
+        if !path.exists() {
+            return Err(format!("Path '{}' does not exist", path.display()));
+        }
+
+        let context = {
+            let contexts = self.context_manager.get_contexts_ref().read().await;
+            contexts
+                .get(context_id)
+                .cloned()
+                .ok_or_else(|| format!("Context '{}' not found", context_id))?
+        };
+        let context_dir = self.context_dir(context_id, context.persistent);
+        let include_patterns = Some(context.include_patterns.clone()).filter(|patterns| !patterns.is_empty());
+        let exclude_patterns = Some(context.exclude_patterns.clone()).filter(|patterns| !patterns.is_empty());
+
+        self.update_operation_status(operation_id, "Comparing files with the last index...".to_string())
+            .await;
+
+        let files = self
+            .file_processor
+            .list_files(path, &include_patterns, &exclude_patterns)
+            .await?;
+        if files.len() > self.config.max_files {
+            return Err(format!(
+                "Failed: Directory contains more files than the maximum limit of {} files",
+                self.config.max_files
+            ));
+        }
+        let file_count = files.len();
+
+        // Contexts indexed before manifests existed are rebuilt from scratch
+        let previous = FileManifest::load(&context_dir);
+        let has_manifest = previous.is_some();
+        let (mut manifest, diff) = tokio::task::spawn_blocking(move || previous.unwrap_or_default().diff(&files))
+            .await
+            .map_err(|e| format!("File hashing task failed: {}", e))?;
+        let summary = diff.summary();
+
+        if cancel_token.is_cancelled() {
+            return Err("Operation was cancelled before re-indexing".to_string());
+        }
+
+        if has_manifest && diff.is_empty() {
+            manifest
+                .save(&context_dir)
+                .map_err(|e| format!("Failed to save file manifest: {}", e))?;
+            self.update_operation_status(operation_id, format!("Already up to date: {}", summary))
+                .await;
+            return Ok(summary);
+        }
+
+        self.update_operation_status(operation_id, format!("Re-indexing: {}", summary))
+            .await;
+
+        let items = self
+            .file_processor
+            .process_files(
+                &diff.files_to_index(),
+                operation_id,
+                &cancel_token,
+                &self.operation_manager,
+                &summary,
+                &mut manifest,
+            )
+            .await?;
+
+        let stale_paths = diff.stale_paths();
+        self.context_creator
+            .update_context(
+                &context_dir,
+                &items,
+                has_manifest.then_some(&stale_paths),
+                context.embedding_type,
+                operation_id,
+                &cancel_token,
+                &self.operation_manager,
+                &*self.embedder,
+                &self.context_manager,
+            )
+            .await?;
+
+        manifest
+            .save(&context_dir)
+            .map_err(|e| format!("Failed to save file manifest: {}", e))?;
+
+        {
+            let mut contexts = self.context_manager.get_contexts_ref().write().await;
+            if let Some(context) = contexts.get_mut(context_id) {
+                context.source_path = Some(path.to_string_lossy().to_string());
+                context.item_count = file_count;
+                context.updated_at = chrono::Utc::now();
+            }
+        }
+        if context.persistent {
+            self.context_manager
+                .save_contexts_metadata(&self.base_dir)
+                .await
+                .map_err(|e| format!("Failed to save contexts metadata: {}", e))?;
+        }
+
+        // Follow a watched context to its new source path
+        if context.watched && context.source_path.as_deref() != Some(&*path.to_string_lossy()) {
+            if let Err(e) = self
+                .watcher
+                .watch(context_id, path, &context.include_patterns, &context.exclude_patterns)
+            {
+                tracing::warn!("Failed to watch {}: {}", path.display(), e);
+            }
+        }
+
+        self.update_operation_progress(
+            operation_id,
+            file_count as u64,
+            file_count as u64,
+            format!("Update complete: {}", summary),
+        )
+        .await;
+
+        Ok(summary)
+    }
+
     async fn process_clear(&self, operation_id: Uuid, cancel_token: CancellationToken) {
         debug!("Processing Clear job");
 
@@ -323,6 +542,7 @@ impl BackgroundWorker {
             )
             .await;
 
+            self.watcher.unwatch(&context.id);
             {
                 let mut contexts_guard = self.context_manager.get_contexts_ref().write().await;
                 contexts_guard.remove(&context.id);
@@ -379,34 +599,36 @@ impl BackgroundWorker {
         }
     }
 
+    // Final states wait for the lock, so that callers waiting for operations to finish see them
     async fn mark_operation_completed(&self, operation_id: Uuid) {
-        if let Ok(mut operations) = self.operation_manager.get_active_operations_ref().try_write() {
-            operations.remove(&operation_id);
+        let mut operations = self.operation_manager.get_active_operations_ref().write().await;
+        if let Some(operation) = operations.get_mut(&operation_id) {
+            operation.finish(OperationState::Completed);
         }
+        OperationManager::prune_finished(&mut operations);
         debug!("Operation {} completed", operation_id);
     }
 
     async fn mark_operation_failed(&self, operation_id: Uuid, error: String) {
-        if let Ok(mut operations) = self.operation_manager.get_active_operations_ref().try_write() {
-            if let Some(operation) = operations.get_mut(&operation_id) {
-                if let Ok(mut progress) = operation.progress.try_lock() {
-                    progress.message = error.clone();
-                }
-            }
+        let mut operations = self.operation_manager.get_active_operations_ref().write().await;
+        if let Some(operation) = operations.get_mut(&operation_id) {
+            operation.finish(OperationState::Failed);
+            operation.progress.lock().await.message = error.clone();
         }
+        OperationManager::prune_finished(&mut operations);
         tracing::error!("Operation {} failed: {}", operation_id, error);
     }
 
     async fn mark_operation_cancelled(&self, operation_id: Uuid) {
-        if let Ok(mut operations) = self.operation_manager.get_active_operations_ref().try_write() {
-            if let Some(operation) = operations.get_mut(&operation_id) {
-                if let Ok(mut progress) = operation.progress.try_lock() {
-                    progress.message = "Operation cancelled by user".to_string();
-                    progress.current = 0;
-                    progress.total = 0;
-                }
-            }
+        let mut operations = self.operation_manager.get_active_operations_ref().write().await;
+        if let Some(operation) = operations.get_mut(&operation_id) {
+            operation.finish(OperationState::Cancelled);
+            let mut progress = operation.progress.lock().await;
+            progress.message = "Operation cancelled by user".to_string();
+            progress.current = 0;
+            progress.total = 0;
         }
+        OperationManager::prune_finished(&mut operations);
         debug!("Operation {} cancelled", operation_id);
     }
 
@@ -422,8 +644,10 @@ impl BackgroundWorker {
         exclude_patterns: &Option<Vec<String>>,
         item_count: usize,
         embedding_type: crate::embedding::EmbeddingType,
+        watched: bool,
+        rerank: bool,
     ) -> std::result::Result<(), String> {
-        let context = KnowledgeContext::new(
+        let mut context = KnowledgeContext::new(
             context_id.to_string(),
             name,
             description,
@@ -436,6 +660,8 @@ impl BackgroundWorker {
             item_count,
             embedding_type,
         );
+        context.watched = watched;
+        context.rerank = rerank;
 
         {
             let mut contexts = self.context_manager.get_contexts_ref().write().await;
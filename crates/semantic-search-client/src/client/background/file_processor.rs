@@ -1,11 +1,17 @@
-use std::path::Path;
+use std::path::{
+    Path,
+    PathBuf,
+};
 
 use tokio_util::sync::CancellationToken;
+use tracing::warn;
 use uuid::Uuid;
 
+use super::super::context::FileManifest;
 use super::super::operation::OperationManager;
 use crate::config::SemanticSearchConfig;
 use crate::processing::process_file_with_config;
+use crate::types::OperationState;
 
 /// File processor for handling directory operations
 pub struct FileProcessor {
@@ -62,14 +68,9 @@ impl FileProcessor {
                 if checked % 100 == 0 {
                     if let Ok(operations) = active_operations.try_read() {
                         if let Some(handle) = operations.get(&operation_id) {
-                            if handle.cancel_token.is_cancelled() {
+                            if handle.cancel_token.is_cancelled() || handle.state == OperationState::Cancelled {
                                 return Err("Operation cancelled during file counting".to_string());
                             }
-                            if let Ok(progress) = handle.progress.try_lock() {
-                                if progress.message.contains("cancelled") {
-                                    return Err("Operation cancelled during file counting".to_string());
-                                }
-                            }
                         }
                     }
                 }
@@ -90,7 +91,7 @@ impl FileProcessor {
         }
     }
 
-    /// Process directory files
+    /// Process directory files, recording each processed file in `manifest`
     #[allow(clippy::too_many_arguments)]
     pub async fn process_directory_files(
         &self,
@@ -101,6 +102,7 @@ impl FileProcessor {
         include_patterns: &Option<Vec<String>>,
         exclude_patterns: &Option<Vec<String>>,
         operation_manager: &OperationManager,
+        manifest: &mut FileManifest,
     ) -> std::result::Result<Vec<serde_json::Value>, String> {
         self.update_operation_status(
             operation_manager,
@@ -138,6 +140,9 @@ impl FileProcessor {
                 continue;
             }
 
+            if !Self::record(manifest, path) {
+                continue;
+            }
             match process_file_with_config(path, Some(self.config.chunk_size), Some(self.config.chunk_overlap)) {
                 Ok(mut file_items) => items.append(&mut file_items),
                 Err(_) => continue,
@@ -160,6 +165,95 @@ impl FileProcessor {
         Ok(items)
     }
 
+    /// List the files of a directory that would be indexed, up to one more than `max_files`
+    pub async fn list_files(
+        &self,
+        dir_path: &Path,
+        include_patterns: &Option<Vec<String>>,
+        exclude_patterns: &Option<Vec<String>>,
+    ) -> std::result::Result<Vec<PathBuf>, String> {
+        let dir_path = dir_path.to_path_buf();
+        let pattern_filter = Self::create_pattern_filter(include_patterns, exclude_patterns)?;
+        let max_files = self.config.max_files;
+
+        tokio::task::spawn_blocking(move || {
+            walkdir::WalkDir::new(&dir_path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter(|e| {
+                    !e.path()
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|s| s.starts_with('.'))
+                })
+                .filter(|e| {
+                    pattern_filter
+                        .as_ref()
+                        .is_none_or(|filter| filter.should_include(e.path()))
+                })
+                .take(max_files + 1)
+                .map(|e| e.into_path())
+                .collect()
+        })
+        .await
+        .map_err(|e| format!("File listing task failed: {}", e))
+    }
+
+    /// Process the given files, reporting progress with `summary` appended and recording each
+    /// processed file in `manifest`
+    pub async fn process_files(
+        &self,
+        files: &[PathBuf],
+        operation_id: Uuid,
+        cancel_token: &CancellationToken,
+        operation_manager: &OperationManager,
+        summary: &str,
+        manifest: &mut FileManifest,
+    ) -> std::result::Result<Vec<serde_json::Value>, String> {
+        let mut items = Vec::new();
+
+        for (processed_files, path) in files.iter().enumerate() {
+            if cancel_token.is_cancelled() {
+                return Err("Operation was cancelled during file processing".to_string());
+            }
+
+            if processed_files % 10 == 0 {
+                self.update_operation_progress(
+                    operation_manager,
+                    operation_id,
+                    processed_files as u64,
+                    files.len() as u64,
+                    format!("Indexing files ({}/{}): {}", processed_files, files.len(), summary),
+                )
+                .await;
+            }
+
+            if !Self::record(manifest, path) {
+                continue;
+            }
+            if let Ok(mut file_items) =
+                process_file_with_config(path, Some(self.config.chunk_size), Some(self.config.chunk_overlap))
+            {
+                items.append(&mut file_items);
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Record `path` in `manifest` before processing it. Returns `false` if it can't be read.
+    fn record(manifest: &mut FileManifest, path: &Path) -> bool {
+        match manifest.record(path) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Skipping unreadable file {}: {}", path.display(), e);
+                false
+            },
+        }
+    }
+
     fn create_pattern_filter(
         include_patterns: &Option<Vec<String>>,
         exclude_patterns: &Option<Vec<String>>,
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::{
     self,
     File,
@@ -10,6 +11,7 @@ use std::path::PathBuf;
 
 use crate::error::Result;
 use crate::index::BM25Index;
+use crate::search_filter::PayloadMatcher;
 use crate::types::BM25DataPoint;
 
 /// BM25 context for managing persistent BM25 search data
@@ -95,6 +97,59 @@ impl BM25Context {
         Ok(count)
     }
 
+    /// Remove all data points
+    pub fn clear_data_points(&mut self) -> Result<()> {
+        self.data_points.clear();
+        self.rebuild_index()
+    }
+
+    /// Remove the data points of the given source files
+    ///
+    /// Remaining data points are renumbered so that ids stay equal to their position, and the
+    /// index is rebuilt.
+    ///
+    /// # Returns
+    ///
+    /// The number of data points removed
+    pub fn remove_data_points_for_paths(&mut self, paths: &HashSet<String>) -> Result<usize> {
+        let before = self.data_points.len();
+        self.data_points.retain(|point| {
+            !point
+                .payload
+                .get("path")
+                .and_then(|path| path.as_str())
+                .is_some_and(|path| paths.contains(path))
+        });
+        let removed = before - self.data_points.len();
+
+        if removed > 0 {
+            for (id, point) in self.data_points.iter_mut().enumerate() {
+                point.id = id;
+            }
+            self.rebuild_index()?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Replace the source file path of every data point, e.g. after the files were moved
+    ///
+    /// Paths for which `rewrite` returns `None` are kept. Call [`Self::save`] to persist them.
+    pub fn rewrite_paths(&mut self, rewrite: impl Fn(&str) -> Option<String>) {
+        for point in &mut self.data_points {
+            if let Some(path) = point
+                .payload
+                .get("path")
+                .and_then(|path| path.as_str())
+                .and_then(&rewrite)
+            {
+                point
+                    .payload
+                    .insert("path".to_string(), serde_json::Value::String(path));
+            }
+        }
+    }
+
     /// Search the context
     pub fn search(&self, query: &str, limit: usize) -> Vec<(usize, f32)> {
         match &self.index {
@@ -107,6 +162,22 @@ impl BM25Context {
         }
     }
 
+    /// Search the context among the data points that pass a filter
+    pub fn search_filtered(&self, query: &str, limit: usize, matcher: &PayloadMatcher) -> Vec<(usize, f32)> {
+        match &self.index {
+            Some(index) => index
+                .search_filtered(query, limit, |id| {
+                    self.data_points
+                        .get(id)
+                        .is_some_and(|point| matcher.matches(&point.payload))
+                })
+                .into_iter()
+                .map(|(id, score, _)| (id, score))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// Get data points
     pub fn get_data_points(&self) -> &[BM25DataPoint] {
         &self.data_points
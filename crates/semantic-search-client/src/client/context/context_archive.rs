@@ -0,0 +1,230 @@
+use std::fs::{
+    self,
+    File,
+};
+use std::io::{
+    self,
+    BufReader,
+    BufWriter,
+};
+use std::path::{
+    Component,
+    Path,
+    PathBuf,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{
+    CompressionMethod,
+    ZipArchive,
+    ZipWriter,
+};
+
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+use crate::types::KnowledgeContext;
+
+/// Version of the archive layout, increased on incompatible changes
+const ARCHIVE_VERSION: u32 = 1;
+/// Name of the metadata entry in an archive
+const METADATA_FILE: &str = "context.json";
+/// Directory of the archive holding the files of the context directory
+const DATA_DIR: &str = "data";
+
+/// Metadata stored next to the data files of an exported context
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveMetadata {
+    /// Version of the archive layout
+    version: u32,
+    /// Identifier of the model that built the indexes
+    model: String,
+    /// The exported context
+    context: KnowledgeContext,
+}
+
+/// Write a context and the files of its directory to a zip archive
+///
+/// # Arguments
+///
+/// * `context` - Metadata of the context
+/// * `context_dir` - Directory holding the index data of the context
+/// * `archive_path` - Path of the archive to create
+pub fn write_archive(context: &KnowledgeContext, context_dir: &Path, archive_path: &Path) -> Result<()> {
+    let metadata = ArchiveMetadata {
+        version: ARCHIVE_VERSION,
+        model: context.embedding_type.model_id().to_string(),
+        context: context.clone(),
+    };
+
+    let mut entries = fs::read_dir(context_dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(archive_path)?));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file(METADATA_FILE, options).map_err(archive_error)?;
+    serde_json::to_writer_pretty(&mut zip, &metadata)?;
+
+    for entry in entries {
+        let metadata = entry.metadata()?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str().filter(|_| metadata.is_file()) else {
+            continue;
+        };
+        zip.start_file(
+            format!("{}/{}", DATA_DIR, file_name),
+            options.large_file(metadata.len() >= u32::MAX as u64),
+        )
+        .map_err(archive_error)?;
+        io::copy(&mut File::open(entry.path())?, &mut zip)?;
+    }
+
+    zip.finish().map_err(archive_error)?;
+    Ok(())
+}
+
+/// Read a context from a zip archive, extracting its data files to `context_dir`
+///
+/// The archive must have been written by a compatible version, with the same model as this
+/// version uses for indexes of the context's type.
+///
+/// # Returns
+///
+/// The context as it was exported
+pub fn read_archive(archive_path: &Path, context_dir: &Path) -> Result<KnowledgeContext> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(archive_path)?)).map_err(archive_error)?;
+
+    let metadata: ArchiveMetadata = serde_json::from_reader(zip.by_name(METADATA_FILE).map_err(archive_error)?)?;
+    if metadata.version != ARCHIVE_VERSION {
+        return Err(SemanticSearchError::InvalidArgument(format!(
+            "Unsupported knowledge archive version {}",
+            metadata.version
+        )));
+    }
+    let model = metadata.context.embedding_type.model_id();
+    if metadata.model != model {
+        return Err(SemanticSearchError::InvalidArgument(format!(
+            "The archive was indexed with model '{}', but {} entries are searched with '{}'",
+            metadata.model,
+            metadata.context.embedding_type.description(),
+            model
+        )));
+    }
+
+    fs::create_dir_all(context_dir)?;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(archive_error)?;
+        // Only extract plain files directly under the data directory
+        let Some(file_name) = entry.enclosed_name().as_deref().and_then(data_file_name) else {
+            continue;
+        };
+        io::copy(&mut entry, &mut File::create(context_dir.join(file_name))?)?;
+    }
+
+    Ok(metadata.context)
+}
+
+/// Move `path` from under `from` to under `to`, or `None` if it isn't under `from`
+pub fn rebase_path(path: &str, from: &Path, to: &Path) -> Option<String> {
+    let relative = Path::new(path).strip_prefix(from).ok()?;
+    let rebased = if relative.as_os_str().is_empty() {
+        to.to_path_buf()
+    } else {
+        to.join(relative)
+    };
+    Some(rebased.to_string_lossy().to_string())
+}
+
+fn data_file_name(entry_path: &Path) -> Option<PathBuf> {
+    let mut components = entry_path.strip_prefix(DATA_DIR).ok()?.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(file_name)), None) => Some(PathBuf::from(file_name)),
+        _ => None,
+    }
+}
+
+fn archive_error(e: ZipError) -> SemanticSearchError {
+    SemanticSearchError::OperationFailed(format!("Invalid knowledge archive: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::embedding::EmbeddingType;
+
+    fn context() -> KnowledgeContext {
+        KnowledgeContext::new(
+            "id".to_string(),
+            "docs",
+            "Project docs",
+            true,
+            Some("/ci/repo/docs".to_string()),
+            (vec!["**/*.md".to_string()], vec![]),
+            2,
+            EmbeddingType::Fast,
+        )
+    }
+
+    #[test]
+    fn test_write_and_read_archive() {
+        let dir = tempdir().unwrap();
+        let context_dir = dir.path().join("context");
+        fs::create_dir(&context_dir).unwrap();
+        fs::write(context_dir.join("data.bm25.json"), "[]").unwrap();
+        fs::write(context_dir.join("manifest.json"), "{}").unwrap();
+        fs::create_dir(context_dir.join("nested")).unwrap();
+
+        let archive_path = dir.path().join("docs.zip");
+        write_archive(&context(), &context_dir, &archive_path).unwrap();
+
+        let imported_dir = dir.path().join("imported");
+        let imported = read_archive(&archive_path, &imported_dir).unwrap();
+        assert_eq!(imported.name, "docs");
+        assert_eq!(imported.source_path.as_deref(), Some("/ci/repo/docs"));
+        assert_eq!(fs::read_to_string(imported_dir.join("data.bm25.json")).unwrap(), "[]");
+        assert_eq!(fs::read_to_string(imported_dir.join("manifest.json")).unwrap(), "{}");
+        assert!(!imported_dir.join("nested").exists());
+    }
+
+    #[test]
+    fn test_read_archive_with_other_model() {
+        let dir = tempdir().unwrap();
+        let archive_path = dir.path().join("docs.zip");
+        let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+        zip.start_file(METADATA_FILE, SimpleFileOptions::default()).unwrap();
+        serde_json::to_writer(&mut zip, &ArchiveMetadata {
+            version: ARCHIVE_VERSION,
+            model: "another-model".to_string(),
+            context: context(),
+        })
+        .unwrap();
+        zip.finish().unwrap();
+
+        let result = read_archive(&archive_path, &dir.path().join("imported"));
+        assert!(matches!(result, Err(SemanticSearchError::InvalidArgument(msg)) if msg.contains("another-model")));
+    }
+
+    #[test]
+    fn test_rebase_path() {
+        let from = Path::new("/ci/repo/docs");
+        let to = Path::new("/home/me/repo/docs");
+        assert_eq!(
+            rebase_path("/ci/repo/docs/guide/intro.md", from, to),
+            Some(to.join("guide/intro.md").to_string_lossy().to_string())
+        );
+        assert_eq!(
+            rebase_path("/ci/repo/docs", from, to),
+            Some(to.to_string_lossy().to_string())
+        );
+        assert_eq!(rebase_path("/ci/repo/docs-old/a.md", from, to), None);
+    }
+}
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -11,6 +14,7 @@ use super::context_manager::ContextManager;
 use super::{
     BM25Context,
     SemanticContext,
+    VectorEncoding,
 };
 use crate::embedding::{
     EmbeddingType,
@@ -23,7 +27,9 @@ use crate::types::{
 };
 
 /// Context creator utility
-pub struct ContextCreator;
+pub struct ContextCreator {
+    vector_encoding: VectorEncoding,
+}
 
 impl Default for ContextCreator {
     fn default() -> Self {
@@ -34,7 +40,15 @@ impl Default for ContextCreator {
 impl ContextCreator {
     /// Create new context creator
     pub fn new() -> Self {
-        Self
+        Self {
+            vector_encoding: VectorEncoding::default(),
+        }
+    }
+
+    /// Set how vectors of new semantic contexts are stored on disk
+    pub fn with_vector_encoding(mut self, vector_encoding: VectorEncoding) -> Self {
+        self.vector_encoding = vector_encoding;
+        self
     }
 
     /// Create context
@@ -50,7 +64,27 @@ impl ContextCreator {
         embedder: &dyn TextEmbedderTrait,
         context_manager: &ContextManager,
     ) -> std::result::Result<(), String> {
-        if embedding_type.is_bm25() {
+        if embedding_type.is_hybrid() {
+            self.create_semantic_context(
+                context_dir,
+                items,
+                operation_id,
+                cancel_token,
+                operation_manager,
+                embedder,
+                context_manager,
+            )
+            .await?;
+            self.create_bm25_context(
+                context_dir,
+                items,
+                operation_id,
+                cancel_token,
+                operation_manager,
+                context_manager,
+            )
+            .await
+        } else if embedding_type.is_bm25() {
             self.create_bm25_context(
                 context_dir,
                 items,
@@ -74,6 +108,139 @@ impl ContextCreator {
         }
     }
 
+    /// Update an existing context in place
+    ///
+    /// Removes the data points of `stale_paths`, or all of them if `None`, then adds data points
+    /// for `items`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_context(
+        &self,
+        context_dir: &Path,
+        items: &[serde_json::Value],
+        stale_paths: Option<&HashSet<String>>,
+        embedding_type: EmbeddingType,
+        operation_id: Uuid,
+        cancel_token: &CancellationToken,
+        operation_manager: &OperationManager,
+        embedder: &dyn TextEmbedderTrait,
+        context_manager: &ContextManager,
+    ) -> std::result::Result<(), String> {
+        let context_id = context_dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        // Embed before taking the locks so that searches keep working meanwhile
+        let semantic_points = if embedding_type.is_bm25() {
+            Vec::new()
+        } else {
+            let mut data_points = Vec::new();
+            for (i, item) in items.iter().enumerate() {
+                if cancel_token.is_cancelled() {
+                    return Err("Operation was cancelled during embedding generation".to_string());
+                }
+
+                if i % 10 == 0 {
+                    self.update_operation_progress(
+                        operation_manager,
+                        operation_id,
+                        i as u64,
+                        items.len() as u64,
+                        format!("Generating embeddings ({}/{})", i, items.len()),
+                    )
+                    .await;
+                }
+
+                let data_point = Self::create_data_point_from_item(item, i, embedder)
+                    .map_err(|e| format!("Failed to create data point: {}", e))?;
+                data_points.push(data_point);
+            }
+            data_points
+        };
+
+        if cancel_token.is_cancelled() {
+            return Err("Operation was cancelled before updating index".to_string());
+        }
+
+        self.update_operation_status(operation_manager, operation_id, "Updating index...".to_string())
+            .await;
+
+        if !embedding_type.is_bm25() {
+            let context = {
+                let mut volatile_contexts = context_manager.get_volatile_contexts_ref().write().await;
+                match volatile_contexts.get(&context_id) {
+                    Some(context) => context.clone(),
+                    None => {
+                        let context = SemanticContext::new(context_dir.join("data.json"))
+                            .map_err(|e| format!("Failed to load semantic context: {}", e))?;
+                        let context = Arc::new(Mutex::new(context));
+                        volatile_contexts.insert(context_id.clone(), context.clone());
+                        context
+                    },
+                }
+            };
+            let mut context = context.lock().await;
+
+            match stale_paths {
+                Some(paths) => context.remove_data_points_for_paths(paths).map(|_| ()),
+                None => context.clear_data_points(),
+            }
+            .map_err(|e| format!("Failed to remove data points: {}", e))?;
+
+            let mut data_points = semantic_points;
+            let start = context.len();
+            for (i, data_point) in data_points.iter_mut().enumerate() {
+                data_point.id = start + i;
+            }
+            context
+                .add_data_points(data_points)
+                .map_err(|e| format!("Failed to add data points: {}", e))?;
+            context
+                .save()
+                .map_err(|e| format!("Failed to save semantic context: {}", e))?;
+        }
+
+        if embedding_type.is_bm25() || embedding_type.is_hybrid() {
+            let context = {
+                let mut bm25_contexts = context_manager.get_bm25_contexts_ref().write().await;
+                match bm25_contexts.get(&context_id) {
+                    Some(context) => context.clone(),
+                    None => {
+                        let context = BM25Context::new(context_dir.join("data.bm25.json"), 5.0)
+                            .map_err(|e| format!("Failed to load BM25 context: {}", e))?;
+                        let context = Arc::new(Mutex::new(context));
+                        bm25_contexts.insert(context_id.clone(), context.clone());
+                        context
+                    },
+                }
+            };
+            let mut context = context.lock().await;
+
+            match stale_paths {
+                Some(paths) => context.remove_data_points_for_paths(paths).map(|_| ()),
+                None => context.clear_data_points(),
+            }
+            .map_err(|e| format!("Failed to remove BM25 data points: {}", e))?;
+
+            let start = context.get_data_points().len();
+            let data_points = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| Self::create_bm25_data_point_from_item(item, start + i))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| format!("Failed to create BM25 data point: {}", e))?;
+            context
+                .add_data_points(data_points)
+                .map_err(|e| format!("Failed to add BM25 data points: {}", e))?;
+            context
+                .save()
+                .map_err(|e| format!("Failed to save BM25 context: {}", e))?;
+        }
+
+        Ok(())
+    }
+
     async fn create_bm25_context(
         &self,
         context_dir: &Path,
@@ -128,7 +295,9 @@ impl ContextCreator {
             .add_data_points(data_points)
             .map_err(|e| format!("Failed to add BM25 data points: {}", e))?;
 
-        let _ = bm25_context.save();
+        bm25_context
+            .save()
+            .map_err(|e| format!("Failed to save BM25 context: {}", e))?;
 
         // Store the BM25 context
         let context_id = context_dir
@@ -169,6 +338,7 @@ impl ContextCreator {
 
         let mut semantic_context = SemanticContext::new(context_dir.join("data.json"))
             .map_err(|e| format!("Failed to create semantic context: {}", e))?;
+        semantic_context.set_vector_encoding(self.vector_encoding);
 
         let mut data_points = Vec::new();
         let total_items = items.len();
@@ -206,7 +376,9 @@ impl ContextCreator {
             .map_err(|e| format!("Failed to add data points: {}", e))?;
 
         // Persist context.
-        let _ = semantic_context.save();
+        semantic_context
+            .save()
+            .map_err(|e| format!("Failed to save semantic context: {}", e))?;
 
         // Store the semantic context
         let context_id = context_dir
@@ -14,17 +14,26 @@ use tracing::warn;
 
 use super::{
     BM25Context,
+    FileManifest,
     SemanticContext,
+    context_archive,
+    rank_fusion,
 };
 use crate::client::utils;
 use crate::embedding::{
     EmbeddingType,
+    Reranker,
     TextEmbedderTrait,
+    rerank,
 };
 use crate::error::{
     Result,
     SemanticSearchError,
 };
+use crate::search_filter::{
+    PayloadMatcher,
+    SearchFilter,
+};
 use crate::types::*;
 
 type VolatileContexts = Arc<RwLock<HashMap<ContextId, Arc<Mutex<SemanticContext>>>>>;
@@ -33,6 +42,8 @@ type BM25Contexts = Arc<RwLock<HashMap<ContextId, Arc<Mutex<BM25Context>>>>>;
 const SEMANTIC_DATA_FILE: &str = "data.json";
 const BM25_DATA_FILE: &str = "data.bm25.json";
 const DEFAULT_BM25_SCORE: f64 = 100.0;
+/// How many candidates each index of a hybrid context contributes per requested result
+const HYBRID_CANDIDATE_FACTOR: usize = 3;
 
 #[derive(Clone)]
 /// Context manager for handling contexts
@@ -41,11 +52,17 @@ pub struct ContextManager {
     volatile_contexts: VolatileContexts,
     bm25_contexts: BM25Contexts,
     base_dir: PathBuf,
+    hybrid_weight: f32,
+    rerank_all: bool,
+    rerank_candidates: usize,
 }
 
 impl ContextManager {
     /// Create new context manager
-    pub async fn new(base_dir: &Path) -> Result<Self> {
+    ///
+    /// `hybrid_weight` is the weight of the vector ranking when fusing results of hybrid
+    /// contexts, from 0.0 (BM25 only) to 1.0 (vectors only).
+    pub async fn new(base_dir: &Path, hybrid_weight: f32) -> Result<Self> {
         let contexts_file = base_dir.join("contexts.json");
         let persistent_contexts: HashMap<ContextId, KnowledgeContext> = utils::load_json_from_file(&contexts_file)?;
 
@@ -54,9 +71,21 @@ impl ContextManager {
             volatile_contexts: Arc::new(RwLock::new(HashMap::new())),
             bm25_contexts: Arc::new(RwLock::new(HashMap::new())),
             base_dir: base_dir.to_path_buf(),
+            hybrid_weight,
+            rerank_all: false,
+            rerank_candidates: 0,
         })
     }
 
+    /// Set which contexts are reranked and how many candidates are reranked per context
+    ///
+    /// Contexts added with reranking are always reranked; `all_contexts` reranks the others too.
+    pub fn with_reranking(mut self, all_contexts: bool, candidates: usize) -> Self {
+        self.rerank_all = all_contexts;
+        self.rerank_candidates = candidates;
+        self
+    }
+
     /// Get all contexts
     pub async fn get_contexts(&self) -> Vec<KnowledgeContext> {
         match tokio::time::timeout(Duration::from_secs(2), self.contexts.read()).await {
@@ -72,26 +101,34 @@ impl ContextManager {
         }
     }
 
-    /// Search all contexts
+    /// Search all contexts, returning only data points that pass `filter`
+    ///
+    /// Results of contexts that rerank are reordered by `reranker` when it's available.
     pub async fn search_all(
         &self,
         query_text: &str,
         effective_limit: usize,
+        filter: &SearchFilter,
         embedder: &dyn TextEmbedderTrait,
+        reranker: Option<&dyn Reranker>,
     ) -> Result<Vec<(ContextId, SearchResults)>> {
+        let matcher = compile_filter(filter)?;
         let mut all_results = Vec::new();
         let contexts_metadata = self.contexts.read().await;
 
-        for (context_id, context_meta) in contexts_metadata.iter() {
-            if context_meta.embedding_type.is_bm25() {
-                if let Some(results) = self.search_bm25_context(context_id, query_text, effective_limit).await {
-                    all_results.push((context_id.clone(), results));
-                }
-            } else if let Some(results) = self
-                .search_semantic_context(context_id, query_text, effective_limit, embedder)
+        for context_meta in contexts_metadata.values() {
+            if let Some(results) = self
+                .search_knowledge_context(
+                    context_meta,
+                    query_text,
+                    effective_limit,
+                    matcher.as_ref(),
+                    embedder,
+                    reranker,
+                )
                 .await?
             {
-                all_results.push((context_id.clone(), results));
+                all_results.push((context_meta.id.clone(), results));
             }
         }
 
@@ -107,35 +144,101 @@ impl ContextManager {
         Ok(all_results)
     }
 
-    /// Search in a specific context
+    /// Search in a specific context, returning only data points that pass `filter`
+    ///
+    /// Results are reordered by `reranker` when it's available and the context reranks.
     pub async fn search_context(
         &self,
         context_id: &str,
         query_text: &str,
         effective_limit: usize,
+        filter: &SearchFilter,
         embedder: &dyn TextEmbedderTrait,
+        reranker: Option<&dyn Reranker>,
     ) -> Result<Option<SearchResults>> {
+        let matcher = compile_filter(filter)?;
         let contexts_metadata = self.contexts.read().await;
         let context_meta = contexts_metadata
             .get(context_id)
             .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?;
 
-        if context_meta.embedding_type.is_bm25() {
-            Ok(self.search_bm25_context(context_id, query_text, effective_limit).await)
-        } else {
-            self.search_semantic_context(context_id, query_text, effective_limit, embedder)
+        self.search_knowledge_context(
+            context_meta,
+            query_text,
+            effective_limit,
+            matcher.as_ref(),
+            embedder,
+            reranker,
+        )
+        .await
+    }
+
+    /// Whether search results of a context are reranked
+    pub fn reranks(&self, context: &KnowledgeContext) -> bool {
+        self.rerank_all || context.rerank
+    }
+
+    async fn search_knowledge_context(
+        &self,
+        context_meta: &KnowledgeContext,
+        query_text: &str,
+        limit: usize,
+        matcher: Option<&PayloadMatcher>,
+        embedder: &dyn TextEmbedderTrait,
+        reranker: Option<&dyn Reranker>,
+    ) -> Result<Option<SearchResults>> {
+        let context_id = context_meta.id.as_str();
+        let reranker = reranker.filter(|_| self.reranks(context_meta));
+        // Retrieve more candidates than requested so that reranking can promote lower ranked ones
+        let candidates = match reranker {
+            Some(_) => limit.max(self.rerank_candidates),
+            None => limit,
+        };
+
+        let results = if context_meta.embedding_type.is_bm25() {
+            self.search_bm25_context(context_id, query_text, candidates, matcher)
                 .await
+        } else if context_meta.embedding_type.is_hybrid() {
+            self.search_hybrid_context(context_id, query_text, candidates, matcher, embedder)
+                .await?
+        } else {
+            self.search_semantic_context(context_id, query_text, candidates, matcher, embedder)
+                .await?
+        };
+
+        let Some(reranker) = reranker else {
+            return Ok(results);
+        };
+        let Some(mut results) = results else {
+            return Ok(None);
+        };
+        match rerank(reranker, query_text, results.clone(), limit) {
+            Ok(reranked) => Ok(Some(reranked)),
+            Err(e) => {
+                warn!("Failed to rerank results of context {}: {}", context_id, e);
+                results.truncate(limit);
+                Ok(Some(results))
+            },
         }
     }
 
-    async fn search_bm25_context(&self, context_id: &str, query_text: &str, limit: usize) -> Option<SearchResults> {
+    async fn search_bm25_context(
+        &self,
+        context_id: &str,
+        query_text: &str,
+        limit: usize,
+        matcher: Option<&PayloadMatcher>,
+    ) -> Option<SearchResults> {
         let bm25_contexts = tokio::time::timeout(Duration::from_millis(100), self.bm25_contexts.read())
             .await
             .ok()?;
         let context_arc = bm25_contexts.get(context_id)?;
         let context = context_arc.try_lock().ok()?;
 
-        let search_results = context.search(query_text, limit);
+        let search_results = match matcher {
+            Some(matcher) => context.search_filtered(query_text, limit, matcher),
+            None => context.search(query_text, limit),
+        };
         let results: Vec<SearchResult> = search_results
             .into_iter()
             .filter_map(|(id, score)| {
@@ -159,6 +262,7 @@ impl ContextManager {
         context_id: &str,
         query_text: &str,
         limit: usize,
+        matcher: Option<&PayloadMatcher>,
         embedder: &dyn TextEmbedderTrait,
     ) -> Result<Option<SearchResults>> {
         let query_vector = embedder.embed(query_text)?;
@@ -168,7 +272,11 @@ impl ContextManager {
 
         if let Some(context_arc) = volatile_contexts.get(context_id) {
             if let Ok(context_guard) = context_arc.try_lock() {
-                match context_guard.search(&query_vector, limit) {
+                let results = match matcher {
+                    Some(matcher) => context_guard.search_filtered(&query_vector, limit, matcher),
+                    None => context_guard.search(&query_vector, limit),
+                };
+                match results {
                     Ok(results) => Ok(if results.is_empty() { None } else { Some(results) }),
                     Err(e) => {
                         warn!("Failed to search context {}: {}", context_id, e);
@@ -183,6 +291,28 @@ impl ContextManager {
         }
     }
 
+    async fn search_hybrid_context(
+        &self,
+        context_id: &str,
+        query_text: &str,
+        limit: usize,
+        matcher: Option<&PayloadMatcher>,
+        embedder: &dyn TextEmbedderTrait,
+    ) -> Result<Option<SearchResults>> {
+        let candidates = limit.saturating_mul(HYBRID_CANDIDATE_FACTOR);
+        let semantic_results = self
+            .search_semantic_context(context_id, query_text, candidates, matcher, embedder)
+            .await?
+            .unwrap_or_default();
+        let bm25_results = self
+            .search_bm25_context(context_id, query_text, candidates, matcher)
+            .await
+            .unwrap_or_default();
+
+        let results = rank_fusion::fuse(semantic_results, bm25_results, self.hybrid_weight, limit);
+        Ok(if results.is_empty() { None } else { Some(results) })
+    }
+
     /// Check if path exists or is being indexed
     pub async fn check_path_exists(
         &self,
@@ -192,22 +322,17 @@ impl ContextManager {
         // First check if there's already an ACTIVE indexing operation for this exact path
         if let Ok(operations) = operation_manager.get_active_operations().try_read() {
             for handle in operations.values() {
-                if let crate::types::OperationType::Indexing { path, name } = &handle.operation_type {
+                if let crate::types::OperationType::Indexing { path, name }
+                | crate::types::OperationType::Updating { path, name } = &handle.operation_type
+                {
                     if let Ok(operation_canonical) = PathBuf::from(path).canonicalize() {
                         if operation_canonical == *canonical_path {
-                            if let Ok(progress) = handle.progress.try_lock() {
-                                // Only block if the operation is truly active (not cancelled, failed, or completed)
-                                let is_cancelled = progress.message.contains("cancelled");
-                                let is_failed =
-                                    progress.message.contains("failed") || progress.message.contains("error");
-                                let is_completed = progress.message.contains("complete");
-
-                                if !is_cancelled && !is_failed && !is_completed {
-                                    return Err(crate::error::SemanticSearchError::InvalidArgument(format!(
-                                        "Already indexing this path: {} (Operation: {})",
-                                        path, name
-                                    )));
-                                }
+                            // Only block if the operation is still running
+                            if !handle.state.is_finished() {
+                                return Err(crate::error::SemanticSearchError::InvalidArgument(format!(
+                                    "Already indexing this path: {} (Operation: {})",
+                                    path, name
+                                )));
                             }
                         }
                     }
@@ -263,6 +388,9 @@ impl ContextManager {
 
         if embedding_type.is_bm25() {
             self.load_bm25_context(context_id, &context_dir).await
+        } else if embedding_type.is_hybrid() {
+            self.load_semantic_context(context_id, &context_dir).await?;
+            self.load_bm25_context(context_id, &context_dir).await
         } else {
             self.load_semantic_context(context_id, &context_dir).await
         }
@@ -324,6 +452,11 @@ impl ContextManager {
             volatile_contexts.clear();
         }
 
+        {
+            let mut bm25_contexts = self.bm25_contexts.write().await;
+            bm25_contexts.clear();
+        }
+
         if base_dir.exists() {
             std::fs::remove_dir_all(base_dir).map_err(SemanticSearchError::IoError)?;
             std::fs::create_dir_all(base_dir).map_err(SemanticSearchError::IoError)?;
@@ -344,6 +477,11 @@ impl ContextManager {
             volatile_contexts.remove(context_id);
         }
 
+        {
+            let mut bm25_contexts = self.bm25_contexts.write().await;
+            bm25_contexts.remove(context_id);
+        }
+
         let context_dir = base_dir.join(context_id);
         if context_dir.exists() {
             tokio::fs::remove_dir_all(&context_dir).await.map_err(|e| {
@@ -355,6 +493,87 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Export a persistent context to a zip archive that can be imported elsewhere
+    pub async fn export_context(&self, context_id: &str, archive_path: &Path) -> Result<KnowledgeContext> {
+        let context = {
+            let contexts = self.contexts.read().await;
+            contexts
+                .get(context_id)
+                .cloned()
+                .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?
+        };
+
+        let context_dir = self.base_dir.join(context_id);
+        if !context.persistent || !context_dir.exists() {
+            return Err(SemanticSearchError::InvalidArgument(format!(
+                "Context '{}' has no saved index to export",
+                context.name
+            )));
+        }
+
+        context_archive::write_archive(&context, &context_dir, archive_path)?;
+        Ok(context)
+    }
+
+    /// Import a context exported with [`Self::export_context`] as a new persistent context
+    ///
+    /// With `source_path`, the source path of the context and the paths of its files are moved
+    /// from the exported source path to `source_path`. Imported contexts aren't watched.
+    pub async fn import_context(&self, archive_path: &Path, source_path: Option<&Path>) -> Result<KnowledgeContext> {
+        let context_id = utils::generate_context_id();
+        let context_dir = self.base_dir.join(&context_id);
+
+        let result = self.extract_context(archive_path, &context_dir, source_path).await;
+        let mut context = match result {
+            Ok(context) => context,
+            Err(e) => {
+                if let Err(remove_error) = std::fs::remove_dir_all(&context_dir) {
+                    warn!("Failed to remove {}: {}", context_dir.display(), remove_error);
+                }
+                return Err(e);
+            },
+        };
+
+        context.id = context_id.clone();
+        context.persistent = true;
+        context.watched = false;
+        {
+            let mut contexts = self.contexts.write().await;
+            contexts.insert(context_id.clone(), context.clone());
+        }
+        self.save_contexts_metadata(&self.base_dir).await?;
+        self.load_persistent_context(&context_id).await?;
+
+        Ok(context)
+    }
+
+    async fn extract_context(
+        &self,
+        archive_path: &Path,
+        context_dir: &Path,
+        source_path: Option<&Path>,
+    ) -> Result<KnowledgeContext> {
+        let mut context = context_archive::read_archive(archive_path, context_dir)?;
+
+        if let Some(source_path) = source_path {
+            let source_path = source_path
+                .canonicalize()
+                .unwrap_or_else(|_e| source_path.to_path_buf());
+            rebase_context_files(&mut context, context_dir, &source_path)?;
+        }
+
+        if let Some(source_path) = &context.source_path {
+            if let Some(existing) = self.get_context_by_path(source_path).await {
+                return Err(SemanticSearchError::InvalidArgument(format!(
+                    "Path already exists in knowledge base: {} (Context: '{}')",
+                    source_path, existing.name
+                )));
+            }
+        }
+
+        Ok(context)
+    }
+
     /// Get context by path
     pub async fn get_context_by_path(&self, path: &str) -> Option<KnowledgeContext> {
         let contexts = self.contexts.read().await;
@@ -429,3 +648,38 @@ impl ContextManager {
         &self.bm25_contexts
     }
 }
+
+/// Move the source path of an extracted context and the file paths in its data to `source_path`
+fn rebase_context_files(context: &mut KnowledgeContext, context_dir: &Path, source_path: &Path) -> Result<()> {
+    let exported_path = context.source_path.as_deref().map(PathBuf::from).ok_or_else(|| {
+        SemanticSearchError::InvalidArgument(format!("Context '{}' has no source path to rebase", context.name))
+    })?;
+    let rewrite = |path: &str| context_archive::rebase_path(path, &exported_path, source_path);
+
+    if !context.embedding_type.is_bm25() {
+        let mut semantic_context = SemanticContext::new(context_dir.join(SEMANTIC_DATA_FILE))?;
+        semantic_context.rewrite_paths(rewrite)?;
+        semantic_context.save()?;
+    }
+    if context.embedding_type.is_bm25() || context.embedding_type.is_hybrid() {
+        let mut bm25_context = BM25Context::new(context_dir.join(BM25_DATA_FILE), DEFAULT_BM25_SCORE)?;
+        bm25_context.rewrite_paths(rewrite);
+        bm25_context.save()?;
+    }
+    if let Some(mut manifest) = FileManifest::load(context_dir) {
+        manifest.rewrite_paths(rewrite);
+        manifest.save(context_dir)?;
+    }
+
+    context.source_path = Some(source_path.to_string_lossy().to_string());
+    Ok(())
+}
+
+/// Compile a search filter, or `None` if it lets every data point through
+fn compile_filter(filter: &SearchFilter) -> Result<Option<PayloadMatcher>> {
+    if filter.is_empty() {
+        Ok(None)
+    } else {
+        filter.matcher().map(Some)
+    }
+}
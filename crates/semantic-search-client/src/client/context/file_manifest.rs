@@ -0,0 +1,281 @@
+use std::collections::{
+    BTreeMap,
+    HashSet,
+};
+use std::fs;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::UNIX_EPOCH;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+use tracing::warn;
+
+use crate::client::utils;
+use crate::error::Result;
+
+/// Name of the manifest file in a context directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Content hash and modification time of every file indexed in a context
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileManifest {
+    /// Entries keyed by file path, as stored in the `path` of data point payloads
+    pub files: BTreeMap<String, FileEntry>,
+}
+
+/// State of a file when it was last indexed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// Hex-encoded SHA-256 of the file content
+    pub hash: String,
+    /// Modification time in milliseconds since the Unix epoch
+    pub modified_ms: u64,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Files that differ between a manifest and the files currently on disk
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestDiff {
+    /// Files that weren't indexed before
+    pub added: Vec<PathBuf>,
+    /// Indexed files whose content changed
+    pub changed: Vec<PathBuf>,
+    /// Indexed files that no longer exist, no longer match the patterns or can't be read
+    pub removed: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// Whether no file was added, changed or removed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Files that need to be processed and embedded
+    pub fn files_to_index(&self) -> Vec<PathBuf> {
+        self.added.iter().chain(&self.changed).cloned().collect()
+    }
+
+    /// Paths whose data points are out of date
+    pub fn stale_paths(&self) -> HashSet<String> {
+        self.changed
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .chain(self.removed.iter().cloned())
+            .collect()
+    }
+
+    /// Counts for progress messages, e.g. "3 added / 1 changed / 0 removed"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added / {} changed / {} removed",
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len()
+        )
+    }
+}
+
+impl FileManifest {
+    /// Load the manifest of a context, or `None` if the context was indexed without one
+    pub fn load(context_dir: &Path) -> Option<Self> {
+        let path = context_dir.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path).ok()?;
+        // An unreadable manifest means a full re-index rather than duplicated data points
+        match serde_json::from_str(&content) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                warn!("Ignoring unreadable manifest {}: {}", path.display(), e);
+                None
+            },
+        }
+    }
+
+    /// Save the manifest in a context directory
+    pub fn save(&self, context_dir: &Path) -> Result<()> {
+        utils::save_json_to_file(&context_dir.join(MANIFEST_FILE), self)
+    }
+
+    /// Replace the path of every entry, keeping paths for which `rewrite` returns `None`
+    pub fn rewrite_paths(&mut self, rewrite: impl Fn(&str) -> Option<String>) {
+        self.files = std::mem::take(&mut self.files)
+            .into_iter()
+            .map(|(path, entry)| (rewrite(&path).unwrap_or(path), entry))
+            .collect();
+    }
+
+    /// Build a manifest for `files`, skipping files that can't be read
+    pub fn build(files: &[PathBuf]) -> Self {
+        Self::default().diff(files).0
+    }
+
+    /// Record the current state of `path`, as it is about to be processed
+    ///
+    /// The file is hashed again even if its entry looks up to date, so that the entry matches the
+    /// content that gets indexed. Files that can't be read are removed from the manifest.
+    pub fn record(&mut self, path: &Path) -> std::io::Result<()> {
+        let key = path.to_string_lossy().to_string();
+        match file_entry(path, None) {
+            Ok(entry) => {
+                self.files.insert(key, entry);
+                Ok(())
+            },
+            Err(e) => {
+                self.files.remove(&key);
+                Err(e)
+            },
+        }
+    }
+
+    /// Compare the manifest with `files`, returning the manifest for `files` and the differences
+    ///
+    /// Files whose size and modification time didn't change are assumed unchanged without hashing
+    /// them again. Files that can't be read are left out of the new manifest, and reported as
+    /// removed if they were indexed.
+    pub fn diff(&self, files: &[PathBuf]) -> (Self, ManifestDiff) {
+        let mut manifest = Self::default();
+        let mut diff = ManifestDiff::default();
+
+        for path in files {
+            let key = path.to_string_lossy().to_string();
+            let previous = self.files.get(&key);
+            let entry = match file_entry(path, previous) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("Failed to hash {}: {}", path.display(), e);
+                    if previous.is_some() {
+                        diff.removed.push(key);
+                    }
+                    continue;
+                },
+            };
+
+            match previous {
+                None => diff.added.push(path.clone()),
+                Some(previous) if previous.hash != entry.hash => diff.changed.push(path.clone()),
+                Some(_) => {},
+            }
+            manifest.files.insert(key, entry);
+        }
+
+        let listed = files
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect::<HashSet<_>>();
+        diff.removed
+            .extend(self.files.keys().filter(|path| !listed.contains(*path)).cloned());
+
+        (manifest, diff)
+    }
+}
+
+fn file_entry(path: &Path, previous: Option<&FileEntry>) -> std::io::Result<FileEntry> {
+    let metadata = fs::metadata(path)?;
+    let modified_ms = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64);
+    let size = metadata.len();
+
+    if let Some(previous) = previous.filter(|previous| previous.modified_ms == modified_ms && previous.size == size) {
+        return Ok(previous.clone());
+    }
+
+    let hash = format!("{:x}", Sha256::digest(fs::read(path)?));
+    Ok(FileEntry {
+        hash,
+        modified_ms,
+        size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let dir = tempdir().unwrap();
+        let kept = dir.path().join("kept.txt");
+        let edited = dir.path().join("edited.txt");
+        let deleted = dir.path().join("deleted.txt");
+        fs::write(&kept, "kept").unwrap();
+        fs::write(&edited, "before").unwrap();
+        fs::write(&deleted, "deleted").unwrap();
+
+        let manifest = FileManifest::build(&[kept.clone(), edited.clone(), deleted.clone()]);
+        assert_eq!(manifest.files.len(), 3);
+
+        let (unchanged, diff) = manifest.diff(&[kept.clone(), edited.clone(), deleted.clone()]);
+        assert!(diff.is_empty());
+        assert_eq!(unchanged, manifest);
+
+        let added = dir.path().join("added.txt");
+        fs::write(&added, "added").unwrap();
+        fs::write(&edited, "after, with a different size").unwrap();
+        fs::remove_file(&deleted).unwrap();
+
+        let (updated, diff) = manifest.diff(&[added.clone(), edited.clone(), kept.clone()]);
+        assert_eq!(diff, ManifestDiff {
+            added: vec![added.clone()],
+            changed: vec![edited.clone()],
+            removed: vec![deleted.to_string_lossy().to_string()],
+        });
+        assert_eq!(diff.summary(), "1 added / 1 changed / 1 removed");
+        assert_eq!(diff.files_to_index(), vec![added, edited.clone()]);
+        assert_eq!(
+            diff.stale_paths(),
+            HashSet::from([
+                edited.to_string_lossy().to_string(),
+                deleted.to_string_lossy().to_string()
+            ])
+        );
+        assert_eq!(updated.files.len(), 3);
+    }
+
+    #[test]
+    fn test_unreadable_files_and_record() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        let key = file.to_string_lossy().to_string();
+        fs::write(&file, "before").unwrap();
+        let mut manifest = FileManifest::build(std::slice::from_ref(&file));
+
+        // Entries are recorded from the content at the time the file is processed
+        fs::write(&file, "during").unwrap();
+        manifest.record(&file).unwrap();
+        assert_eq!(manifest.files[&key].hash, format!("{:x}", Sha256::digest("during")));
+
+        // A listed file that can't be read any more is reported as removed
+        fs::remove_file(&file).unwrap();
+        let (updated, diff) = manifest.diff(std::slice::from_ref(&file));
+        assert_eq!(diff.removed, vec![key.clone()]);
+        assert!(updated.files.is_empty());
+
+        assert!(manifest.record(&file).is_err());
+        assert!(!manifest.files.contains_key(&key));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        assert!(FileManifest::load(dir.path()).is_none());
+
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "content").unwrap();
+        let manifest = FileManifest::build(&[file]);
+        manifest.save(dir.path()).unwrap();
+        assert_eq!(FileManifest::load(dir.path()), Some(manifest));
+    }
+}
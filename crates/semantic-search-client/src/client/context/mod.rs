@@ -1,13 +1,26 @@
 /// BM25 context implementation
 pub mod bm25_context;
+/// Portable archives of exported contexts
+pub mod context_archive;
 /// Context creation utilities
 pub mod context_creator;
 /// Context management
 pub mod context_manager;
+/// Per-file hashes for incremental re-indexing
+pub mod file_manifest;
+/// Binary storage of semantic context data points
+pub mod point_store;
+/// Reciprocal-rank fusion for hybrid contexts
+pub mod rank_fusion;
 /// Semantic context implementation
 pub mod semantic_context;
 
 pub use bm25_context::BM25Context;
 pub use context_creator::ContextCreator;
 pub use context_manager::ContextManager;
+pub use file_manifest::{
+    FileManifest,
+    ManifestDiff,
+};
+pub use point_store::VectorEncoding;
 pub use semantic_context::SemanticContext;
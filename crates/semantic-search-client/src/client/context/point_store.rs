@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::fs::{
+    self,
+    File,
+};
+use std::io::{
+    BufWriter,
+    Write,
+};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use memmap2::Mmap;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+use crate::search_filter::PointMetadata;
+use crate::types::DataPoint;
+
+/// Name of the vector file in a context directory
+pub const VECTORS_FILE: &str = "vectors.bin";
+/// Name of the payload file in a context directory
+pub const PAYLOADS_FILE: &str = "payloads.bin";
+
+const VECTORS_MAGIC: &[u8; 4] = b"QVEC";
+const PAYLOADS_MAGIC: &[u8; 4] = b"QPAY";
+const FORMAT_VERSION: u32 = 1;
+const VECTORS_HEADER_LEN: usize = 24;
+const PAYLOADS_HEADER_LEN: usize = 16;
+
+/// How vectors are stored on disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorEncoding {
+    /// 32-bit floats, stored exactly
+    #[default]
+    F32,
+    /// 8-bit integers with a scale per vector, a quarter of the size with a small loss of
+    /// precision
+    Int8,
+}
+
+impl VectorEncoding {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::F32 => 0,
+            Self::Int8 => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::F32),
+            1 => Some(Self::Int8),
+            _ => None,
+        }
+    }
+
+    /// Number of bytes used by a vector of `dimension` values
+    fn record_len(self, dimension: usize) -> usize {
+        match self {
+            Self::F32 => dimension * 4,
+            Self::Int8 => 4 + dimension,
+        }
+    }
+}
+
+/// Data points of a saved semantic context, memory-mapped and decoded on access
+///
+/// Vectors live in [`VECTORS_FILE`]: a header followed by fixed-size records. Payloads live in
+/// [`PAYLOADS_FILE`]: a header, an offset table and one JSON object per data point. Data point ids
+/// are their position in both files.
+pub struct MappedPoints {
+    vectors: Mmap,
+    payloads: Mmap,
+    encoding: VectorEncoding,
+    dimension: usize,
+    count: usize,
+    /// Filter metadata of every data point, decoded on first use
+    metadata: OnceLock<Vec<PointMetadata>>,
+}
+
+impl MappedPoints {
+    /// Map the data points saved in `dir`, or `None` if the context hasn't been saved in the
+    /// binary format
+    pub fn open(dir: &Path) -> Result<Option<Self>> {
+        let vectors_path = dir.join(VECTORS_FILE);
+        let payloads_path = dir.join(PAYLOADS_FILE);
+        if !vectors_path.exists() || !payloads_path.exists() {
+            return Ok(None);
+        }
+
+        // SAFETY: the files are only replaced by renaming new files over them, never modified in
+        // place, so the mapped content doesn't change.
+        let vectors = unsafe { Mmap::map(&File::open(&vectors_path)?)? };
+        let payloads = unsafe { Mmap::map(&File::open(&payloads_path)?)? };
+
+        if vectors.len() < VECTORS_HEADER_LEN || &vectors[..4] != VECTORS_MAGIC {
+            return Err(corrupt(&vectors_path, "bad header"));
+        }
+        if read_u32(&vectors, 4) != FORMAT_VERSION {
+            return Err(corrupt(&vectors_path, "unsupported version"));
+        }
+        let encoding =
+            VectorEncoding::from_byte(vectors[8]).ok_or_else(|| corrupt(&vectors_path, "unknown vector encoding"))?;
+        let dimension = read_u32(&vectors, 12) as usize;
+        let count = read_u64(&vectors, 16) as usize;
+        if vectors.len() != VECTORS_HEADER_LEN + count * encoding.record_len(dimension) {
+            return Err(corrupt(&vectors_path, "truncated"));
+        }
+
+        if payloads.len() < PAYLOADS_HEADER_LEN || &payloads[..4] != PAYLOADS_MAGIC {
+            return Err(corrupt(&payloads_path, "bad header"));
+        }
+        if read_u64(&payloads, 8) as usize != count {
+            return Err(corrupt(&payloads_path, "data point count doesn't match the vectors"));
+        }
+        let data_start = PAYLOADS_HEADER_LEN + (count + 1) * 8;
+        if payloads.len() < data_start || read_u64(&payloads, data_start - 8) as usize != payloads.len() - data_start {
+            return Err(corrupt(&payloads_path, "truncated"));
+        }
+
+        Ok(Some(Self {
+            vectors,
+            payloads,
+            encoding,
+            dimension,
+            count,
+            metadata: OnceLock::new(),
+        }))
+    }
+
+    /// Number of data points
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether there are no data points
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Encoding of the vectors on disk
+    pub fn encoding(&self) -> VectorEncoding {
+        self.encoding
+    }
+
+    /// Decode the vector of a data point
+    pub fn vector(&self, id: usize) -> Vec<f32> {
+        let record_len = self.encoding.record_len(self.dimension);
+        let start = VECTORS_HEADER_LEN + id * record_len;
+        let record = &self.vectors[start..start + record_len];
+        match self.encoding {
+            VectorEncoding::F32 => record
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                .collect(),
+            VectorEncoding::Int8 => {
+                let scale = f32::from_le_bytes(record[..4].try_into().unwrap());
+                record[4..].iter().map(|byte| *byte as i8 as f32 * scale).collect()
+            },
+        }
+    }
+
+    /// Decode the payload of a data point
+    pub fn payload(&self, id: usize) -> Result<HashMap<String, Value>> {
+        let offset = |index: usize| read_u64(&self.payloads, PAYLOADS_HEADER_LEN + index * 8) as usize;
+        let data_start = PAYLOADS_HEADER_LEN + (self.count + 1) * 8;
+        let (start, end) = (data_start + offset(id), data_start + offset(id + 1));
+        if start > end || end > self.payloads.len() {
+            return Err(SemanticSearchError::SerializationError(format!(
+                "Payload {} is out of bounds",
+                id
+            )));
+        }
+        Ok(serde_json::from_slice(&self.payloads[start..end])?)
+    }
+
+    /// Filter metadata of every data point, decoding all payloads the first time
+    ///
+    /// Data points whose payload can't be decoded get empty metadata.
+    pub fn metadata(&self) -> &[PointMetadata] {
+        self.metadata.get_or_init(|| {
+            (0..self.count)
+                .map(|id| match self.payload(id) {
+                    Ok(payload) => PointMetadata::from_payload(&payload),
+                    Err(e) => {
+                        warn!("Failed to read payload {}: {}", id, e);
+                        PointMetadata::default()
+                    },
+                })
+                .collect()
+        })
+    }
+
+    /// Decode a data point
+    pub fn point(&self, id: usize) -> Result<DataPoint> {
+        Ok(DataPoint {
+            id,
+            payload: self.payload(id)?,
+            vector: self.vector(id),
+        })
+    }
+}
+
+/// Save data points in the binary format, replacing any previously saved ones
+///
+/// Files are written next to their destination and renamed over it, so that contexts mapping the
+/// previous files keep reading consistent data.
+pub fn write_points<'a>(
+    dir: &Path,
+    encoding: VectorEncoding,
+    count: usize,
+    points: impl Iterator<Item = Result<std::borrow::Cow<'a, DataPoint>>>,
+) -> Result<()> {
+    let vectors_tmp = dir.join(format!("{}.tmp", VECTORS_FILE));
+    let payloads_tmp = dir.join(format!("{}.tmp", PAYLOADS_FILE));
+
+    let mut vectors = BufWriter::new(File::create(&vectors_tmp)?);
+    let mut offsets = Vec::with_capacity(count + 1);
+    let mut payload_data = Vec::new();
+    let mut dimension = None;
+    let mut written = 0;
+
+    for point in points {
+        let point = point?;
+        // The first data point sets the dimension of all vectors
+        let point_dimension = match dimension {
+            Some(dimension) => dimension,
+            None => {
+                write_vectors_header(&mut vectors, encoding, point.vector.len(), count)?;
+                *dimension.insert(point.vector.len())
+            },
+        };
+        if point.vector.len() != point_dimension {
+            return Err(SemanticSearchError::InvalidArgument(format!(
+                "Data point {} has {} dimensions instead of {}",
+                point.id,
+                point.vector.len(),
+                point_dimension
+            )));
+        }
+
+        write_vector(&mut vectors, encoding, &point.vector)?;
+        offsets.push(payload_data.len() as u64);
+        serde_json::to_writer(&mut payload_data, &point.payload)?;
+        written += 1;
+    }
+
+    if written != count {
+        return Err(SemanticSearchError::InvalidArgument(format!(
+            "Expected {} data points, got {}",
+            count, written
+        )));
+    }
+    if dimension.is_none() {
+        write_vectors_header(&mut vectors, encoding, 0, 0)?;
+    }
+    vectors.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    offsets.push(payload_data.len() as u64);
+
+    let mut payloads = BufWriter::new(File::create(&payloads_tmp)?);
+    payloads.write_all(PAYLOADS_MAGIC)?;
+    payloads.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    payloads.write_all(&(count as u64).to_le_bytes())?;
+    for offset in offsets {
+        payloads.write_all(&offset.to_le_bytes())?;
+    }
+    payloads.write_all(&payload_data)?;
+    payloads.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+    fs::rename(&vectors_tmp, dir.join(VECTORS_FILE))?;
+    fs::rename(&payloads_tmp, dir.join(PAYLOADS_FILE))?;
+    Ok(())
+}
+
+fn write_vectors_header(out: &mut impl Write, encoding: VectorEncoding, dimension: usize, count: usize) -> Result<()> {
+    out.write_all(VECTORS_MAGIC)?;
+    out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    out.write_all(&[encoding.to_byte(), 0, 0, 0])?;
+    out.write_all(&(dimension as u32).to_le_bytes())?;
+    out.write_all(&(count as u64).to_le_bytes())?;
+    Ok(())
+}
+
+fn write_vector(out: &mut impl Write, encoding: VectorEncoding, vector: &[f32]) -> Result<()> {
+    match encoding {
+        VectorEncoding::F32 => {
+            for value in vector {
+                out.write_all(&value.to_le_bytes())?;
+            }
+        },
+        VectorEncoding::Int8 => {
+            // Symmetric quantization, so that the largest component maps to ±127
+            let max = vector.iter().fold(0.0f32, |max, value| max.max(value.abs()));
+            let scale = if max > 0.0 { max / 127.0 } else { 0.0 };
+            out.write_all(&scale.to_le_bytes())?;
+            let quantized: Vec<u8> = vector
+                .iter()
+                .map(|value| {
+                    let value = if scale > 0.0 { (value / scale).round() } else { 0.0 };
+                    value.clamp(-127.0, 127.0) as i8 as u8
+                })
+                .collect();
+            out.write_all(&quantized)?;
+        },
+    }
+    Ok(())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn corrupt(path: &Path, reason: &str) -> SemanticSearchError {
+    SemanticSearchError::SerializationError(format!("Invalid data file {}: {}", path.display(), reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn point(id: usize, vector: Vec<f32>) -> DataPoint {
+        DataPoint {
+            id,
+            payload: HashMap::from([("text".to_string(), Value::String(format!("point {}", id)))]),
+            vector,
+        }
+    }
+
+    fn save(dir: &Path, encoding: VectorEncoding, points: &[DataPoint]) {
+        write_points(dir, encoding, points.len(), points.iter().map(|p| Ok(Cow::Borrowed(p)))).unwrap();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempdir().unwrap();
+        assert!(MappedPoints::open(dir.path()).unwrap().is_none());
+
+        let points = vec![point(0, vec![0.5, -1.0, 0.25]), point(1, vec![0.0, 2.0, -3.5])];
+        save(dir.path(), VectorEncoding::F32, &points);
+
+        let mapped = MappedPoints::open(dir.path()).unwrap().unwrap();
+        assert_eq!(mapped.len(), 2);
+        assert_eq!(mapped.encoding(), VectorEncoding::F32);
+        for expected in &points {
+            let loaded = mapped.point(expected.id).unwrap();
+            assert_eq!(loaded.vector, expected.vector);
+            assert_eq!(loaded.payload, expected.payload);
+        }
+    }
+
+    #[test]
+    fn test_int8_quantization() {
+        let dir = tempdir().unwrap();
+        let vector: Vec<f32> = (0..384).map(|i| ((i as f32) * 0.37).sin()).collect();
+        save(dir.path(), VectorEncoding::Int8, &[
+            point(0, vector.clone()),
+            point(1, vec![0.0; 384]),
+        ]);
+
+        let mapped = MappedPoints::open(dir.path()).unwrap().unwrap();
+        assert_eq!(mapped.encoding(), VectorEncoding::Int8);
+        let loaded = mapped.vector(0);
+        let max_error = loaded
+            .iter()
+            .zip(&vector)
+            .fold(0.0f32, |max, (a, b)| max.max((a - b).abs()));
+        assert!(max_error <= 1.0 / 254.0 + f32::EPSILON);
+        assert_eq!(mapped.vector(1), vec![0.0; 384]);
+
+        // A quarter of the f32 size, plus the scale
+        let size = fs::metadata(dir.path().join(VECTORS_FILE)).unwrap().len() as usize;
+        assert_eq!(size, VECTORS_HEADER_LEN + 2 * (4 + 384));
+    }
+
+    #[test]
+    fn test_empty_and_corrupt() {
+        let dir = tempdir().unwrap();
+        save(dir.path(), VectorEncoding::F32, &[]);
+        assert!(MappedPoints::open(dir.path()).unwrap().unwrap().is_empty());
+
+        save(dir.path(), VectorEncoding::F32, &[point(0, vec![1.0; 4])]);
+        let vectors = dir.path().join(VECTORS_FILE);
+        let mut bytes = fs::read(&vectors).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        fs::write(&vectors, bytes).unwrap();
+        assert!(MappedPoints::open(dir.path()).is_err());
+    }
+}
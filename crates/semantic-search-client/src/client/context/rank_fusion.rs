@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use crate::types::{
+    SearchResult,
+    SearchResults,
+};
+
+/// Rank offset from the reciprocal-rank fusion paper, which keeps the top few ranks from
+/// dominating the fused score
+const RRF_K: f32 = 60.0;
+
+/// Merge a vector ranking and a BM25 ranking with weighted reciprocal-rank fusion
+///
+/// Each result scores `weight / (k + rank)` for its rank in the vector results plus
+/// `(1 - weight) / (k + rank)` for its rank in the BM25 results, with ranks starting at 1. Scores
+/// are normalized so that a result ranked first in both lists scores 1.0. Both rankings must
+/// come from the same items so that data point ids match.
+///
+/// # Arguments
+///
+/// * `semantic` - Vector search results, best first
+/// * `bm25` - BM25 search results, best first
+/// * `weight` - Weight of the vector ranking, from 0.0 to 1.0
+/// * `limit` - Maximum number of results to return
+///
+/// # Returns
+///
+/// Fused results, best first
+pub fn fuse(semantic: SearchResults, bm25: SearchResults, weight: f32, limit: usize) -> SearchResults {
+    let weight = weight.clamp(0.0, 1.0);
+    let mut fused: HashMap<usize, (f32, SearchResult)> = HashMap::new();
+
+    for (weight, results) in [(weight, semantic), (1.0 - weight, bm25)] {
+        for (rank, result) in results.into_iter().enumerate() {
+            let score = weight / (RRF_K + rank as f32 + 1.0);
+            // Keep the first point seen for an id, which is the vector one when both exist
+            fused
+                .entry(result.point.id)
+                .and_modify(|(total, _)| *total += score)
+                .or_insert((score, result));
+        }
+    }
+
+    let mut results: SearchResults = fused
+        .into_values()
+        .map(|(score, result)| SearchResult::fused(result.point, score * (RRF_K + 1.0)))
+        .collect();
+    results.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.point.id.cmp(&b.point.id)));
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DataPoint;
+
+    fn results(ids: &[usize]) -> SearchResults {
+        ids.iter()
+            .enumerate()
+            .map(|(rank, id)| {
+                let point = DataPoint {
+                    id: *id,
+                    payload: HashMap::new(),
+                    vector: vec![],
+                };
+                SearchResult::new(point, rank as f32)
+            })
+            .collect()
+    }
+
+    fn ids(results: &SearchResults) -> Vec<usize> {
+        results.iter().map(|r| r.point.id).collect()
+    }
+
+    #[test]
+    fn test_fuse() {
+        let fused = fuse(results(&[1, 2, 3]), results(&[3, 1, 4]), 0.5, 10);
+        // 1 is ranked first and second, 3 is ranked first and third
+        assert_eq!(ids(&fused), vec![1, 3, 2, 4]);
+        assert!(fused.iter().all(|r| r.fused_score.is_some()));
+
+        let top = fused[0].fused_score.unwrap();
+        assert!(top < 1.0 && top > 0.9);
+        assert!((fused[0].distance - (1.0 - top)).abs() < f32::EPSILON);
+
+        // Agreeing rankings put the shared first result at exactly 1.0
+        let fused = fuse(results(&[7]), results(&[7]), 0.5, 10);
+        assert!((fused[0].fused_score.unwrap() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fuse_weight() {
+        let semantic_only = fuse(results(&[1, 2]), results(&[2, 1]), 1.0, 10);
+        assert_eq!(ids(&semantic_only), vec![1, 2]);
+
+        let bm25_only = fuse(results(&[1, 2]), results(&[2, 1]), 0.0, 10);
+        assert_eq!(ids(&bm25_only), vec![2, 1]);
+
+        let limited = fuse(results(&[1, 2, 3]), results(&[4, 5, 6]), 0.5, 2);
+        assert_eq!(limited.len(), 2);
+    }
+}
@@ -1,28 +1,55 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs::{
     self,
     File,
 };
-use std::io::{
-    BufReader,
-    BufWriter,
-};
+use std::io::BufReader;
 use std::path::PathBuf;
 
+use serde_json::Value;
+use tracing::{
+    info,
+    warn,
+};
+
+use super::point_store::{
+    self,
+    MappedPoints,
+    VectorEncoding,
+};
 use crate::error::Result;
 use crate::index::VectorIndex;
+use crate::search_filter::{
+    PayloadMatcher,
+    PointMetadata,
+};
 use crate::types::{
     DataPoint,
     SearchResult,
 };
 
+/// Base name of the saved vector index files
+const INDEX_BASENAME: &str = "index";
+
 /// A semantic context containing data points and a vector index
+///
+/// Saved contexts are stored in a binary format next to `data_path`: memory-mapped vectors and
+/// payloads that are decoded on access, and the vector index graph, which is loaded instead of
+/// being rebuilt. Contexts saved as a JSON `data_path` by older versions are converted on load.
 pub struct SemanticContext {
-    /// The data points stored in the index
+    /// Data points loaded from disk, with ids `0..mapped.len()`
+    mapped: Option<MappedPoints>,
+    /// Data points added since the context was loaded, with ids following the mapped ones
     pub(crate) data_points: Vec<DataPoint>,
+    /// Filter metadata of `data_points`, in the same order
+    added_metadata: Vec<PointMetadata>,
     /// The vector index for fast approximate nearest neighbor search
     index: Option<VectorIndex>,
-    /// Path to save/load the data points
+    /// Path of the legacy JSON data file, whose directory holds the binary files
     data_path: PathBuf,
+    /// Encoding of vectors when saving
+    vector_encoding: VectorEncoding,
 }
 
 impl SemanticContext {
@@ -35,44 +62,83 @@ impl SemanticContext {
 
         // Create a new instance
         let mut context = Self {
+            mapped: None,
             data_points: Vec::new(),
+            added_metadata: Vec::new(),
             index: None,
             data_path: data_path.clone(),
+            vector_encoding: VectorEncoding::default(),
         };
 
-        // Load data points if the file exists
-        if data_path.exists() {
-            let file = File::open(&data_path)?;
-            let reader = BufReader::new(file);
-            context.data_points = serde_json::from_reader(reader)?;
+        if let Some(mapped) = MappedPoints::open(&context.dir())? {
+            context.vector_encoding = mapped.encoding();
+            context.mapped = Some(mapped);
+            context.index =
+                VectorIndex::load(&context.dir(), INDEX_BASENAME)?.filter(|index| index.len() == context.len());
+        } else if data_path.exists() {
+            context.migrate_json()?;
         }
 
-        // If we have data points, rebuild the index
-        if !context.data_points.is_empty() {
+        // Rebuild the index if it wasn't saved or doesn't match the data points
+        if context.index.is_none() && !context.is_empty() {
             context.rebuild_index()?;
         }
 
         Ok(context)
     }
 
-    /// Save data points to disk
-    pub fn save(&self) -> Result<()> {
-        // Save the data points as JSON
-        let file = File::create(&self.data_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, &self.data_points)?;
+    /// Convert a context saved as JSON to the binary format
+    fn migrate_json(&mut self) -> Result<()> {
+        let file = File::open(&self.data_path)?;
+        let reader = BufReader::new(file);
+        self.set_added_points(serde_json::from_reader(reader)?);
+        self.rebuild_index()?;
+        self.save()?;
 
+        info!(
+            "Converted {} data points in {} to the binary format",
+            self.data_points.len(),
+            self.data_path.display()
+        );
+        if let Err(e) = fs::remove_file(&self.data_path) {
+            warn!("Failed to remove {}: {}", self.data_path.display(), e);
+        }
+        if let Some(mapped) = MappedPoints::open(&self.dir())? {
+            self.mapped = Some(mapped);
+            self.set_added_points(Vec::new());
+        }
         Ok(())
     }
 
+    /// Set how vectors are encoded when the context is saved
+    pub fn set_vector_encoding(&mut self, encoding: VectorEncoding) {
+        self.vector_encoding = encoding;
+    }
+
+    /// Save data points and the vector index to disk
+    pub fn save(&self) -> Result<()> {
+        let dir = self.dir();
+        point_store::write_points(
+            &dir,
+            self.vector_encoding,
+            self.len(),
+            (0..self.len()).map(|id| self.point(id)),
+        )?;
+
+        match &self.index {
+            Some(index) if !index.is_empty() => index.save(&dir, INDEX_BASENAME),
+            _ => VectorIndex::remove_saved(&dir, INDEX_BASENAME),
+        }
+    }
+
     /// Rebuild the index from the current data points
     pub fn rebuild_index(&mut self) -> Result<()> {
         // Create a new index with the current data points
-        let index = VectorIndex::new(self.data_points.len().max(100));
+        let index = VectorIndex::new(self.len().max(100));
 
         // Add all data points to the index
-        for (i, point) in self.data_points.iter().enumerate() {
-            index.insert(&point.vector, i);
+        for id in 0..self.len() {
+            index.insert(&self.vector(id), id);
         }
 
         // Set the new index
@@ -91,9 +157,14 @@ impl SemanticContext {
         }
 
         // Add the new points to our data store
-        let start_idx = self.data_points.len();
+        let start_idx = self.len();
+        self.added_metadata.extend(
+            data_points
+                .iter()
+                .map(|point| PointMetadata::from_payload(&point.payload)),
+        );
         self.data_points.extend(data_points);
-        let end_idx = self.data_points.len();
+        let end_idx = self.len();
 
         // Update the index
         self.update_index_by_range(start_idx, end_idx)?;
@@ -101,12 +172,73 @@ impl SemanticContext {
         Ok(count)
     }
 
+    /// Remove all data points
+    pub fn clear_data_points(&mut self) -> Result<()> {
+        self.mapped = None;
+        self.set_added_points(Vec::new());
+        self.rebuild_index()
+    }
+
+    /// Remove the data points of the given source files
+    ///
+    /// Remaining data points are renumbered so that ids stay equal to their position, and the
+    /// index is rebuilt.
+    ///
+    /// # Returns
+    ///
+    /// The number of data points removed
+    pub fn remove_data_points_for_paths(&mut self, paths: &HashSet<String>) -> Result<usize> {
+        let is_stale = |point: &DataPoint| {
+            point
+                .payload
+                .get("path")
+                .and_then(|path| path.as_str())
+                .is_some_and(|path| paths.contains(path))
+        };
+        let before = self.len();
+        let mut kept = Vec::with_capacity(before);
+        for id in 0..before {
+            let point = self.point(id)?;
+            if !is_stale(&point) {
+                kept.push(point.into_owned());
+            }
+        }
+        let removed = before - kept.len();
+
+        if removed > 0 {
+            for (id, point) in kept.iter_mut().enumerate() {
+                point.id = id;
+            }
+            self.mapped = None;
+            self.set_added_points(kept);
+            self.rebuild_index()?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Replace the source file path of every data point, e.g. after the files were moved
+    ///
+    /// Paths for which `rewrite` returns `None` are kept. Call [`Self::save`] to persist them.
+    pub fn rewrite_paths(&mut self, rewrite: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let mut points = Vec::with_capacity(self.len());
+        for id in 0..self.len() {
+            let mut point = self.point(id)?.into_owned();
+            if let Some(path) = point.payload.get("path").and_then(Value::as_str).and_then(&rewrite) {
+                point.payload.insert("path".to_string(), Value::String(path));
+            }
+            points.push(point);
+        }
+        self.mapped = None;
+        self.set_added_points(points);
+        Ok(())
+    }
+
     /// Update the index with data points in a specific range
     pub fn update_index_by_range(&mut self, start_idx: usize, end_idx: usize) -> Result<()> {
         // If we don't have an index yet, or if the index is small and we're adding many points,
         // it might be more efficient to rebuild from scratch
-        if self.index.is_none() || (self.data_points.len() < 1000 && (end_idx - start_idx) > self.data_points.len() / 2)
-        {
+        if self.index.is_none() || (self.len() < 1000 && (end_idx - start_idx) > self.len() / 2) {
             return self.rebuild_index();
         }
 
@@ -115,7 +247,7 @@ impl SemanticContext {
 
         // Add only the points in the specified range to the index
         for i in start_idx..end_idx {
-            index.insert(&self.data_points[i].vector, i);
+            index.insert(&self.vector(i), i);
         }
 
         Ok(())
@@ -134,17 +266,122 @@ impl SemanticContext {
         // Convert the results to our SearchResult type
         let search_results = results
             .into_iter()
-            .map(|(id, distance)| {
-                let point = self.data_points[id].clone();
-                SearchResult::new(point, distance)
+            .filter_map(|(id, distance)| {
+                let point = self.get_data_point(id)?;
+                Some(SearchResult::new(point, distance))
             })
             .collect();
 
         Ok(search_results)
     }
 
-    /// Get the data points for serialization
-    pub fn get_data_points(&self) -> &Vec<DataPoint> {
-        &self.data_points
+    /// Search for similar items among the data points that pass a filter
+    pub fn search_filtered(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+        matcher: &PayloadMatcher,
+    ) -> Result<Vec<SearchResult>> {
+        let index = match &self.index {
+            Some(idx) => idx,
+            None => return Ok(Vec::new()),
+        };
+
+        let mapped_metadata = self.mapped.as_ref().map_or(&[][..], MappedPoints::metadata);
+        let allowed_ids: Vec<usize> = mapped_metadata
+            .iter()
+            .chain(&self.added_metadata)
+            .enumerate()
+            .filter(|(_, metadata)| matcher.matches_metadata(metadata))
+            .map(|(id, _)| id)
+            .collect();
+        if allowed_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut results = index.search_filtered(query_vector, limit, 100, &allowed_ids);
+        // The graph may not lead to every allowed data point, so if the search comes up short the
+        // allowed data points are compared one by one
+        if results.len() < limit.min(allowed_ids.len()) {
+            results = allowed_ids
+                .iter()
+                .map(|&id| (id, VectorIndex::distance(query_vector, &self.vector(id))))
+                .collect();
+            results.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+            results.truncate(limit);
+        }
+
+        Ok(results
+            .into_iter()
+            .filter_map(|(id, distance)| {
+                let point = self.get_data_point(id)?;
+                Some(SearchResult::new(point, distance))
+            })
+            .collect())
+    }
+
+    /// Number of data points
+    pub fn len(&self) -> usize {
+        self.mapped_len() + self.data_points.len()
+    }
+
+    /// Whether the context has no data points
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a data point by id, decoding it if it was loaded from disk
+    pub fn get_data_point(&self, id: usize) -> Option<DataPoint> {
+        if id >= self.len() {
+            return None;
+        }
+        match self.point(id) {
+            Ok(point) => Some(point.into_owned()),
+            Err(e) => {
+                warn!("Failed to read data point {} of {}: {}", id, self.dir().display(), e);
+                None
+            },
+        }
+    }
+
+    /// Get all data points, decoding the ones loaded from disk
+    pub fn get_data_points(&self) -> Vec<DataPoint> {
+        (0..self.len()).filter_map(|id| self.get_data_point(id)).collect()
+    }
+
+    /// Replace the data points held in memory
+    fn set_added_points(&mut self, points: Vec<DataPoint>) {
+        self.added_metadata = points
+            .iter()
+            .map(|point| PointMetadata::from_payload(&point.payload))
+            .collect();
+        self.data_points = points;
+    }
+
+    fn mapped_len(&self) -> usize {
+        self.mapped.as_ref().map_or(0, MappedPoints::len)
+    }
+
+    fn point(&self, id: usize) -> Result<Cow<'_, DataPoint>> {
+        match (&self.mapped, id.checked_sub(self.mapped_len())) {
+            (_, Some(added)) => Ok(Cow::Borrowed(&self.data_points[added])),
+            (Some(mapped), None) => mapped.point(id).map(Cow::Owned),
+            (None, None) => unreachable!("ids below the mapped length require mapped points"),
+        }
+    }
+
+    fn vector(&self, id: usize) -> Cow<'_, [f32]> {
+        match (&self.mapped, id.checked_sub(self.mapped_len())) {
+            (_, Some(added)) => Cow::Borrowed(&self.data_points[added].vector),
+            (Some(mapped), None) => Cow::Owned(mapped.vector(id)),
+            (None, None) => unreachable!("ids below the mapped length require mapped points"),
+        }
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.data_path
+            .parent()
+            .map(|parent| parent.to_path_buf())
+            .unwrap_or_default()
     }
 }
@@ -1,10 +1,13 @@
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+use crate::embedding::CandleReranker;
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
 use crate::embedding::CandleTextEmbedder;
 use crate::embedding::MockTextEmbedder; // Used for Fast type since BM25 doesn't need embeddings
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
 use crate::embedding::ModelType;
 use crate::embedding::{
     EmbeddingType,
+    Reranker,
     TextEmbedderTrait,
 };
 use crate::error::Result;
@@ -23,7 +26,9 @@ pub fn create_embedder(embedding_type: EmbeddingType) -> Result<Box<dyn TextEmbe
     let embedder: Box<dyn TextEmbedderTrait> = match embedding_type {
         EmbeddingType::Fast => Box::new(MockTextEmbedder::new(384)), // BM25 doesn't use embeddings
         #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
-        EmbeddingType::Best => Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?),
+        EmbeddingType::Best | EmbeddingType::Hybrid => {
+            Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?)
+        },
         #[cfg(test)]
         EmbeddingType::Mock => Box::new(MockTextEmbedder::new(384)),
     };
@@ -46,10 +51,35 @@ pub fn create_embedder(embedding_type: EmbeddingType) -> Result<Box<dyn TextEmbe
     let embedder: Box<dyn TextEmbedderTrait> = match embedding_type {
         EmbeddingType::Fast => Box::new(MockTextEmbedder::new(384)), // BM25 doesn't use embeddings
         #[cfg(not(target_arch = "aarch64"))]
-        EmbeddingType::Best => Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?),
+        EmbeddingType::Best | EmbeddingType::Hybrid => {
+            Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?)
+        },
         #[cfg(test)]
         EmbeddingType::Mock => Box::new(MockTextEmbedder::new(384)),
     };
 
     Ok(embedder)
 }
+
+/// Creates the cross-encoder reranker, whose model must already be downloaded
+///
+/// # Returns
+///
+/// A reranker instance
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+pub fn create_reranker() -> Result<Box<dyn Reranker>> {
+    Ok(Box::new(CandleReranker::new()?))
+}
+
+/// Creates the cross-encoder reranker
+/// (Linux ARM version, where Candle models aren't available)
+///
+/// # Returns
+///
+/// An error, as reranking is not supported
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+pub fn create_reranker() -> Result<Box<dyn Reranker>> {
+    Err(crate::error::SemanticSearchError::EmbeddingError(
+        "Reranking is not supported on this platform".to_string(),
+    ))
+}
@@ -97,7 +97,16 @@ impl HostedModelClient {
         }
 
         debug!("Downloading hosted model: {}", model_config.name);
-        self.download_model(model_config, target_dir, progress_callback).await
+        self.download_model(model_config, target_dir, progress_callback).await?;
+
+        // Never leave files that weren't allowlisted for the model to be loaded
+        if !self.is_model_valid(model_config, target_dir).await? {
+            return Err(anyhow::anyhow!(
+                "Downloaded files for model '{}' don't match the allowlisted hashes",
+                model_config.name
+            ));
+        }
+        Ok(())
     }
 
     /// Download model from hosted CDN (asynchronous) with optional progress
@@ -273,8 +282,8 @@ impl HostedModelClient {
         let model_path = target_dir.join(&model_config.model_file);
         let tokenizer_path = target_dir.join(&model_config.tokenizer_file);
 
-        let model_valid = self.validator.validate_file(&model_path);
-        let tokenizer_valid = self.validator.validate_file(&tokenizer_path);
+        let model_valid = self.validator.validate_file(&model_config.name, &model_path);
+        let tokenizer_valid = self.validator.validate_file(&model_config.name, &tokenizer_path);
 
         let valid = model_valid && tokenizer_valid;
 
@@ -291,8 +300,8 @@ impl HostedModelClient {
         let model_path = target_dir.join(&model_config.model_file);
         let tokenizer_path = target_dir.join(&model_config.tokenizer_file);
 
-        let model_valid = self.validator.validate_file(&model_path);
-        let tokenizer_valid = self.validator.validate_file(&tokenizer_path);
+        let model_valid = self.validator.validate_file(&model_config.name, &model_path);
+        let tokenizer_valid = self.validator.validate_file(&model_config.name, &tokenizer_path);
 
         let valid = model_valid && tokenizer_valid;
 
@@ -523,7 +523,7 @@ impl SemanticSearchClient {
         semantic_context: SemanticContext,
     ) -> Result<()> {
         // Notify progress: Finalizing (90% progress point)
-        let item_count = semantic_context.get_data_points().len();
+        let item_count = semantic_context.len();
 
         // Save to disk if persistent
         if persistent {
@@ -864,8 +864,9 @@ impl SemanticSearchClient {
             .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to acquire lock on context: {}", e)))?;
 
         // Save the data to the persistent directory
-        let data_path = persistent_dir.join("data.json");
-        utils::save_json_to_file(&data_path, context_guard.get_data_points())?;
+        let mut persistent_context = SemanticContext::new(persistent_dir.join("data.json"))?;
+        persistent_context.add_data_points(context_guard.get_data_points())?;
+        persistent_context.save()?;
 
         // Create the context metadata
         let context_meta = KnowledgeContext::new(
@@ -875,7 +876,7 @@ impl SemanticSearchClient {
             true,
             None,
             (vec![], vec![]),
-            context_guard.get_data_points().len(),
+            context_guard.len(),
             self.config.embedding_type, // Use client default
         );
 
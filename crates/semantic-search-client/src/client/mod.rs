@@ -16,6 +16,8 @@ pub mod operation;
 pub mod embedder_factory;
 /// Utility functions
 pub mod utils;
+/// Filesystem watching for contexts
+pub mod watcher;
 
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
 /// Hosted model client for downloading models
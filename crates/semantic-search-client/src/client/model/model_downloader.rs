@@ -14,7 +14,7 @@ impl ModelDownloader {
     pub async fn ensure_models_downloaded(embedding_type: &EmbeddingType) -> Result<()> {
         match embedding_type {
             #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
-            EmbeddingType::Best => {
+            EmbeddingType::Best | EmbeddingType::Hybrid => {
                 Self::download_best_model().await?;
             },
             EmbeddingType::Fast => {
@@ -28,12 +28,31 @@ impl ModelDownloader {
         Ok(())
     }
 
+    /// Ensure the cross-encoder model used to rerank search results is downloaded
+    pub async fn ensure_reranker_downloaded() -> Result<()> {
+        #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+        {
+            use crate::embedding::RerankerModelType;
+
+            Self::download_model(RerankerModelType::default().get_config()).await
+        }
+        #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+        Err(SemanticSearchError::EmbeddingError(
+            "Reranking is not supported on this platform".to_string(),
+        ))
+    }
+
     #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
     async fn download_best_model() -> Result<()> {
-        use crate::client::hosted_model_client::HostedModelClient;
         use crate::embedding::ModelType;
 
-        let model_config = ModelType::default().get_config();
+        Self::download_model(ModelType::default().get_config()).await
+    }
+
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    async fn download_model(model_config: crate::embedding::ModelConfig) -> Result<()> {
+        use crate::client::hosted_model_client::HostedModelClient;
+
         let (model_path, _tokenizer_path) = model_config.get_local_paths();
 
         // Create model directory if it doesn't exist
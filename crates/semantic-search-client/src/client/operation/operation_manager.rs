@@ -21,6 +21,9 @@ use crate::types::*;
 
 const MAX_CONCURRENT_OPERATIONS: usize = 3;
 
+/// How long finished operations are still listed in the status
+const FINISHED_OPERATION_RETENTION: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 /// Operation manager for tracking operations
 pub struct OperationManager {
@@ -59,18 +62,38 @@ impl OperationManager {
             progress: Arc::new(Mutex::new(ProgressInfo::new())),
             cancel_token,
             task_handle: None,
+            state: OperationState::Running,
+            finished_at: None,
         };
 
         let mut operations = self.active_operations.write().await;
+        Self::prune_finished(&mut operations);
         operations.insert(operation_id, handle);
     }
 
+    /// Remove operations that finished more than [FINISHED_OPERATION_RETENTION] ago
+    pub(crate) fn prune_finished(operations: &mut HashMap<Uuid, OperationHandle>) {
+        let now = SystemTime::now();
+        operations.retain(|_, handle| {
+            handle.finished_at.is_none_or(|finished_at| {
+                now.duration_since(finished_at).unwrap_or_default() < FINISHED_OPERATION_RETENTION
+            })
+        });
+    }
+
     /// Cancel operation
     pub async fn cancel_operation(&self, operation_id: Uuid) -> Result<String> {
         let mut operations = self.active_operations.write().await;
 
         if let Some(handle) = operations.get_mut(&operation_id) {
+            if handle.state.is_finished() {
+                return Ok(format!(
+                    "Operation {} has already finished",
+                    &operation_id.to_string()[..8]
+                ));
+            }
             handle.cancel_token.cancel();
+            handle.finish(OperationState::Cancelled);
 
             if let Some(task_handle) = &handle.task_handle {
                 task_handle.abort();
@@ -83,7 +106,9 @@ impl OperationManager {
                 progress.message = "Operation cancelled by user".to_string();
             }
 
-            Ok(format!("✅ Cancelled operation: {} (ID: {})", op_type, id_display))
+            let message = format!("✅ Cancelled operation: {} (ID: {})", op_type, id_display);
+            Self::prune_finished(&mut operations);
+            Ok(message)
         } else {
             Err(SemanticSearchError::OperationFailed(format!(
                 "Operation not found: {}",
@@ -96,13 +121,14 @@ impl OperationManager {
     pub async fn cancel_most_recent_operation(&self) -> Result<String> {
         let operations = self.active_operations.read().await;
 
-        if operations.is_empty() {
+        if operations.values().all(|handle| handle.state.is_finished()) {
             return Ok("No active operations to cancel".to_string());
         }
 
         // Find the most recent operation (highest started_at time)
         let most_recent = operations
             .iter()
+            .filter(|(_, handle)| !handle.state.is_finished())
             .max_by_key(|(_, handle)| handle.started_at)
             .map(|(id, _)| *id);
 
@@ -120,14 +146,15 @@ impl OperationManager {
     /// Cancel all operations
     pub async fn cancel_all_operations(&self) -> Result<String> {
         let mut operations = self.active_operations.write().await;
-        let count = operations.len();
+        let count = operations.values().filter(|handle| !handle.state.is_finished()).count();
 
         if count == 0 {
             return Ok("No active operations to cancel".to_string());
         }
 
-        for handle in operations.values_mut() {
+        for handle in operations.values_mut().filter(|handle| !handle.state.is_finished()) {
             handle.cancel_token.cancel();
+            handle.finish(OperationState::Cancelled);
 
             if let Some(task_handle) = &handle.task_handle {
                 task_handle.abort();
@@ -139,6 +166,7 @@ impl OperationManager {
                 progress.total = 0;
             }
         }
+        Self::prune_finished(&mut operations);
 
         Ok(format!("✅ Cancelled {} active operations", count))
     }
@@ -152,11 +180,12 @@ impl OperationManager {
             .map(|(id, _)| *id)
     }
 
-    /// List operation IDs
+    /// List the IDs of running operations
     pub async fn list_operation_ids(&self) -> Vec<String> {
         let operations = self.active_operations.read().await;
         operations
             .iter()
+            .filter(|(_, handle)| !handle.state.is_finished())
             .map(|(id, _)| format!("{} (short: {})", id, &id.to_string()[..8]))
             .collect()
     }
@@ -166,23 +195,8 @@ impl OperationManager {
         let mut operations = self.active_operations.write().await;
         let contexts = context_manager.get_contexts_ref().read().await;
 
-        // Clean up old cancelled operations
-        let now = SystemTime::now();
-        let cleanup_threshold = Duration::from_secs(30);
-
-        operations.retain(|_, handle| {
-            if let Ok(progress) = handle.progress.try_lock() {
-                let is_cancelled = progress.message.to_lowercase().contains("cancelled");
-                let is_failed = progress.message.to_lowercase().contains("failed");
-                if is_cancelled || is_failed {
-                    now.duration_since(handle.started_at).unwrap_or_default() < cleanup_threshold
-                } else {
-                    true
-                }
-            } else {
-                true
-            }
-        });
+        // Clean up old finished operations
+        Self::prune_finished(&mut operations);
 
         // Collect context information
         let total_contexts = contexts.len();
@@ -196,16 +210,12 @@ impl OperationManager {
 
         for (id, handle) in operations.iter() {
             if let Ok(progress) = handle.progress.try_lock() {
-                let is_failed = progress.message.to_lowercase().contains("failed");
-                let is_cancelled = progress.message.to_lowercase().contains("cancelled");
-                let is_waiting = Self::is_operation_waiting(&progress);
-
-                if is_cancelled {
-                    // Don't count cancelled operations
-                } else if is_failed || is_waiting {
-                    waiting_count += 1;
-                } else {
-                    active_count += 1;
+                let is_waiting = !handle.state.is_finished() && Self::is_operation_waiting(&progress);
+
+                match handle.state {
+                    OperationState::Running if is_waiting => waiting_count += 1,
+                    OperationState::Running => active_count += 1,
+                    OperationState::Completed | OperationState::Failed | OperationState::Cancelled => {},
                 }
 
                 let operation_status = OperationStatus {
@@ -216,8 +226,7 @@ impl OperationManager {
                     current: progress.current,
                     total: progress.total,
                     message: progress.message.clone(),
-                    is_cancelled,
-                    is_failed,
+                    state: handle.state,
                     is_waiting,
                     eta: progress.calculate_eta(),
                 };
@@ -252,3 +261,75 @@ impl OperationManager {
         &self.active_operations
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_operation_states() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let context_manager = ContextManager::new(temp_dir.path(), 0.5).await.unwrap();
+        let manager = OperationManager::new();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        manager
+            .register_operation(first, OperationType::Clearing, CancellationToken::new())
+            .await;
+        manager
+            .register_operation(second, OperationType::Clearing, CancellationToken::new())
+            .await;
+
+        // A failure recorded after a cancellation doesn't replace it, whatever the messages say
+        manager.cancel_operation(first).await.unwrap();
+        let mut operations = manager.get_active_operations_ref().write().await;
+        operations.get_mut(&first).unwrap().finish(OperationState::Failed);
+        let handle = operations.get_mut(&second).unwrap();
+        handle.finish(OperationState::Completed);
+        handle.progress.lock().await.message = "Indexing failed files: 0".to_string();
+        drop(operations);
+
+        let status = manager.get_status_data(&context_manager).await.unwrap();
+        let state = |id: Uuid| {
+            status
+                .operations
+                .iter()
+                .find(|op| op.id == id.to_string())
+                .unwrap()
+                .state
+        };
+        assert_eq!(state(first), OperationState::Cancelled);
+        assert_eq!(state(second), OperationState::Completed);
+        assert_eq!(status.active_count, 0);
+
+        // Finished operations can't be cancelled
+        assert!(manager.list_operation_ids().await.is_empty());
+        assert_eq!(
+            manager.cancel_most_recent_operation().await.unwrap(),
+            "No active operations to cancel"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_finished_operations() {
+        let manager = OperationManager::new();
+        let (old, recent) = (Uuid::new_v4(), Uuid::new_v4());
+        for id in [old, recent] {
+            manager
+                .register_operation(id, OperationType::Clearing, CancellationToken::new())
+                .await;
+        }
+        let mut operations = manager.get_active_operations_ref().write().await;
+        // Operations are kept for a while after they finish, however long they ran
+        let handle = operations.get_mut(&old).unwrap();
+        handle.started_at = SystemTime::now() - Duration::from_secs(3600);
+        handle.finish(OperationState::Completed);
+        handle.finished_at = Some(SystemTime::now() - FINISHED_OPERATION_RETENTION);
+        operations.get_mut(&recent).unwrap().started_at = SystemTime::now() - Duration::from_secs(3600);
+        drop(operations);
+
+        manager.cancel_operation(recent).await.unwrap();
+        let operations = manager.get_active_operations_ref().read().await;
+        assert!(!operations.contains_key(&old));
+        assert!(operations.contains_key(&recent));
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
+
+use notify::{
+    Event,
+    EventKind,
+    RecommendedWatcher,
+    RecursiveMode,
+    Watcher,
+};
+use tokio::sync::{
+    mpsc,
+    oneshot,
+};
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{
+    debug,
+    warn,
+};
+use uuid::Uuid;
+
+use super::context::ContextManager;
+use super::operation::OperationManager;
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+use crate::pattern_filter::PatternFilter;
+use crate::types::*;
+
+/// How long a watched path must stay quiet before its context is updated
+pub const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Keeps contexts up to date with changes to their source paths
+///
+/// Each watched context gets a filesystem watcher and a task that queues an incremental
+/// [`IndexingJob::Update`] once changes settle for [`WATCH_DEBOUNCE`]. Only one update per context
+/// is queued at a time; changes made while it runs trigger another update afterwards.
+///
+/// Changes to files the context doesn't index, to hidden files and directories such as `.git`,
+/// and to the knowledge base directory itself are ignored.
+#[derive(Clone)]
+pub struct ContextWatcher {
+    watches: Arc<Mutex<HashMap<ContextId, WatchHandle>>>,
+    job_tx: mpsc::WeakUnboundedSender<IndexingJob>,
+    context_manager: ContextManager,
+    operation_manager: OperationManager,
+    /// Where indexes are stored, so that saving them doesn't trigger another update
+    base_dir: PathBuf,
+}
+
+/// Decides which changes to a watched path are relevant to its context
+struct EventFilter {
+    root: PathBuf,
+    base_dir: PathBuf,
+    patterns: PatternFilter,
+}
+
+impl EventFilter {
+    fn is_relevant(&self, event: &Event) -> bool {
+        // Reads don't change the index, and would retrigger on the update's own reads
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+        // Events without paths, e.g. when changes were dropped, need a full comparison
+        event.paths.is_empty() || event.paths.iter().any(|path| self.is_relevant_path(path))
+    }
+
+    fn is_relevant_path(&self, path: &Path) -> bool {
+        if path.starts_with(&self.base_dir) {
+            return false;
+        }
+        let hidden = path.strip_prefix(&self.root).is_ok_and(|relative| {
+            relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+        });
+        !hidden && self.patterns.should_include(path)
+    }
+}
+
+/// Filesystem watcher and debounce task of a watched context, stopped when dropped
+struct WatchHandle {
+    _watcher: RecommendedWatcher,
+    task: AbortHandle,
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ContextWatcher {
+    /// Create a watcher that queues updates on `job_tx`
+    ///
+    /// The sender is held weakly so that watches don't keep the background worker running after
+    /// the client is dropped.
+    pub fn new(
+        job_tx: &mpsc::UnboundedSender<IndexingJob>,
+        context_manager: ContextManager,
+        operation_manager: OperationManager,
+        base_dir: &Path,
+    ) -> Self {
+        Self {
+            watches: Arc::new(Mutex::new(HashMap::new())),
+            job_tx: job_tx.downgrade(),
+            context_manager,
+            operation_manager,
+            // Event paths are canonical
+            base_dir: base_dir.canonicalize().unwrap_or_else(|_| base_dir.to_path_buf()),
+        }
+    }
+
+    /// Start watching `path` for changes to the files of a context matching its patterns,
+    /// replacing any previous watch
+    pub fn watch(
+        &self,
+        context_id: &str,
+        path: &Path,
+        include_patterns: &[String],
+        exclude_patterns: &[String],
+    ) -> Result<()> {
+        let filter = EventFilter {
+            root: path.canonicalize().unwrap_or_else(|_| path.to_path_buf()),
+            base_dir: self.base_dir.clone(),
+            patterns: PatternFilter::new(include_patterns, exclude_patterns)
+                .map_err(SemanticSearchError::InvalidArgument)?,
+        };
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) if filter.is_relevant(&event) => {
+                let _ = event_tx.send(());
+            },
+            Ok(_) => {},
+            Err(e) => warn!("File watcher error: {}", e),
+        })
+        .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to create file watcher: {}", e)))?;
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to watch {}: {}", path.display(), e)))?;
+
+        let task = tokio::spawn(debounce_updates(
+            context_id.to_string(),
+            event_rx,
+            self.job_tx.clone(),
+            self.context_manager.clone(),
+            self.operation_manager.clone(),
+        ));
+
+        debug!("Watching {} for context {}", path.display(), context_id);
+        self.lock().insert(context_id.to_string(), WatchHandle {
+            _watcher: watcher,
+            task: task.abort_handle(),
+        });
+        Ok(())
+    }
+
+    /// Stop watching the source path of a context
+    pub fn unwatch(&self, context_id: &str) {
+        if self.lock().remove(context_id).is_some() {
+            debug!("Stopped watching context {}", context_id);
+        }
+    }
+
+    /// Stop watching all contexts
+    pub fn unwatch_all(&self) {
+        self.lock().clear();
+    }
+
+    /// Whether the source path of a context is being watched
+    pub fn is_watching(&self, context_id: &str) -> bool {
+        self.lock().contains_key(context_id)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ContextId, WatchHandle>> {
+        self.watches.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Queue an incremental update of a context, registering it as an operation
+pub(crate) async fn enqueue_update(
+    job_tx: &mpsc::UnboundedSender<IndexingJob>,
+    operation_manager: &OperationManager,
+    context: &KnowledgeContext,
+    path: PathBuf,
+    done: Option<oneshot::Sender<()>>,
+) -> Result<(Uuid, CancellationToken)> {
+    let operation_id = Uuid::new_v4();
+    let cancel_token = CancellationToken::new();
+
+    operation_manager
+        .register_operation(
+            operation_id,
+            OperationType::Updating {
+                name: context.name.clone(),
+                path: path.to_string_lossy().to_string(),
+            },
+            cancel_token.clone(),
+        )
+        .await;
+
+    let job = IndexingJob::Update {
+        id: operation_id,
+        cancel: cancel_token.clone(),
+        context_id: context.id.clone(),
+        path,
+        done,
+    };
+
+    job_tx
+        .send(job)
+        .map_err(|_send_error| SemanticSearchError::OperationFailed("Background worker unavailable".to_string()))?;
+
+    Ok((operation_id, cancel_token))
+}
+
+async fn debounce_updates(
+    context_id: ContextId,
+    mut events: mpsc::UnboundedReceiver<()>,
+    job_tx: mpsc::WeakUnboundedSender<IndexingJob>,
+    context_manager: ContextManager,
+    operation_manager: OperationManager,
+) {
+    while events.recv().await.is_some() {
+        // Wait for a quiet period so that a burst of writes triggers a single update
+        loop {
+            match tokio::time::timeout(WATCH_DEBOUNCE, events.recv()).await {
+                Ok(Some(())) => {},
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+
+        let Some(job_tx) = job_tx.upgrade() else {
+            return;
+        };
+        let Some(context) = context_manager
+            .get_contexts_ref()
+            .read()
+            .await
+            .get(&context_id)
+            .cloned()
+        else {
+            return;
+        };
+        let Some(path) = context.source_path.as_ref().map(PathBuf::from) else {
+            return;
+        };
+
+        let (done_tx, done_rx) = oneshot::channel();
+        match enqueue_update(&job_tx, &operation_manager, &context, path, Some(done_tx)).await {
+            Ok((operation_id, _)) => debug!("Queued update {} for watched context {}", operation_id, context_id),
+            Err(e) => {
+                warn!("Failed to queue update for watched context {}: {}", context_id, e);
+                return;
+            },
+        }
+        drop(job_tx);
+
+        // Changes made during the update are picked up by the next iteration
+        let _ = done_rx.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{
+        AccessKind,
+        ModifyKind,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_event_filter() {
+        let filter = EventFilter {
+            root: PathBuf::from("/project"),
+            base_dir: PathBuf::from("/project/kb"),
+            patterns: PatternFilter::new(&[], &["**/target/**".to_string()]).unwrap(),
+        };
+        let modified = |path: &str| Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from(path));
+
+        assert!(filter.is_relevant(&modified("/project/src/main.rs")));
+        assert!(filter.is_relevant(&Event::new(EventKind::Any)));
+        assert!(!filter.is_relevant(&modified("/project/target/debug/main")));
+        assert!(!filter.is_relevant(&modified("/project/.git/index")));
+        assert!(!filter.is_relevant(&modified("/project/kb/contexts.json")));
+        assert!(!filter.is_relevant(
+            &Event::new(EventKind::Access(AccessKind::Any)).add_path(PathBuf::from("/project/src/main.rs"))
+        ));
+    }
+}
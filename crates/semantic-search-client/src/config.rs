@@ -16,6 +16,7 @@ use serde::{
     Serialize,
 };
 
+use crate::client::context::VectorEncoding;
 use crate::embedding::EmbeddingType;
 
 /// Main configuration structure for the semantic search client.
@@ -47,6 +48,32 @@ pub struct SemanticSearchConfig {
 
     /// Embedding engine type to use
     pub embedding_type: EmbeddingType,
+
+    /// Weight of the vector ranking against the BM25 ranking when fusing hybrid results, from 0.0
+    /// (BM25 only) to 1.0 (vectors only)
+    #[serde(default = "default_hybrid_weight")]
+    pub hybrid_weight: f32,
+
+    /// How vectors of new semantic contexts are stored on disk
+    #[serde(default)]
+    pub vector_encoding: VectorEncoding,
+
+    /// Whether to rerank the results of every context with a cross-encoder, rather than only those
+    /// of contexts added with reranking
+    #[serde(default)]
+    pub rerank: bool,
+
+    /// Number of candidates retrieved per context for the cross-encoder to rerank
+    #[serde(default = "default_rerank_candidates")]
+    pub rerank_candidates: usize,
+}
+
+fn default_hybrid_weight() -> f32 {
+    0.5
+}
+
+fn default_rerank_candidates() -> usize {
+    20
 }
 
 impl SemanticSearchConfig {
@@ -93,6 +120,10 @@ impl Default for SemanticSearchConfig {
             max_files: 10000, // Default limit of 10000 files
             hosted_models_base_url: "https://desktop-release.q.us-east-1.amazonaws.com/models".to_string(),
             embedding_type: EmbeddingType::default(),
+            hybrid_weight: default_hybrid_weight(),
+            vector_encoding: VectorEncoding::default(),
+            rerank: false,
+            rerank_candidates: default_rerank_candidates(),
         }
     }
 }
@@ -341,6 +372,10 @@ mod tests {
             max_files: 10000,
             hosted_models_base_url: "http://test.example.com/models".to_string(),
             embedding_type: EmbeddingType::default(),
+            hybrid_weight: 0.5,
+            vector_encoding: VectorEncoding::default(),
+            rerank: false,
+            rerank_candidates: 20,
         };
 
         // Update the config
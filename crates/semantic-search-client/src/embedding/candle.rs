@@ -254,7 +254,7 @@ impl CandleTextEmbedder {
 }
 
 /// Initialize thread pool for parallel processing
-fn initialize_thread_pool() -> Result<usize> {
+pub(super) fn initialize_thread_pool() -> Result<usize> {
     // Automatically detect available parallelism
     let threads = match available_parallelism() {
         Ok(n) => n.get(),
@@ -275,7 +275,7 @@ fn initialize_thread_pool() -> Result<usize> {
 }
 
 /// Load tokenizer from file
-fn load_tokenizer(tokenizer_path: &Path) -> Result<Tokenizer> {
+pub(super) fn load_tokenizer(tokenizer_path: &Path) -> Result<Tokenizer> {
     match Tokenizer::from_file(tokenizer_path) {
         Ok(t) => Ok(t),
         Err(e) => {
@@ -289,7 +289,7 @@ fn load_tokenizer(tokenizer_path: &Path) -> Result<Tokenizer> {
 }
 
 /// Get the best available device for inference
-fn get_best_available_device() -> Device {
+pub(super) fn get_best_available_device() -> Device {
     // Always use CPU for embedding to avoid hardware acceleration issues
     info!("Using CPU for text embedding (hardware acceleration disabled)");
     Device::Cpu
@@ -325,7 +325,7 @@ fn load_model(model_path: &Path, config: &ModelConfig, device: &Device) -> Resul
 }
 
 /// Prepare tokenizer with padding configuration
-fn prepare_tokenizer(tokenizer: &Tokenizer) -> Result<Tokenizer> {
+pub(super) fn prepare_tokenizer(tokenizer: &Tokenizer) -> Result<Tokenizer> {
     let mut tokenizer = tokenizer.clone();
     if let Some(pp) = tokenizer.get_padding_mut() {
         pp.strategy = tokenizers::PaddingStrategy::BatchLongest;
@@ -113,6 +113,49 @@ impl ModelType {
     }
 }
 
+/// Type of cross-encoder model to use for reranking search results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RerankerModelType {
+    /// ms-marco-MiniLM-L-6-v2 cross-encoder, trained on MS MARCO passage ranking
+    #[default]
+    MsMarcoMiniLML6V2,
+}
+
+impl RerankerModelType {
+    /// Get the configuration for this model type
+    pub fn get_config(&self) -> ModelConfig {
+        match self {
+            Self::MsMarcoMiniLML6V2 => ModelConfig {
+                name: "ms-marco-MiniLM-L-6-v2".to_string(),
+                repo_path: "cross-encoder/ms-marco-MiniLM-L-6-v2".to_string(),
+                model_file: "model.safetensors".to_string(),
+                tokenizer_file: "tokenizer.json".to_string(),
+                #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+                config: BertConfig {
+                    vocab_size: 30522,
+                    hidden_size: 384,
+                    num_hidden_layers: 6,
+                    num_attention_heads: 12,
+                    intermediate_size: 1536,
+                    hidden_act: candle_transformers::models::bert::HiddenAct::Gelu,
+                    hidden_dropout_prob: 0.0,
+                    max_position_embeddings: 512,
+                    type_vocab_size: 2,
+                    initializer_range: 0.02,
+                    layer_norm_eps: 1e-12,
+                    pad_token_id: 0,
+                    position_embedding_type: candle_transformers::models::bert::PositionEmbeddingType::Absolute,
+                    use_cache: true,
+                    classifier_dropout: None,
+                    model_type: Some("bert".to_string()),
+                },
+                normalize_embeddings: false,
+                batch_size: 16,
+            },
+        }
+    }
+}
+
 impl ModelConfig {
     /// Get the local paths for model files
     pub fn get_local_paths(&self) -> (PathBuf, PathBuf) {
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use candle_core::{
+    Device,
+    Tensor,
+};
+use candle_nn::{
+    Linear,
+    Module,
+    VarBuilder,
+};
+use candle_transformers::models::bert::{
+    BertModel,
+    DTYPE,
+};
+use tokenizers::{
+    Encoding,
+    Tokenizer,
+    TruncationParams,
+    TruncationStrategy,
+};
+use tracing::{
+    debug,
+    info,
+};
+
+use super::candle::{
+    get_best_available_device,
+    initialize_thread_pool,
+    load_tokenizer,
+    prepare_tokenizer,
+};
+use crate::embedding::Reranker;
+use crate::embedding::candle_models::{
+    ModelConfig,
+    RerankerModelType,
+};
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+
+/// Cross-encoder reranker using Candle for BERT sequence classification models
+///
+/// The query and each candidate text are encoded together as a sentence pair, and the model's
+/// classification head scores their relevance.
+pub struct CandleReranker {
+    /// The BERT encoder
+    model: BertModel,
+    /// Dense layer applied to the `[CLS]` token
+    pooler: Linear,
+    /// Relevance head producing one logit per pair
+    classifier: Linear,
+    /// The tokenizer, with padding and truncation configured for sentence pairs
+    tokenizer: Tokenizer,
+    /// The device to run on
+    device: Device,
+    /// Model configuration
+    config: ModelConfig,
+}
+
+impl CandleReranker {
+    /// Create a new reranker with the default model (ms-marco-MiniLM-L-6-v2)
+    pub fn new() -> Result<Self> {
+        Self::with_model_type(RerankerModelType::default())
+    }
+
+    /// Create a new reranker with a specific model type
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - The type of model to use
+    pub fn with_model_type(model_type: RerankerModelType) -> Result<Self> {
+        let model_config = model_type.get_config();
+        let (model_path, tokenizer_path) = model_config.get_local_paths();
+
+        Self::with_model_config(&model_path, &tokenizer_path, model_config)
+    }
+
+    /// Create a new reranker with specific model paths and configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Path to the model file (.safetensors)
+    /// * `tokenizer_path` - Path to the tokenizer file (.json)
+    /// * `config` - Model configuration
+    pub fn with_model_config(model_path: &Path, tokenizer_path: &Path, config: ModelConfig) -> Result<Self> {
+        info!("Initializing reranker with model: {:?}", model_path);
+
+        initialize_thread_pool()?;
+        let mut tokenizer = prepare_tokenizer(&load_tokenizer(tokenizer_path)?)?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.config.max_position_embeddings,
+                strategy: TruncationStrategy::OnlySecond,
+                ..Default::default()
+            }))
+            .map_err(|e| SemanticSearchError::EmbeddingError(format!("Failed to configure tokenizer: {}", e)))?;
+
+        let device = get_best_available_device();
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[model_path], DTYPE, &device) }
+            .map_err(|e| SemanticSearchError::EmbeddingError(format!("Failed to load model weights: {}", e)))?;
+
+        let hidden_size = config.config.hidden_size;
+        let model = BertModel::load(vb.clone(), &config.config).map_err(model_error)?;
+        let pooler = candle_nn::linear(hidden_size, hidden_size, vb.pp("bert.pooler.dense")).map_err(model_error)?;
+        let classifier = candle_nn::linear(hidden_size, 1, vb.pp("classifier")).map_err(model_error)?;
+
+        debug!("Reranker initialized successfully");
+
+        Ok(Self {
+            model,
+            pooler,
+            classifier,
+            tokenizer,
+            device,
+            config,
+        })
+    }
+
+    /// Score a batch of query and text pairs
+    fn score_batch(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>> {
+        let pairs: Vec<(&str, &str)> = texts.iter().map(|text| (query, *text)).collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| SemanticSearchError::EmbeddingError(format!("Failed to tokenize texts: {}", e)))?;
+
+        let token_ids = self.batch_tensor(&encodings, Encoding::get_ids)?;
+        let token_type_ids = self.batch_tensor(&encodings, Encoding::get_type_ids)?;
+        let attention_mask = self.batch_tensor(&encodings, Encoding::get_attention_mask)?;
+
+        let logits = self
+            .model
+            .forward(&token_ids, &token_type_ids, Some(&attention_mask))
+            .and_then(|sequence| sequence.narrow(1, 0, 1)?.squeeze(1))
+            .and_then(|cls| self.pooler.forward(&cls)?.tanh())
+            .and_then(|pooled| self.classifier.forward(&pooled)?.squeeze(1))
+            .and_then(|logits| logits.to_vec1::<f32>())
+            .map_err(|e| SemanticSearchError::EmbeddingError(format!("Reranker inference failed: {}", e)))?;
+
+        Ok(logits)
+    }
+
+    /// Stack one field of padded encodings into a `(batch, length)` tensor
+    fn batch_tensor(&self, encodings: &[Encoding], field: fn(&Encoding) -> &[u32]) -> Result<Tensor> {
+        let length = encodings.first().map_or(0, |encoding| field(encoding).len());
+        let values: Vec<u32> = encodings.iter().flat_map(|encoding| field(encoding).to_vec()).collect();
+        Tensor::from_vec(values, (encodings.len(), length), &self.device)
+            .map_err(|e| SemanticSearchError::EmbeddingError(format!("Failed to create input tensor: {}", e)))
+    }
+}
+
+impl Reranker for CandleReranker {
+    fn score(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>> {
+        let mut scores = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.batch_size) {
+            scores.extend(self.score_batch(query, batch)?);
+        }
+        Ok(scores)
+    }
+}
+
+fn model_error(e: candle_core::Error) -> SemanticSearchError {
+    SemanticSearchError::EmbeddingError(format!("Failed to create reranker model: {}", e))
+}
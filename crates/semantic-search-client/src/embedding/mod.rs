@@ -4,8 +4,11 @@ mod benchmark_utils;
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
 mod candle;
 mod candle_models;
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+mod candle_reranker;
 /// Mock embedder for testing and as placeholder for BM25
 pub mod mock;
+mod reranker;
 mod trait_def;
 
 pub use benchmark_utils::{
@@ -19,8 +22,15 @@ pub use candle::CandleTextEmbedder;
 pub use candle_models::{
     ModelConfig,
     ModelType,
+    RerankerModelType,
 };
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+pub use candle_reranker::CandleReranker;
 pub use mock::MockTextEmbedder;
+pub use reranker::{
+    Reranker,
+    rerank,
+};
 pub use trait_def::{
     EmbeddingType,
     TextEmbedderTrait,
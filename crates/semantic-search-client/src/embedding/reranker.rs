@@ -0,0 +1,107 @@
+use crate::error::Result;
+use crate::types::SearchResults;
+
+/// Scores how relevant texts are to a query, to rerank search candidates
+///
+/// Unlike embedders, which encode the query and each text separately, rerankers read the query
+/// and a text together, which ranks long technical queries more accurately at a higher cost per
+/// candidate.
+pub trait Reranker: Send + Sync {
+    /// Score each text against the query, higher being more relevant
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The search query
+    /// * `texts` - The candidate texts
+    ///
+    /// # Returns
+    ///
+    /// One raw relevance score (logit) per text, in the order of `texts`
+    fn score(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>>;
+}
+
+/// Reorder search results by reranker score, keeping the best `limit`
+///
+/// # Arguments
+///
+/// * `reranker` - The reranker to score candidates with
+/// * `query` - The search query
+/// * `candidates` - Search results to rerank
+/// * `limit` - Maximum number of results to return
+///
+/// # Returns
+///
+/// Reranked results, best first
+pub fn rerank(reranker: &dyn Reranker, query: &str, candidates: SearchResults, limit: usize) -> Result<SearchResults> {
+    if candidates.is_empty() {
+        return Ok(candidates);
+    }
+
+    let texts: Vec<&str> = candidates
+        .iter()
+        .map(|result| result.text().unwrap_or_default())
+        .collect();
+    let scores = reranker.score(query, &texts)?;
+
+    let mut results: SearchResults = candidates
+        .into_iter()
+        .zip(scores)
+        .map(|(result, score)| result.reranked(score))
+        .collect();
+    results.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    results.truncate(limit);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::{
+        DataPoint,
+        SearchResult,
+    };
+
+    /// Scores texts by how many query words they contain
+    struct WordOverlapReranker;
+
+    impl Reranker for WordOverlapReranker {
+        fn score(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>> {
+            Ok(texts
+                .iter()
+                .map(|text| query.split_whitespace().filter(|word| text.contains(word)).count() as f32)
+                .collect())
+        }
+    }
+
+    fn result(id: usize, text: &str) -> SearchResult {
+        let point = DataPoint {
+            id,
+            payload: HashMap::from([("text".to_string(), serde_json::json!(text))]),
+            vector: vec![],
+        };
+        SearchResult::new(point, id as f32)
+    }
+
+    #[test]
+    fn test_rerank() {
+        let candidates = vec![
+            result(0, "unrelated"),
+            result(1, "token refresh"),
+            result(2, "refresh the auth token on expiry"),
+        ];
+        let results = rerank(&WordOverlapReranker, "auth token refresh", candidates, 2).unwrap();
+
+        assert_eq!(results.iter().map(|r| r.point.id).collect::<Vec<_>>(), vec![2, 1]);
+        let scores: Vec<f32> = results.iter().map(|r| r.rerank_score.unwrap()).collect();
+        assert!(scores[0] > scores[1]);
+        assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+        assert!((results[0].distance - (1.0 - scores[0])).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_rerank_empty() {
+        assert!(rerank(&WordOverlapReranker, "query", Vec::new(), 5).unwrap().is_empty());
+    }
+}
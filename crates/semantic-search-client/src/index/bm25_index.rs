@@ -140,6 +140,27 @@ impl BM25Index {
             .collect()
     }
 
+    /// Search the index, returning only documents whose id passes `filter`
+    ///
+    /// All matching documents are scored before filtering, so up to `limit` allowed documents are
+    /// returned.
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: impl Fn(usize) -> bool,
+    ) -> Vec<(usize, f32, String)> {
+        let engine = self.engine.read().unwrap();
+        let results = engine.search(query, None);
+
+        results
+            .into_iter()
+            .filter(|result| filter(result.document.id))
+            .take(limit)
+            .map(|result| (result.document.id, result.score, result.document.contents))
+            .collect()
+    }
+
     /// Remove a document from the index
     pub fn remove_document(&self, id: usize) {
         let mut engine = self.engine.write().unwrap();
@@ -1,18 +1,83 @@
+use std::fs::{
+    self,
+    File,
+};
+use std::io::{
+    self,
+    BufReader,
+    Read,
+};
+use std::path::Path;
+use std::ptr::NonNull;
 use std::sync::RwLock;
 
+use hnsw_rs::api::AnnT;
 use hnsw_rs::hnsw::Hnsw;
-use hnsw_rs::prelude::DistCosine;
+use hnsw_rs::hnswio::HnswIo;
+use hnsw_rs::prelude::{
+    DistCosine,
+    Distance,
+};
 use tracing::{
     debug,
     info,
+    warn,
 };
 
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+
+/// Suffixes hnsw_rs appends to the base name of a saved index
+const INDEX_FILE_SUFFIXES: [&str; 2] = [".hnsw.graph", ".hnsw.data"];
+
+/// Magic numbers of the hnsw_rs dump format
+const MAGIC_DESCRIPTION: u32 = 0x002a6779;
+const MAGIC_LAYER: u32 = 0x000a676f;
+const MAGIC_POINT: u32 = 0x000a678f;
+const MAGIC_DATA: u32 = 0xa67f0000;
+
+/// Number of layers hnsw_rs records neighbours for, whatever the number of layers in use
+const MAX_LAYERS: u8 = 16;
+
 /// Vector index for fast approximate nearest neighbor search
 pub struct VectorIndex {
     /// The HNSW index protected by RwLock for thread safety
     index: RwLock<Hnsw<'static, f32, DistCosine>>,
     /// Counter to track the number of elements
     count: std::sync::atomic::AtomicUsize,
+    /// Reloader of a loaded index, which the index may borrow from. Declared after the index so
+    /// that it is dropped last.
+    _reloader: Option<Reloader>,
+}
+
+/// Owns the [HnswIo] an index was loaded with, at a fixed address for as long as the index lives
+struct Reloader(NonNull<HnswIo>);
+
+impl Reloader {
+    fn new(reloader: HnswIo) -> Self {
+        Self(NonNull::from(Box::leak(Box::new(reloader))))
+    }
+
+    /// # Safety
+    ///
+    /// The returned reference, and anything borrowing from it, must not outlive `self`.
+    unsafe fn get(&mut self) -> &'static mut HnswIo {
+        // SAFETY: the pointer comes from a box that is only freed when self is dropped
+        unsafe { &mut *self.0.as_ptr() }
+    }
+}
+
+// SAFETY: the HnswIo is only accessed through the unique owner, and HnswIo is Send and Sync
+unsafe impl Send for Reloader {}
+unsafe impl Sync for Reloader {}
+
+impl Drop for Reloader {
+    fn drop(&mut self) {
+        // SAFETY: the pointer comes from Box::leak and isn't used after this
+        drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+    }
 }
 
 impl VectorIndex {
@@ -40,9 +105,86 @@ impl VectorIndex {
         Self {
             index: RwLock::new(index),
             count: std::sync::atomic::AtomicUsize::new(0),
+            _reloader: None,
         }
     }
 
+    /// Load an index saved with [`VectorIndex::save`], or `None` if there is no saved index
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory containing the index files
+    /// * `basename` - Base name of the index files
+    pub fn load(dir: &Path, basename: &str) -> Result<Option<Self>> {
+        if !index_files(dir, basename).iter().all(|path| path.exists()) {
+            return Ok(None);
+        }
+
+        // hnsw_rs panics or exits the process on malformed dumps, so check them first and let
+        // the caller rebuild the index from the data points instead
+        if let Err(e) = validate_dump(dir, basename) {
+            warn!("Ignoring saved vector index in {}: {}", dir.display(), e);
+            return Ok(None);
+        }
+
+        // The graph may borrow from its reloader for as long as it lives, so the reloader is
+        // kept in the index and dropped after the graph
+        let mut reloader = Reloader::new(HnswIo::new(dir, basename));
+        // SAFETY: the graph is stored next to the reloader, which outlives it
+        let index: Hnsw<'static, f32, DistCosine> = unsafe { reloader.get() }
+            .load_hnsw()
+            .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to load vector index: {}", e)))?;
+        let count = index.get_nb_point();
+
+        info!("Loaded vector index with {} elements", count);
+        Ok(Some(Self {
+            index: RwLock::new(index),
+            count: std::sync::atomic::AtomicUsize::new(count),
+            _reloader: Some(reloader),
+        }))
+    }
+
+    /// Save the index so that it can be loaded without inserting every vector again
+    ///
+    /// The files are written to a staging directory and moved into `dir` once complete.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory to save the index files in
+    /// * `basename` - Base name of the index files
+    pub fn save(&self, dir: &Path, basename: &str) -> Result<()> {
+        let staging_dir = dir.join(format!(".{}.tmp", basename));
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir)?;
+        }
+        fs::create_dir_all(&staging_dir)?;
+
+        {
+            let index = self.index.read().unwrap();
+            index
+                .file_dump(&staging_dir, basename)
+                .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to save vector index: {}", e)))?;
+        }
+        for (staged, path) in index_files(&staging_dir, basename)
+            .iter()
+            .zip(index_files(dir, basename))
+        {
+            fs::rename(staged, path)?;
+        }
+        fs::remove_dir_all(&staging_dir)?;
+        Ok(())
+    }
+
+    /// Remove the files of a saved index, if any
+    pub fn remove_saved(dir: &Path, basename: &str) -> Result<()> {
+        for path in index_files(dir, basename) {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Insert a vector into the index
     ///
     /// # Arguments
@@ -76,6 +218,46 @@ impl VectorIndex {
             .collect()
     }
 
+    /// Search for nearest neighbors among the given ids
+    ///
+    /// The filter is applied while the graph is traversed, so up to `limit` allowed neighbors are
+    /// returned however few of the indexed vectors are allowed. Allowed vectors the graph doesn't
+    /// lead to from its entry point are missed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query vector
+    /// * `limit` - Maximum number of results to return
+    /// * `ef_search` - Size of the dynamic candidate list for search
+    /// * `allowed_ids` - Sorted ids that may be returned
+    ///
+    /// # Returns
+    ///
+    /// A vector of (id, distance) pairs
+    pub fn search_filtered(
+        &self,
+        query: &[f32],
+        limit: usize,
+        ef_search: usize,
+        allowed_ids: &[usize],
+    ) -> Vec<(usize, f32)> {
+        let index = self.index.read().unwrap();
+        let filter = |id: &usize| allowed_ids.binary_search(id).is_ok();
+        let results = index.search_filter(query, limit, ef_search.max(limit), Some(&filter));
+
+        // The entry point of the graph is returned when no allowed vector could be reached
+        results
+            .into_iter()
+            .filter(|neighbor| filter(&neighbor.d_id))
+            .map(|neighbor| (neighbor.d_id, neighbor.distance))
+            .collect()
+    }
+
+    /// Distance between two vectors, as measured by the index
+    pub fn distance(a: &[f32], b: &[f32]) -> f32 {
+        DistCosine.eval(a, b)
+    }
+
     /// Get the number of elements in the index
     ///
     /// # Returns
@@ -94,3 +276,160 @@ impl VectorIndex {
         self.len() == 0
     }
 }
+
+fn index_files(dir: &Path, basename: &str) -> [std::path::PathBuf; 2] {
+    INDEX_FILE_SUFFIXES.map(|suffix| dir.join(format!("{}{}", basename, suffix)))
+}
+
+/// Check that a saved index is a complete dump of an index of `f32` vectors with cosine distance
+///
+/// This walks both files the way hnsw_rs reads them, and checks everything it would otherwise
+/// assert on: magic numbers, point ids, vector sizes, neighbour and entry point ids, and that
+/// neither file is truncated or has trailing data.
+fn validate_dump(dir: &Path, basename: &str) -> io::Result<()> {
+    let [graph_path, data_path] = index_files(dir, basename);
+    let mut graph = DumpReader(BufReader::new(File::open(graph_path)?));
+    let mut data = DumpReader(BufReader::new(File::open(data_path)?));
+
+    // Description
+    graph.expect_u32(MAGIC_DESCRIPTION, "description magic")?;
+    graph.expect_u8(1, "dump mode")?;
+    graph.u8()?; // max number of connections
+    graph.skip(std::mem::size_of::<f64>() as u64)?; // level scale
+    graph.expect_u8(MAX_LAYERS, "number of layers")?;
+    graph.usize()?; // ef construction
+    let nb_point = graph.usize()?;
+    let dimension = graph.usize()?;
+    if nb_point == 0 {
+        return Err(invalid_dump("no points"));
+    }
+    graph.expect_name(std::any::type_name::<DistCosine>(), "distance")?;
+    graph.expect_name(std::any::type_name::<f32>(), "vector type")?;
+    let vector_len = dimension
+        .checked_mul(std::mem::size_of::<f32>())
+        .ok_or_else(|| invalid_dump("dimension too large"))?;
+
+    data.expect_u32(MAGIC_DATA, "data magic")?;
+    data.expect_usize(dimension, "data dimension")?;
+
+    // Points, layer by layer, each followed by its neighbours in every layer
+    let nb_layer = graph.u8()?;
+    if nb_layer > MAX_LAYERS {
+        return Err(invalid_dump("too many layers"));
+    }
+    let mut layer_sizes = Vec::with_capacity(nb_layer as usize);
+    let mut neighbours = Vec::new();
+    for layer in 0..nb_layer {
+        graph.expect_u32(MAGIC_LAYER, "layer magic")?;
+        let layer_size = graph.usize()?;
+        for rank in 0..layer_size {
+            graph.expect_u32(MAGIC_POINT, "point magic")?;
+            let origin_id = graph.usize()?;
+            graph.expect_u8(layer, "point layer")?;
+            if usize::try_from(graph.i32()?).ok() != Some(rank) {
+                return Err(invalid_dump("point rank out of order"));
+            }
+            for _ in 0..MAX_LAYERS {
+                for _ in 0..graph.usize()? {
+                    graph.usize()?; // origin id
+                    neighbours.push((graph.u8()?, graph.i32()?));
+                    graph.skip(std::mem::size_of::<f32>() as u64)?; // distance
+                }
+            }
+
+            data.expect_u32(MAGIC_DATA, "vector magic")?;
+            data.expect_usize(origin_id, "vector id")?;
+            data.expect_usize(vector_len, "vector size")?;
+            data.skip(vector_len as u64)?;
+        }
+        layer_sizes.push(layer_size);
+    }
+    if layer_sizes.iter().sum::<usize>() != nb_point {
+        return Err(invalid_dump("wrong number of points"));
+    }
+
+    graph.usize()?; // entry point origin id
+    neighbours.push((graph.u8()?, graph.i32()?));
+    let exists = |&(layer, rank): &(u8, i32)| {
+        let size = layer_sizes.get(layer as usize).copied().unwrap_or(0);
+        usize::try_from(rank).is_ok_and(|rank| rank < size)
+    };
+    if !neighbours.iter().all(exists) {
+        return Err(invalid_dump("reference to a missing point"));
+    }
+
+    graph.expect_end()?;
+    data.expect_end()
+}
+
+fn invalid_dump(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Reads the native endian values hnsw_rs dumps are made of
+struct DumpReader<R>(R);
+
+impl<R: Read> DumpReader<R> {
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.0.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        self.bytes().map(u8::from_ne_bytes)
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        self.bytes().map(i32::from_ne_bytes)
+    }
+
+    fn usize(&mut self) -> io::Result<usize> {
+        self.bytes().map(usize::from_ne_bytes)
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        if io::copy(&mut (&mut self.0).take(len), &mut io::sink())? != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn expect_u8(&mut self, expected: u8, what: &str) -> io::Result<()> {
+        if self.u8()? != expected {
+            return Err(invalid_dump(&format!("unexpected {}", what)));
+        }
+        Ok(())
+    }
+
+    fn expect_u32(&mut self, expected: u32, what: &str) -> io::Result<()> {
+        if self.bytes().map(u32::from_ne_bytes)? != expected {
+            return Err(invalid_dump(&format!("unexpected {}", what)));
+        }
+        Ok(())
+    }
+
+    fn expect_usize(&mut self, expected: usize, what: &str) -> io::Result<()> {
+        if self.usize()? != expected {
+            return Err(invalid_dump(&format!("unexpected {}", what)));
+        }
+        Ok(())
+    }
+
+    fn expect_name(&mut self, expected: &str, what: &str) -> io::Result<()> {
+        self.expect_usize(expected.len(), what)?;
+        let mut name = vec![0; expected.len()];
+        self.0.read_exact(&mut name)?;
+        if name != expected.as_bytes() {
+            return Err(invalid_dump(&format!("unexpected {}", what)));
+        }
+        Ok(())
+    }
+
+    fn expect_end(&mut self) -> io::Result<()> {
+        if self.0.read(&mut [0])? != 0 {
+            return Err(invalid_dump("trailing data"));
+        }
+        Ok(())
+    }
+}
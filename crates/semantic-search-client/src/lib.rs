@@ -20,6 +20,8 @@ pub mod model_validator;
 pub mod pattern_filter;
 /// File processing utilities
 pub mod processing;
+/// Metadata filters for search queries
+pub mod search_filter;
 /// Data types for semantic search operations
 pub mod types;
 
@@ -36,11 +38,13 @@ pub use error::{
     Result,
     SemanticSearchError,
 };
+pub use search_filter::SearchFilter;
 pub use types::{
     BM25DataPoint,
     DataPoint,
     FileType,
     KnowledgeContext,
+    OperationState,
     OperationStatus,
     OperationType,
     ProgressInfo,
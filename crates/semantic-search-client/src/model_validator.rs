@@ -10,7 +10,8 @@ use sha2::{
 
 /// Validator for model files using allowlisted SHA256 hashes
 pub struct ModelValidator {
-    allowlisted_shas: HashMap<&'static str, Vec<&'static str>>,
+    /// Valid SHAs of each file, keyed by model name and file name
+    allowlisted_shas: HashMap<&'static str, HashMap<&'static str, Vec<&'static str>>>,
 }
 
 impl Default for ModelValidator {
@@ -23,24 +24,27 @@ impl ModelValidator {
     /// Create a new model validator with official allowlisted SHAs
     pub fn new() -> Self {
         let mut allowlisted_shas = HashMap::new();
+        let mut minilm_shas = HashMap::new();
 
         // Each file can have multiple valid SHAs (for different versions)
-        allowlisted_shas.insert("model.safetensors", vec![
+        minilm_shas.insert("model.safetensors", vec![
             "53aa51172d142c89d9012cce15ae4d6cc0ca6895895114379cacb4fab128d9db",
         ]);
 
-        allowlisted_shas.insert("tokenizer.json", vec![
+        minilm_shas.insert("tokenizer.json", vec![
             "be50c3628f2bf5bb5e3a7f17b1f74611b2561a3a27eeab05e5aa30f411572037",
         ]);
 
+        allowlisted_shas.insert("all-MiniLM-L6-v2", minilm_shas);
+
         Self { allowlisted_shas }
     }
 
-    /// Validate a file against allowlisted SHAs, removing it if invalid
+    /// Validate a file of a model against allowlisted SHAs, removing it if invalid
     ///
-    /// Returns true if the file exists and matches an allowlisted SHA,
+    /// Returns true if the file exists and matches an allowlisted SHA for the model,
     /// false if the file doesn't exist, has an invalid SHA, or is removed.
-    pub fn validate_file(&self, file_path: &Path) -> bool {
+    pub fn validate_file(&self, model_name: &str, file_path: &Path) -> bool {
         if !file_path.exists() {
             return false;
         }
@@ -50,18 +54,18 @@ impl ModelValidator {
             None => return false,
         };
 
-        let valid_shas = match self.allowlisted_shas.get(filename) {
-            Some(shas) => shas,
-            None => return false,
-        };
+        let valid_shas = self
+            .allowlisted_shas
+            .get(model_name)
+            .and_then(|model_shas| model_shas.get(filename));
 
         let actual_sha = match Self::calculate_sha256(file_path) {
             Ok(sha) => sha,
             Err(_) => return false,
         };
 
-        // Check if actual SHA matches any of the valid SHAs
-        if !valid_shas.contains(&actual_sha.as_str()) {
+        // Check if actual SHA matches any of the valid SHAs, files without any being invalid
+        if !valid_shas.is_some_and(|shas| shas.contains(&actual_sha.as_str())) {
             let _ = std::fs::remove_file(file_path); // Remove invalid file
             return false;
         }
@@ -0,0 +1,648 @@
+use std::path::Path;
+
+use tree_sitter::{
+    Node,
+    Parser,
+};
+
+use crate::config;
+use crate::processing::text_chunker::chunk_text;
+
+/// A chunk of a source file that follows its syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeChunk {
+    /// Source text of the chunk
+    pub text: String,
+    /// Qualified name of the enclosing item (e.g. `Parser::parse`), `None` for module-level code
+    pub symbol: Option<String>,
+    /// Kind of the enclosing item (e.g. `function`, `class`, `impl`), `module` for module-level
+    /// code
+    pub kind: String,
+    /// First line of the chunk (1-based)
+    pub start_line: usize,
+    /// Last line of the chunk (1-based, inclusive)
+    pub end_line: usize,
+}
+
+/// Languages with syntax-aware chunking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    Python,
+    TypeScript,
+    Tsx,
+    Go,
+    Java,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|s| s.to_lowercase())
+            .as_deref()
+        {
+            Some("rs") => Some(Self::Rust),
+            Some("py" | "pyi") => Some(Self::Python),
+            Some("ts" | "mts" | "cts") => Some(Self::TypeScript),
+            // The TSX grammar also parses plain JavaScript
+            Some("tsx" | "js" | "jsx" | "mjs" | "cjs") => Some(Self::Tsx),
+            Some("go") => Some(Self::Go),
+            Some("java") => Some(Self::Java),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::Java => tree_sitter_java::LANGUAGE.into(),
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            Self::Rust => "::",
+            _ => ".",
+        }
+    }
+
+    /// Whether `node` is a comment or attribute that documents the item following it
+    fn is_leading_trivia(self, node: Node<'_>) -> bool {
+        match self {
+            Self::Rust => matches!(node.kind(), "line_comment" | "block_comment" | "attribute_item"),
+            Self::Java => matches!(node.kind(), "line_comment" | "block_comment"),
+            Self::Python | Self::TypeScript | Self::Tsx | Self::Go => node.kind() == "comment",
+        }
+    }
+
+    /// Recognizes a function, class, impl or similar item
+    fn definition<'tree>(self, node: Node<'tree>, source: &[u8], in_type: bool) -> Option<Definition<'tree>> {
+        let name = |node: Node<'_>, field: &str| {
+            node.child_by_field_name(field)
+                .and_then(|name| name.utf8_text(source).ok())
+                .map(str::to_string)
+        };
+        let function_kind = if in_type { "method" } else { "function" };
+
+        let (kind, name, body) = match (self, node.kind()) {
+            (Self::Rust, "function_item") => (function_kind, name(node, "name")?, None),
+            (Self::Rust, "struct_item" | "union_item") => ("struct", name(node, "name")?, None),
+            (Self::Rust, "enum_item") => ("enum", name(node, "name")?, None),
+            (Self::Rust, "macro_definition") => ("macro", name(node, "name")?, None),
+            (Self::Rust, "trait_item") => ("trait", name(node, "name")?, node.child_by_field_name("body")),
+            (Self::Rust, "mod_item") => ("module", name(node, "name")?, node.child_by_field_name("body")),
+            (Self::Rust, "impl_item") => {
+                let ty = node.child_by_field_name("type")?;
+                // Name methods of `impl Foo<T>` after `Foo`
+                let ty = ty.child_by_field_name("type").unwrap_or(ty);
+                (
+                    "impl",
+                    ty.utf8_text(source).ok()?.to_string(),
+                    node.child_by_field_name("body"),
+                )
+            },
+
+            (Self::Python, "function_definition") => (function_kind, name(node, "name")?, None),
+            (Self::Python, "class_definition") => ("class", name(node, "name")?, node.child_by_field_name("body")),
+            (Self::Python, "decorated_definition") => {
+                // The span of the decorated node includes the decorators
+                return self.definition(node.child_by_field_name("definition")?, source, in_type);
+            },
+
+            (
+                Self::TypeScript | Self::Tsx,
+                "function_declaration" | "generator_function_declaration" | "function_signature",
+            ) => ("function", name(node, "name")?, None),
+            (Self::TypeScript | Self::Tsx, "method_definition") => ("method", name(node, "name")?, None),
+            (Self::TypeScript | Self::Tsx, "class_declaration" | "abstract_class_declaration") => {
+                ("class", name(node, "name")?, node.child_by_field_name("body"))
+            },
+            (Self::TypeScript | Self::Tsx, "interface_declaration") => ("interface", name(node, "name")?, None),
+            (Self::TypeScript | Self::Tsx, "enum_declaration") => ("enum", name(node, "name")?, None),
+            (Self::TypeScript | Self::Tsx, "lexical_declaration") => {
+                // `const handler = () => { ... }`
+                let declarator = node.named_child(0)?;
+                let value = declarator.child_by_field_name("value")?;
+                if !matches!(value.kind(), "arrow_function" | "function_expression" | "function") {
+                    return None;
+                }
+                ("function", name(declarator, "name")?, None)
+            },
+            (Self::TypeScript | Self::Tsx, "export_statement") => {
+                return self.definition(node.child_by_field_name("declaration")?, source, in_type);
+            },
+
+            (Self::Go, "function_declaration") => ("function", name(node, "name")?, None),
+            (Self::Go, "method_declaration") => {
+                // Qualify methods with their receiver type, e.g. `Server.Start`
+                let receiver = node
+                    .child_by_field_name("receiver")
+                    .and_then(|receiver| receiver.named_child(0))
+                    .and_then(|parameter| parameter.child_by_field_name("type"))
+                    .and_then(|ty| ty.utf8_text(source).ok())
+                    .map(|ty| ty.trim_start_matches('*'));
+                let method = name(node, "name")?;
+                let name = match receiver {
+                    Some(receiver) => format!("{receiver}.{method}"),
+                    None => method,
+                };
+                ("method", name, None)
+            },
+            (Self::Go, "type_declaration") => {
+                let spec = node.named_child(0).filter(|spec| spec.kind() == "type_spec")?;
+                let kind = match spec.child_by_field_name("type").map(|ty| ty.kind()) {
+                    Some("struct_type") => "struct",
+                    Some("interface_type") => "interface",
+                    _ => "type",
+                };
+                (kind, name(spec, "name")?, None)
+            },
+
+            (Self::Java, "class_declaration" | "record_declaration") => {
+                ("class", name(node, "name")?, node.child_by_field_name("body"))
+            },
+            (Self::Java, "interface_declaration") => {
+                ("interface", name(node, "name")?, node.child_by_field_name("body"))
+            },
+            (Self::Java, "enum_declaration") => ("enum", name(node, "name")?, node.child_by_field_name("body")),
+            (Self::Java, "method_declaration") => ("method", name(node, "name")?, None),
+            (Self::Java, "constructor_declaration") => ("constructor", name(node, "name")?, None),
+
+            _ => return None,
+        };
+
+        Some(Definition { kind, name, body })
+    }
+}
+
+struct Definition<'tree> {
+    kind: &'static str,
+    name: String,
+    /// Node holding the members of a type or module, split further when the item is too large
+    body: Option<Node<'tree>>,
+}
+
+/// A range of lines owned by an item
+#[derive(Debug, Clone)]
+struct Span {
+    start: usize,
+    end: usize,
+    symbol: Option<String>,
+    kind: &'static str,
+}
+
+struct Collector<'a> {
+    language: Language,
+    source: &'a [u8],
+    lines: &'a [&'a str],
+    chunk_size: usize,
+    /// Items emitted as chunks
+    items: Vec<Span>,
+    /// Items split into their members, which own the lines between those members
+    containers: Vec<Span>,
+}
+
+impl Collector<'_> {
+    fn collect(&mut self, parent: Node<'_>, scope: Option<&str>) {
+        let mut cursor = parent.walk();
+        // First line of the comments and attributes right above the current node
+        let mut trivia: Option<(usize, usize)> = None;
+
+        for node in parent.named_children(&mut cursor) {
+            let (start, end) = rows(node);
+            if self.language.is_leading_trivia(node) && self.starts_line(node) {
+                trivia = match trivia {
+                    Some((first, last)) if start <= last + 1 => Some((first, end)),
+                    _ => Some((start, end)),
+                };
+                continue;
+            }
+
+            let Some(definition) = self.language.definition(node, self.source, scope.is_some()) else {
+                trivia = None;
+                continue;
+            };
+            let start = match trivia.take() {
+                Some((first, last)) if start <= last + 1 => first,
+                _ => start,
+            };
+            let symbol = match scope {
+                Some(scope) => format!("{scope}{}{}", self.language.separator(), definition.name),
+                None => definition.name,
+            };
+            let span = Span {
+                start,
+                end,
+                symbol: Some(symbol),
+                kind: definition.kind,
+            };
+
+            match definition.body {
+                Some(body) if self.word_count(span.start, span.end) > self.chunk_size => {
+                    let symbol = span.symbol.clone();
+                    self.containers.push(span);
+                    self.collect(body, symbol.as_deref());
+                },
+                _ => self.items.push(span),
+            }
+        }
+    }
+
+    fn starts_line(&self, node: Node<'_>) -> bool {
+        let position = node.start_position();
+        self.lines
+            .get(position.row)
+            .and_then(|line| line.get(..position.column))
+            .is_some_and(|prefix| prefix.trim().is_empty())
+    }
+
+    fn word_count(&self, start: usize, end: usize) -> usize {
+        self.lines[start..=end.min(self.lines.len() - 1)]
+            .iter()
+            .map(|line| line.split_whitespace().count())
+            .sum()
+    }
+
+    /// The innermost split container holding `row`
+    fn owner(&self, row: usize) -> (Option<String>, &'static str) {
+        self.containers
+            .iter()
+            .filter(|container| container.start <= row && row <= container.end)
+            .min_by_key(|container| container.end - container.start)
+            .map_or((None, "module"), |container| (container.symbol.clone(), container.kind))
+    }
+}
+
+/// Split a source file on function, class and impl boundaries
+///
+/// Supports Rust, Python, TypeScript, JavaScript, Go and Java. Items that don't fit in
+/// `chunk_size` words are split into their members when they have any, and into overlapping
+/// windows of lines otherwise. Code between items, such as imports, becomes chunks of its own.
+///
+/// # Arguments
+///
+/// * `path` - Path of the file, used to pick the language
+/// * `content` - Content of the file
+/// * `chunk_size` - Optional chunk size in words (if None, uses config value)
+/// * `overlap` - Optional overlap in words for windowed items (if None, uses config value)
+///
+/// # Returns
+///
+/// The chunks in file order, or `None` if the language isn't supported
+pub fn chunk_code(
+    path: &Path,
+    content: &str,
+    chunk_size: Option<usize>,
+    overlap: Option<usize>,
+) -> Option<Vec<CodeChunk>> {
+    let language = Language::from_path(path)?;
+    let config = config::get_config();
+    let chunk_size = chunk_size.unwrap_or(config.chunk_size).max(1);
+    let overlap = overlap.unwrap_or(config.chunk_overlap);
+
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar()).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut collector = Collector {
+        language,
+        source: content.as_bytes(),
+        lines: &lines,
+        chunk_size,
+        items: Vec::new(),
+        containers: Vec::new(),
+    };
+    collector.collect(tree.root_node(), None);
+
+    // Lay out items and the code between them in file order
+    let mut items = std::mem::take(&mut collector.items);
+    items.sort_by_key(|item| item.start);
+    let mut spans = Vec::new();
+    let mut row = 0;
+    for mut item in items {
+        // Items sharing a line with the previous one are already part of it
+        item.start = item.start.max(row);
+        if item.start > item.end {
+            continue;
+        }
+        gap_spans(&collector, row, item.start, &mut spans);
+        row = item.end + 1;
+        spans.push(item);
+    }
+    gap_spans(&collector, row, lines.len(), &mut spans);
+
+    let mut chunks = Vec::new();
+    for span in spans {
+        window(&lines, &span, chunk_size, overlap, &mut chunks);
+    }
+    Some(chunks)
+}
+
+/// Group the lines in `start..end` by the container owning them
+fn gap_spans(collector: &Collector<'_>, start: usize, end: usize, spans: &mut Vec<Span>) {
+    let mut current: Option<Span> = None;
+    for row in start..end.min(collector.lines.len()) {
+        let (symbol, kind) = collector.owner(row);
+        match &mut current {
+            Some(span) if span.symbol == symbol && span.kind == kind => span.end = row,
+            _ => {
+                spans.extend(current.take());
+                current = Some(Span {
+                    start: row,
+                    end: row,
+                    symbol,
+                    kind,
+                });
+            },
+        }
+    }
+    spans.extend(current);
+}
+
+/// Emit `span` as one chunk, or as overlapping windows of lines if it's larger than `chunk_size`
+fn window(lines: &[&str], span: &Span, chunk_size: usize, overlap: usize, chunks: &mut Vec<CodeChunk>) {
+    let end = span.end.min(lines.len() - 1);
+    // Trim blank lines so that gaps don't produce empty chunks
+    let Some(start) = (span.start..=end).find(|row| !lines[*row].trim().is_empty()) else {
+        return;
+    };
+    let end = (start..=end)
+        .rev()
+        .find(|row| !lines[*row].trim().is_empty())
+        .unwrap_or(start);
+    // Skip leftovers such as the closing brace of a split class
+    if !lines[start..=end]
+        .iter()
+        .any(|line| line.chars().any(char::is_alphanumeric))
+    {
+        return;
+    }
+
+    let words: Vec<usize> = lines[start..=end]
+        .iter()
+        .map(|line| line.split_whitespace().count())
+        .collect();
+    let push = |chunks: &mut Vec<CodeChunk>, text: String, first: usize, last: usize| {
+        chunks.push(CodeChunk {
+            text,
+            symbol: span.symbol.clone(),
+            kind: span.kind.to_string(),
+            start_line: first + 1,
+            end_line: last + 1,
+        });
+    };
+
+    let mut first = 0;
+    loop {
+        let mut last = first;
+        let mut count = words[first];
+        while last + 1 < words.len() && count + words[last + 1] <= chunk_size {
+            last += 1;
+            count += words[last];
+        }
+
+        if count > chunk_size {
+            // A single line longer than a chunk, e.g. minified code
+            for text in chunk_text(
+                lines[start + first],
+                Some(chunk_size),
+                Some(overlap.min(chunk_size - 1)),
+            ) {
+                push(chunks, text, start + first, start + first);
+            }
+        } else {
+            push(
+                chunks,
+                lines[start + first..=start + last].join("\n"),
+                start + first,
+                start + last,
+            );
+        }
+
+        if last + 1 >= words.len() {
+            break;
+        }
+        // Start the next window early enough to repeat `overlap` words
+        let mut next = last + 1;
+        let mut repeated = 0;
+        while next > first + 1 && repeated + words[next - 1] <= overlap {
+            next -= 1;
+            repeated += words[next];
+        }
+        first = next;
+    }
+}
+
+/// First and last row of `node`, not counting a trailing newline
+fn rows(node: Node<'_>) -> (usize, usize) {
+    let start = node.start_position().row;
+    let end = node.end_position();
+    if end.column == 0 && end.row > start {
+        (start, end.row - 1)
+    } else {
+        (start, end.row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(file: &str, content: &str, chunk_size: usize) -> Vec<CodeChunk> {
+        chunk_code(Path::new(file), content, Some(chunk_size), Some(0)).unwrap()
+    }
+
+    fn summary(chunks: &[CodeChunk]) -> Vec<(Option<&str>, &str, usize, usize)> {
+        chunks
+            .iter()
+            .map(|c| (c.symbol.as_deref(), c.kind.as_str(), c.start_line, c.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(chunk_code(Path::new("notes.txt"), "hello", Some(10), Some(0)).is_none());
+        assert!(chunk_code(Path::new("style.css"), "a { }", Some(10), Some(0)).is_none());
+    }
+
+    #[test]
+    fn test_rust() {
+        let content = r#"use std::fmt;
+
+/// A point
+#[derive(Debug)]
+struct Point {
+    x: i32,
+}
+
+impl Point {
+    const ORIGIN: i32 = 0;
+
+    fn new(x: i32) -> Self {
+        Self { x }
+    }
+
+    /// Distance to the origin
+    fn norm(&self) -> i32 {
+        self.x.abs()
+    }
+}
+
+fn main() {
+    println!("{:?}", Point::new(1));
+}
+"#;
+        let chunks = split("main.rs", content, 20);
+        assert_eq!(summary(&chunks), vec![
+            (None, "module", 1, 1),
+            (Some("Point"), "struct", 3, 7),
+            (Some("Point"), "impl", 9, 10),
+            (Some("Point::new"), "method", 12, 14),
+            (Some("Point::norm"), "method", 16, 19),
+            (Some("main"), "function", 22, 24),
+        ]);
+        assert_eq!(
+            chunks[3].text,
+            "    fn new(x: i32) -> Self {\n        Self { x }\n    }"
+        );
+
+        // The whole impl fits in a bigger chunk
+        let chunks = split("main.rs", content, 100);
+        assert!(summary(&chunks).contains(&(Some("Point"), "impl", 9, 20)));
+    }
+
+    #[test]
+    fn test_python() {
+        let content = r#"import os
+
+
+class Greeter:
+    """Greets people"""
+
+    def __init__(self, name):
+        self.name = name
+
+    @staticmethod
+    def greet(name):
+        return f"Hello {name} from {os.getcwd()} and some more words here"
+
+
+def main():
+    Greeter.greet("world")
+"#;
+        let chunks = split("greet.py", content, 15);
+        assert_eq!(summary(&chunks), vec![
+            (None, "module", 1, 1),
+            (Some("Greeter"), "class", 4, 5),
+            (Some("Greeter.__init__"), "method", 7, 8),
+            (Some("Greeter.greet"), "method", 10, 12),
+            (Some("main"), "function", 15, 16),
+        ]);
+    }
+
+    #[test]
+    fn test_typescript() {
+        let content = r#"import { readFile } from "fs";
+
+// Loads the config
+export function load(path: string): string {
+    return readFile(path);
+}
+
+export const handler = async () => {
+    return load("config.json");
+};
+
+interface Options {
+    verbose: boolean;
+}
+"#;
+        let chunks = split("index.ts", content, 50);
+        assert_eq!(summary(&chunks), vec![
+            (None, "module", 1, 1),
+            (Some("load"), "function", 3, 6),
+            (Some("handler"), "function", 8, 10),
+            (Some("Options"), "interface", 12, 14),
+        ]);
+    }
+
+    #[test]
+    fn test_go() {
+        let content = r#"package main
+
+type Server struct {
+    addr string
+}
+
+func (s *Server) Start() error {
+    return nil
+}
+
+func main() {
+}
+"#;
+        let chunks = split("main.go", content, 50);
+        assert_eq!(summary(&chunks), vec![
+            (None, "module", 1, 1),
+            (Some("Server"), "struct", 3, 5),
+            (Some("Server.Start"), "method", 7, 9),
+            (Some("main"), "function", 11, 12),
+        ]);
+    }
+
+    #[test]
+    fn test_java() {
+        let content = r#"package app;
+
+public class App {
+    private final String name;
+
+    public App(String name) {
+        this.name = name;
+    }
+
+    @Override
+    public String toString() {
+        return "App " + name;
+    }
+}
+"#;
+        let chunks = split("App.java", content, 20);
+        assert_eq!(summary(&chunks), vec![
+            (None, "module", 1, 1),
+            (Some("App"), "class", 3, 4),
+            (Some("App.App"), "constructor", 6, 8),
+            (Some("App.toString"), "method", 10, 13),
+        ]);
+    }
+
+    #[test]
+    fn test_oversized_item() {
+        let body: Vec<String> = (0..30).map(|i| format!("    let v{i} = {i};")).collect();
+        let content = format!("fn big() {{\n{}\n}}\n", body.join("\n"));
+        let chunks = split("big.rs", &content, 40);
+
+        assert!(chunks.len() > 1);
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.symbol.as_deref() == Some("big") && c.kind == "function")
+        );
+        assert_eq!(chunks[0].start_line, 1);
+        assert_eq!(chunks.last().unwrap().end_line, 32);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[1].start_line, pair[0].end_line + 1);
+        }
+    }
+}
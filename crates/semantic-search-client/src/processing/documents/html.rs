@@ -0,0 +1,337 @@
+use super::{
+    DocumentSection,
+    SectionKind,
+};
+
+/// Elements whose content is never displayed as text
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "noscript", "template", "svg"];
+
+/// Elements that start a new line
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Extract the text of an HTML document, split into sections at each heading
+///
+/// Tags are stripped and headings are kept as Markdown headings, so chunks keep the structure
+/// of the document.
+pub fn extract(html: &str) -> Vec<DocumentSection> {
+    let mut extractor = HtmlExtractor::default();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        extractor.push_text(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let end = tag_end(rest);
+        let tag = rest[1..end].trim_end_matches('>');
+        let Some((name, closing)) = tag_name(tag) else {
+            // Not a tag, e.g. a `<` in text
+            extractor.push_text("<");
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[end..];
+
+        if !closing && HIDDEN_ELEMENTS.contains(&name.as_str()) && !tag.ends_with('/') {
+            rest = skip_element(rest, &name);
+            continue;
+        }
+        extractor.tag(&name, closing);
+    }
+    extractor.push_text(rest);
+
+    extractor.finish()
+}
+
+#[derive(Default)]
+struct HtmlExtractor {
+    sections: Vec<DocumentSection>,
+    text: String,
+    title: Option<String>,
+    /// Level and text of the heading being read
+    heading: Option<(usize, String)>,
+    /// Depth of `pre` elements, whose whitespace is kept
+    preformatted: usize,
+}
+
+impl HtmlExtractor {
+    fn push_text(&mut self, raw: &str) {
+        if raw.is_empty() {
+            return;
+        }
+        let decoded = decode_entities(raw);
+        let target = match &mut self.heading {
+            Some((_, heading)) => heading,
+            None => &mut self.text,
+        };
+        if self.preformatted > 0 {
+            target.push_str(&decoded);
+            return;
+        }
+
+        // Collapse whitespace as browsers do
+        let mut space = decoded.starts_with(char::is_whitespace);
+        for word in decoded.split_whitespace() {
+            if space && !target.is_empty() && !target.ends_with(char::is_whitespace) {
+                target.push(' ');
+            }
+            target.push_str(word);
+            space = true;
+        }
+        if decoded.ends_with(char::is_whitespace) && !target.is_empty() && !target.ends_with(char::is_whitespace) {
+            target.push(' ');
+        }
+    }
+
+    fn tag(&mut self, name: &str, closing: bool) {
+        if let Some(level) = heading_level(name) {
+            if closing {
+                if let Some((level, heading)) = self.heading.take() {
+                    let heading = heading.trim().to_string();
+                    self.start_section(heading.clone());
+                    self.text.push_str(&format!("{} {}\n", "#".repeat(level), heading));
+                }
+            } else {
+                self.heading = Some((level, String::new()));
+            }
+            return;
+        }
+
+        if name == "pre" {
+            self.preformatted = if closing {
+                self.preformatted.saturating_sub(1)
+            } else {
+                self.preformatted + 1
+            };
+        }
+        if BLOCK_ELEMENTS.contains(&name) {
+            self.newline();
+            if name == "li" && !closing {
+                self.text.push_str("- ");
+            }
+        } else if matches!(name, "td" | "th") && !closing && !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\t');
+        }
+    }
+
+    fn newline(&mut self) {
+        let trimmed = self.text.trim_end_matches([' ', '\t']).len();
+        self.text.truncate(trimmed);
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+
+    fn start_section(&mut self, title: String) {
+        let text = std::mem::take(&mut self.text);
+        let previous_title = self.title.replace(title);
+        self.push_section(previous_title, text);
+    }
+
+    fn push_section(&mut self, title: Option<String>, text: String) {
+        let text = text.trim();
+        if !text.is_empty() {
+            self.sections.push(DocumentSection {
+                kind: SectionKind::Section,
+                number: self.sections.len() + 1,
+                title,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    fn finish(mut self) -> Vec<DocumentSection> {
+        if let Some((_, heading)) = self.heading.take() {
+            self.text.push_str(&heading);
+        }
+        let text = std::mem::take(&mut self.text);
+        let title = self.title.take();
+        self.push_section(title, text);
+        self.sections
+    }
+}
+
+/// Index just past the `>` ending the tag at the start of `html`, skipping quoted attributes
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => {},
+        }
+    }
+    html.len()
+}
+
+/// The lowercase name of a tag and whether it closes an element
+fn tag_name(tag: &str) -> Option<(String, bool)> {
+    let (closing, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    };
+    let name: String = tag
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let declaration = tag.starts_with('!') || tag.starts_with('?');
+    if declaration {
+        return Some((String::new(), false));
+    }
+    (!name.is_empty() && name.starts_with(|c: char| c.is_ascii_alphabetic())).then_some((name, closing))
+}
+
+/// Skip past the closing tag of a hidden element
+fn skip_element<'a>(html: &'a str, name: &str) -> &'a str {
+    let mut offset = 0;
+    while let Some(start) = html[offset..].find("</") {
+        let rest = &html[offset + start..];
+        let closes = rest
+            .as_bytes()
+            .get(2..2 + name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes()));
+        if closes {
+            return &rest[tag_end(rest)..];
+        }
+        offset += start + 2;
+    }
+    ""
+}
+
+fn heading_level(name: &str) -> Option<usize> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some((level - b'0') as usize),
+        _ => None,
+    }
+}
+
+/// Decode character references such as `&amp;`, `&#39;` and `&#x2014;`
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            },
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_sections() {
+        let html = r#"<!DOCTYPE html>
+<html>
+<head><title>Ignored</title><style>body { color: red; }</style></head>
+<body>
+  <p>Intro   text &amp; more.</p>
+  <!-- <h1>commented out</h1> -->
+  <h1 class="title">Design <em>RFC</em></h1>
+  <p>First<br>line</p>
+  <script>let heading = "<h2>not a heading</h2>";</script>
+  <h2>Open questions</h2>
+  <ul><li>One</li><li>Two &lt;three&gt;</li></ul>
+</body>
+</html>"#;
+
+        let sections = extract(html);
+        assert_eq!(sections.len(), 3);
+
+        assert_eq!(sections[0].number, 1);
+        assert_eq!(sections[0].title, None);
+        assert_eq!(sections[0].text, "Intro text & more.");
+
+        assert_eq!(sections[1].number, 2);
+        assert_eq!(sections[1].title.as_deref(), Some("Design RFC"));
+        assert_eq!(sections[1].text, "# Design RFC\nFirst\nline");
+
+        assert_eq!(sections[2].title.as_deref(), Some("Open questions"));
+        assert_eq!(sections[2].text, "## Open questions\n- One\n- Two <three>");
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &amp; b &#39;c&#x27; &unknown; & d"),
+            "a & b 'c' &unknown; & d"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+/// Tag stripping for HTML pages
+pub mod html;
+/// Text extraction from DOCX and XLSX files
+pub mod office;
+/// Text extraction from PDF files
+pub mod pdf;
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+
+/// Maximum size of a decompressed part of a document, such as the XML of a Word document
+///
+/// Documents with a larger part fail to index instead of exhausting memory.
+const MAX_PART_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A part of a document that search results can cite
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSection {
+    /// What kind of part this is
+    pub kind: SectionKind,
+    /// Number of the part in the document, starting at 1
+    pub number: usize,
+    /// Heading or sheet name of the part, if it has one
+    pub title: Option<String>,
+    /// Extracted text
+    pub text: String,
+}
+
+/// The kinds of document parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    /// A page of a PDF file
+    Page,
+    /// Text under a heading of an HTML or Word document
+    Section,
+    /// A worksheet of a spreadsheet
+    Sheet,
+}
+
+impl SectionKind {
+    /// Key of the part number in the payload of data points
+    pub fn payload_key(&self) -> &'static str {
+        match self {
+            SectionKind::Page => "page",
+            SectionKind::Section => "section",
+            SectionKind::Sheet => "sheet",
+        }
+    }
+}
+
+/// Extract the text of a PDF, HTML, DOCX or XLSX document, split into pages or sections
+///
+/// # Arguments
+///
+/// * `path` - Path to the document
+///
+/// # Returns
+///
+/// The parts of the document that contain text, in document order
+pub fn extract_document(path: &Path) -> Result<Vec<DocumentSection>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => pdf::extract(&fs::read(path)?),
+        Some("html" | "htm") => Ok(html::extract(&String::from_utf8_lossy(&fs::read(path)?))),
+        Some("docx") => office::extract_docx(path),
+        Some("xlsx") => office::extract_xlsx(path),
+        _ => Err(SemanticSearchError::InvalidArgument(format!(
+            "Unsupported document type: {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Read a decompressed part of a document as text, failing if it's larger than `limit` bytes
+fn read_part(reader: impl Read, name: &str, limit: u64) -> Result<String> {
+    let mut text = String::new();
+    reader.take(limit + 1).read_to_string(&mut text)?;
+    if text.len() as u64 > limit {
+        return Err(SemanticSearchError::InvalidArgument(format!(
+            "{} is larger than {} bytes",
+            name, limit
+        )));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_part() {
+        assert_eq!(read_part(&b"<w:p/>"[..], "part", 6).unwrap(), "<w:p/>");
+        assert!(matches!(
+            read_part(&b"<w:p/>"[..], "part", 5),
+            Err(SemanticSearchError::InvalidArgument(_))
+        ));
+    }
+}
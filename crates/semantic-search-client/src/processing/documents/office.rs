@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use quick_xml::Reader;
+use quick_xml::events::{
+    BytesRef,
+    BytesStart,
+    Event,
+};
+use zip::ZipArchive;
+
+use super::{
+    DocumentSection,
+    MAX_PART_BYTES,
+    SectionKind,
+    read_part,
+};
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+
+type Archive = ZipArchive<BufReader<File>>;
+
+/// Extract the text of a Word document, split into sections at each heading
+///
+/// Headings are kept as Markdown headings, so chunks keep the structure of the document.
+pub fn extract_docx(path: &Path) -> Result<Vec<DocumentSection>> {
+    let mut archive = open(path)?;
+    let xml =
+        read_entry(&mut archive, "word/document.xml")?.ok_or_else(|| invalid(path, "word/document.xml is missing"))?;
+
+    let mut sections = Vec::new();
+    let mut title = None;
+    let mut text = String::new();
+    let mut paragraph = String::new();
+    let mut heading_level = None;
+    let mut in_text = false;
+
+    let mut reader = Reader::from_str(&xml);
+    loop {
+        match reader.read_event().map_err(|e| invalid(path, e))? {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(e) if e.local_name().as_ref() == b"t" => in_text = false,
+            Event::Start(e) if e.local_name().as_ref() == b"p" => {
+                paragraph.clear();
+                heading_level = None;
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"pStyle" => heading_level = heading_level.or(attribute(&e, b"val").and_then(style_heading_level)),
+                b"outlineLvl" => {
+                    heading_level = attribute(&e, b"val")
+                        .and_then(|level| level.parse::<usize>().ok())
+                        .filter(|level| *level < 9)
+                        .map(|level| level + 1);
+                },
+                b"tab" => paragraph.push('\t'),
+                b"br" | b"cr" => paragraph.push('\n'),
+                _ => {},
+            },
+            Event::Text(e) if in_text => paragraph.push_str(&e.xml_content().map_err(|e| invalid(path, e))?),
+            Event::GeneralRef(e) if in_text => push_reference(&mut paragraph, &e),
+            Event::End(e) if e.local_name().as_ref() == b"p" => {
+                let content = paragraph.trim();
+                match heading_level {
+                    Some(level) if !content.is_empty() => {
+                        push_section(&mut sections, SectionKind::Section, title.take(), &text);
+                        text = format!("{} {}\n", "#".repeat(level), content);
+                        title = Some(content.to_string());
+                    },
+                    _ => {
+                        text.push_str(&paragraph);
+                        text.push('\n');
+                    },
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    push_section(&mut sections, SectionKind::Section, title, &text);
+
+    Ok(sections)
+}
+
+/// Extract the cell values of an Excel workbook, one section per sheet
+///
+/// Each row becomes a line with tab-separated cells.
+pub fn extract_xlsx(path: &Path) -> Result<Vec<DocumentSection>> {
+    let mut archive = open(path)?;
+    let shared_strings = match read_entry(&mut archive, "xl/sharedStrings.xml")? {
+        Some(xml) => read_shared_strings(&xml).map_err(|e| invalid(path, e))?,
+        None => Vec::new(),
+    };
+    let workbook =
+        read_entry(&mut archive, "xl/workbook.xml")?.ok_or_else(|| invalid(path, "xl/workbook.xml is missing"))?;
+    let relationships = match read_entry(&mut archive, "xl/_rels/workbook.xml.rels")? {
+        Some(xml) => read_relationships(&xml).map_err(|e| invalid(path, e))?,
+        None => HashMap::new(),
+    };
+
+    let mut sections = Vec::new();
+    for (number, (name, relationship)) in read_sheets(&workbook)
+        .map_err(|e| invalid(path, e))?
+        .into_iter()
+        .enumerate()
+    {
+        let target = relationships
+            .get(&relationship)
+            .cloned()
+            .unwrap_or_else(|| format!("worksheets/sheet{}.xml", number + 1));
+        let entry = match target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("xl/{}", target),
+        };
+        let Some(xml) = read_entry(&mut archive, &entry)? else {
+            continue;
+        };
+        let text = read_sheet(&xml, &shared_strings).map_err(|e| invalid(path, e))?;
+        if !text.trim().is_empty() {
+            sections.push(DocumentSection {
+                kind: SectionKind::Sheet,
+                number: number + 1,
+                title: Some(name),
+                text: text.trim().to_string(),
+            });
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Names and relationship ids of the sheets of a workbook, in order
+fn read_sheets(xml: &str) -> quick_xml::Result<Vec<(String, String)>> {
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sheet" => {
+                let name = attribute(&e, b"name").unwrap_or_default();
+                let id = attribute(&e, b"id").unwrap_or_default();
+                sheets.push((name, id));
+            },
+            Event::Eof => return Ok(sheets),
+            _ => {},
+        }
+    }
+}
+
+/// Targets of the relationships of a workbook, keyed by relationship id
+fn read_relationships(xml: &str) -> quick_xml::Result<HashMap<String, String>> {
+    let mut relationships = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attribute(&e, b"Id"), attribute(&e, b"Target")) {
+                    relationships.insert(id, target);
+                }
+            },
+            Event::Eof => return Ok(relationships),
+            _ => {},
+        }
+    }
+}
+
+/// The shared string table that cells of type `s` index into
+fn read_shared_strings(xml: &str) -> quick_xml::Result<Vec<String>> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+    // Phonetic guides repeat the text in another script
+    let mut in_phonetic = false;
+
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"si" => current.clear(),
+                b"t" => in_text = !in_phonetic,
+                b"rPh" => in_phonetic = true,
+                _ => {},
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"si" => strings.push(std::mem::take(&mut current)),
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                _ => {},
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"si" => strings.push(String::new()),
+            Event::Text(e) if in_text => current.push_str(&e.xml_content()?),
+            Event::GeneralRef(e) if in_text => push_reference(&mut current, &e),
+            Event::Eof => return Ok(strings),
+            _ => {},
+        }
+    }
+}
+
+/// The rows of a sheet as lines of tab-separated cell values
+fn read_sheet(xml: &str, shared_strings: &[String]) -> quick_xml::Result<String> {
+    let mut text = String::new();
+    let mut row = Vec::new();
+    let mut cell_type = None;
+    let mut value = String::new();
+    let mut in_value = false;
+
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"row" => row.clear(),
+                b"c" => {
+                    cell_type = attribute(&e, b"t");
+                    value.clear();
+                },
+                b"v" | b"t" => in_value = true,
+                _ => {},
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"c" => {
+                    let cell = match cell_type.as_deref() {
+                        Some("s") => value
+                            .trim()
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|index| shared_strings.get(index))
+                            .cloned()
+                            .unwrap_or_default(),
+                        Some("b") => (if value.trim() == "1" { "TRUE" } else { "FALSE" }).to_string(),
+                        _ => value.trim().to_string(),
+                    };
+                    row.push(cell);
+                },
+                b"row" => {
+                    while row.last().is_some_and(|cell| cell.is_empty()) {
+                        row.pop();
+                    }
+                    if !row.is_empty() {
+                        text.push_str(&row.join("\t"));
+                        text.push('\n');
+                    }
+                },
+                _ => {},
+            },
+            Event::Text(e) if in_value => value.push_str(&e.xml_content()?),
+            Event::GeneralRef(e) if in_value => push_reference(&mut value, &e),
+            Event::Eof => return Ok(text),
+            _ => {},
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<Archive> {
+    ZipArchive::new(BufReader::new(File::open(path)?)).map_err(|e| invalid(path, e))
+}
+
+/// Read an entry of the archive as text, or `None` if it doesn't exist
+fn read_entry(archive: &mut Archive, name: &str) -> Result<Option<String>> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => {
+            return Err(SemanticSearchError::OperationFailed(format!(
+                "Failed to read {}: {}",
+                name, e
+            )));
+        },
+    };
+    let xml = read_part(entry, name, MAX_PART_BYTES)?;
+    Ok(Some(xml))
+}
+
+/// The unescaped value of an attribute, matched by local name
+fn attribute(element: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.local_name().as_ref() == name)
+        .and_then(|attribute| attribute.unescape_value().ok().map(|value| value.to_string()))
+}
+
+/// The heading level of built-in paragraph styles such as `Heading1` and `Title`
+fn style_heading_level(style: String) -> Option<usize> {
+    let style = style.to_ascii_lowercase();
+    if style == "title" {
+        return Some(1);
+    }
+    style
+        .strip_prefix("heading")
+        .and_then(|level| level.trim().parse::<usize>().ok())
+        .filter(|level| (1..=9).contains(level))
+}
+
+/// Append the text of an entity or character reference
+fn push_reference(text: &mut String, reference: &BytesRef<'_>) {
+    if let Ok(Some(c)) = reference.resolve_char_ref() {
+        text.push(c);
+    } else if let Some(resolved) = reference
+        .decode()
+        .ok()
+        .and_then(|name| quick_xml::escape::resolve_predefined_entity(&name))
+    {
+        text.push_str(resolved);
+    }
+}
+
+fn push_section(sections: &mut Vec<DocumentSection>, kind: SectionKind, title: Option<String>, text: &str) {
+    let text = text.trim();
+    if !text.is_empty() {
+        sections.push(DocumentSection {
+            kind,
+            number: sections.len() + 1,
+            title,
+            text: text.to_string(),
+        });
+    }
+}
+
+fn invalid(path: &Path, error: impl std::fmt::Display) -> SemanticSearchError {
+    SemanticSearchError::OperationFailed(format!("Failed to read document {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    /// Write a zip archive with the given entries
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_extract_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("design.docx");
+        write_zip(&path, &[(
+            "word/document.xml",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:r><w:t>Draft &amp; notes</w:t></w:r></w:p>
+    <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Overview</w:t></w:r></w:p>
+    <w:p><w:r><w:t xml:space="preserve">Split </w:t></w:r><w:r><w:t>runs</w:t></w:r></w:p>
+    <w:p><w:pPr><w:outlineLvl w:val="1"/></w:pPr><w:r><w:t>Details</w:t></w:r></w:p>
+    <w:p><w:r><w:t>Cell</w:t><w:tab/><w:t>value</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#,
+        )]);
+
+        let sections = extract_docx(&path).unwrap();
+        let summary: Vec<_> = sections
+            .iter()
+            .map(|section| (section.number, section.title.as_deref(), section.text.as_str()))
+            .collect();
+        assert_eq!(summary, vec![
+            (1, None, "Draft & notes"),
+            (2, Some("Overview"), "# Overview\nSplit runs"),
+            (3, Some("Details"), "## Details\nCell\tvalue"),
+        ]);
+    }
+
+    #[test]
+    fn test_extract_xlsx() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("budget.xlsx");
+        write_zip(&path, &[
+            (
+                "xl/workbook.xml",
+                r#"<workbook xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>
+                    <sheet name="Summary" sheetId="1" r:id="rId2"/>
+                    <sheet name="Empty" sheetId="2" r:id="rId1"/>
+                </sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships>
+                    <Relationship Id="rId1" Target="worksheets/sheet1.xml"/>
+                    <Relationship Id="rId2" Target="worksheets/sheet2.xml"/>
+                </Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Item</t></si><si><r><t>Co</t></r><r><t>st</t></r></si><si><t>R&amp;D</t></si></sst>"#,
+            ),
+            ("xl/worksheets/sheet1.xml", r#"<worksheet><sheetData/></worksheet>"#),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData>
+                    <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row>
+                    <row r="2"><c r="A2" t="s"><v>2</v></c><c r="B2"><v>1250.5</v></c><c r="C2" t="b"><v>1</v></c></row>
+                    <row r="3"><c r="A3" t="inlineStr"><is><t>Total</t></is></c></row>
+                </sheetData></worksheet>"#,
+            ),
+        ]);
+
+        let sections = extract_xlsx(&path).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].kind, SectionKind::Sheet);
+        assert_eq!(sections[0].number, 1);
+        assert_eq!(sections[0].title.as_deref(), Some("Summary"));
+        assert_eq!(sections[0].text, "Item\tCost\nR&D\t1250.5\tTRUE\nTotal");
+    }
+}
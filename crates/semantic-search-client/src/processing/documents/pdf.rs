@@ -0,0 +1,169 @@
+use std::panic::{
+    self,
+    AssertUnwindSafe,
+};
+
+use pdf_extract::{
+    Document,
+    PlainTextOutput,
+};
+use tracing::warn;
+
+use super::{
+    DocumentSection,
+    SectionKind,
+};
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+
+/// Extract the text of each page of a PDF file
+///
+/// Text is read with `pdf-extract`, which lays out the text drawn by page content streams and maps
+/// character codes to Unicode with the fonts' encodings. Encrypted files aren't supported, text in
+/// images isn't recognized, and pages whose text can't be extracted are skipped.
+pub fn extract(data: &[u8]) -> Result<Vec<DocumentSection>> {
+    let document = Document::load_mem(data)
+        .map_err(|e| SemanticSearchError::InvalidArgument(format!("Not a valid PDF file: {}", e)))?;
+    if document.is_encrypted() {
+        return Err(SemanticSearchError::InvalidArgument(
+            "Encrypted PDF files are not supported".to_string(),
+        ));
+    }
+
+    let mut sections = Vec::new();
+    for number in document.get_pages().into_keys() {
+        let Some(text) = page_text(&document, number) else {
+            continue;
+        };
+        let text = text.trim();
+        if !text.is_empty() {
+            sections.push(DocumentSection {
+                kind: SectionKind::Page,
+                number: number as usize,
+                title: None,
+                text: text.to_string(),
+            });
+        }
+    }
+    Ok(sections)
+}
+
+/// The text of a page, or `None` if it can't be extracted
+fn page_text(document: &Document, number: u32) -> Option<String> {
+    let mut text = String::new();
+    // pdf-extract panics on some malformed pages rather than returning an error, which must not
+    // abort indexing the other pages and files
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::output_doc_page(document, &mut PlainTextOutput::new(&mut text), number)
+    }));
+    match result {
+        Ok(Ok(())) => Some(text),
+        Ok(Err(e)) => {
+            warn!("Failed to extract the text of PDF page {}: {}", number, e);
+            None
+        },
+        Err(_) => {
+            warn!("Failed to extract the text of malformed PDF page {}", number);
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pdf_extract::content::{
+        Content,
+        Operation,
+    };
+    use pdf_extract::{
+        Object,
+        Stream,
+        dictionary,
+    };
+
+    use super::*;
+
+    /// Build a PDF file with a page for each list of lines, drawn with a standard font
+    fn pdf(pages: &[&[&str]]) -> Vec<u8> {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+
+        let mut kids = Vec::new();
+        for lines in pages {
+            let mut operations = vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 720.into()]),
+            ];
+            for line in *lines {
+                operations.push(Operation::new("Tj", vec![Object::string_literal(*line)]));
+                operations.push(Operation::new("Td", vec![0.into(), (-14).into()]));
+            }
+            operations.push(Operation::new("ET", vec![]));
+            let content = Content { operations }.encode().unwrap();
+            let content_id = document.add_object(Stream::new(dictionary! {}, content));
+            kids.push(
+                document
+                    .add_object(dictionary! {
+                        "Type" => "Page",
+                        "Parent" => pages_id,
+                        "Contents" => content_id,
+                    })
+                    .into(),
+            );
+        }
+
+        document.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }),
+        );
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let mut data = Vec::new();
+        document.save_to(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_extract_pages() {
+        let data = pdf(&[&["Design (draft)", "Open issues"], &[], &["Appendix"]]);
+
+        let sections = extract(&data).unwrap();
+        let pages: Vec<_> = sections
+            .iter()
+            .map(|section| {
+                let words = section.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                (section.kind, section.number, words)
+            })
+            .collect();
+        assert_eq!(pages, vec![
+            (SectionKind::Page, 1, "Design (draft) Open issues".to_string()),
+            (SectionKind::Page, 3, "Appendix".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_files() {
+        assert!(extract(b"not a pdf").is_err());
+
+        let data = pdf(&[&["truncated"]]);
+        assert!(extract(&data[..data.len() / 2]).is_err());
+    }
+}
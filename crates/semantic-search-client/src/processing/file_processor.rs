@@ -1,12 +1,18 @@
 use std::fs;
 use std::path::Path;
 
+use chrono::{
+    DateTime,
+    Utc,
+};
 use serde_json::Value;
 
 use crate::error::{
     Result,
     SemanticSearchError,
 };
+use crate::processing::code_chunker::chunk_code;
+use crate::processing::documents::extract_document;
 use crate::processing::text_chunker::chunk_text;
 use crate::types::FileType;
 
@@ -42,6 +48,9 @@ pub fn get_file_type(path: &Path) -> FileType {
         // Web and markup formats (text-based)
         Some("svg") => FileType::Text,
 
+        // Documents whose text is extracted before indexing
+        Some("pdf" | "html" | "htm" | "docx" | "xlsx") => FileType::Document,
+
         // Code file extensions
         Some("rs") => FileType::Code,
         Some("py") => FileType::Code,
@@ -55,7 +64,7 @@ pub fn get_file_type(path: &Path) -> FileType {
         Some("kt" | "kts") => FileType::Code,
         Some("cs") => FileType::Code,
         Some("sh" | "bash" | "zsh") => FileType::Code,
-        Some("html" | "htm" | "xml") => FileType::Code,
+        Some("xml") => FileType::Code,
         Some("css" | "scss" | "sass" | "less") => FileType::Code,
         Some("sql") => FileType::Code,
         Some("yaml" | "yml") => FileType::Code,
@@ -71,7 +80,7 @@ pub fn get_file_type(path: &Path) -> FileType {
             _ => FileType::Unknown,
         },
 
-        // Default to unknown (includes office docs, PDFs, etc.)
+        // Default to unknown (includes images, archives, etc.)
         _ => FileType::Unknown,
     }
 }
@@ -113,18 +122,32 @@ pub fn process_file_with_config(
     }
 
     let file_type = get_file_type(path);
-    let content = fs::read_to_string(path).map_err(|e| {
+    // Stored with every data point so that searches can filter on recency
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|modified| Value::String(DateTime::<Utc>::from(modified).to_rfc3339()));
+    let read_error = |e: std::io::Error| {
         SemanticSearchError::IoError(std::io::Error::new(
             e.kind(),
             format!("Failed to read file {}: {}", path.display(), e),
         ))
-    })?;
+    };
 
     match file_type {
         FileType::Text | FileType::Markdown | FileType::Code | FileType::Json => {
+            let content = fs::read_to_string(path).map_err(read_error)?;
             // For text-based files (including JSON), chunk the content and create multiple data points
             // Use the configured chunk size and overlap
-            let chunks = chunk_text(&content, chunk_size, chunk_overlap);
+            // Source files in supported languages are split on item boundaries instead
+            let code_chunks = match file_type {
+                FileType::Code => chunk_code(path, &content, chunk_size, chunk_overlap),
+                _ => None,
+            };
+            let chunks = match &code_chunks {
+                Some(code_chunks) => code_chunks.iter().map(|chunk| chunk.text.clone()).collect(),
+                None => chunk_text(&content, chunk_size, chunk_overlap),
+            };
             let path_str = path.to_string_lossy().to_string();
             let file_type_str = format!("{:?}", file_type);
 
@@ -137,6 +160,9 @@ pub fn process_file_with_config(
                 metadata.insert("file_type".to_string(), Value::String(file_type_str.clone()));
                 metadata.insert("chunk_index".to_string(), Value::Number((i as u64).into()));
                 metadata.insert("total_chunks".to_string(), Value::Number((chunks.len() as u64).into()));
+                if let Some(modified) = &modified {
+                    metadata.insert("modified".to_string(), modified.clone());
+                }
 
                 // For code files, add additional metadata
                 if file_type == FileType::Code {
@@ -151,6 +177,16 @@ pub fn process_file_with_config(
                     );
                 }
 
+                if let Some(code_chunk) = code_chunks.as_ref().and_then(|code_chunks| code_chunks.get(i)) {
+                    metadata.insert(
+                        "symbol".to_string(),
+                        code_chunk.symbol.clone().map_or(Value::Null, Value::String),
+                    );
+                    metadata.insert("kind".to_string(), Value::String(code_chunk.kind.clone()));
+                    metadata.insert("start_line".to_string(), Value::Number(code_chunk.start_line.into()));
+                    metadata.insert("end_line".to_string(), Value::Number(code_chunk.end_line.into()));
+                }
+
                 results.push(Value::Object(metadata));
             }
 
@@ -162,6 +198,64 @@ pub fn process_file_with_config(
                 metadata.insert("file_type".to_string(), Value::String(file_type_str));
                 metadata.insert("chunk_index".to_string(), Value::Number(0.into()));
                 metadata.insert("total_chunks".to_string(), Value::Number(1.into()));
+                if let Some(modified) = modified {
+                    metadata.insert("modified".to_string(), modified);
+                }
+
+                results.push(Value::Object(metadata));
+            }
+
+            Ok(results)
+        },
+        FileType::Document => {
+            // Chunk each page or section separately, so that results can cite where they were found
+            let sections = extract_document(path).map_err(|e| match e {
+                SemanticSearchError::IoError(e) => read_error(e),
+                e => e,
+            })?;
+            let chunks: Vec<_> = sections
+                .iter()
+                .flat_map(|section| {
+                    chunk_text(&section.text, chunk_size, chunk_overlap)
+                        .into_iter()
+                        .map(move |chunk| (section, chunk))
+                })
+                .collect();
+            let path_str = path.to_string_lossy().to_string();
+
+            let mut results = Vec::new();
+            for (i, (section, chunk)) in chunks.iter().enumerate() {
+                let mut metadata = serde_json::Map::new();
+                metadata.insert("text".to_string(), Value::String(chunk.clone()));
+                metadata.insert("path".to_string(), Value::String(path_str.clone()));
+                metadata.insert("file_type".to_string(), Value::String("Document".to_string()));
+                metadata.insert("chunk_index".to_string(), Value::Number((i as u64).into()));
+                metadata.insert("total_chunks".to_string(), Value::Number((chunks.len() as u64).into()));
+                if let Some(modified) = &modified {
+                    metadata.insert("modified".to_string(), modified.clone());
+                }
+                metadata.insert(
+                    section.kind.payload_key().to_string(),
+                    Value::Number((section.number as u64).into()),
+                );
+                if let Some(title) = &section.title {
+                    metadata.insert("section_title".to_string(), Value::String(title.clone()));
+                }
+
+                results.push(Value::Object(metadata));
+            }
+
+            // If no text was extracted (e.g. a scanned PDF), create at least one entry
+            if results.is_empty() {
+                let mut metadata = serde_json::Map::new();
+                metadata.insert("text".to_string(), Value::String(String::new()));
+                metadata.insert("path".to_string(), Value::String(path_str));
+                metadata.insert("file_type".to_string(), Value::String("Document".to_string()));
+                metadata.insert("chunk_index".to_string(), Value::Number(0.into()));
+                metadata.insert("total_chunks".to_string(), Value::Number(1.into()));
+                if let Some(modified) = modified {
+                    metadata.insert("modified".to_string(), modified);
+                }
 
                 results.push(Value::Object(metadata));
             }
@@ -173,6 +267,9 @@ pub fn process_file_with_config(
             let mut metadata = serde_json::Map::new();
             metadata.insert("path".to_string(), Value::String(path.to_string_lossy().to_string()));
             metadata.insert("file_type".to_string(), Value::String("Unknown".to_string()));
+            if let Some(modified) = modified {
+                metadata.insert("modified".to_string(), modified);
+            }
 
             Ok(vec![Value::Object(metadata)])
         },
@@ -240,7 +337,7 @@ mod tests {
             ("component.tsx", FileType::Code),
             ("Main.java", FileType::Code),
             ("main.c", FileType::Code),
-            ("index.html", FileType::Code),
+            ("config.xml", FileType::Code),
             ("styles.css", FileType::Code),
             ("config.yaml", FileType::Code),
             ("Cargo.toml", FileType::Code),
@@ -260,9 +357,14 @@ mod tests {
             ("Main.RS", FileType::Code),
             ("README.MD", FileType::Markdown),
             ("notes.TXT", FileType::Text),
+            // Documents
+            ("document.pdf", FileType::Document),
+            ("index.html", FileType::Document),
+            ("report.docx", FileType::Document),
+            ("budget.XLSX", FileType::Document),
             // Unknown files
             ("image.png", FileType::Unknown),
-            ("document.pdf", FileType::Unknown),
+            ("slides.pptx", FileType::Unknown),
             ("binary.exe", FileType::Unknown),
             ("unknown_file", FileType::Unknown),
         ];
@@ -281,9 +383,70 @@ mod tests {
     fn test_unknown_file_types() {
         // Binary files and unsupported formats
         assert_eq!(get_file_type(&PathBuf::from("image.png")), FileType::Unknown);
-        assert_eq!(get_file_type(&PathBuf::from("document.pdf")), FileType::Unknown);
+        assert_eq!(get_file_type(&PathBuf::from("document.doc")), FileType::Unknown);
         assert_eq!(get_file_type(&PathBuf::from("archive.zip")), FileType::Unknown);
         assert_eq!(get_file_type(&PathBuf::from("binary.exe")), FileType::Unknown);
         assert_eq!(get_file_type(&PathBuf::from("data.db")), FileType::Unknown);
     }
+
+    #[test]
+    fn test_code_chunk_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        fs::write(
+            &path,
+            "use std::fmt;\n\nfn first() {}\n\nfn second() {\n    first();\n}\n",
+        )
+        .unwrap();
+
+        let items = process_file_with_config(&path, Some(50), Some(0)).unwrap();
+        let fields: Vec<_> = items
+            .iter()
+            .map(|item| {
+                (
+                    item["symbol"].clone(),
+                    item["kind"].clone(),
+                    item["start_line"].clone(),
+                    item["end_line"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(fields, vec![
+            (Value::Null, "module".into(), 1.into(), 1.into()),
+            ("first".into(), "function".into(), 3.into(), 3.into()),
+            ("second".into(), "function".into(), 5.into(), 7.into()),
+        ]);
+        assert_eq!(items[2]["text"], "fn second() {\n    first();\n}");
+        assert_eq!(items[2]["total_chunks"], 3);
+    }
+
+    #[test]
+    fn test_document_section_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("guide.html");
+        fs::write(
+            &path,
+            "<p>Welcome.</p><h1>Install</h1><p>Run the installer.</p><h2>Upgrade</h2><p>Run it again.</p>",
+        )
+        .unwrap();
+
+        let items = process_file(&path).unwrap();
+        let fields: Vec<_> = items
+            .iter()
+            .map(|item| {
+                (
+                    item["section"].clone(),
+                    item["section_title"].clone(),
+                    item["file_type"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(fields, vec![
+            (1.into(), Value::Null, "Document".into()),
+            (2.into(), "Install".into(), "Document".into()),
+            (3.into(), "Upgrade".into(), "Document".into()),
+        ]);
+        assert_eq!(items[2]["text"], "## Upgrade Run it again.");
+        assert_eq!(items[2]["total_chunks"], 3);
+    }
 }
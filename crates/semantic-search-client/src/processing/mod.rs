@@ -1,8 +1,16 @@
+/// Syntax-aware chunking of source files
+pub mod code_chunker;
+/// Text extraction from PDF, HTML and Office documents
+pub mod documents;
 /// File processing utilities for handling different file types and extracting content
 pub mod file_processor;
 /// Text chunking utilities for breaking down text into manageable pieces for embedding
 pub mod text_chunker;
 
+pub use code_chunker::{
+    CodeChunk,
+    chunk_code,
+};
 pub use file_processor::{
     get_file_type,
     process_directory,
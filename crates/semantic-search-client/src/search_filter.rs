@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+use crate::pattern_filter::PatternFilter;
+use crate::types::FileType;
+
+/// Restrictions on which data points a search may return
+///
+/// Filters are matched against data point payloads while the index is queried, so a limit of `k`
+/// returns the `k` best matches among the data points that pass the filter. Each non-empty field
+/// must match; values within a field are alternatives.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilter {
+    /// Glob patterns matched against the source file path, e.g. `src/**/*.rs`
+    pub path_globs: Vec<String>,
+    /// File types of the source file
+    pub file_types: Vec<FileType>,
+    /// File extensions of the source file, without the leading dot
+    pub extensions: Vec<String>,
+    /// Only files last modified after this time
+    pub modified_after: Option<DateTime<Utc>>,
+}
+
+impl SearchFilter {
+    /// Whether the filter lets every data point through
+    pub fn is_empty(&self) -> bool {
+        self.path_globs.is_empty()
+            && self.file_types.is_empty()
+            && self.extensions.is_empty()
+            && self.modified_after.is_none()
+    }
+
+    /// Compile the filter, validating its glob patterns
+    pub fn matcher(&self) -> Result<PayloadMatcher> {
+        let paths = PatternFilter::new(&self.path_globs, &[]).map_err(SemanticSearchError::InvalidArgument)?;
+        Ok(PayloadMatcher {
+            paths,
+            file_types: self
+                .file_types
+                .iter()
+                .map(|file_type| format!("{:?}", file_type))
+                .collect(),
+            extensions: self.extensions.iter().map(|ext| normalize_extension(ext)).collect(),
+            modified_after: self.modified_after,
+        })
+    }
+}
+
+/// A compiled [`SearchFilter`] that checks data point payloads
+#[derive(Debug, Clone)]
+pub struct PayloadMatcher {
+    paths: PatternFilter,
+    file_types: Vec<String>,
+    extensions: Vec<String>,
+    modified_after: Option<DateTime<Utc>>,
+}
+
+impl PayloadMatcher {
+    /// Whether a data point with this payload passes the filter
+    ///
+    /// Payloads without the field a filter needs, such as the `modified` time of data points
+    /// indexed by older versions, don't match it.
+    pub fn matches(&self, payload: &HashMap<String, Value>) -> bool {
+        self.matches_metadata(&PointMetadata::from_payload(payload))
+    }
+
+    /// Whether a data point with this metadata passes the filter
+    pub fn matches_metadata(&self, metadata: &PointMetadata) -> bool {
+        let path = metadata.path.as_deref().map(Path::new);
+
+        if !self.file_types.is_empty()
+            && !metadata
+                .file_type
+                .as_ref()
+                .is_some_and(|file_type| self.file_types.contains(file_type))
+        {
+            return false;
+        }
+
+        if !self.extensions.is_empty() {
+            let extension = path
+                .and_then(Path::extension)
+                .map(|ext| normalize_extension(&ext.to_string_lossy()));
+            if !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return false;
+            }
+        }
+
+        if let Some(modified_after) = self.modified_after {
+            if metadata.modified.is_none_or(|modified| modified <= modified_after) {
+                return false;
+            }
+        }
+
+        self.paths.should_include(path.unwrap_or(Path::new("")))
+    }
+}
+
+/// The payload fields of a data point that filters look at
+///
+/// Contexts keep these in memory so that filtered searches don't decode every payload.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointMetadata {
+    path: Option<String>,
+    file_type: Option<String>,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl PointMetadata {
+    /// Extract the metadata of a data point from its payload
+    pub fn from_payload(payload: &HashMap<String, Value>) -> Self {
+        let field = |key: &str| payload.get(key).and_then(Value::as_str);
+        Self {
+            path: field("path").map(str::to_string),
+            file_type: field("file_type").map(str::to_string),
+            modified: field("modified")
+                .and_then(|modified| DateTime::parse_from_rfc3339(modified).ok())
+                .map(|modified| modified.with_timezone(&Utc)),
+        }
+    }
+}
+
+fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use super::*;
+
+    fn payload(path: &str, file_type: &str, modified: Option<&str>) -> HashMap<String, Value> {
+        let mut payload = HashMap::from([
+            ("path".to_string(), json!(path)),
+            ("file_type".to_string(), json!(file_type)),
+        ]);
+        if let Some(modified) = modified {
+            payload.insert("modified".to_string(), json!(modified));
+        }
+        payload
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = SearchFilter::default();
+        assert!(filter.is_empty());
+        let matcher = filter.matcher().unwrap();
+        assert!(matcher.matches(&payload("/repo/src/main.rs", "Code", None)));
+        assert!(matcher.matches(&HashMap::new()));
+    }
+
+    #[test]
+    fn test_path_globs_and_extensions() {
+        let matcher = SearchFilter {
+            path_globs: vec!["src/**".to_string()],
+            extensions: vec![".RS".to_string(), "toml".to_string()],
+            ..Default::default()
+        }
+        .matcher()
+        .unwrap();
+
+        assert!(matcher.matches(&payload("/repo/src/main.rs", "Code", None)));
+        assert!(matcher.matches(&payload("/repo/src/nested/Cargo.toml", "Text", None)));
+        assert!(!matcher.matches(&payload("/repo/src/README.md", "Markdown", None)));
+        assert!(!matcher.matches(&payload("/repo/tests/main.rs", "Code", None)));
+    }
+
+    #[test]
+    fn test_file_types() {
+        let matcher = SearchFilter {
+            file_types: vec![FileType::Markdown, FileType::Text],
+            ..Default::default()
+        }
+        .matcher()
+        .unwrap();
+
+        assert!(matcher.matches(&payload("/repo/README.md", "Markdown", None)));
+        assert!(matcher.matches(&payload("/repo/notes.txt", "Text", None)));
+        assert!(!matcher.matches(&payload("/repo/src/main.rs", "Code", None)));
+    }
+
+    #[test]
+    fn test_modified_after() {
+        let matcher = SearchFilter {
+            modified_after: Some(Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        }
+        .matcher()
+        .unwrap();
+
+        assert!(matcher.matches(&payload("/a.rs", "Code", Some("2025-07-01T12:00:00+00:00"))));
+        assert!(!matcher.matches(&payload("/a.rs", "Code", Some("2025-05-31T23:59:59Z"))));
+        assert!(!matcher.matches(&payload("/a.rs", "Code", None)));
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        let filter = SearchFilter {
+            path_globs: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(filter.matcher(), Err(SemanticSearchError::InvalidArgument(_))));
+    }
+}
@@ -14,6 +14,7 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use tokio::sync::oneshot;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
@@ -34,6 +35,10 @@ pub struct AddContextRequest {
     pub exclude_patterns: Option<Vec<String>>,
     /// Optional embedding type override for this context
     pub embedding_type: Option<EmbeddingType>,
+    /// Whether to keep the context up to date with changes to `path`
+    pub watch: bool,
+    /// Whether to rerank search results of the context with a cross-encoder
+    pub rerank: bool,
 }
 
 /// Parameters for indexing operations (internal use)
@@ -56,6 +61,10 @@ pub struct IndexingParams {
     pub exclude_patterns: Option<Vec<String>>,
     /// Optional embedding type override (uses client default if None)
     pub embedding_type: Option<EmbeddingType>,
+    /// Whether to keep the context up to date with changes to `path`
+    pub watch: bool,
+    /// Whether to rerank search results of the context with a cross-encoder
+    pub rerank: bool,
 }
 
 use crate::client::context::SemanticContext;
@@ -107,6 +116,14 @@ pub struct KnowledgeContext {
     /// Embedding type used for this context
     #[serde(default)]
     pub embedding_type: EmbeddingType,
+
+    /// Whether the source path is watched for changes
+    #[serde(default)]
+    pub watched: bool,
+
+    /// Whether search results are reranked with a cross-encoder
+    #[serde(default)]
+    pub rerank: bool,
 }
 
 impl KnowledgeContext {
@@ -135,6 +152,8 @@ impl KnowledgeContext {
             persistent,
             item_count,
             embedding_type,
+            watched: false,
+            rerank: false,
         }
     }
 }
@@ -173,22 +192,70 @@ pub struct SearchResult {
 
     /// Distance/similarity score (lower is better)
     pub distance: f32,
+
+    /// Reciprocal-rank fusion score for hybrid contexts, from 0.0 to 1.0 (higher is better)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fused_score: Option<f32>,
+
+    /// Cross-encoder relevance for reranked results, from 0.0 to 1.0 (higher is better)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
 }
 
 impl SearchResult {
     /// Create a new search result
     pub fn new(point: DataPoint, distance: f32) -> Self {
-        Self { point, distance }
+        Self {
+            point,
+            distance,
+            fused_score: None,
+            rerank_score: None,
+        }
+    }
+
+    /// Create a search result from a hybrid context, with a distance of `1 - fused_score`
+    pub fn fused(point: DataPoint, fused_score: f32) -> Self {
+        Self {
+            point,
+            distance: 1.0 - fused_score,
+            fused_score: Some(fused_score),
+            rerank_score: None,
+        }
+    }
+
+    /// Rescore this result with a cross-encoder logit, with a distance of `1 - rerank_score`
+    pub fn reranked(self, logit: f32) -> Self {
+        let rerank_score = 1.0 / (1.0 + (-logit).exp());
+        Self {
+            distance: 1.0 - rerank_score,
+            rerank_score: Some(rerank_score),
+            ..self
+        }
     }
 
     /// Get the text content of this result
     pub fn text(&self) -> Option<&str> {
         self.point.payload.get("text").and_then(|v| v.as_str())
     }
+
+    /// Where in its document this result was found, e.g. `guide.pdf, page 3`
+    ///
+    /// Only results from extracted documents have a page, section or sheet to cite.
+    pub fn citation(&self) -> Option<String> {
+        let path = self.point.payload.get("path").and_then(|v| v.as_str())?;
+        let (kind, number) = ["page", "section", "sheet"]
+            .into_iter()
+            .find_map(|kind| Some((kind, self.point.payload.get(kind)?.as_u64()?)))?;
+        let mut citation = format!("{}, {} {}", path, kind, number);
+        if let Some(title) = self.point.payload.get("section_title").and_then(|v| v.as_str()) {
+            citation.push_str(&format!(" ({})", title));
+        }
+        Some(citation)
+    }
 }
 
 /// File type for processing
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileType {
     /// Plain text file
     Text,
@@ -198,10 +265,32 @@ pub enum FileType {
     Json,
     /// Source code file (programming languages)
     Code,
+    /// PDF, HTML or Office document
+    Document,
     /// Unknown file type
     Unknown,
 }
 
+impl std::str::FromStr for FileType {
+    type Err = String;
+
+    /// Parse a file type name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(FileType::Text),
+            "markdown" => Ok(FileType::Markdown),
+            "json" => Ok(FileType::Json),
+            "code" => Ok(FileType::Code),
+            "document" => Ok(FileType::Document),
+            "unknown" => Ok(FileType::Unknown),
+            _ => Err(format!(
+                "Unknown file type '{}', expected one of: text, markdown, json, code, document, unknown",
+                s
+            )),
+        }
+    }
+}
+
 /// Progress status for indexing operations
 #[derive(Debug, Clone)]
 pub enum ProgressStatus {
@@ -234,6 +323,39 @@ pub struct OperationHandle {
     pub(crate) cancel_token: CancellationToken,
     /// Task handle for proper cancellation
     pub(crate) task_handle: Option<tokio::task::AbortHandle>,
+    pub(crate) state: OperationState,
+    /// When the operation ended, if it has
+    pub(crate) finished_at: Option<SystemTime>,
+}
+
+/// Whether an operation is still running, and how it ended otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationState {
+    /// Waiting for its turn or in progress
+    Running,
+    /// Finished successfully
+    Completed,
+    /// Stopped by an error, described by the operation's message
+    Failed,
+    /// Cancelled by the user
+    Cancelled,
+}
+
+impl OperationHandle {
+    /// Record how the operation ended, unless it already has
+    pub(crate) fn finish(&mut self, state: OperationState) {
+        if !self.state.is_finished() {
+            self.state = state;
+            self.finished_at = Some(SystemTime::now());
+        }
+    }
+}
+
+impl OperationState {
+    /// Whether the operation has ended
+    pub fn is_finished(&self) -> bool {
+        *self != OperationState::Running
+    }
 }
 
 /// Type of operation being performed
@@ -246,6 +368,13 @@ pub enum OperationType {
         /// Path being indexed
         path: String,
     },
+    /// Re-indexing the changed files of a context
+    Updating {
+        /// Display name for the operation
+        name: String,
+        /// Path being indexed
+        path: String,
+    },
     /// Clearing all contexts
     Clearing,
 }
@@ -255,6 +384,7 @@ impl OperationType {
     pub fn display_name(&self) -> String {
         match self {
             OperationType::Indexing { name, .. } => format!("Indexing '{}'", name),
+            OperationType::Updating { name, .. } => format!("Updating '{}'", name),
             OperationType::Clearing => "Clearing all".to_string(),
         }
     }
@@ -277,10 +407,8 @@ pub struct OperationStatus {
     pub total: u64,
     /// Current status message
     pub message: String,
-    /// Whether the operation was cancelled
-    pub is_cancelled: bool,
-    /// Whether the operation failed
-    pub is_failed: bool,
+    /// Whether the operation is running or how it ended
+    pub state: OperationState,
     /// Whether the operation is waiting
     pub is_waiting: bool,
     /// Estimated time to completion
@@ -390,6 +518,23 @@ pub enum IndexingJob {
         exclude_patterns: Option<Vec<String>>,
         /// Embedding type
         embedding_type: Option<EmbeddingType>,
+        /// Whether to watch the path once indexed
+        watch: bool,
+        /// Whether to rerank search results
+        rerank: bool,
+    },
+    /// Incrementally re-index an existing context
+    Update {
+        /// Operation ID
+        id: Uuid,
+        /// Cancellation token
+        cancel: CancellationToken,
+        /// ID of the context to update
+        context_id: String,
+        /// Directory path, which may differ from the context's current source path
+        path: PathBuf,
+        /// Notified when the update finishes, whether or not it succeeded
+        done: Option<oneshot::Sender<()>>,
     },
     /// Clear all contexts job
     Clear {
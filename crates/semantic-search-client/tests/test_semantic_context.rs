@@ -4,8 +4,12 @@ use std::{
     fs,
 };
 
+use semantic_search_client::SearchFilter;
 use semantic_search_client::client::SemanticContext;
-use semantic_search_client::types::DataPoint;
+use semantic_search_client::types::{
+    DataPoint,
+    FileType,
+};
 use serde_json::Value;
 
 #[test]
@@ -98,3 +102,90 @@ fn test_add_data_points() {
     // Clean up
     fs::remove_dir_all(temp_dir).unwrap_or(());
 }
+
+#[test]
+fn test_migrate_json_context() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let data_path = temp_dir.path().join("data.json");
+
+    // Contexts used to be saved as a JSON array of data points
+    let data_points: Vec<DataPoint> = (0..3)
+        .map(|id| {
+            let mut vector = vec![0.0; 384];
+            vector[id] = 1.0;
+            let mut payload = HashMap::new();
+            payload.insert("text".to_string(), Value::String(format!("point {}", id)));
+            DataPoint { id, payload, vector }
+        })
+        .collect();
+    fs::write(&data_path, serde_json::to_string(&data_points).unwrap()).unwrap();
+
+    let context = SemanticContext::new(data_path.clone()).unwrap();
+    assert!(!data_path.exists());
+    for file in ["vectors.bin", "payloads.bin", "index.hnsw.graph", "index.hnsw.data"] {
+        assert!(temp_dir.path().join(file).exists(), "{} is missing", file);
+    }
+    assert_eq!(context.len(), 3);
+
+    let mut query = vec![0.0; 384];
+    query[1] = 1.0;
+    let results = context.search(&query, 1).unwrap();
+    assert_eq!(results[0].point.id, 1);
+    assert_eq!(results[0].point.payload["text"], "point 1");
+    drop(context);
+
+    // Reloading uses the saved index and the mapped data points
+    let reloaded = SemanticContext::new(data_path).unwrap();
+    assert_eq!(reloaded.len(), 3);
+    let results = reloaded.search(&query, 1).unwrap();
+    assert_eq!(results[0].point.id, 1);
+    assert_eq!(results[0].point.vector, data_points[1].vector);
+}
+
+#[test]
+fn test_search_filtered_saved_and_added_points() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let data_path = temp_dir.path().join("data.json");
+    let point = |id: usize, path: &str, file_type: &str| {
+        let mut vector = vec![0.0; 384];
+        vector[id] = 1.0;
+        let payload = HashMap::from([
+            ("path".to_string(), Value::String(path.to_string())),
+            ("file_type".to_string(), Value::String(file_type.to_string())),
+        ]);
+        DataPoint { id, payload, vector }
+    };
+
+    let mut context = SemanticContext::new(data_path.clone()).unwrap();
+    context
+        .add_data_points(vec![
+            point(0, "/repo/src/main.rs", "Code"),
+            point(1, "/repo/README.md", "Markdown"),
+        ])
+        .unwrap();
+    context.save().unwrap();
+    drop(context);
+
+    // Filters apply to the saved points and to the points added since loading
+    let mut context = SemanticContext::new(data_path).unwrap();
+    context
+        .add_data_points(vec![point(2, "/repo/src/lib.rs", "Code")])
+        .unwrap();
+    let matcher = SearchFilter {
+        file_types: vec![FileType::Code],
+        ..Default::default()
+    }
+    .matcher()
+    .unwrap();
+
+    let mut query = vec![0.0; 384];
+    query[1] = 1.0;
+    let mut ids: Vec<usize> = context
+        .search_filtered(&query, 5, &matcher)
+        .unwrap()
+        .into_iter()
+        .map(|result| result.point.id)
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec![0, 2]);
+}
@@ -53,3 +53,38 @@ fn test_search() {
         assert!(results[0].0 <= 2);
     }
 }
+
+#[test]
+fn test_load_corrupt_index() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+
+    let index = VectorIndex::new(100);
+    for id in 0..20 {
+        let vector: Vec<f32> = (0..8).map(|i| ((id * 8 + i) as f32).sin()).collect();
+        index.insert(&vector, id);
+    }
+    index.save(dir, "index").unwrap();
+    assert_eq!(VectorIndex::load(dir, "index").unwrap().unwrap().len(), 20);
+
+    // Corrupt, truncated or extended dumps are ignored rather than crashing the loader
+    let graph_path = dir.join("index.hnsw.graph");
+    let data_path = dir.join("index.hnsw.data");
+    let graph = std::fs::read(&graph_path).unwrap();
+    let data = std::fs::read(&data_path).unwrap();
+    let mut corrupt_data = data.clone();
+    corrupt_data[20] ^= 0xff;
+    let mut extended_graph = graph.clone();
+    extended_graph.push(0);
+    for (graph, data) in [
+        (graph[..graph.len() / 2].to_vec(), data.clone()),
+        (graph.clone(), data[..data.len() - 1].to_vec()),
+        (graph.clone(), corrupt_data),
+        (extended_graph, data.clone()),
+        (vec![0; graph.len()], data.clone()),
+    ] {
+        std::fs::write(&graph_path, graph).unwrap();
+        std::fs::write(&data_path, data).unwrap();
+        assert!(VectorIndex::load(dir, "index").unwrap().is_none());
+    }
+}